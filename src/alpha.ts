@@ -0,0 +1,188 @@
+/**
+ * Companion alpha masks
+ * JPEG has no alpha channel, so transparency travels beside the image as a
+ * second file: a grayscale JPEG, the raw 8-bit mask, or the mask run-length
+ * coded with PackBits. The mask is collected from the same strips the main
+ * encoder consumes, and `mergeAlpha` puts the two back together.
+ */
+
+import { decode } from './decoder.js';
+import type { DecodedImage } from './decoder.js';
+import { toGrayscale } from './transcode.js';
+import { concatBytes } from './pipeline.js';
+import type { StripEncoder } from './pipeline.js';
+
+/**
+ * - jpeg: single-component JPEG, lossy but small for soft edges
+ * - raw: one byte per pixel, row-major
+ * - rle: the raw mask coded with PackBits, compact for hard-edged cut-outs
+ */
+export type AlphaMaskFormat = 'jpeg' | 'raw' | 'rle';
+
+export interface AlphaMask {
+  format: AlphaMaskFormat;
+  width: number;
+  height: number;
+  data: Uint8Array;
+}
+
+export interface AlphaOutputOptions {
+  format?: AlphaMaskFormat;   // Defaults to 'jpeg'
+  quality?: number;           // For 'jpeg' masks; defaults to the image quality
+  onAlpha(mask: AlphaMask): Promise<void> | void;
+}
+
+/**
+ * PackBits run-length coding: a header n of 0-127 copies n + 1 literal bytes,
+ * 129-255 repeats the next byte 257 - n times
+ */
+export function packBits(data: Uint8Array): Uint8Array {
+  const out: number[] = [];
+  let i = 0;
+  while (i < data.length) {
+    let run = 1;
+    while (i + run < data.length && run < 128 && data[i + run] === data[i]) {
+      run++;
+    }
+    if (run > 1) {
+      out.push(257 - run, data[i]);
+      i += run;
+      continue;
+    }
+    const start = i;
+    while (i < data.length && i - start < 128 && (i + 1 >= data.length || data[i + 1] !== data[i])) {
+      i++;
+    }
+    if (i === start) {
+      i++;
+    }
+    out.push(i - start - 1, ...data.subarray(start, i));
+  }
+  return Uint8Array.from(out);
+}
+
+/**
+ * Expand PackBits data to `length` bytes
+ */
+export function unpackBits(data: Uint8Array, length: number): Uint8Array {
+  const out = new Uint8Array(length);
+  let offset = 0;
+  let i = 0;
+  while (i < data.length && offset < length) {
+    const header = data[i++];
+    if (header < 128) {
+      out.set(data.subarray(i, i + header + 1), offset);
+      offset += header + 1;
+      i += header + 1;
+    } else if (header > 128) {
+      out.fill(data[i++], offset, offset + 257 - header);
+      offset += 257 - header;
+    }
+  }
+  if (offset < length) {
+    throw new Error(`PackBits data ended after ${offset} of ${length} bytes`);
+  }
+  return out;
+}
+
+/**
+ * Collects the alpha channel of RGBA strips and produces the mask
+ * For 'jpeg' masks the alpha rows are fed, as gray pixels, to `encoder` as
+ * they arrive, so neither mask nor image is held in full.
+ */
+export class AlphaCollector {
+  private readonly width: number;
+  private readonly height: number;
+  private readonly format: AlphaMaskFormat;
+  private readonly encoder?: StripEncoder;
+  private readonly mask?: Uint8Array;
+  private readonly pending: Uint8Array;
+  private readonly chunks: Uint8Array[] = [];
+  private pendingRows = 0;
+  private rows = 0;
+
+  constructor(width: number, height: number, format: AlphaMaskFormat, encoder?: StripEncoder) {
+    if (format === 'jpeg' && !encoder) {
+      throw new Error('JPEG alpha masks need an encoder');
+    }
+    this.width = width;
+    this.height = height;
+    this.format = format;
+    this.encoder = format === 'jpeg' ? encoder : undefined;
+    this.mask = format === 'jpeg' ? undefined : new Uint8Array(width * height);
+    this.pending = new Uint8Array(this.encoder ? width * this.encoder.stripHeight * 4 : 0);
+  }
+
+  /**
+   * Add the next strip of RGBA rows (strips must arrive top to bottom)
+   */
+  addStrip(strip: Uint8Array): void {
+    const count = Math.min(Math.floor(strip.length / (this.width * 4)), this.height - this.rows);
+    for (let row = 0; row < count; row++, this.rows++) {
+      for (let x = 0; x < this.width; x++) {
+        const alpha = strip[(row * this.width + x) * 4 + 3];
+        if (this.mask) {
+          this.mask[this.rows * this.width + x] = alpha;
+        } else {
+          this.pending.set([alpha, alpha, alpha, 255], (this.pendingRows * this.width + x) * 4);
+        }
+      }
+      if (this.encoder && ++this.pendingRows === this.encoder.stripHeight) {
+        this.flush();
+      }
+    }
+  }
+
+  private flush(): void {
+    if (this.encoder && this.pendingRows > 0) {
+      this.chunks.push(this.encoder.encodeStrip(this.pending.subarray(0, this.pendingRows * this.width * 4)));
+      this.pendingRows = 0;
+    }
+  }
+
+  /**
+   * Finish the mask once every row has been added
+   */
+  finish(): AlphaMask {
+    const { width, height, format } = this;
+    if (this.rows < height) {
+      throw new Error(`Alpha mask ended after ${this.rows} of ${height} rows`);
+    }
+    if (this.mask) {
+      return { format, width, height, data: format === 'rle' ? packBits(this.mask) : this.mask };
+    }
+    this.flush();
+    this.chunks.push(this.encoder!.finish());
+    return { format, width, height, data: toGrayscale(concatBytes(this.chunks)) };
+  }
+}
+
+/**
+ * Decode a JPEG and apply its companion alpha mask
+ *
+ * @param jpeg The color image
+ * @param mask Mask produced alongside it, of the same size
+ */
+export function mergeAlpha(jpeg: Uint8Array, mask: AlphaMask): DecodedImage {
+  const image = decode(jpeg);
+  const { width, height, data } = image;
+  if (mask.width !== width || mask.height !== height) {
+    throw new Error(`Mask is ${mask.width}x${mask.height} but the image is ${width}x${height}`);
+  }
+  const count = width * height;
+  if (mask.format === 'jpeg') {
+    const gray = decode(mask.data).data;
+    for (let i = 0; i < count; i++) {
+      data[i * 4 + 3] = gray[i * 4];
+    }
+    return image;
+  }
+  const alpha = mask.format === 'rle' ? unpackBits(mask.data, count) : mask.data;
+  if (alpha.length < count) {
+    throw new Error(`Raw mask holds ${alpha.length} of ${count} bytes`);
+  }
+  for (let i = 0; i < count; i++) {
+    data[i * 4 + 3] = alpha[i];
+  }
+  return image;
+}
@@ -0,0 +1,100 @@
+/**
+ * Animation frame compositing
+ * Applies GIF-style frame placement and disposal so partial frames of an
+ * animation become a sequence of complete RGBA images.
+ */
+
+export type FrameDisposal = 'none' | 'background' | 'previous';
+
+/**
+ * One frame of an animation
+ * - data covers only the frame rectangle (width x height at left/top)
+ * - pixels with alpha 0 leave the canvas untouched
+ * - disposal says what happens to the rectangle after the frame is shown
+ */
+export interface AnimationFrame {
+  data: Uint8Array;
+  left: number;
+  top: number;
+  width: number;
+  height: number;
+  delay: number;          // Display time in milliseconds
+  disposal: FrameDisposal;
+}
+
+/**
+ * Maintains the canvas an animation is drawn on
+ */
+export class FrameCompositor {
+  readonly width: number;
+  readonly height: number;
+  private readonly canvas: Uint8Array;
+  private readonly background: [number, number, number];
+  private pending?: { frame: AnimationFrame; saved?: Uint8Array };
+
+  /**
+   * @param background Opaque RGB used for the initial canvas and 'background' disposal
+   */
+  constructor(width: number, height: number, background: [number, number, number] = [0, 0, 0]) {
+    this.width = width;
+    this.height = height;
+    this.background = background;
+    this.canvas = new Uint8Array(width * height * 4);
+    this.fillRect(0, 0, width, height);
+  }
+
+  /**
+   * Dispose of the previous frame, draw this one, and return the full canvas
+   * The returned buffer is reused by the next call.
+   */
+  composite(frame: AnimationFrame): Uint8Array {
+    this.dispose();
+    const saved = frame.disposal === 'previous' ? this.canvas.slice() : undefined;
+    this.pending = { frame, saved };
+
+    const x0 = Math.max(0, frame.left);
+    const y0 = Math.max(0, frame.top);
+    const x1 = Math.min(this.width, frame.left + frame.width);
+    const y1 = Math.min(this.height, frame.top + frame.height);
+    for (let y = y0; y < y1; y++) {
+      for (let x = x0; x < x1; x++) {
+        const source = ((y - frame.top) * frame.width + (x - frame.left)) * 4;
+        if (frame.data[source + 3] === 0) {
+          continue;
+        }
+        const target = (y * this.width + x) * 4;
+        this.canvas[target] = frame.data[source];
+        this.canvas[target + 1] = frame.data[source + 1];
+        this.canvas[target + 2] = frame.data[source + 2];
+        this.canvas[target + 3] = 255;
+      }
+    }
+    return this.canvas;
+  }
+
+  private dispose(): void {
+    if (!this.pending) {
+      return;
+    }
+    const { frame, saved } = this.pending;
+    if (frame.disposal === 'background') {
+      this.fillRect(frame.left, frame.top, frame.width, frame.height);
+    } else if (frame.disposal === 'previous' && saved) {
+      this.canvas.set(saved);
+    }
+    this.pending = undefined;
+  }
+
+  private fillRect(left: number, top: number, width: number, height: number): void {
+    const [r, g, b] = this.background;
+    for (let y = Math.max(0, top); y < Math.min(this.height, top + height); y++) {
+      for (let x = Math.max(0, left); x < Math.min(this.width, left + width); x++) {
+        const offset = (y * this.width + x) * 4;
+        this.canvas[offset] = r;
+        this.canvas[offset + 1] = g;
+        this.canvas[offset + 2] = b;
+        this.canvas[offset + 3] = 255;
+      }
+    }
+  }
+}
@@ -0,0 +1,176 @@
+/**
+ * Bitstream annotator
+ * Walks an encoded stream and describes every marker segment, table, scan, and
+ * restart marker it contains, for debugging interop problems.
+ */
+
+import {
+  Marker,
+  iterateSegments,
+  isFrameMarker,
+  parseFrameHeader,
+  parseHuffmanTables,
+  parseQuantTables,
+  parseScanHeader,
+  readUint16,
+} from './jpeg.js';
+import type { Segment } from './jpeg.js';
+
+/**
+ * One annotated position in the stream
+ * - length: total bytes including the marker (entropy-coded data has no marker)
+ * - details: human-readable decoding of the segment contents
+ */
+export interface Annotation {
+  offset: number;
+  marker: number | null; // null for entropy-coded data
+  name: string;
+  length: number;
+  details: string[];
+}
+
+const MARKER_NAMES: Record<number, string> = {
+  [Marker.SOF0]: 'SOF0 (baseline DCT)',
+  [Marker.SOF1]: 'SOF1 (extended sequential DCT)',
+  [Marker.SOF2]: 'SOF2 (progressive DCT)',
+  [Marker.DHT]: 'DHT',
+  [Marker.SOI]: 'SOI',
+  [Marker.EOI]: 'EOI',
+  [Marker.SOS]: 'SOS',
+  [Marker.DQT]: 'DQT',
+  [Marker.DRI]: 'DRI',
+  [Marker.COM]: 'COM',
+};
+
+/**
+ * Name a marker code (e.g. 'DQT', 'APP1', 'RST3')
+ */
+export function markerName(marker: number): string {
+  if (MARKER_NAMES[marker]) {
+    return MARKER_NAMES[marker];
+  }
+  if (marker >= Marker.RST0 && marker <= Marker.RST7) {
+    return `RST${marker - Marker.RST0}`;
+  }
+  if (marker >= Marker.APP0 && marker <= Marker.APP15) {
+    return `APP${marker - Marker.APP0}`;
+  }
+  if (isFrameMarker(marker)) {
+    return `SOF${marker - Marker.SOF0}`;
+  }
+  return `0x${marker.toString(16).padStart(2, '0')}`;
+}
+
+function describeSegment(segment: Segment): string[] {
+  const { marker, data } = segment;
+  if (isFrameMarker(marker)) {
+    const frame = parseFrameHeader(marker, data);
+    return [
+      `${frame.width}x${frame.height}, ${frame.precision}-bit, ${frame.components.length} component(s)`,
+      ...frame.components.map((c) => `component ${c.id}: sampling ${c.h}x${c.v}, quant table ${c.quantTable}`),
+    ];
+  }
+  if (marker === Marker.DQT) {
+    return parseQuantTables(data).map((t) =>
+      `table ${t.id}: ${t.precision === 0 ? 8 : 16}-bit, DC ${t.values[0]}, first AC ${t.values[1]}, last ${t.values[63]}`
+    );
+  }
+  if (marker === Marker.DHT) {
+    return parseHuffmanTables(data).map((t) =>
+      `${t.tableClass === 0 ? 'DC' : 'AC'} table ${t.id}: ${t.symbols.length} symbols`
+    );
+  }
+  if (marker === Marker.SOS) {
+    const header = parseScanHeader(data);
+    return [
+      ...header.components.map((c) => `component ${c.id}: DC table ${c.dcTable}, AC table ${c.acTable}`),
+      `spectral ${header.spectralStart}-${header.spectralEnd}, approximation ${header.approxHigh}/${header.approxLow}`,
+    ];
+  }
+  if (marker === Marker.DRI) {
+    return [`restart interval ${readUint16(data, 0)} MCUs`];
+  }
+  if (marker >= Marker.APP0 && marker <= Marker.APP15) {
+    let end = 0;
+    while (end < data.length && end < 32 && data[end] !== 0) {
+      end++;
+    }
+    const identifier = String.fromCharCode(...data.subarray(0, end));
+    return identifier ? [`identifier "${identifier}"`] : [];
+  }
+  if (marker === Marker.COM) {
+    return [`"${String.fromCharCode(...data.subarray(0, 64))}${data.length > 64 ? '…' : ''}"`];
+  }
+  return [];
+}
+
+/**
+ * Annotate every segment of a JPEG stream
+ * Entropy-coded data is reported as one entry per restart interval, with the
+ * restart markers between them listed individually.
+ *
+ * @param jpeg Encoded JPEG bytes
+ */
+export function annotate(jpeg: Uint8Array): Annotation[] {
+  const annotations: Annotation[] = [];
+
+  for (const segment of iterateSegments(jpeg)) {
+    annotations.push({
+      offset: segment.offset,
+      marker: segment.marker,
+      name: markerName(segment.marker),
+      length: segment.length,
+      details: describeSegment(segment),
+    });
+
+    if (segment.marker === Marker.SOS) {
+      // Split the entropy-coded data at restart markers
+      let start = segment.offset + segment.length;
+      let pos = start;
+      const pushData = (end: number): void => {
+        if (end > start) {
+          annotations.push({ offset: start, marker: null, name: 'entropy-coded data', length: end - start, details: [] });
+        }
+      };
+      while (pos < jpeg.length - 1) {
+        if (jpeg[pos] === 0xff && jpeg[pos + 1] !== 0x00 && jpeg[pos + 1] !== 0xff) {
+          const marker = jpeg[pos + 1];
+          if (marker < Marker.RST0 || marker > Marker.RST7) {
+            break;
+          }
+          pushData(pos);
+          annotations.push({ offset: pos, marker, name: markerName(marker), length: 2, details: [] });
+          pos += 2;
+          start = pos;
+          continue;
+        }
+        pos++;
+      }
+      pushData(pos < jpeg.length - 1 ? pos : jpeg.length);
+    }
+  }
+
+  return annotations;
+}
+
+/**
+ * Render annotations as an indented, one-line-per-entry text dump
+ *
+ * @example
+ * console.log(formatAnnotations(annotate(jpeg)));
+ * // 0x000000  SOI
+ * // 0x000002  APP0  length 18
+ * //             identifier "JFIF"
+ */
+export function formatAnnotations(annotations: Annotation[]): string {
+  const lines: string[] = [];
+  for (const annotation of annotations) {
+    const offset = `0x${annotation.offset.toString(16).padStart(6, '0')}`;
+    const length = annotation.marker === null || annotation.length > 2 ? `  length ${annotation.length}` : '';
+    lines.push(`${offset}  ${annotation.name}${length}`);
+    for (const detail of annotation.details) {
+      lines.push(`            ${detail}`);
+    }
+  }
+  return lines.join('\n');
+}
@@ -0,0 +1,88 @@
+/**
+ * Incremental base64 output
+ * Turns encoder output into base64 text chunk by chunk, carrying the one or two
+ * bytes that do not fill a 3-byte group over to the next chunk, so a data URL
+ * preview is built while encoding instead of by converting a finished JPEG.
+ */
+
+const ALPHABET = new TextEncoder().encode('ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/');
+const PAD = 0x3d;
+const ascii = new TextDecoder();
+
+/**
+ * Base64 encoder fed in pieces of any size
+ * The concatenated output of `push` calls and the final `flush` equals the
+ * base64 of all the bytes pushed.
+ */
+export class Base64Encoder {
+  private readonly carry = new Uint8Array(2);
+  private carried = 0;
+
+  /**
+   * Encode every complete 3-byte group available, keeping the remainder
+   */
+  push(chunk: Uint8Array): string {
+    const total = this.carried + chunk.length;
+    const groups = Math.floor(total / 3);
+    const out = new Uint8Array(groups * 4);
+    let position = 0;
+    const next = (): number => (position < this.carried ? this.carry[position++] : chunk[position++ - this.carried]);
+    for (let group = 0; group < groups; group++) {
+      const n = (next() << 16) | (next() << 8) | next();
+      out[group * 4] = ALPHABET[n >> 18];
+      out[group * 4 + 1] = ALPHABET[(n >> 12) & 63];
+      out[group * 4 + 2] = ALPHABET[(n >> 6) & 63];
+      out[group * 4 + 3] = ALPHABET[n & 63];
+    }
+    const rest = total - position;
+    const remainder = Uint8Array.from({ length: rest }, next);
+    this.carry.set(remainder);
+    this.carried = rest;
+    return ascii.decode(out);
+  }
+
+  /**
+   * Encode the carried bytes with padding; the encoder can then be reused
+   */
+  flush(): string {
+    if (this.carried === 0) {
+      return '';
+    }
+    const [a, b = 0] = this.carry.subarray(0, this.carried);
+    const n = (a << 16) | (b << 8);
+    const out = Uint8Array.from([ALPHABET[n >> 18], ALPHABET[(n >> 12) & 63], this.carried === 2 ? ALPHABET[(n >> 6) & 63] : PAD, PAD]);
+    this.carried = 0;
+    return ascii.decode(out);
+  }
+}
+
+/**
+ * Transform stream from bytes to base64 text
+ *
+ * @example
+ * const text = encodeToStream(canvas).pipeThrough(new Base64EncoderStream());
+ */
+export class Base64EncoderStream {
+  readonly readable: ReadableStream<string>;
+  readonly writable: WritableStream<Uint8Array>;
+
+  constructor() {
+    const encoder = new Base64Encoder();
+    const { readable, writable } = new TransformStream<Uint8Array, string>({
+      transform(chunk, controller): void {
+        const text = encoder.push(chunk);
+        if (text) {
+          controller.enqueue(text);
+        }
+      },
+      flush(controller): void {
+        const text = encoder.flush();
+        if (text) {
+          controller.enqueue(text);
+        }
+      },
+    });
+    this.readable = readable;
+    this.writable = writable;
+  }
+}
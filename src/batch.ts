@@ -0,0 +1,81 @@
+/**
+ * Batch encoding
+ * Encodes many independent images with a bounded number in flight, sharing
+ * default options and isolating failures to the item that caused them.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { encodeToBytes } from './encoder.js';
+
+export interface BatchItem {
+  source: ImageSource;
+  options?: EncodeOptions; // Merged over the batch defaults
+}
+
+export type BatchResult =
+  | { index: number; ok: true; jpeg: Uint8Array }
+  | { index: number; ok: false; error: Error };
+
+export interface BatchEncoderOptions {
+  concurrency?: number;                  // Images in flight at once, defaults to 4
+  defaults?: EncodeOptions;              // Options applied to every item
+  onResult?(result: BatchResult): void;  // Called as each item finishes, in completion order
+}
+
+/**
+ * Encoder for batches of independent images, e.g. a thumbnailing service
+ * Items are started in order with at most `concurrency` in flight, so file and
+ * stream sources are read while others encode. A failing item is reported in
+ * its result and does not stop the rest of the batch.
+ *
+ * @example
+ * const batch = new BatchEncoder({ concurrency: 8, defaults: { quality: 80 } });
+ * const results = await batch.encode(paths.map((source) => ({ source })));
+ */
+export class BatchEncoder {
+  private readonly concurrency: number;
+  private readonly defaults: EncodeOptions;
+  private readonly onResult?: (result: BatchResult) => void;
+
+  constructor(options: BatchEncoderOptions = {}) {
+    const { concurrency = 4 } = options;
+    if (!Number.isInteger(concurrency) || concurrency < 1) {
+      throw new Error(`Concurrency must be a positive integer, got ${concurrency}`);
+    }
+    this.concurrency = concurrency;
+    this.defaults = options.defaults ?? {};
+    this.onResult = options.onResult;
+  }
+
+  /**
+   * Encode every item
+   * @returns One result per item, in input order
+   */
+  async encode(items: Iterable<BatchItem | ImageSource>): Promise<BatchResult[]> {
+    const queue = [...items].map((item, index) => ({ index, item: toBatchItem(item) }));
+    const results: BatchResult[] = new Array(queue.length);
+    let next = 0;
+
+    const worker = async (): Promise<void> => {
+      while (next < queue.length) {
+        const { index, item } = queue[next++];
+        let result: BatchResult;
+        try {
+          const jpeg = await encodeToBytes(item.source, { ...this.defaults, ...item.options });
+          result = { index, ok: true, jpeg };
+        } catch (error) {
+          result = { index, ok: false, error: error instanceof Error ? error : new Error(String(error)) };
+        }
+        results[index] = result;
+        this.onResult?.(result);
+      }
+    };
+
+    await Promise.all(Array.from({ length: Math.min(this.concurrency, queue.length) }, worker));
+    return results;
+  }
+}
+
+function toBatchItem(item: BatchItem | ImageSource): BatchItem {
+  return typeof item === 'object' && !ArrayBuffer.isView(item) && 'source' in item ? item : { source: item };
+}
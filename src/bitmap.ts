@@ -0,0 +1,51 @@
+/**
+ * ImageBitmap input
+ * Draws a bitmap onto an OffscreenCanvas once and reads it back strip by
+ * strip, so only one strip of RGBA pixels is held outside the canvas.
+ */
+
+import type { StripSource } from './pipeline.js';
+
+type ReadbackContext = OffscreenCanvasRenderingContext2D | CanvasRenderingContext2D;
+
+function readbackContext(width: number, height: number): ReadbackContext {
+  let context: ReadbackContext | null = null;
+  if (typeof OffscreenCanvas !== 'undefined') {
+    context = new OffscreenCanvas(width, height).getContext('2d', { willReadFrequently: true });
+  } else if (typeof document !== 'undefined') {
+    const canvas = document.createElement('canvas');
+    canvas.width = width;
+    canvas.height = height;
+    context = canvas.getContext('2d', { willReadFrequently: true });
+  }
+  if (!context) {
+    throw new Error('ImageBitmap sources need OffscreenCanvas or a DOM canvas with a 2D context');
+  }
+  return context;
+}
+
+/**
+ * Create a strip source over an ImageBitmap
+ *
+ * @param bitmap Decoded bitmap, e.g. from createImageBitmap(blob)
+ */
+export function imageBitmapSource(bitmap: ImageBitmap): StripSource {
+  const { width, height } = bitmap;
+  const context = readbackContext(width, height);
+  context.drawImage(bitmap, 0, 0);
+  let y = 0;
+
+  return {
+    width,
+    height,
+    readStrip(rows: number): Uint8Array | null {
+      if (y >= height) {
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      const { data } = context.getImageData(0, y, width, count);
+      y += count;
+      return new Uint8Array(data.buffer, data.byteOffset, data.byteLength);
+    },
+  };
+}
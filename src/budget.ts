@@ -0,0 +1,163 @@
+/**
+ * Per-MCU bit budgets
+ * Fixed-size packet transports need a hard bound on how much data any part of
+ * a frame produces. Here every MCU is held to a maximum number of entropy-coded
+ * bits: where one would exceed it, the highest-frequency coefficients of its
+ * blocks are dropped until it fits. The image is re-coded with the standard
+ * Huffman tables, so each MCU's cost is known exactly while dropping.
+ */
+
+import { Marker, metadataSegments, parseJpeg } from './jpeg.js';
+import { readCoefficients } from './decoder.js';
+import { writeBaseline } from './transcode.js';
+import type { CodedComponent } from './transcode.js';
+import { STANDARD_TABLES, sharedStandardCodes } from './libjpeg.js';
+import { magnitudeCategory } from './huffman.js';
+import type { HuffmanCodeTable } from './huffman.js';
+
+export interface McuBudgetResult {
+  jpeg: Uint8Array;
+  droppedCoefficients: number; // Nonzero coefficients set to zero
+  limitedMcus: number;         // MCUs that were over budget
+}
+
+/**
+ * Bits of the AC terms of one block, EOB included
+ */
+function acBits(values: Int32Array, base: number, code: HuffmanCodeTable): number {
+  let bits = 0;
+  let run = 0;
+  for (let k = 1; k < 64; k++) {
+    const value = values[base + k];
+    if (value === 0) {
+      run++;
+      continue;
+    }
+    for (; run > 15; run -= 16) {
+      bits += code.lengths[0xf0];
+    }
+    const size = magnitudeCategory(value);
+    bits += code.lengths[(run << 4) | size] + size;
+    run = 0;
+  }
+  return run > 0 ? bits + code.lengths[0x00] : bits;
+}
+
+/**
+ * Hold every MCU to at most `maxBits` bits of entropy-coded data
+ * Bits are counted before byte stuffing, which adds at most one byte per 0xFF
+ * written. DC terms are never dropped, so a budget below what an MCU's DC
+ * terms and end-of-block codes need is an error. APPn and COM segments and the
+ * restart interval are kept.
+ *
+ * @param jpeg Sequential 8-bit JPEG with a single scan
+ */
+export function limitMcuBits(jpeg: Uint8Array, maxBits: number): McuBudgetResult {
+  if (!Number.isInteger(maxBits) || maxBits < 1) {
+    throw new Error(`MCU bit budget must be a positive integer, got ${maxBits}`);
+  }
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 || file.scans.length !== 1) {
+    throw new Error('A per-MCU bit budget needs an 8-bit sequential JPEG with a single scan');
+  }
+  const scan = file.scans[0];
+  const coefficients = readCoefficients(file);
+
+  // Standard tables are numbered 0 for luma and 1 for chroma
+  const components: CodedComponent[] = coefficients.map((source, index) => {
+    const { id, h, v, quantTable } = frame.components[index];
+    const selector = scan.header.components.find((c) => c.id === id)?.dcTable ?? 0;
+    return {
+      id,
+      h,
+      v,
+      quantId: quantTable,
+      huffmanId: selector <= 1 ? selector : Math.min(index, 1),
+      blocksPerLine: source.blocksPerLine,
+      blocksPerColumn: source.blocksPerColumn,
+      blocks: source.blocks.slice(),
+    };
+  });
+  const codes = sharedStandardCodes();
+  const dcCode = (c: CodedComponent): HuffmanCodeTable => codes[c.huffmanId! * 2];
+  const acCode = (c: CodedComponent): HuffmanCodeTable => codes[c.huffmanId! * 2 + 1];
+
+  // Walk MCUs in scan order, as writeBaseline will write them
+  const single = components.length === 1;
+  const maxH = Math.max(...components.map((c) => c.h));
+  const maxV = Math.max(...components.map((c) => c.v));
+  const mcusPerLine = single ? Math.ceil(frame.width / 8) : Math.ceil(frame.width / (8 * maxH));
+  const mcusPerColumn = single ? Math.ceil(frame.height / 8) : Math.ceil(frame.height / (8 * maxV));
+  const { restartInterval } = scan;
+  const predictors = new Int32Array(components.length);
+  let droppedCoefficients = 0;
+  let limitedMcus = 0;
+
+  for (let mcu = 0; mcu < mcusPerLine * mcusPerColumn; mcu++) {
+    if (restartInterval > 0 && mcu % restartInterval === 0) {
+      predictors.fill(0);
+    }
+    const mcuRow = Math.floor(mcu / mcusPerLine);
+    const mcuCol = mcu % mcusPerLine;
+    const blocks: { component: CodedComponent; base: number; bits: number }[] = [];
+    components.forEach((component, index) => {
+      const [h, v] = single ? [1, 1] : [component.h, component.v];
+      for (let y = 0; y < v; y++) {
+        for (let x = 0; x < h; x++) {
+          const base = ((mcuRow * v + y) * component.blocksPerLine + mcuCol * h + x) * 64;
+          const dc = component.blocks[base];
+          const size = magnitudeCategory(dc - predictors[index]);
+          predictors[index] = dc;
+          blocks.push({ component, base, bits: dcCode(component).lengths[size] + size });
+        }
+      }
+    });
+    const dcBits = blocks.reduce((sum, block) => sum + block.bits, 0);
+    let total = 0;
+    for (const block of blocks) {
+      block.bits = acBits(block.component.blocks, block.base, acCode(block.component));
+      total += block.bits;
+    }
+    if (dcBits + total <= maxBits) {
+      continue;
+    }
+
+    // Drop the highest-frequency term left in the MCU until it fits
+    limitedMcus++;
+    while (dcBits + total > maxBits) {
+      let target: (typeof blocks)[number] | undefined;
+      let highest = 0;
+      for (const block of blocks) {
+        for (let k = 63; k > highest; k--) {
+          if (block.component.blocks[block.base + k] !== 0) {
+            target = block;
+            highest = k;
+            break;
+          }
+        }
+      }
+      if (!target) {
+        throw new Error(`MCU ${mcu} needs ${dcBits + total} bits for its DC terms alone; the budget is ${maxBits}`);
+      }
+      target.component.blocks[target.base + highest] = 0;
+      droppedCoefficients++;
+      const bits = acBits(target.component.blocks, target.base, acCode(target.component));
+      total += bits - target.bits;
+      target.bits = bits;
+    }
+  }
+
+  const segments = metadataSegments(file);
+  const usedIds = new Set(components.map((c) => c.quantId));
+  const output = writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components,
+    quantTables: [...scan.quantTables.values()].filter((t) => usedIds.has(t.id)).sort((a, b) => a.id - b.id),
+    segments,
+    restartInterval,
+    huffmanTables: STANDARD_TABLES,
+  });
+  return { jpeg: output, droppedCoefficients, limitedMcus };
+}
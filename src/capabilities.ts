@@ -0,0 +1,96 @@
+/**
+ * WebAssembly capability probing
+ * Detects SIMD and thread support so a loader can choose between engine builds
+ * compiled with and without those features.
+ */
+
+export interface WasmFeatures {
+  simd: boolean;
+  threads: boolean;   // Shared memory and atomics, usable from this context
+}
+
+export type EngineVariant = 'baseline' | 'simd' | 'threads';
+
+/**
+ * Loaders for each engine build; only `baseline` is required
+ * The threaded build is assumed to also use SIMD.
+ */
+export interface EngineVariants<T> {
+  baseline: () => Promise<T>;
+  simd?: () => Promise<T>;
+  threads?: () => Promise<T>;
+}
+
+export interface LoadedEngine<T> {
+  variant: EngineVariant;
+  engine: T;
+}
+
+// Smallest modules using a v128 instruction and a shared memory with atomic.notify
+const SIMD_PROBE = new Uint8Array([
+  0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8, 0, 65, 0, 253, 15, 253, 98, 11,
+]);
+const THREADS_PROBE = new Uint8Array([
+  0, 97, 115, 109, 1, 0, 0, 0, 1, 4, 1, 96, 0, 0, 3, 2, 1, 0, 5, 4, 1, 3, 1, 1, 10, 11, 1, 9, 0, 65, 0, 254, 16, 2, 0, 26, 11,
+]);
+
+function validates(probe: Uint8Array): boolean {
+  try {
+    return typeof WebAssembly !== 'undefined' && WebAssembly.validate(probe);
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Probe the current runtime for WebAssembly SIMD and thread support
+ * Threads also need SharedArrayBuffer, which browsers only expose to
+ * cross-origin isolated pages.
+ */
+export function detectWasmFeatures(): WasmFeatures {
+  const isolated = (globalThis as { crossOriginIsolated?: boolean }).crossOriginIsolated;
+  const sharedMemory = typeof SharedArrayBuffer !== 'undefined' && isolated !== false;
+  return {
+    simd: validates(SIMD_PROBE),
+    threads: sharedMemory && validates(THREADS_PROBE),
+  };
+}
+
+/**
+ * Pick the most capable engine build the runtime supports
+ */
+export function selectEngineVariant(
+  features: WasmFeatures,
+  available: EngineVariant[] = ['baseline', 'simd', 'threads']
+): EngineVariant {
+  if (available.includes('threads') && features.threads && features.simd) {
+    return 'threads';
+  }
+  if (available.includes('simd') && features.simd) {
+    return 'simd';
+  }
+  return 'baseline';
+}
+
+/**
+ * Load the best engine build for this runtime
+ * Falls back to the baseline build if the selected build fails to load.
+ *
+ * @param variants Loader per build, typically dynamic imports of each wasm-pack output
+ * @param features Override the detected features, e.g. to force the baseline build
+ */
+export async function loadEngine<T>(
+  variants: EngineVariants<T>,
+  features: WasmFeatures = detectWasmFeatures()
+): Promise<LoadedEngine<T>> {
+  const available = (Object.keys(variants) as EngineVariant[]).filter((key) => variants[key] !== undefined);
+  const variant = selectEngineVariant(features, available);
+  if (variant !== 'baseline') {
+    try {
+      return { variant, engine: await (variants[variant] as () => Promise<T>)() };
+    } catch {
+      // Fall through to the baseline build
+    }
+  }
+  return { variant: 'baseline', engine: await variants.baseline() };
+}
@@ -0,0 +1,336 @@
+/**
+ * Canvas and video track capture
+ * Samples a canvas at a target frame rate, or reads VideoFrames from a media
+ * track, and yields full RGBA frames for the MJPEG encoder.
+ */
+
+import type { AnimationFrame } from './animation.js';
+
+export interface CaptureOptions {
+  fps?: number;          // Target frame rate, defaults to 15
+  signal?: AbortSignal;  // Stops the capture
+  maxFrames?: number;    // Stop after this many frames
+}
+
+export type FrameDropPolicy = 'latest' | 'queue';
+
+export interface TrackCaptureOptions {
+  policy?: FrameDropPolicy; // 'latest' keeps only the newest unread frame, 'queue' reads every frame; defaults to 'latest'
+  signal?: AbortSignal;     // Stops the capture
+  maxFrames?: number;       // Stop after this many frames
+}
+
+export interface CapturedFrame extends AnimationFrame {
+  timestamp: number;     // Milliseconds since capture started
+  dropped: number;       // Frame slots skipped since the previous frame because the consumer was busy
+}
+
+/**
+ * Fixed-rate schedule of frame slots
+ * When a slot is claimed late, the slots that passed in the meantime are
+ * counted as dropped rather than delivered in a burst.
+ */
+export class FrameClock {
+  readonly interval: number;
+  private readonly start: number;
+  private slot = 0;
+
+  constructor(fps: number, start: number) {
+    if (!(fps > 0)) {
+      throw new Error(`Frame rate must be positive, got ${fps}`);
+    }
+    this.interval = 1000 / fps;
+    this.start = start;
+  }
+
+  /**
+   * Claim the next slot at time `now`
+   * @returns How long to wait for the slot, and how many slots were skipped
+   */
+  next(now: number): { wait: number; dropped: number; timestamp: number } {
+    const due = Math.floor((now - this.start) / this.interval);
+    const dropped = Math.max(0, due - this.slot);
+    this.slot += dropped;
+    const timestamp = this.slot * this.interval;
+    this.slot++;
+    return { wait: Math.max(0, this.start + timestamp - now), dropped, timestamp };
+  }
+}
+
+export interface VideoFrameLike {
+  readonly displayWidth: number;
+  readonly displayHeight: number;
+  readonly timestamp: number;        // Microseconds
+  readonly duration: number | null;  // Microseconds
+  close(): void;
+}
+
+type TrackProcessorConstructor = new (init: { track: MediaStreamTrack }) => { readable: ReadableStream<VideoFrameLike> };
+
+const now = (): number => (typeof performance !== 'undefined' ? performance.now() : Date.now());
+
+function delay(ms: number, signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve) => {
+    if (ms <= 0 || signal?.aborted) {
+      resolve();
+      return;
+    }
+    const abort = (): void => {
+      clearTimeout(timer);
+      resolve();
+    };
+    const timer = setTimeout(() => {
+      signal?.removeEventListener('abort', abort);
+      resolve();
+    }, ms);
+    signal?.addEventListener('abort', abort, { once: true });
+  });
+}
+
+/**
+ * Wait for the next animation frame, where the page has just drawn
+ * Resolves at once where requestAnimationFrame is not available.
+ */
+function animationFrame(signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve) => {
+    if (typeof requestAnimationFrame === 'undefined' || signal?.aborted) {
+      resolve();
+      return;
+    }
+    const abort = (): void => {
+      cancelAnimationFrame(handle);
+      resolve();
+    };
+    const handle = requestAnimationFrame(() => {
+      signal?.removeEventListener('abort', abort);
+      resolve();
+    });
+    signal?.addEventListener('abort', abort, { once: true });
+  });
+}
+
+/**
+ * Read RGBA pixels from anything drawable
+ */
+function readPixels(image: CanvasImageSource, width: number, height: number, scratch?: OffscreenCanvasRenderingContext2D | CanvasRenderingContext2D): Uint8Array {
+  if (!scratch) {
+    throw new Error('2D canvas context is not available for readback');
+  }
+  if (scratch.canvas.width !== width || scratch.canvas.height !== height) {
+    scratch.canvas.width = width;
+    scratch.canvas.height = height;
+  }
+  scratch.drawImage(image, 0, 0);
+  return new Uint8Array(scratch.getImageData(0, 0, width, height).data.buffer);
+}
+
+function scratchContext(): OffscreenCanvasRenderingContext2D | CanvasRenderingContext2D | undefined {
+  if (typeof OffscreenCanvas !== 'undefined') {
+    return new OffscreenCanvas(1, 1).getContext('2d', { willReadFrequently: true }) ?? undefined;
+  }
+  if (typeof document !== 'undefined') {
+    return document.createElement('canvas').getContext('2d', { willReadFrequently: true }) ?? undefined;
+  }
+  return undefined;
+}
+
+/**
+ * Capture frames from a canvas at a fixed rate
+ * Frames are only read when the consumer asks for the next one, so a slow
+ * encoder causes dropped slots instead of an unbounded queue. The canvas track
+ * is read through MediaStreamTrackProcessor where available; otherwise each
+ * slot reads the canvas back in the next animation frame, after the page has
+ * drawn it, which also keeps WebGL canvases without preserveDrawingBuffer
+ * from reading back cleared.
+ *
+ * @param canvas Canvas being drawn to (2D or WebGL)
+ * @param options Frame rate, frame limit, and abort signal
+ */
+export async function* captureCanvasFrames(
+  canvas: HTMLCanvasElement,
+  options: CaptureOptions = {}
+): AsyncGenerator<CapturedFrame> {
+  const { fps = 15, signal, maxFrames = Infinity } = options;
+  const clock = new FrameClock(fps, now());
+  const Processor = (globalThis as { MediaStreamTrackProcessor?: TrackProcessorConstructor }).MediaStreamTrackProcessor;
+  const track = Processor && typeof canvas.captureStream === 'function'
+    ? canvas.captureStream(fps).getVideoTracks()[0]
+    : undefined;
+  const reader = track && Processor ? new Processor({ track }).readable.getReader() : undefined;
+  // Direct readback from a 2D canvas avoids a copy; other contexts go through a scratch canvas
+  const direct = reader ? null : canvas.getContext('2d', { willReadFrequently: true });
+  const scratch = direct ? undefined : scratchContext();
+  // A pending read only resolves when the canvas repaints, so aborting cancels it
+  const cancel = (): void => { void reader?.cancel(); };
+  signal?.addEventListener('abort', cancel, { once: true });
+
+  try {
+    for (let count = 0; count < maxFrames && !signal?.aborted; count++) {
+      const slot = clock.next(now());
+      await delay(slot.wait, signal);
+      if (!reader) {
+        await animationFrame(signal);
+      }
+      if (signal?.aborted) {
+        break;
+      }
+
+      let data: Uint8Array;
+      let width = canvas.width;
+      let height = canvas.height;
+      if (reader) {
+        const { value: frame, done } = await reader.read();
+        if (done || !frame) {
+          break;
+        }
+        try {
+          width = frame.displayWidth;
+          height = frame.displayHeight;
+          data = readPixels(frame as unknown as CanvasImageSource, width, height, scratch);
+        } finally {
+          frame.close();
+        }
+      } else if (direct) {
+        data = new Uint8Array(direct.getImageData(0, 0, width, height).data.buffer);
+      } else {
+        data = readPixels(canvas, width, height, scratch);
+      }
+
+      yield {
+        data,
+        left: 0,
+        top: 0,
+        width,
+        height,
+        delay: clock.interval,
+        disposal: 'none',
+        timestamp: slot.timestamp,
+        dropped: slot.dropped,
+      };
+    }
+  } finally {
+    signal?.removeEventListener('abort', cancel);
+    await reader?.cancel();
+    track?.stop();
+  }
+}
+
+/**
+ * Read VideoFrames from a stream, dropping stale frames under the 'latest' policy
+ */
+async function* pollFrames(
+  reader: ReadableStreamDefaultReader<VideoFrameLike>,
+  policy: FrameDropPolicy
+): AsyncGenerator<{ frame: VideoFrameLike; dropped: number }> {
+  if (policy === 'queue') {
+    for (let result = await reader.read(); !result.done; result = await reader.read()) {
+      yield { frame: result.value, dropped: 0 };
+    }
+    return;
+  }
+
+  // A background loop keeps only the newest frame while the consumer is busy
+  let latest: VideoFrameLike | undefined;
+  let dropped = 0;
+  let finished = false;
+  let failure: unknown;
+  let wake: (() => void) | undefined;
+  const pump = async (): Promise<void> => {
+    try {
+      for (let result = await reader.read(); !result.done; result = await reader.read()) {
+        if (latest) {
+          latest.close();
+          dropped++;
+        }
+        latest = result.value;
+        wake?.();
+      }
+    } catch (error) {
+      failure = error;
+    } finally {
+      finished = true;
+      wake?.();
+    }
+  };
+  void pump();
+
+  try {
+    for (;;) {
+      while (!latest && !finished) {
+        await new Promise<void>((resolve) => { wake = resolve; });
+      }
+      if (failure) {
+        throw failure;
+      }
+      if (!latest) {
+        return;
+      }
+      const frame = latest;
+      const skipped = dropped;
+      latest = undefined;
+      dropped = 0;
+      yield { frame, dropped: skipped };
+    }
+  } finally {
+    latest?.close();
+  }
+}
+
+/**
+ * Capture frames from a video track through MediaStreamTrackProcessor
+ * Under the default 'latest' policy frames arriving while the consumer is busy
+ * are closed and counted as dropped, so a slow encoder never builds a backlog;
+ * 'queue' delivers every frame the processor buffers.
+ *
+ * @param source Video track (e.g. from getUserMedia), or a stream of VideoFrames
+ * @param options Drop policy, frame limit, and abort signal
+ */
+export async function* captureVideoFrames(
+  source: MediaStreamTrack | ReadableStream<VideoFrameLike>,
+  options: TrackCaptureOptions = {}
+): AsyncGenerator<CapturedFrame> {
+  const { policy = 'latest', signal, maxFrames = Infinity } = options;
+  let frames: ReadableStream<VideoFrameLike>;
+  if (source instanceof ReadableStream) {
+    frames = source;
+  } else {
+    const Processor = (globalThis as { MediaStreamTrackProcessor?: TrackProcessorConstructor }).MediaStreamTrackProcessor;
+    if (!Processor) {
+      throw new Error('MediaStreamTrackProcessor is not available in this context');
+    }
+    frames = new Processor({ track: source }).readable;
+  }
+  const reader = frames.getReader();
+  const scratch = scratchContext();
+  signal?.addEventListener('abort', () => { void reader.cancel(); }, { once: true });
+
+  let first: number | undefined;
+  let count = 0;
+  try {
+    for await (const { frame, dropped } of pollFrames(reader, policy)) {
+      try {
+        first ??= frame.timestamp;
+        const width = frame.displayWidth;
+        const height = frame.displayHeight;
+        yield {
+          data: readPixels(frame as unknown as CanvasImageSource, width, height, scratch),
+          left: 0,
+          top: 0,
+          width,
+          height,
+          delay: (frame.duration ?? 0) / 1000,
+          disposal: 'none',
+          timestamp: (frame.timestamp - first) / 1000,
+          dropped,
+        };
+      } finally {
+        frame.close();
+      }
+      if (++count >= maxFrames || signal?.aborted) {
+        break;
+      }
+    }
+  } finally {
+    await reader.cancel();
+  }
+}
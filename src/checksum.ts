@@ -0,0 +1,214 @@
+/**
+ * Streaming checksums
+ * Hashes output as it is produced so an upload can carry an integrity header
+ * (S3 x-amz-checksum-crc32, Content-Digest, ...) without a second pass over a
+ * large file. Both hashes are incremental and need no platform crypto, since
+ * WebCrypto's digest only accepts a whole buffer.
+ */
+
+import type { OutputSink } from './pipeline.js';
+import { Base64Encoder } from './base64.js';
+
+export type ChecksumAlgorithm = 'crc32' | 'sha256';
+
+export interface Checksum {
+  update(chunk: Uint8Array): void;
+  digest(): Uint8Array;   // Of everything added so far; more data may follow
+}
+
+let crcTable: Uint32Array | undefined;
+
+/**
+ * CRC-32 as used by zlib, PNG, and S3 (reflected polynomial 0xEDB88320)
+ */
+export class Crc32 implements Checksum {
+  private crc = 0xffffffff;
+
+  update(chunk: Uint8Array): void {
+    const table = crcTable ??= Uint32Array.from({ length: 256 }, (_, n) => {
+      let c = n;
+      for (let k = 0; k < 8; k++) {
+        c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1;
+      }
+      return c;
+    });
+    let crc = this.crc;
+    for (let i = 0; i < chunk.length; i++) {
+      crc = table[(crc ^ chunk[i]) & 0xff] ^ (crc >>> 8);
+    }
+    this.crc = crc;
+  }
+
+  /**
+   * Big-endian, as S3 and PNG write it
+   */
+  digest(): Uint8Array {
+    const value = (this.crc ^ 0xffffffff) >>> 0;
+    return new Uint8Array([value >>> 24, (value >>> 16) & 0xff, (value >>> 8) & 0xff, value & 0xff]);
+  }
+}
+
+// FIPS 180-4 round constants and initial hash
+const K = Uint32Array.from([
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+]);
+const H0 = [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19];
+
+function rotr(x: number, n: number): number {
+  return (x >>> n) | (x << (32 - n));
+}
+
+/**
+ * SHA-256 over data added in pieces of any size
+ */
+export class Sha256 implements Checksum {
+  private readonly state = Uint32Array.from(H0);
+  private readonly block = new Uint8Array(64);
+  private readonly words = new Uint32Array(64);
+  private filled = 0;
+  private length = 0;
+
+  update(chunk: Uint8Array): void {
+    this.length += chunk.length;
+    let position = 0;
+    while (position < chunk.length) {
+      const take = Math.min(64 - this.filled, chunk.length - position);
+      this.block.set(chunk.subarray(position, position + take), this.filled);
+      this.filled += take;
+      position += take;
+      if (this.filled === 64) {
+        this.compress(this.block, this.state);
+        this.filled = 0;
+      }
+    }
+  }
+
+  private compress(block: Uint8Array, state: Uint32Array): void {
+    const w = this.words;
+    for (let i = 0; i < 16; i++) {
+      w[i] = (block[i * 4] << 24) | (block[i * 4 + 1] << 16) | (block[i * 4 + 2] << 8) | block[i * 4 + 3];
+    }
+    for (let i = 16; i < 64; i++) {
+      const s0 = rotr(w[i - 15], 7) ^ rotr(w[i - 15], 18) ^ (w[i - 15] >>> 3);
+      const s1 = rotr(w[i - 2], 17) ^ rotr(w[i - 2], 19) ^ (w[i - 2] >>> 10);
+      w[i] = w[i - 16] + s0 + w[i - 7] + s1;
+    }
+    let [a, b, c, d, e, f, g, h] = state;
+    for (let i = 0; i < 64; i++) {
+      const t1 = h + (rotr(e, 6) ^ rotr(e, 11) ^ rotr(e, 25)) + ((e & f) ^ (~e & g)) + K[i] + w[i];
+      const t2 = (rotr(a, 2) ^ rotr(a, 13) ^ rotr(a, 22)) + ((a & b) ^ (a & c) ^ (b & c));
+      h = g;
+      g = f;
+      f = e;
+      e = (d + t1) | 0;
+      d = c;
+      c = b;
+      b = a;
+      a = (t1 + t2) | 0;
+    }
+    [a, b, c, d, e, f, g, h].forEach((value, i) => { state[i] += value; });
+  }
+
+  digest(): Uint8Array {
+    // Pad a copy so more data can still be added afterwards
+    const state = this.state.slice();
+    const tail = new Uint8Array(this.filled < 56 ? 64 : 128);
+    tail.set(this.block.subarray(0, this.filled));
+    tail[this.filled] = 0x80;
+    const bits = this.length * 8;
+    const view = new DataView(tail.buffer);
+    view.setUint32(tail.length - 8, Math.floor(bits / 0x100000000));
+    view.setUint32(tail.length - 4, bits >>> 0);
+    for (let offset = 0; offset < tail.length; offset += 64) {
+      this.compress(tail.subarray(offset, offset + 64), state);
+    }
+    const out = new Uint8Array(32);
+    const outView = new DataView(out.buffer);
+    state.forEach((value, i) => outView.setUint32(i * 4, value));
+    return out;
+  }
+}
+
+/**
+ * Create a checksum by algorithm name
+ */
+export function createChecksum(algorithm: ChecksumAlgorithm): Checksum {
+  switch (algorithm) {
+    case 'crc32':
+      return new Crc32();
+    case 'sha256':
+      return new Sha256();
+    default:
+      throw new Error(`Unknown checksum algorithm: ${algorithm}`);
+  }
+}
+
+/**
+ * Lowercase hex of a digest
+ */
+export function toHex(digest: Uint8Array): string {
+  return Array.from(digest, (byte) => byte.toString(16).padStart(2, '0')).join('');
+}
+
+/**
+ * Base64 of a digest, as S3 checksum headers and Content-Digest expect
+ */
+export function toBase64(digest: Uint8Array): string {
+  const encoder = new Base64Encoder();
+  return encoder.push(digest) + encoder.flush();
+}
+
+/**
+ * Sink that hashes output on its way to another sink
+ *
+ * @example
+ * const sink = new ChecksumSink(new PartSink(partSize, uploadPart), 'crc32');
+ * await encodeToSink('photo.png', sink);
+ * await completeUpload({ ChecksumCRC32: toBase64(sink.digest()) });
+ */
+export class ChecksumSink implements OutputSink {
+  readonly algorithm: ChecksumAlgorithm;
+  private readonly inner?: OutputSink;
+  private readonly checksum: Checksum;
+  private bytes = 0;
+
+  /**
+   * @param inner Destination of the output; omit to only hash it
+   */
+  constructor(inner: OutputSink | undefined, algorithm: ChecksumAlgorithm = 'sha256') {
+    this.inner = inner;
+    this.algorithm = algorithm;
+    this.checksum = createChecksum(algorithm);
+  }
+
+  async write(chunk: Uint8Array): Promise<void> {
+    this.checksum.update(chunk);
+    this.bytes += chunk.length;
+    await this.inner?.write(chunk);
+  }
+
+  async close(): Promise<void> {
+    await this.inner?.close?.();
+  }
+
+  /**
+   * Bytes hashed so far
+   */
+  get length(): number {
+    return this.bytes;
+  }
+
+  /**
+   * Digest of the output so far; complete once the encode has finished
+   */
+  digest(): Uint8Array {
+    return this.checksum.digest();
+  }
+}
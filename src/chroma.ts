@@ -0,0 +1,93 @@
+/**
+ * Chroma content analysis
+ * Decides between 4:4:4 and 4:2:0 from the pixels as they stream past. 4:2:0
+ * averages chroma over 2x2 pixels, which smears sharp color edges such as red
+ * text on a screenshot but costs nothing visible on photographs, where chroma
+ * changes slowly. Each 2x2 block whose Cb or Cr spans a large part of the range
+ * counts as detail that subsampling would lose.
+ */
+
+import type { ChromaSubsampling } from './subsample.js';
+
+// Cb or Cr range within a 2x2 block that marks a sharp color edge
+const EDGE_RANGE = 64;
+
+/**
+ * Fraction of 2x2 blocks with sharp color edges above which 4:4:4 is kept
+ */
+export const CHROMA_DETAIL_THRESHOLD = 0.002;
+
+export interface ChromaAnalysis {
+  detail: number;                  // Fraction of 2x2 blocks with sharp color edges
+  subsampling: ChromaSubsampling;  // '4:4:4' at or above CHROMA_DETAIL_THRESHOLD, else '4:2:0'
+}
+
+/**
+ * Accumulates chroma detail over strips of RGBA rows
+ */
+export class ChromaAnalyzer {
+  private readonly width: number;
+  private pending?: Uint8Array;   // Unpaired last row of the previous strip
+  private blocks = 0;
+  private edges = 0;
+
+  constructor(width: number) {
+    this.width = width;
+  }
+
+  private addPair(top: Uint8Array, bottom: Uint8Array): void {
+    for (let x = 0; x < this.width; x += 2) {
+      const right = Math.min(x + 1, this.width - 1);
+      let minCb = Infinity;
+      let maxCb = -Infinity;
+      let minCr = Infinity;
+      let maxCr = -Infinity;
+      for (const row of [top, bottom]) {
+        for (const i of [x * 4, right * 4]) {
+          const cb = -0.1687 * row[i] - 0.3313 * row[i + 1] + 0.5 * row[i + 2];
+          const cr = 0.5 * row[i] - 0.4187 * row[i + 1] - 0.0813 * row[i + 2];
+          minCb = Math.min(minCb, cb);
+          maxCb = Math.max(maxCb, cb);
+          minCr = Math.min(minCr, cr);
+          maxCr = Math.max(maxCr, cr);
+        }
+      }
+      this.blocks++;
+      if (maxCb - minCb >= EDGE_RANGE || maxCr - minCr >= EDGE_RANGE) {
+        this.edges++;
+      }
+    }
+  }
+
+  /**
+   * Add the next strip of RGBA rows (strips must arrive top to bottom)
+   */
+  addStrip(strip: Uint8Array): void {
+    const rowBytes = this.width * 4;
+    const rows = Math.floor(strip.length / rowBytes);
+    let row = 0;
+    if (this.pending && rows > 0) {
+      this.addPair(this.pending, strip.subarray(0, rowBytes));
+      this.pending = undefined;
+      row = 1;
+    }
+    for (; row + 1 < rows; row += 2) {
+      this.addPair(strip.subarray(row * rowBytes, (row + 1) * rowBytes), strip.subarray((row + 1) * rowBytes, (row + 2) * rowBytes));
+    }
+    if (row < rows) {
+      this.pending = strip.slice(row * rowBytes, (row + 1) * rowBytes);
+    }
+  }
+
+  /**
+   * Measure the rows seen so far; an unpaired last row is paired with itself
+   */
+  finish(): ChromaAnalysis {
+    if (this.pending) {
+      this.addPair(this.pending, this.pending);
+      this.pending = undefined;
+    }
+    const detail = this.blocks > 0 ? this.edges / this.blocks : 0;
+    return { detail, subsampling: detail >= CHROMA_DETAIL_THRESHOLD ? '4:4:4' : '4:2:0' };
+  }
+}
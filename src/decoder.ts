@@ -0,0 +1,243 @@
+/**
+ * Reference decoder
+ * A straightforward (unoptimized) decoder used to check encoder output; it
+ * favours clarity over speed and supports sequential and progressive Huffman JPEGs.
+ */
+
+import { Marker, parseJpeg, ZIGZAG } from './jpeg.js';
+import type { JpegFile } from './jpeg.js';
+import { decodeProgressiveScan, frameGeometry, walkScan } from './entropy.js';
+
+/**
+ * Quantized coefficients of one component
+ * - blocks holds 64 zig-zag ordered values per block, row-major over the padded block grid
+ * - quantTable holds the matching zig-zag ordered quantizer values
+ */
+export interface ComponentCoefficients {
+  componentId: number;
+  blocksPerLine: number;
+  blocksPerColumn: number;
+  blocks: Int32Array;
+  quantTable: Uint16Array;
+}
+
+/**
+ * Decoded image as RGBA (alpha is always 255)
+ */
+export interface DecodedImage {
+  width: number;
+  height: number;
+  data: Uint8ClampedArray;
+}
+
+/**
+ * Entropy-decode every scan and collect the quantized coefficients per component
+ */
+export function readCoefficients(jpeg: Uint8Array | JpegFile): ComponentCoefficients[] {
+  const file = jpeg instanceof Uint8Array ? parseJpeg(jpeg) : jpeg;
+  const { frame } = file;
+  const geometry = frameGeometry(frame);
+
+  const result: ComponentCoefficients[] = frame.components.map((c, i) => ({
+    componentId: c.id,
+    blocksPerLine: geometry.components[i].paddedBlocksPerLine,
+    blocksPerColumn: geometry.components[i].paddedBlocksPerColumn,
+    blocks: new Int32Array(geometry.components[i].paddedBlocksPerLine * geometry.components[i].paddedBlocksPerColumn * 64),
+    quantTable: new Uint16Array(64),
+  }));
+
+  for (const scan of file.scans) {
+    for (const sc of scan.header.components) {
+      const index = frame.components.findIndex((c) => c.id === sc.id);
+      const table = index >= 0 ? scan.quantTables.get(frame.components[index].quantTable) : undefined;
+      if (table) {
+        result[index].quantTable.set(table.values);
+      }
+    }
+    if (frame.marker === Marker.SOF2) {
+      decodeProgressiveScan(file, scan, result);
+      continue;
+    }
+    walkScan(file, scan, {
+      onBlock(componentIndex, blockRow, blockCol, coefficients) {
+        const target = result[componentIndex];
+        target.blocks.set(coefficients, (blockRow * target.blocksPerLine + blockCol) * 64);
+      },
+    });
+  }
+
+  return result;
+}
+
+// cos((2x + 1) * u * pi / 16) scaled by C(u) / 2; orthonormal, so it serves both directions
+export const DCT_BASIS = (() => {
+  const basis = new Float64Array(64);
+  for (let u = 0; u < 8; u++) {
+    const scale = u === 0 ? Math.SQRT1_2 / 2 : 0.5;
+    for (let x = 0; x < 8; x++) {
+      basis[u * 8 + x] = scale * Math.cos(((2 * x + 1) * u * Math.PI) / 16);
+    }
+  }
+  return basis;
+})();
+
+/**
+ * Dequantize one zig-zag block and transform it to samples (level shift undone)
+ */
+export function inverseDct(coefficients: Int32Array, offset: number, quant: Uint16Array, out: Float64Array): void {
+  const natural = new Float64Array(64);
+  for (let k = 0; k < 64; k++) {
+    natural[ZIGZAG[k]] = coefficients[offset + k] * quant[k];
+  }
+  // Rows then columns
+  const temp = new Float64Array(64);
+  for (let v = 0; v < 8; v++) {
+    for (let x = 0; x < 8; x++) {
+      let sum = 0;
+      for (let u = 0; u < 8; u++) {
+        sum += natural[v * 8 + u] * DCT_BASIS[u * 8 + x];
+      }
+      temp[v * 8 + x] = sum;
+    }
+  }
+  for (let x = 0; x < 8; x++) {
+    for (let y = 0; y < 8; y++) {
+      let sum = 0;
+      for (let v = 0; v < 8; v++) {
+        sum += temp[v * 8 + x] * DCT_BASIS[v * 8 + y];
+      }
+      out[y * 8 + x] = sum + 128;
+    }
+  }
+}
+
+interface Plane {
+  plane: Float64Array;
+  planeWidth: number;
+}
+
+/**
+ * Upsample component planes by replication and convert them to RGBA
+ */
+function planesToRgba(file: JpegFile, planes: Plane[], width: number, height: number): Uint8ClampedArray {
+  const { frame } = file;
+  const geometry = frameGeometry(frame);
+  const data = new Uint8ClampedArray(width * height * 4);
+  const sample = (index: number, x: number, y: number): number => {
+    const c = frame.components[index];
+    const { plane, planeWidth } = planes[index];
+    const sx = Math.floor((x * c.h) / geometry.maxH);
+    const sy = Math.floor((y * c.v) / geometry.maxV);
+    return plane[sy * planeWidth + sx];
+  };
+
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const offset = (y * width + x) * 4;
+      const luma = sample(0, x, y);
+      if (planes.length === 1) {
+        data[offset] = data[offset + 1] = data[offset + 2] = Math.round(luma);
+      } else {
+        const cb = sample(1, x, y) - 128;
+        const cr = sample(2, x, y) - 128;
+        data[offset] = Math.round(luma + 1.402 * cr);
+        data[offset + 1] = Math.round(luma - 0.344136 * cb - 0.714136 * cr);
+        data[offset + 2] = Math.round(luma + 1.772 * cb);
+      }
+      data[offset + 3] = 255;
+    }
+  }
+  return data;
+}
+
+/**
+ * Decode a baseline or progressive JPEG to RGBA
+ * Grayscale and YCbCr images are supported; chroma is upsampled by replication.
+ *
+ * @param jpeg Encoded JPEG bytes
+ */
+export function decode(jpeg: Uint8Array): DecodedImage {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  const { width, height } = frame;
+  if (frame.components.length !== 1 && frame.components.length !== 3) {
+    throw new Error(`Unsupported component count: ${frame.components.length}`);
+  }
+  const components = readCoefficients(file);
+
+  // Reconstruct each component plane at its own resolution
+  const planes = components.map((component) => {
+    const planeWidth = component.blocksPerLine * 8;
+    const plane = new Float64Array(planeWidth * component.blocksPerColumn * 8);
+    const block = new Float64Array(64);
+    for (let row = 0; row < component.blocksPerColumn; row++) {
+      for (let col = 0; col < component.blocksPerLine; col++) {
+        inverseDct(component.blocks, (row * component.blocksPerLine + col) * 64, component.quantTable, block);
+        for (let y = 0; y < 8; y++) {
+          plane.set(block.subarray(y * 8, y * 8 + 8), (row * 8 + y) * planeWidth + col * 8);
+        }
+      }
+    }
+    return { plane, planeWidth };
+  });
+
+  return { width, height, data: planesToRgba(file, planes, width, height) };
+}
+
+/**
+ * Decode a JPEG at 1/8 scale from its DC coefficients alone
+ * Each 8x8 block becomes one pixel, so no inverse DCT is needed. Sequential
+ * files keep only one value per block; progressive files are decoded in full first.
+ *
+ * @param jpeg Encoded JPEG bytes
+ */
+export function decodeDc(jpeg: Uint8Array): DecodedImage {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if (frame.components.length !== 1 && frame.components.length !== 3) {
+    throw new Error(`Unsupported component count: ${frame.components.length}`);
+  }
+  const geometry = frameGeometry(frame);
+  const width = Math.ceil(frame.width / 8);
+  const height = Math.ceil(frame.height / 8);
+
+  const planes: Plane[] = geometry.components.map((c) => ({
+    plane: new Float64Array(c.paddedBlocksPerLine * c.paddedBlocksPerColumn),
+    planeWidth: c.paddedBlocksPerLine,
+  }));
+  const quantizers = new Float64Array(frame.components.length);
+
+  if (frame.marker === Marker.SOF2) {
+    readCoefficients(file).forEach((component, index) => {
+      quantizers[index] = component.quantTable[0];
+      for (let block = 0; block < planes[index].plane.length; block++) {
+        planes[index].plane[block] = component.blocks[block * 64];
+      }
+    });
+  } else {
+    for (const scan of file.scans) {
+      for (const sc of scan.header.components) {
+        const index = frame.components.findIndex((c) => c.id === sc.id);
+        const table = index >= 0 ? scan.quantTables.get(frame.components[index].quantTable) : undefined;
+        if (table) {
+          quantizers[index] = table.values[0];
+        }
+      }
+      walkScan(file, scan, {
+        onBlock(componentIndex, blockRow, blockCol, coefficients) {
+          const { plane, planeWidth } = planes[componentIndex];
+          plane[blockRow * planeWidth + blockCol] = coefficients[0];
+        },
+      });
+    }
+  }
+
+  // The DC term is 8 times the block mean of the level-shifted samples
+  planes.forEach(({ plane }, index) => {
+    for (let i = 0; i < plane.length; i++) {
+      plane[i] = (plane[i] * quantizers[index]) / 8 + 128;
+    }
+  });
+
+  return { width, height, data: planesToRgba(file, planes, width, height) };
+}
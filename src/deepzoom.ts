@@ -0,0 +1,184 @@
+/**
+ * Deep Zoom tile pyramids
+ * Cuts a streamed image into fixed-size JPEG tiles at every zoom level of a
+ * Deep Zoom (DZI) pyramid. Each level keeps only the band of rows covering its
+ * current tile row, and each smaller level is halved from the one above as rows
+ * arrive, so gigapixel sources never need to be resident.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { createJpegStripEncoder, openStripSource } from './encoder.js';
+import { RowDownscaler } from './downscale.js';
+import { CollectingSink } from './pipeline.js';
+
+export interface DeepZoomOptions extends Pick<EncodeOptions, 'width' | 'height' | 'quality'> {
+  tileSize?: number;  // Tile edge without overlap, defaults to 254
+  overlap?: number;   // Pixels shared with each neighbouring tile, defaults to 1
+  onTile?(tile: DeepZoomTile): Promise<void> | void; // Receives tiles as they complete; otherwise they are collected
+}
+
+export interface DeepZoomTile {
+  level: number;
+  column: number;
+  row: number;
+  path: string;       // Relative to the `<name>_files` directory, e.g. "12/3_5.jpg"
+  width: number;
+  height: number;
+  jpeg: Uint8Array;
+}
+
+export interface DeepZoomResult {
+  manifest: string;      // The .dzi XML descriptor
+  maxLevel: number;      // Level holding the full-size image; level 0 is 1x1
+  tiles: DeepZoomTile[]; // Empty when `onTile` was given
+}
+
+interface Level {
+  level: number;
+  width: number;
+  height: number;
+  scaler?: RowDownscaler;
+  band: Uint8Array[];    // Rows from bandStart upwards
+  bandStart: number;
+  received: number;
+  tileRow: number;
+  child?: Level;
+}
+
+function encodeTile(pixels: Uint8Array, width: number, height: number, quality?: number): Uint8Array {
+  const encoder = createJpegStripEncoder(width, height, quality);
+  const sink = new CollectingSink();
+  const rowBytes = width * 4;
+  for (let y = 0; y < height; y += encoder.stripHeight) {
+    sink.write(encoder.encodeStrip(pixels.subarray(y * rowBytes, Math.min(y + encoder.stripHeight, height) * rowBytes)));
+  }
+  sink.write(encoder.finish());
+  return sink.toBytes();
+}
+
+/**
+ * Build the DZI descriptor for an image
+ */
+export function deepZoomManifest(width: number, height: number, tileSize = 254, overlap = 1): string {
+  return '<?xml version="1.0" encoding="UTF-8"?>\n' +
+    `<Image xmlns="http://schemas.microsoft.com/deepzoom/2008" Format="jpg" Overlap="${overlap}" TileSize="${tileSize}">\n` +
+    `  <Size Width="${width}" Height="${height}"/>\n` +
+    '</Image>\n';
+}
+
+/**
+ * Encode an image as a Deep Zoom tile pyramid
+ *
+ * @example
+ * const { manifest } = await encodeDeepZoom('scan.png', {
+ *   onTile: (tile) => writeFile(`scan_files/${tile.path}`, tile.jpeg),
+ * });
+ * await writeFile('scan.dzi', manifest);
+ */
+export async function encodeDeepZoom(source: ImageSource, options: DeepZoomOptions = {}): Promise<DeepZoomResult> {
+  const { tileSize = 254, overlap = 1, quality } = options;
+  if (!Number.isInteger(tileSize) || tileSize < 1 || !Number.isInteger(overlap) || overlap < 0 || overlap >= tileSize) {
+    throw new Error(`Invalid tiling: tile size ${tileSize}, overlap ${overlap}`);
+  }
+  const strips = await openStripSource(source, options);
+  const { width, height } = strips;
+  const maxLevel = Math.ceil(Math.log2(Math.max(width, height)));
+
+  // Full size first; each level halves the one above, rounding up
+  let top: Level | undefined;
+  let above: Level | undefined;
+  for (let level = maxLevel; level >= 0; level--) {
+    const scale = 2 ** (maxLevel - level);
+    const current: Level = {
+      level,
+      width: Math.ceil(width / scale),
+      height: Math.ceil(height / scale),
+      band: [],
+      bandStart: 0,
+      received: 0,
+      tileRow: 0,
+    };
+    if (above) {
+      current.scaler = new RowDownscaler(above.width, above.height, current.width, current.height);
+      above.child = current;
+    }
+    top ??= current;
+    above = current;
+  }
+
+  const tiles: DeepZoomTile[] = [];
+  const pending: DeepZoomTile[] = [];
+
+  const cutTileRow = (level: Level): void => {
+    const { tileRow: row } = level;
+    const y0 = Math.max(0, row * tileSize - overlap);
+    const y1 = Math.min(level.height, (row + 1) * tileSize + overlap);
+    const columns = Math.ceil(level.width / tileSize);
+    for (let column = 0; column < columns; column++) {
+      const x0 = Math.max(0, column * tileSize - overlap);
+      const x1 = Math.min(level.width, (column + 1) * tileSize + overlap);
+      const tileWidth = x1 - x0;
+      const pixels = new Uint8Array(tileWidth * (y1 - y0) * 4);
+      for (let y = y0; y < y1; y++) {
+        pixels.set(level.band[y - level.bandStart].subarray(x0 * 4, x1 * 4), (y - y0) * tileWidth * 4);
+      }
+      pending.push({
+        level: level.level,
+        column,
+        row,
+        path: `${level.level}/${column}_${row}.jpg`,
+        width: tileWidth,
+        height: y1 - y0,
+        jpeg: encodeTile(pixels, tileWidth, y1 - y0, quality),
+      });
+    }
+    // Keep only the rows the next tile row overlaps
+    level.tileRow++;
+    const keepFrom = Math.max(0, level.tileRow * tileSize - overlap);
+    level.band.splice(0, keepFrom - level.bandStart);
+    level.bandStart = keepFrom;
+  };
+
+  const pushRow = (level: Level, row: Uint8Array): void => {
+    const accept = (output: Uint8Array): void => {
+      level.band.push(output.slice());
+      level.received++;
+      const rowEnd = Math.min(level.height, (level.tileRow + 1) * tileSize + overlap);
+      if (level.received === rowEnd) {
+        cutTileRow(level);
+      }
+      if (level.child) {
+        pushRow(level.child, output);
+      }
+    };
+    if (level.scaler) {
+      level.scaler.push(row, accept);
+    } else {
+      accept(row);
+    }
+  };
+
+  const flush = async (): Promise<void> => {
+    for (const tile of pending.splice(0)) {
+      if (options.onTile) {
+        await options.onTile(tile);
+      } else {
+        tiles.push(tile);
+      }
+    }
+  };
+
+  const rowBytes = width * 4;
+  let y = 0;
+  for (let strip = await strips.readStrip(8); strip && strip.length > 0; strip = await strips.readStrip(8)) {
+    for (let offset = 0; offset + rowBytes <= strip.length; offset += rowBytes, y++) {
+      pushRow(top as Level, strip.subarray(offset, offset + rowBytes));
+    }
+    await flush();
+  }
+  if (y < height) {
+    throw new Error(`Source ended after ${y} of ${height} rows`);
+  }
+
+  return { manifest: deepZoomManifest(width, height, tileSize, overlap), maxLevel, tiles };
+}
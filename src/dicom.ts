@@ -0,0 +1,76 @@
+/**
+ * DICOM baseline profile
+ * Rewrites an encoded stream into the minimal form expected for DICOM
+ * Encapsulated Pixel Data (JPEG Baseline, transfer syntax 1.2.840.10008.1.2.4.50):
+ * 8-bit SOF0, no application segments, tables in a fixed order.
+ */
+
+import { Marker, parseJpeg } from './jpeg.js';
+import type { Segment } from './jpeg.js';
+
+export type DicomRestartPolicy = 'any' | 'required' | 'forbidden';
+
+export interface DicomOptions {
+  keepApplicationSegments?: boolean; // Keep APPn segments (JFIF, EXIF, ...), defaults to false
+  keepComments?: boolean;            // Keep COM segments, defaults to false
+  restart?: DicomRestartPolicy;      // Whether restart markers must or must not be present, defaults to 'any'
+}
+
+/**
+ * Rewrite a baseline JPEG stream for DICOM encapsulation
+ * Output order is SOI, [APPn], [COM], DQT, SOF0, DHT, [DRI], SOS + scan data, EOI.
+ * Entropy-coded data is copied unchanged; anything after EOI is dropped.
+ *
+ * @param jpeg Encoded baseline JPEG
+ * @param options Which optional segments to keep and the restart marker policy
+ */
+export function toDicomBaseline(jpeg: Uint8Array, options: DicomOptions = {}): Uint8Array {
+  const { keepApplicationSegments = false, keepComments = false, restart = 'any' } = options;
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+
+  if (frame.marker !== Marker.SOF0 || frame.precision !== 8) {
+    throw new Error(`DICOM baseline requires an 8-bit SOF0 frame, found marker 0x${frame.marker.toString(16)} with ${frame.precision}-bit samples`);
+  }
+  if (frame.components.length !== 1 && frame.components.length !== 3) {
+    throw new Error(`DICOM baseline supports 1 or 3 components, found ${frame.components.length}`);
+  }
+  if (file.scans.length !== 1) {
+    throw new Error(`DICOM baseline requires a single scan, found ${file.scans.length}`);
+  }
+  const [scan] = file.scans;
+  if (restart === 'required' && scan.restartInterval === 0) {
+    throw new Error('DICOM profile requires restart markers but the stream has no restart interval');
+  }
+  if (restart === 'forbidden' && scan.restartInterval !== 0) {
+    throw new Error(`DICOM profile forbids restart markers but the stream uses an interval of ${scan.restartInterval}`);
+  }
+
+  const sos = file.segments.find((s) => s.marker === Marker.SOS) as Segment;
+  const header = file.segments.filter((s) => s.offset < sos.offset);
+  const pick = (test: (marker: number) => boolean): Segment[] => header.filter((s) => test(s.marker));
+  const ordered = [
+    ...(keepApplicationSegments ? pick((m) => m >= Marker.APP0 && m <= Marker.APP15) : []),
+    ...(keepComments ? pick((m) => m === Marker.COM) : []),
+    ...pick((m) => m === Marker.DQT),
+    ...pick((m) => m === Marker.SOF0),
+    ...pick((m) => m === Marker.DHT),
+    ...(scan.restartInterval !== 0 ? pick((m) => m === Marker.DRI).slice(-1) : []),
+  ];
+
+  const scanBytes = jpeg.subarray(sos.offset, scan.dataEnd);
+  const total = 2 + ordered.reduce((sum, s) => sum + s.length, 0) + scanBytes.length + 2;
+  const output = new Uint8Array(total);
+  output[0] = 0xff;
+  output[1] = Marker.SOI;
+  let offset = 2;
+  for (const segment of ordered) {
+    output.set(jpeg.subarray(segment.offset, segment.offset + segment.length), offset);
+    offset += segment.length;
+  }
+  output.set(scanBytes, offset);
+  offset += scanBytes.length;
+  output[offset] = 0xff;
+  output[offset + 1] = Marker.EOI;
+  return output;
+}
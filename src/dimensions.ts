@@ -0,0 +1,44 @@
+/**
+ * Image dimension limits
+ * SOF stores width and height as 16-bit values, so a single JPEG cannot exceed
+ * 65,535 pixels on either side. Larger images have to be split into tiles, and
+ * empty ones cannot be encoded at all.
+ */
+
+import { EncodeError } from './errors.js';
+
+export const MAX_JPEG_DIMENSION = 65535;
+
+/**
+ * Thrown when an image cannot be stored in one JPEG frame
+ */
+export class DimensionError extends EncodeError {
+  readonly width: number;
+  readonly height: number;
+
+  constructor(message: string, width: number, height: number) {
+    super('bad-dimensions', message);
+    this.name = 'DimensionError';
+    this.width = width;
+    this.height = height;
+  }
+}
+
+/**
+ * Throw a DimensionError unless the size fits in a JPEG frame
+ * One-pixel rows and columns are fine; zero, negative, or fractional sizes are not.
+ *
+ * @param maxDimension Largest side allowed; sources that may be tiled pass Infinity
+ */
+export function checkDimensions(width: number, height: number, maxDimension = MAX_JPEG_DIMENSION): void {
+  if (!Number.isInteger(width) || !Number.isInteger(height) || width < 1 || height < 1) {
+    throw new DimensionError(`Image dimensions must be positive integers, got ${width}x${height}`, width, height);
+  }
+  if (width > maxDimension || height > maxDimension) {
+    throw new DimensionError(
+      `JPEG images are limited to ${MAX_JPEG_DIMENSION}x${MAX_JPEG_DIMENSION} pixels, got ${width}x${height}; use encodeTiled to split larger images`,
+      width,
+      height
+    );
+  }
+}
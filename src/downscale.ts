@@ -0,0 +1,71 @@
+/**
+ * Streaming downscaling
+ * Box-filters RGBA rows to a smaller size as they arrive, holding only the
+ * output row being accumulated.
+ */
+
+/**
+ * Streaming box filter from one row size to another
+ * Source rows are pushed top to bottom; each output row is emitted as soon as
+ * the last source row contributing to it has arrived.
+ */
+export class RowDownscaler {
+  private readonly sourceWidth: number;
+  private readonly sourceHeight: number;
+  private readonly width: number;
+  private readonly height: number;
+  private readonly columns: Uint32Array;
+  private readonly sums: Float64Array;
+  private readonly counts: Uint32Array;
+  private readonly row: Uint8Array;
+  private sourceRow = 0;
+  private targetRow = 0;
+
+  constructor(sourceWidth: number, sourceHeight: number, width: number, height: number) {
+    if (width > sourceWidth || height > sourceHeight) {
+      throw new Error(`Cannot downscale ${sourceWidth}x${sourceHeight} to ${width}x${height}`);
+    }
+    this.sourceWidth = sourceWidth;
+    this.sourceHeight = sourceHeight;
+    this.width = width;
+    this.height = height;
+    this.columns = Uint32Array.from({ length: sourceWidth }, (_, x) => Math.floor((x * width) / sourceWidth));
+    this.sums = new Float64Array(width * 4);
+    this.counts = new Uint32Array(width);
+    this.row = new Uint8Array(width * 4);
+  }
+
+  /**
+   * Add one source row of RGBA pixels
+   * @param emit Receives each completed output row; the array is reused between calls
+   */
+  push(row: Uint8Array, emit: (row: Uint8Array) => void): void {
+    const target = Math.floor((this.sourceRow * this.height) / this.sourceHeight);
+    if (target !== this.targetRow) {
+      this.flush(emit);
+      this.targetRow = target;
+    }
+    for (let x = 0; x < this.sourceWidth; x++) {
+      const tx = this.columns[x];
+      for (let c = 0; c < 4; c++) {
+        this.sums[tx * 4 + c] += row[x * 4 + c];
+      }
+      this.counts[tx]++;
+    }
+    if (++this.sourceRow === this.sourceHeight) {
+      this.flush(emit);
+    }
+  }
+
+  private flush(emit: (row: Uint8Array) => void): void {
+    for (let x = 0; x < this.width; x++) {
+      const count = this.counts[x] || 1;
+      for (let c = 0; c < 4; c++) {
+        this.row[x * 4 + c] = Math.round(this.sums[x * 4 + c] / count);
+      }
+    }
+    this.sums.fill(0);
+    this.counts.fill(0);
+    emit(this.row);
+  }
+}
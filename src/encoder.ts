@@ -0,0 +1,593 @@
+/**
+ * Encoder core
+ * Normalizes image sources and drives the WASM encoder strip by strip.
+ */
+
+// @ts-ignore - WASM package may not have perfect types
+import { StreamingJpegEncoder, WasmColorType } from 'jpeg-encoder-wasm/pkg/jpeg_encoder.js';
+import type { EncodeOptions, EncodeResult, ImageSource, SegmentedEncodeOptions } from './types.js';
+import { ChromaAnalyzer } from './chroma.js';
+import { AlphaCollector } from './alpha.js';
+import { applyFrameLayout, validateFrameLayout } from './layout.js';
+import { limitMcuBits } from './budget.js';
+import { addRecoveryPoints } from './recovery.js';
+import { qualityQuantTables } from './scaling.js';
+import { stitchRestartSegments } from './restart.js';
+import { addStripIndex } from './stripindex.js';
+import { insertSegments } from './markers.js';
+import { toSpiff } from './spiff.js';
+import { toDicomBaseline } from './dicom.js';
+import { toProgressive } from './progressive.js';
+import { createLibjpegStripEncoder, libjpegQuantTables } from './libjpeg.js';
+import { checkDimensions } from './dimensions.js';
+import { EncodeError } from './errors.js';
+import { toSubsampled } from './subsample.js';
+import { requantize, requantizeRows } from './requantize.js';
+import { optimizeHuffman, toGrayscale } from './transcode.js';
+import { applyPreset } from './presets.js';
+import { stripMetadata } from './metadata.js';
+import { CollectingSink, bufferSource, concatBytes, iterableSource, readAllStrips, runPipeline } from './pipeline.js';
+import type { StripEncoder, StripSource } from './pipeline.js';
+import { BYTES_PER_PIXEL } from './rawfile.js';
+import type { RawPixelFormat } from './rawfile.js';
+import { decodedSource } from './inputs.js';
+import { imageBitmapSource } from './bitmap.js';
+import { MAX_RGB_THUMBNAIL_SIZE, ThumbnailBuilder, jfxxJpegSegment, jfxxRgbSegment } from './thumbnail.js';
+
+/**
+ * Runtime environment detection
+ */
+export const runtime = {
+  hasNodeFS: typeof process !== 'undefined' &&
+             process.versions != null &&
+             process.versions.node != null,
+  hasBuffer: typeof Buffer !== 'undefined',
+  hasBlob: typeof Blob !== 'undefined',
+  hasCanvas: typeof HTMLCanvasElement !== 'undefined',
+  hasImageData: typeof ImageData !== 'undefined',
+  hasImageBitmap: typeof ImageBitmap !== 'undefined',
+};
+
+/**
+ * The source as an iterable of byte chunks, unless it is a buffer, path, or Node.js stream
+ */
+function chunkIterable(source: ImageSource): AsyncIterable<Uint8Array> | Iterable<Uint8Array> | undefined {
+  if (source instanceof Uint8Array || typeof source === 'string' || typeof (source as any).read === 'function') {
+    return undefined;
+  }
+  return Symbol.asyncIterator in source || Symbol.iterator in source
+    ? source as AsyncIterable<Uint8Array> | Iterable<Uint8Array>
+    : undefined;
+}
+
+/**
+ * Convert any supported image source to a Uint8Array buffer
+ */
+export async function sourceToBuffer(source: ImageSource): Promise<{
+  buffer: Uint8Array;
+  width?: number;
+  height?: number;
+}> {
+  // Handle HTMLCanvasElement (browser/jsdom)
+  if (runtime.hasCanvas && source instanceof HTMLCanvasElement) {
+    const ctx = source.getContext('2d');
+    if (!ctx) {
+      throw new Error('Failed to get canvas 2D context');
+    }
+    const imageData = ctx.getImageData(0, 0, source.width, source.height);
+    return {
+      buffer: new Uint8Array(imageData.data),
+      width: source.width,
+      height: source.height,
+    };
+  }
+
+  // Handle ImageBitmap (browser)
+  if (runtime.hasImageBitmap && source instanceof ImageBitmap) {
+    return {
+      buffer: await readAllStrips(imageBitmapSource(source)),
+      width: source.width,
+      height: source.height,
+    };
+  }
+
+  // Handle ImageData (browser/jsdom)
+  if (runtime.hasImageData && source instanceof ImageData) {
+    return {
+      buffer: new Uint8Array(source.data),
+      width: source.width,
+      height: source.height,
+    };
+  }
+
+  // Handle file path (Node.js only)
+  if (typeof source === 'string') {
+    if (!runtime.hasNodeFS) {
+      throw new Error('File path sources are only supported in Node.js environments');
+    }
+    // Dynamic import to avoid bundling fs in browser builds
+    const { readFile } = await import('fs/promises');
+    const buffer = await readFile(source);
+    return { buffer: new Uint8Array(buffer) };
+  }
+
+  // Handle Node.js Buffer
+  if (runtime.hasBuffer && Buffer.isBuffer(source)) {
+    return { buffer: new Uint8Array(source) };
+  }
+
+  // Handle Uint8Array
+  if (source instanceof Uint8Array) {
+    return { buffer: source };
+  }
+
+  // Handle Node.js Readable stream
+  if (runtime.hasNodeFS && source && typeof (source as any).read === 'function') {
+    // Dynamic import to avoid bundling stream in browser builds
+    const { Readable } = await import('stream');
+    if (source instanceof Readable) {
+      const chunks: Buffer[] = [];
+      for await (const chunk of source) {
+        chunks.push(chunk);
+      }
+      return { buffer: new Uint8Array(Buffer.concat(chunks)) };
+    }
+  }
+
+  // Handle iterables of chunks, collected whole here; openStripSource pulls raw RGBA incrementally
+  const iterable = chunkIterable(source);
+  if (iterable) {
+    const chunks: Uint8Array[] = [];
+    for await (const chunk of iterable) {
+      chunks.push(chunk);
+    }
+    return { buffer: concatBytes(chunks) };
+  }
+
+  throw new Error('Unsupported image source type');
+}
+
+/**
+ * Strip encoder backed by the WASM JPEG engine
+ * Arguments and strips are checked here, since the engine traps on input it
+ * cannot use; a trap that happens anyway is reported as an `engine-failure`
+ * EncodeError, and the encoder refuses further calls.
+ */
+export function createJpegStripEncoder(width: number, height: number, quality = 100): StripEncoder {
+  checkDimensions(width, height);
+  if (!Number.isFinite(quality) || quality < 1 || quality > 100) {
+    throw new EncodeError('bad-quality', `Quality must be 1-100, got ${quality}`);
+  }
+  const rowBytes = width * 4;
+  let y = 0;
+  let failure: EncodeError | undefined;
+  function guarded<T>(call: () => T): T {
+    if (failure) {
+      throw failure;
+    }
+    try {
+      return call();
+    } catch (error) {
+      if (error instanceof WebAssembly.RuntimeError) {
+        failure = new EncodeError('engine-failure', `The JPEG engine failed (${error.message}); this encoder cannot be used again`);
+        throw failure;
+      }
+      throw error;
+    }
+  }
+
+  const encoder = guarded(() => new StreamingJpegEncoder(width, height, WasmColorType.Rgba, quality));
+  return {
+    stripHeight: 8,
+    encodeStrip: (strip: Uint8Array): Uint8Array => {
+      const rows = strip.length / rowBytes;
+      if (!Number.isInteger(rows) || rows < 1 || y + rows > height) {
+        throw new EncodeError(
+          'size-mismatch',
+          `A strip of ${strip.length} bytes is not a whole number of ${rowBytes}-byte rows within the ${height - y} rows left`
+        );
+      }
+      const output = guarded(() => encoder.encode_strip(strip));
+      y += rows;
+      return output;
+    },
+    finish: (): Uint8Array => {
+      if (y < height) {
+        throw new EncodeError('size-mismatch', `Finished after ${y} of ${height} rows`);
+      }
+      return guarded(() => encoder.finish());
+    },
+  };
+}
+
+/**
+ * Open a strip source for any supported image source
+ * Bitmaps and image files are read incrementally; raw buffers are checked
+ * against the requested dimensions.
+ */
+export async function openStripSource(
+  source: ImageSource,
+  options: Pick<EncodeOptions, 'width' | 'height' | 'pixelFormat' | 'stride'> = {}
+): Promise<StripSource> {
+  // Raw RGBA chunks of known dimensions are pulled as strips are read; image files are collected first
+  const iterable = chunkIterable(source);
+  const packedRgba = options.pixelFormat === undefined && options.stride === undefined;
+  if (iterable && !packedRgba) {
+    throw new Error('Streamed and iterable input is tightly packed RGBA; pixelFormat and stride apply to buffers');
+  }
+  if (iterable && options.width !== undefined && options.height !== undefined) {
+    checkDimensions(options.width, options.height, Infinity);
+    return iterableSource(iterable, options.width, options.height);
+  }
+
+  // Bitmaps are read back from a canvas strip by strip instead of copied out whole
+  const bitmap = runtime.hasImageBitmap && source instanceof ImageBitmap ? imageBitmapSource(source) : undefined;
+
+  // Convert source to buffer and extract dimensions if available
+  const { buffer: imageData, width: detectedWidth, height: detectedHeight } =
+    bitmap ? { buffer: new Uint8Array(0), width: bitmap.width, height: bitmap.height } : await sourceToBuffer(source);
+
+  if (bitmap && !packedRgba) {
+    throw new Error('Bitmaps carry their own pixel layout; pixelFormat and stride apply to raw buffers');
+  }
+
+  // Image files (PNG, QOI, PNM) are decoded row by row as the encoder consumes strips; a pixel format or
+  // stride marks the buffer as raw
+  const decoded = bitmap ??
+    (packedRgba ? decodedSource(imageData, options.width !== undefined && options.height !== undefined) : undefined);
+
+  // Determine final dimensions
+  const width = decoded ? decoded.width : options.width ?? detectedWidth;
+  const height = decoded ? decoded.height : options.height ?? detectedHeight;
+
+  if (width === undefined || height === undefined) {
+    throw new Error('Width and height are required (provide in options or use Canvas/ImageData source)');
+  }
+  // Oversized sources are fine here (encodeTiled splits them); encoders enforce the JPEG limit
+  checkDimensions(width, height, Infinity);
+  if (decoded) {
+    return decoded;
+  }
+
+  const { format, stride } = checkRawBuffer(imageData, width, height, options);
+  return bufferSource(imageData, width, height, format, stride);
+}
+
+/**
+ * Check that a raw buffer holds every row of its pixel format and stride
+ */
+function checkRawBuffer(
+  data: Uint8Array,
+  width: number,
+  height: number,
+  options: Pick<EncodeOptions, 'pixelFormat' | 'stride'>
+): { format: RawPixelFormat; stride: number } {
+  const { pixelFormat: format = 'rgba' } = options;
+  const bytesPerPixel = BYTES_PER_PIXEL[format];
+  if (!bytesPerPixel) {
+    throw new Error(`Unsupported pixel format: ${format}`);
+  }
+  const stride = options.stride ?? width * bytesPerPixel;
+  if (!Number.isInteger(stride) || stride < width * bytesPerPixel) {
+    throw new Error(`A stride of ${stride} bytes is shorter than a row of ${width} ${format} pixels`);
+  }
+  // The last row needs no padding after it
+  const expectedSize = stride * (height - 1) + width * bytesPerPixel;
+  if (data.length < expectedSize) {
+    throw new Error(
+      `Buffer too small: expected at least ${expectedSize} bytes for ${width}x${height} ${format.toUpperCase()} image, got ${data.length}`
+    );
+  }
+  return { format, stride };
+}
+
+/**
+ * Encode an image source to JPEG bytes
+ * Environment-independent core of `encode`; always resolves to a Uint8Array.
+ */
+export async function encodeToBytes(
+  source: ImageSource,
+  options: EncodeOptions = {}
+): Promise<Uint8Array> {
+  return encodeStripsToBytes(await openStripSource(source, options), options);
+}
+
+/**
+ * Encode an already opened strip source to JPEG bytes
+ * Dimensions come from the source; width and height in `options` are ignored.
+ */
+export async function encodeStripsToBytes(
+  strips: StripSource,
+  encodeOptions: EncodeOptions = {}
+): Promise<Uint8Array> {
+  const preset = applyPreset(encodeOptions);
+  if (encodeOptions.layout && encodeOptions.subsampling) {
+    throw new Error('Sampling factors come from the frame layout; leave subsampling unset');
+  }
+  if (encodeOptions.maxMcuBits !== undefined && (encodeOptions.optimizeHuffman || encodeOptions.progressive)) {
+    throw new Error('A per-MCU bit budget is counted against the standard Huffman tables; optimized tables and progressive scans are not available');
+  }
+  if (encodeOptions.recoveryInterval !== undefined && encodeOptions.progressive) {
+    throw new Error('Recovery points are restart markers in a sequential scan; progressive output cannot carry them');
+  }
+  if (encodeOptions.grayscale && (encodeOptions.subsampling || encodeOptions.layout)) {
+    throw new Error('Grayscale output has a single luma component; leave subsampling and layout unset');
+  }
+  // A layout's sampling factors or grayscale output replace the preset's subsampling, a bit budget its
+  // table and scan settings, and recovery points its scan settings
+  let options = preset.layout || preset.grayscale ? { ...preset, subsampling: undefined } : preset;
+  if (options.maxMcuBits !== undefined) {
+    options = { ...options, optimizeHuffman: undefined, progressive: undefined };
+  }
+  if (options.recoveryInterval !== undefined) {
+    options = { ...options, progressive: undefined };
+  }
+  if (options.quantTables && options.qualityScaling !== undefined) {
+    throw new Error('Custom quantization tables replace quality scaling; leave qualityScaling unset');
+  }
+  // Other curves become explicit tables, which both encoders already accept
+  if (options.qualityScaling !== undefined && options.qualityScaling !== 'libjpeg') {
+    const [luma, chroma] = qualityQuantTables(options.quality ?? 100, options.qualityScaling);
+    options = { ...options, quantTables: { luma, chroma } };
+  }
+  const { quality = 100, header = 'jfif' } = options;
+  if (header !== 'jfif' && options.thumbnail) {
+    throw new Error('JFXX thumbnails require a JFIF header');
+  }
+  if (options.stripMetadata && options.thumbnail) {
+    throw new Error('An embedded thumbnail is metadata and would be stripped; set stripMetadata to false to keep it');
+  }
+  if (header === 'dicom' && options.progressive) {
+    throw new Error('The DICOM baseline profile cannot be progressive');
+  }
+  const libjpeg = options.compat === 'libjpeg';
+  if (libjpeg && (options.dither || options.deband)) {
+    throw new Error('libjpeg-compatible output cannot be dithered or debanded');
+  }
+  if (!libjpeg && (options.transform || options.quantizer)) {
+    throw new Error('Custom transform and quantizer stages run in the TypeScript encoder; set compat to \'libjpeg\'');
+  }
+  if (libjpeg && options.maxMcuBits !== undefined) {
+    throw new Error('libjpeg-compatible output keeps every coefficient; a per-MCU bit budget is not available');
+  }
+  if (libjpeg && options.recoveryInterval !== undefined) {
+    throw new Error('libjpeg-compatible output is written without restart markers; recovery points are not available');
+  }
+  if (libjpeg && options.stripQuality) {
+    throw new Error('libjpeg-compatible output uses one quality throughout; per-strip quality is not available');
+  }
+  if (options.layout) {
+    if (libjpeg) {
+      throw new Error('libjpeg-compatible output uses libjpeg\'s own frame layout; a custom layout is not available');
+    }
+    validateFrameLayout(options.layout);
+  }
+  if (libjpeg && options.subsampling === 'auto') {
+    throw new Error('libjpeg-compatible output needs the subsampling before the first strip; automatic selection is not available');
+  }
+
+  const { width, height } = strips;
+  const chroma = options.subsampling === 'auto' && !options.grayscale ? new ChromaAnalyzer(width) : undefined;
+
+  // Downscale strips for the thumbnail as they pass through
+  const thumbnailFormat = options.thumbnail?.format ?? 'rgb';
+  let thumbnailSize = options.thumbnail?.maxSize ?? 80;
+  if (thumbnailFormat === 'rgb') {
+    thumbnailSize = Math.min(thumbnailSize, MAX_RGB_THUMBNAIL_SIZE);
+  }
+  const thumbnail = options.thumbnail ? new ThumbnailBuilder(width, height, thumbnailSize) : undefined;
+
+  // Collect the alpha channel, which JPEG would drop, for a companion mask
+  const alphaFormat = options.alpha?.format ?? 'jpeg';
+  const alpha = options.alpha
+    ? new AlphaCollector(width, height, alphaFormat,
+      alphaFormat === 'jpeg' ? createJpegStripEncoder(width, height, options.alpha.quality ?? quality) : undefined)
+    : undefined;
+
+  // Process in 8-scanline strips (one iMCU row for libjpeg compatibility), collecting output chunks
+  const sink = new CollectingSink();
+  await runPipeline(
+    strips,
+    libjpeg
+      ? createLibjpegStripEncoder(width, height, {
+        quality,
+        subsampling: options.subsampling === 'auto' ? undefined : options.subsampling,
+        grayscale: options.grayscale,
+        quantTables: options.quantTables,
+        optimizeCoding: options.optimizeHuffman,
+        transform: options.transform,
+        quantizer: options.quantizer,
+      })
+      : createJpegStripEncoder(width, height, options.quantTables || options.dither || options.deband ? 100 : quality),
+    sink,
+    {
+      onStrip: (strip) => {
+        thumbnail?.addStrip(strip);
+        chroma?.addStrip(strip);
+        alpha?.addStrip(strip);
+      },
+    }
+  );
+  let jpegBuffer = sink.toBytes();
+
+  if (thumbnail) {
+    const segment = thumbnailFormat === 'jpeg'
+      ? jfxxJpegSegment(await encodeToBytes(thumbnail.toRgba(), { width: thumbnail.width, height: thumbnail.height, quality }))
+      : jfxxRgbSegment(thumbnail.width, thumbnail.height, thumbnail.toRgb());
+    jpegBuffer = insertSegments(jpegBuffer, [segment]);
+  }
+
+  const analysis = chroma?.finish();
+  const requested = options.subsampling === 'auto' ? analysis?.subsampling : options.subsampling;
+  const subsampling = requested ?? (libjpeg ? '4:2:0' : '4:4:4');
+  const output = reshapeEncoded(jpegBuffer, { ...options, subsampling });
+  if (alpha) {
+    await options.alpha!.onAlpha(alpha.finish());
+  }
+  const written = options.layout ? validateFrameLayout(options.layout) : subsampling;
+  options.onStats?.({ width, height, quality, subsampling: written, chromaDetail: analysis?.detail, bytes: output.length });
+  return output;
+}
+
+/**
+ * Apply the options that rewrite encoded output: custom tables, grayscale or chroma
+ * subsampling, Huffman optimization, progressive scans, metadata, and header
+ * Expects the encoder's output for `options` (quality 100 when tables are replaced).
+ */
+export function reshapeEncoded(jpeg: Uint8Array, options: EncodeOptions): Uint8Array {
+  const { header = 'jfif' } = options;
+  const libjpeg = options.compat === 'libjpeg';
+  let jpegBuffer = jpeg;
+
+  // Dithering and debanding quantize here rather than in the engine, from coefficients encoded at quality 100
+  if (!libjpeg && (options.quantTables || options.dither || options.deband)) {
+    const [luma, chroma] = libjpegQuantTables(options.quality ?? 100);
+    jpegBuffer = requantize(jpegBuffer, options.quantTables ?? { luma, chroma }, { dither: options.dither, deband: options.deband });
+  }
+
+  // Only the luma coefficients are kept, with Huffman tables built for them
+  const subsampling = options.subsampling ?? '4:4:4';
+  if (!libjpeg && options.grayscale) {
+    jpegBuffer = toGrayscale(jpegBuffer);
+  } else if (!libjpeg && subsampling !== '4:4:4') {
+    jpegBuffer = toSubsampled(jpegBuffer, subsampling);
+  }
+
+  if (!libjpeg && options.stripQuality) {
+    jpegBuffer = requantizeRows(jpegBuffer, options.stripQuality, options.qualityScaling);
+  }
+
+  if (!libjpeg && options.layout) {
+    jpegBuffer = applyFrameLayout(jpegBuffer, options.layout);
+  }
+
+  // Before the bit budget, which keeps the restart interval and counts the predictor resets
+  if (!libjpeg && options.recoveryInterval !== undefined) {
+    jpegBuffer = addRecoveryPoints(jpegBuffer, options.recoveryInterval).jpeg;
+  }
+
+  if (!libjpeg && options.maxMcuBits !== undefined) {
+    jpegBuffer = limitMcuBits(jpegBuffer, options.maxMcuBits).jpeg;
+  }
+
+  // Re-coding and progressive output already build their tables from the image
+  if (!libjpeg && options.optimizeHuffman && !options.quantTables && !options.dither && !options.deband && !options.stripQuality && !options.layout && !options.grayscale && options.recoveryInterval === undefined && subsampling === '4:4:4' && !options.progressive) {
+    jpegBuffer = optimizeHuffman(jpegBuffer);
+  }
+
+  if (options.progressive) {
+    jpegBuffer = toProgressive(jpegBuffer, options.progressive === true ? {} : options.progressive);
+  }
+
+  if (options.stripMetadata) {
+    jpegBuffer = stripMetadata(jpegBuffer);
+  }
+
+  if (header === 'spiff') {
+    return toSpiff(jpegBuffer);
+  }
+
+  if (header === 'dicom') {
+    return toDicomBaseline(jpegBuffer);
+  }
+
+  return jpegBuffer;
+}
+
+/**
+ * Encode an image source as independent restart segments and stitch them
+ * Each horizontal band is encoded by its own encoder instance, so bands can be
+ * produced concurrently; the result is a single JPEG with restart markers.
+ */
+export async function encodeSegmentedToBytes(
+  source: ImageSource,
+  options: SegmentedEncodeOptions = {}
+): Promise<Uint8Array> {
+  const { segmentHeight = 64 } = options;
+  if (!Number.isInteger(segmentHeight) || segmentHeight <= 0 || segmentHeight % 8 !== 0) {
+    throw new Error(`Segment height must be a positive multiple of 8, got ${segmentHeight}`);
+  }
+  if (options.progressive) {
+    throw new Error('Restart segments cannot be kept in progressive output; use encode instead');
+  }
+  if (options.subsampling && options.subsampling !== '4:4:4') {
+    throw new Error('Restart segments cannot be kept when chroma is subsampled; use encode instead');
+  }
+  if (options.compat) {
+    throw new Error('libjpeg-compatible output is a single scan without restart segments; use encode instead');
+  }
+  if (options.quantTables || options.dither || options.deband || (options.qualityScaling && options.qualityScaling !== 'libjpeg')) {
+    throw new Error('Restart segments cannot be kept when tables are replaced, rescaled, dithered, or debanded; use encode instead');
+  }
+  if (options.preset || options.optimizeHuffman) {
+    throw new Error('Presets and Huffman optimization are not available for restart segments; use encode instead');
+  }
+  if (options.stripQuality) {
+    throw new Error('Per-strip quality re-codes the whole scan, so restart segments cannot be kept; use encode instead');
+  }
+  if (options.alpha) {
+    throw new Error('Alpha masks are not produced for restart segments; use encode instead');
+  }
+  if (options.layout) {
+    throw new Error('Restart segments keep the engine\'s frame layout; use encode instead');
+  }
+  if (options.grayscale) {
+    throw new Error('Grayscale output re-codes the whole scan, so restart segments cannot be kept; use encode instead');
+  }
+  if (options.maxMcuBits !== undefined) {
+    throw new Error('A per-MCU bit budget re-codes the whole scan, so restart segments cannot be kept; use encode instead');
+  }
+  if (options.recoveryInterval !== undefined) {
+    throw new Error('Restart segments already end at recovery points; use encode to place them every few MCU rows');
+  }
+  if (options.stripIndex && options.header && options.header !== 'jfif') {
+    throw new Error('A strip index is embedded in JFIF output only');
+  }
+
+  const { buffer, width: detectedWidth, height: detectedHeight } = await sourceToBuffer(source);
+  const packedRgba = options.pixelFormat === undefined && options.stride === undefined;
+  const decoded = packedRgba ? decodedSource(buffer, options.width !== undefined && options.height !== undefined) : undefined;
+  const imageData = decoded ? await readAllStrips(decoded) : buffer;
+  const width = decoded ? decoded.width : options.width ?? detectedWidth;
+  const height = decoded ? decoded.height : options.height ?? detectedHeight;
+
+  if (width === undefined || height === undefined) {
+    throw new Error('Width and height are required (provide in options or use Canvas/ImageData source)');
+  }
+  checkDimensions(width, height);
+
+  // Bands keep the source layout and are converted as each is encoded
+  const { format: pixelFormat, stride } = decoded
+    ? { format: 'rgba' as const, stride: width * 4 }
+    : checkRawBuffer(imageData, width, height, options);
+  const bands: Promise<Uint8Array>[] = [];
+  for (let y = 0; y < height; y += segmentHeight) {
+    const bandHeight = Math.min(segmentHeight, height - y);
+    const band = imageData.subarray(y * stride, (y + bandHeight) * stride);
+    bands.push(encodeToBytes(band, { width, height: bandHeight, quality: options.quality, pixelFormat, stride }));
+  }
+
+  const stitched = stitchRestartSegments(await Promise.all(bands));
+  if (options.stripIndex) {
+    return addStripIndex(stitched).jpeg;
+  }
+  if (options.header === 'dicom') {
+    return toDicomBaseline(stitched, { restart: 'required' });
+  }
+  return options.header === 'spiff' ? toSpiff(stitched) : stitched;
+}
+
+/**
+ * Wrap encoded bytes in the type best suited to the current environment
+ */
+export function toEncodeResult(jpegBuffer: Uint8Array): EncodeResult {
+  // Return appropriate type based on environment
+  // Prioritize Buffer in Node.js, even if Blob is available (e.g., via polyfill)
+  if (runtime.hasBuffer && runtime.hasNodeFS) {
+    return Buffer.from(jpegBuffer);
+  } else if (runtime.hasBlob) {
+    return new Blob([jpegBuffer], { type: 'image/jpeg' });
+  } else {
+    return jpegBuffer;
+  }
+}
@@ -0,0 +1,551 @@
+/**
+ * Entropy-coded segment decoding
+ * Walks the Huffman-coded blocks of a baseline scan so analysis helpers can
+ * observe symbols and quantized coefficients without a full pixel decode.
+ */
+
+import type { FrameHeader, HuffmanSpec, JpegFile, Scan } from './jpeg.js';
+import { JpegFormatError, Marker } from './jpeg.js';
+
+/**
+ * Canonical Huffman decoding table (ITU T.81 F.2.2.3)
+ */
+export interface HuffmanDecoder {
+  minCode: Int32Array;
+  maxCode: Int32Array;
+  valPtr: Int32Array;
+  symbols: Uint8Array;
+}
+
+export function buildHuffmanDecoder(spec: HuffmanSpec): HuffmanDecoder {
+  const minCode = new Int32Array(17);
+  const maxCode = new Int32Array(17).fill(-1);
+  const valPtr = new Int32Array(17);
+  let code = 0;
+  let k = 0;
+  for (let length = 1; length <= 16; length++) {
+    const count = spec.counts[length - 1];
+    valPtr[length] = k;
+    minCode[length] = code;
+    code += count;
+    k += count;
+    if (count > 0) {
+      maxCode[length] = code - 1;
+    }
+    code <<= 1;
+  }
+  return { minCode, maxCode, valPtr, symbols: spec.symbols };
+}
+
+/**
+ * Bit reader over entropy-coded data
+ * Removes byte stuffing and stops (feeding zero bits) at the first marker.
+ */
+export class BitReader {
+  private bytes: Uint8Array;
+  private end: number;
+  private bitBuffer = 0;
+  private bitCount = 0;
+  private zeroBits = 0;
+  position: number;
+  markerHit = false;
+  paddedBytes = 0;
+
+  constructor(bytes: Uint8Array, offset: number, end: number) {
+    this.bytes = bytes;
+    this.position = offset;
+    this.end = end;
+  }
+
+  private fill(): void {
+    while (this.bitCount <= 24) {
+      let byte = 0;
+      let real = false;
+      if (!this.markerHit && this.position < this.end) {
+        byte = this.bytes[this.position];
+        if (byte === 0xff) {
+          const next = this.position + 1 < this.bytes.length ? this.bytes[this.position + 1] : -1;
+          if (next === 0x00) {
+            this.position += 2;
+            real = true;
+          } else {
+            this.markerHit = true;
+            byte = 0;
+          }
+        } else {
+          this.position++;
+          real = true;
+        }
+      }
+      if (!real) {
+        this.zeroBits += 8;
+      }
+      this.bitBuffer = ((this.bitBuffer << 8) | byte) >>> 0;
+      this.bitCount += 8;
+    }
+  }
+
+  readBit(): number {
+    return this.readBits(1);
+  }
+
+  readBits(count: number): number {
+    if (count === 0) {
+      return 0;
+    }
+    if (this.bitCount < count) {
+      this.fill();
+    }
+    this.bitCount -= count;
+    if (this.zeroBits > this.bitCount) {
+      // Consumed bits that were synthesised past the end of the data
+      this.paddedBytes = Math.ceil((this.zeroBits - this.bitCount) / 8);
+    }
+    return (this.bitBuffer >>> this.bitCount) & ((1 << count) - 1);
+  }
+
+  /**
+   * Read `size` magnitude bits and sign-extend them (ITU T.81 F.2.2.1 EXTEND)
+   */
+  receiveExtend(size: number): number {
+    if (size === 0) {
+      return 0;
+    }
+    const value = this.readBits(size);
+    return value < (1 << (size - 1)) ? value - (1 << size) + 1 : value;
+  }
+
+  decodeSymbol(decoder: HuffmanDecoder): number {
+    let code = this.readBit();
+    let length = 1;
+    while (length <= 16 && code > decoder.maxCode[length]) {
+      code = (code << 1) | this.readBit();
+      length++;
+    }
+    if (length > 16) {
+      throw new JpegFormatError('Invalid Huffman code', this.position);
+    }
+    return decoder.symbols[decoder.valPtr[length] + code - decoder.minCode[length]];
+  }
+
+  /**
+   * Number of bits still buffered that came from real entropy-coded bytes
+   */
+  get pendingBits(): number {
+    return Math.max(0, this.bitCount - this.zeroBits);
+  }
+
+  /**
+   * Inspect the bits left after the last decoded symbol of an interval
+   * Whole bytes are extraneous data; the partial byte should be padded with 1-bits.
+   */
+  trailing(): { extraneousBytes: number; paddingValid: boolean } {
+    const pending = this.pendingBits;
+    const partial = pending % 8;
+    const padding = partial === 0 ? 0 : (this.bitBuffer >>> (this.bitCount - partial)) & ((1 << partial) - 1);
+    let extraneousBytes = Math.floor(pending / 8);
+    if (!this.markerHit) {
+      let pos = this.position;
+      while (pos < this.end && !(this.bytes[pos] === 0xff && this.bytes[pos + 1] !== 0x00)) {
+        pos += this.bytes[pos] === 0xff ? 2 : 1;
+        extraneousBytes++;
+      }
+    }
+    return { extraneousBytes, paddingValid: padding === (1 << partial) - 1 };
+  }
+
+  /**
+   * Discard buffered bits and consume the restart marker expected at the current position
+   * Returns the marker code found, or -1 if the next bytes are not a marker.
+   */
+  restart(): number {
+    this.bitBuffer = 0;
+    this.bitCount = 0;
+    this.zeroBits = 0;
+    this.markerHit = false;
+    let pos = this.position;
+    while (pos + 1 < this.bytes.length && this.bytes[pos] === 0xff && this.bytes[pos + 1] === 0xff) {
+      pos++;
+    }
+    if (pos + 1 >= this.bytes.length || this.bytes[pos] !== 0xff) {
+      return -1;
+    }
+    const marker = this.bytes[pos + 1];
+    this.position = pos + 2;
+    return marker;
+  }
+}
+
+/**
+ * Block layout derived from a frame header
+ * - blocksPerLine/blocksPerColumn cover the component's own (unpadded) extent
+ * - paddedBlocksPerLine/Column cover the full MCU grid used by interleaved scans
+ */
+export interface ComponentGeometry {
+  blocksPerLine: number;
+  blocksPerColumn: number;
+  paddedBlocksPerLine: number;
+  paddedBlocksPerColumn: number;
+}
+
+export interface FrameGeometry {
+  maxH: number;
+  maxV: number;
+  mcusPerLine: number;
+  mcusPerColumn: number;
+  components: ComponentGeometry[];
+}
+
+export function frameGeometry(frame: FrameHeader): FrameGeometry {
+  const maxH = Math.max(...frame.components.map((c) => c.h));
+  const maxV = Math.max(...frame.components.map((c) => c.v));
+  const mcusPerLine = Math.ceil(frame.width / (8 * maxH));
+  const mcusPerColumn = Math.ceil(frame.height / (8 * maxV));
+  return {
+    maxH,
+    maxV,
+    mcusPerLine,
+    mcusPerColumn,
+    components: frame.components.map((c) => ({
+      blocksPerLine: Math.ceil(Math.ceil((frame.width * c.h) / maxH) / 8),
+      blocksPerColumn: Math.ceil(Math.ceil((frame.height * c.v) / maxV) / 8),
+      paddedBlocksPerLine: mcusPerLine * c.h,
+      paddedBlocksPerColumn: mcusPerColumn * c.v,
+    })),
+  };
+}
+
+/**
+ * Callbacks invoked while walking a scan
+ * - componentIndex refers to the component's position in the frame header
+ * - coefficients are quantized values in zig-zag order, DC already un-predicted;
+ *   the array is reused between blocks, so copy it if it must be retained
+ */
+export interface ScanVisitor {
+  onSymbol?(componentIndex: number, tableClass: number, symbol: number): void;
+  onBlock?(componentIndex: number, blockRow: number, blockCol: number, coefficients: Int32Array): void;
+  onRestart?(mcu: number, marker: number, offset: number): void;
+}
+
+/**
+ * Summary of a walked scan
+ * - paddedBytes: bytes the decoder had to invent because the data ended early
+ * - extraneousBytes: data left over after the last MCU of an interval
+ * - invalidPadding: intervals whose final byte was not padded with 1-bits
+ */
+export interface ScanResult {
+  mcuCount: number;
+  restartCount: number;
+  endOffset: number;
+  paddedBytes: number;
+  extraneousBytes: number;
+  invalidPadding: number;
+}
+
+/**
+ * Decode every block of a sequential Huffman scan, reporting to the visitor
+ */
+export function walkScan(file: JpegFile, scan: Scan, visitor: ScanVisitor = {}): ScanResult {
+  const { frame } = file;
+  const { header } = scan;
+  if (header.spectralStart !== 0 || header.spectralEnd !== 63 || header.approxHigh !== 0 || header.approxLow !== 0) {
+    throw new JpegFormatError('Progressive scans are not supported', scan.dataOffset);
+  }
+
+  const geometry = frameGeometry(frame);
+  const components = header.components.map((sc) => {
+    const index = frame.components.findIndex((c) => c.id === sc.id);
+    if (index < 0) {
+      throw new JpegFormatError(`Scan references unknown component ${sc.id}`, scan.dataOffset);
+    }
+    const dcSpec = scan.dcTables.get(sc.dcTable);
+    const acSpec = scan.acTables.get(sc.acTable);
+    if (!dcSpec || !acSpec) {
+      throw new JpegFormatError(`Missing Huffman table for component ${sc.id}`, scan.dataOffset);
+    }
+    return {
+      index,
+      h: frame.components[index].h,
+      v: frame.components[index].v,
+      dc: buildHuffmanDecoder(dcSpec),
+      ac: buildHuffmanDecoder(acSpec),
+    };
+  });
+
+  const reader = new BitReader(file.bytes, scan.dataOffset, scan.dataEnd);
+  const predictors = new Int32Array(components.length);
+  const coefficients = new Int32Array(64);
+
+  const decodeBlock = (slot: number, blockRow: number, blockCol: number): void => {
+    const component = components[slot];
+    coefficients.fill(0);
+
+    const category = reader.decodeSymbol(component.dc);
+    visitor.onSymbol?.(component.index, 0, category);
+    predictors[slot] += reader.receiveExtend(category);
+    coefficients[0] = predictors[slot];
+
+    let k = 1;
+    while (k < 64) {
+      const symbol = reader.decodeSymbol(component.ac);
+      visitor.onSymbol?.(component.index, 1, symbol);
+      const run = symbol >> 4;
+      const size = symbol & 0x0f;
+      if (size === 0) {
+        if (run !== 15) {
+          break;
+        }
+        k += 16;
+        continue;
+      }
+      k += run;
+      if (k > 63) {
+        throw new JpegFormatError(
+          `AC coefficient index out of range in component ${frame.components[component.index].id}`,
+          reader.position
+        );
+      }
+      coefficients[k] = reader.receiveExtend(size);
+      k++;
+    }
+    if (k > 64) {
+      throw new JpegFormatError(
+        `Zero run overflows block in component ${frame.components[component.index].id}`,
+        reader.position
+      );
+    }
+    visitor.onBlock?.(component.index, blockRow, blockCol, coefficients);
+  };
+
+  const single = components.length === 1;
+  const singleGeometry = geometry.components[components[0].index];
+  const mcuCount = single
+    ? singleGeometry.blocksPerLine * singleGeometry.blocksPerColumn
+    : geometry.mcusPerLine * geometry.mcusPerColumn;
+
+  let restartCount = 0;
+  let extraneousBytes = 0;
+  let invalidPadding = 0;
+  const checkTrailing = (): void => {
+    const trailing = reader.trailing();
+    extraneousBytes += trailing.extraneousBytes;
+    if (!trailing.paddingValid) {
+      invalidPadding++;
+    }
+  };
+
+  for (let mcu = 0; mcu < mcuCount; mcu++) {
+    if (scan.restartInterval > 0 && mcu > 0 && mcu % scan.restartInterval === 0) {
+      checkTrailing();
+      const offset = reader.position;
+      const marker = reader.restart();
+      const expected = Marker.RST0 + (restartCount % 8);
+      if (marker !== expected) {
+        throw new JpegFormatError(
+          `Expected RST${restartCount % 8} before MCU ${mcu}, found ${marker < 0 ? 'data' : `marker 0x${marker.toString(16)}`}`,
+          offset
+        );
+      }
+      predictors.fill(0);
+      visitor.onRestart?.(mcu, marker, offset);
+      restartCount++;
+    }
+
+    if (single) {
+      decodeBlock(0, Math.floor(mcu / singleGeometry.blocksPerLine), mcu % singleGeometry.blocksPerLine);
+      continue;
+    }
+
+    const mcuRow = Math.floor(mcu / geometry.mcusPerLine);
+    const mcuCol = mcu % geometry.mcusPerLine;
+    for (let slot = 0; slot < components.length; slot++) {
+      const { h, v } = components[slot];
+      for (let y = 0; y < v; y++) {
+        for (let x = 0; x < h; x++) {
+          decodeBlock(slot, mcuRow * v + y, mcuCol * h + x);
+        }
+      }
+    }
+  }
+
+  checkTrailing();
+  return {
+    mcuCount,
+    restartCount,
+    endOffset: reader.position,
+    paddedBytes: reader.paddedBytes,
+    extraneousBytes,
+    invalidPadding,
+  };
+}
+
+/**
+ * Coefficient storage for one component: zig-zag ordered blocks, row-major
+ */
+export interface CoefficientTarget {
+  blocks: Int32Array;
+  blocksPerLine: number;
+}
+
+/**
+ * Decode a progressive scan (ITU T.81 G.2), accumulating into the targets
+ * DC and AC scans, first passes and successive-approximation refinements are
+ * all supported; coefficients are left scaled by the point transform.
+ *
+ * @param targets Storage per frame component, indexed like the frame header
+ */
+export function decodeProgressiveScan(file: JpegFile, scan: Scan, targets: CoefficientTarget[]): void {
+  const { frame } = file;
+  const { spectralStart: ss, spectralEnd: se, approxHigh: ah, approxLow: al } = scan.header;
+  if (ss > se || se > 63 || (ss === 0 && se !== 0) || (ss > 0 && scan.header.components.length !== 1)) {
+    throw new JpegFormatError(`Invalid progressive scan parameters Ss=${ss} Se=${se}`, scan.dataOffset);
+  }
+
+  const geometry = frameGeometry(frame);
+  const components = scan.header.components.map((sc) => {
+    const index = frame.components.findIndex((c) => c.id === sc.id);
+    if (index < 0) {
+      throw new JpegFormatError(`Scan references unknown component ${sc.id}`, scan.dataOffset);
+    }
+    const dcSpec = ss === 0 && ah === 0 ? scan.dcTables.get(sc.dcTable) : undefined;
+    const acSpec = ss > 0 ? scan.acTables.get(sc.acTable) : undefined;
+    if ((ss === 0 && ah === 0 && !dcSpec) || (ss > 0 && !acSpec)) {
+      throw new JpegFormatError(`Missing Huffman table for component ${sc.id}`, scan.dataOffset);
+    }
+    return {
+      index,
+      h: frame.components[index].h,
+      v: frame.components[index].v,
+      dc: dcSpec ? buildHuffmanDecoder(dcSpec) : undefined,
+      ac: acSpec ? buildHuffmanDecoder(acSpec) : undefined,
+    };
+  });
+
+  const reader = new BitReader(file.bytes, scan.dataOffset, scan.dataEnd);
+  const predictors = new Int32Array(components.length);
+  const p1 = 1 << al;
+  const m1 = -1 << al;
+  let eobrun = 0;
+
+  const refine = (blocks: Int32Array, z: number): void => {
+    if (reader.readBit() && (blocks[z] & p1) === 0) {
+      blocks[z] += blocks[z] >= 0 ? p1 : m1;
+    }
+  };
+
+  const decodeBlock = (slot: number, blockRow: number, blockCol: number): void => {
+    const component = components[slot];
+    const { blocks, blocksPerLine } = targets[component.index];
+    const base = (blockRow * blocksPerLine + blockCol) * 64;
+
+    if (ss === 0) {
+      if (ah === 0) {
+        predictors[slot] += reader.receiveExtend(reader.decodeSymbol(component.dc as HuffmanDecoder));
+        blocks[base] = predictors[slot] * p1;
+      } else if (reader.readBit()) {
+        blocks[base] |= p1;
+      }
+      return;
+    }
+
+    const ac = component.ac as HuffmanDecoder;
+    if (ah === 0) {
+      if (eobrun > 0) {
+        eobrun--;
+        return;
+      }
+      for (let k = ss; k <= se;) {
+        const symbol = reader.decodeSymbol(ac);
+        const run = symbol >> 4;
+        const size = symbol & 0x0f;
+        if (size === 0) {
+          if (run < 15) {
+            eobrun = (1 << run) - 1 + reader.readBits(run);
+            break;
+          }
+          k += 16;
+          continue;
+        }
+        k += run;
+        if (k > se) {
+          throw new JpegFormatError('AC coefficient index out of range', reader.position);
+        }
+        blocks[base + k] = reader.receiveExtend(size) * p1;
+        k++;
+      }
+      return;
+    }
+
+    // Successive approximation refinement of AC coefficients (G.1.2.3)
+    let k = ss;
+    if (eobrun === 0) {
+      for (; k <= se; k++) {
+        const symbol = reader.decodeSymbol(ac);
+        let run = symbol >> 4;
+        const size = symbol & 0x0f;
+        let value = 0;
+        if (size !== 0) {
+          value = reader.readBit() ? p1 : m1;
+        } else if (run !== 15) {
+          eobrun = (1 << run) + reader.readBits(run);
+          break;
+        }
+        while (k <= se) {
+          const z = base + k;
+          if (blocks[z] !== 0) {
+            refine(blocks, z);
+          } else if (--run < 0) {
+            break;
+          }
+          k++;
+        }
+        if (value !== 0 && k <= se) {
+          blocks[base + k] = value;
+        }
+      }
+    }
+    if (eobrun > 0) {
+      for (; k <= se; k++) {
+        if (blocks[base + k] !== 0) {
+          refine(blocks, base + k);
+        }
+      }
+      eobrun--;
+    }
+  };
+
+  const single = components.length === 1;
+  const singleGeometry = geometry.components[components[0].index];
+  const mcuCount = single
+    ? singleGeometry.blocksPerLine * singleGeometry.blocksPerColumn
+    : geometry.mcusPerLine * geometry.mcusPerColumn;
+
+  let restartCount = 0;
+  for (let mcu = 0; mcu < mcuCount; mcu++) {
+    if (scan.restartInterval > 0 && mcu > 0 && mcu % scan.restartInterval === 0) {
+      const offset = reader.position;
+      const marker = reader.restart();
+      if (marker !== Marker.RST0 + (restartCount % 8)) {
+        throw new JpegFormatError(`Expected RST${restartCount % 8} before MCU ${mcu}`, offset);
+      }
+      predictors.fill(0);
+      eobrun = 0;
+      restartCount++;
+    }
+
+    if (single) {
+      decodeBlock(0, Math.floor(mcu / singleGeometry.blocksPerLine), mcu % singleGeometry.blocksPerLine);
+      continue;
+    }
+    const mcuRow = Math.floor(mcu / geometry.mcusPerLine);
+    const mcuCol = mcu % geometry.mcusPerLine;
+    for (let slot = 0; slot < components.length; slot++) {
+      const { h, v } = components[slot];
+      for (let y = 0; y < v; y++) {
+        for (let x = 0; x < h; x++) {
+          decodeBlock(slot, mcuRow * v + y, mcuCol * h + x);
+        }
+      }
+    }
+  }
+}
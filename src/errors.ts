@@ -0,0 +1,44 @@
+/**
+ * Encoder errors
+ * Bad input is reported as an EncodeError with a code, checked before the
+ * engine sees it: a trap inside WebAssembly cannot be caught and leaves the
+ * instance unusable, while an error thrown here leaves everything intact, so
+ * a caller can tell a bad argument from a fault and carry on.
+ */
+
+/**
+ * - bad-dimensions: the size does not fit a JPEG frame (thrown as a DimensionError)
+ * - bad-quality: quality outside 1-100
+ * - bad-table: a quantization table of the wrong length or with entries out of range
+ * - zero-quant-entry: a quantization table entry of 0, which would divide by zero
+ * - size-mismatch: a strip whose byte length does not match the image's rows
+ * - engine-failure: the engine trapped anyway; its encoder cannot be used again
+ */
+export type EncodeErrorCode =
+  | 'bad-dimensions'
+  | 'bad-quality'
+  | 'bad-table'
+  | 'zero-quant-entry'
+  | 'size-mismatch'
+  | 'engine-failure';
+
+/**
+ * Thrown for input the encoder cannot use
+ *
+ * @example
+ * try {
+ *   await encode(pixels, { width, height, quality });
+ * } catch (error) {
+ *   if (error instanceof EncodeError && error.code === 'bad-quality') showQualityHint();
+ *   else throw error;
+ * }
+ */
+export class EncodeError extends Error {
+  readonly code: EncodeErrorCode;
+
+  constructor(code: EncodeErrorCode, message: string) {
+    super(message);
+    this.name = 'EncodeError';
+    this.code = code;
+  }
+}
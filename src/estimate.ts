@@ -0,0 +1,214 @@
+/**
+ * Output size estimation
+ * Predicts the size of an encode before running it, so a UI can warn about a
+ * huge file or pick another quality first. Evenly spaced bands of the source
+ * are transformed and quantized as the encoder would, and the DC differences
+ * and AC run/size symbols they produce are priced with the Huffman tables the
+ * encode would use; the scan size is then scaled up to the full image height.
+ * Nothing is entropy coded and only the sampled bands are kept in memory.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { openStripSource } from './encoder.js';
+import { ISLOW_TRANSFORM, LIBJPEG_QUANTIZER, STANDARD_TABLES, sharedStandardCodes } from './libjpeg.js';
+import { buildHuffmanCodes, magnitudeCategory, optimalHuffmanSpec } from './huffman.js';
+import { LUMA_FACTORS } from './subsample.js';
+import { qualityQuantTables } from './scaling.js';
+import type { ChromaSubsampling } from './subsample.js';
+
+// Band height covering whole MCUs for every subsampling mode
+const BAND_ROWS = 16;
+
+// SOI, JFIF APP0, two DQT, SOF with three components, SOS, EOI
+const FIXED_HEADER_BYTES = 2 + 18 + 2 * 69 + 19 + 14 + 2;
+
+// Share of entropy-coded bytes that are 0xFF and get a stuffed zero
+const STUFFING = 1 / 256;
+
+export interface SizeEstimateOptions extends Pick<EncodeOptions, 'width' | 'height' | 'quality' | 'qualityScaling' | 'optimizeHuffman'> {
+  subsampling?: ChromaSubsampling; // Defaults to '4:4:4', as for encode
+  sampleBands?: number;   // Bands of 16 rows to model, defaults to 16
+}
+
+export interface SizeEstimate {
+  bytes: number;          // Predicted file size
+  sampledRows: number;    // Source rows the model looked at
+}
+
+/**
+ * Top rows of evenly spaced bands, or every band when the image is short
+ * Bands start on MCU rows so their blocks line up with the encoder's.
+ */
+function bandTops(height: number, bands: number): number[] {
+  const rows = Math.ceil(height / BAND_ROWS);
+  if (rows <= bands * 2) {
+    return Array.from({ length: rows }, (_, band) => band * BAND_ROWS);
+  }
+  const spacing = (rows - 1) / (bands - 1 || 1);
+  return Array.from({ length: bands }, (_, band) => Math.round(band * spacing) * BAND_ROWS);
+}
+
+/**
+ * Symbol statistics of the blocks modeled so far
+ */
+class SymbolCounter {
+  // Luma DC, luma AC, chroma DC, chroma AC, as the tables are numbered
+  readonly frequencies = [0, 1, 2, 3].map(() => new Float64Array(256));
+  extraBits = 0;
+
+  addBlock(coefficients: Int32Array, previousDc: number, chroma: boolean): void {
+    const [dc, ac] = chroma ? [this.frequencies[2], this.frequencies[3]] : [this.frequencies[0], this.frequencies[1]];
+    const size = magnitudeCategory(coefficients[0] - previousDc);
+    dc[size]++;
+    this.extraBits += size;
+    let run = 0;
+    for (let k = 1; k < 64; k++) {
+      const value = coefficients[k];
+      if (value === 0) {
+        run++;
+        continue;
+      }
+      for (; run > 15; run -= 16) {
+        ac[0xf0]++;
+      }
+      const category = magnitudeCategory(value);
+      ac[(run << 4) | category]++;
+      this.extraBits += category;
+      run = 0;
+    }
+    if (run > 0) {
+      ac[0x00]++;
+    }
+  }
+}
+
+/**
+ * Transform, quantize, and count the symbols of one band
+ *
+ * @param rgba Band pixels, `rows` rows of `width`
+ */
+function modelBand(rgba: Uint8Array, width: number, rows: number, subsampling: ChromaSubsampling, tables: Uint16Array[], counter: SymbolCounter): number {
+  const [maxH, maxV] = LUMA_FACTORS[subsampling];
+  const mcuRows = Math.ceil(rows / (8 * maxV));
+  const mcusPerLine = Math.ceil(width / (8 * maxH));
+  const paddedWidth = mcusPerLine * 8 * maxH;
+  const paddedHeight = mcuRows * 8 * maxV;
+
+  // Full-resolution planes, padded by repeating the last row and column
+  const planes = [0, 1, 2].map(() => new Float64Array(paddedWidth * paddedHeight));
+  for (let y = 0; y < paddedHeight; y++) {
+    for (let x = 0; x < paddedWidth; x++) {
+      const input = (Math.min(y, rows - 1) * width + Math.min(x, width - 1)) * 4;
+      const [r, g, b] = [rgba[input], rgba[input + 1], rgba[input + 2]];
+      const out = y * paddedWidth + x;
+      planes[0][out] = 0.299 * r + 0.587 * g + 0.114 * b;
+      planes[1][out] = -0.16874 * r - 0.33126 * g + 0.5 * b + 128;
+      planes[2][out] = 0.5 * r - 0.41869 * g - 0.08131 * b + 128;
+    }
+  }
+
+  const components = [[maxH, maxV], [1, 1], [1, 1]];
+  const block = new Int32Array(64);
+  const quantized = new Int32Array(64);
+  const predictors = [0, 0, 0];
+  for (let mcuRow = 0; mcuRow < mcuRows; mcuRow++) {
+    for (let mcu = 0; mcu < mcusPerLine; mcu++) {
+      components.forEach(([h, v], index) => {
+        // Box filter from the full-resolution plane for subsampled chroma
+        const sx = maxH / h;
+        const sy = maxV / v;
+        for (let by = 0; by < v; by++) {
+          for (let bx = 0; bx < h; bx++) {
+            const top = (mcuRow * v + by) * 8 * sy;
+            const left = (mcu * h + bx) * 8 * sx;
+            for (let k = 0; k < 64; k++) {
+              let sum = 0;
+              for (let dy = 0; dy < sy; dy++) {
+                for (let dx = 0; dx < sx; dx++) {
+                  sum += planes[index][(top + (k >> 3) * sy + dy) * paddedWidth + left + (k & 7) * sx + dx];
+                }
+              }
+              block[k] = Math.round(sum / (sx * sy)) - 128;
+            }
+            ISLOW_TRANSFORM.forward(block);
+            LIBJPEG_QUANTIZER.quantize(block, tables[index === 0 ? 0 : 1], quantized, 0, index);
+            counter.addBlock(quantized, predictors[index], index > 0);
+            predictors[index] = quantized[0];
+          }
+        }
+      });
+    }
+  }
+  return mcuRows;
+}
+
+/**
+ * Predict the size of `encode(source, options)` from sampled bands
+ * Aims at the plain engine output: thumbnails, metadata, alpha masks, and
+ * rewriting options such as progressive scans are not modeled. The source is
+ * read through once, but only the sampled bands are transformed.
+ *
+ * @example
+ * const { bytes } = await estimateSize(file, { quality: 90 });
+ * if (bytes > 10_000_000) warn(`About ${Math.round(bytes / 1e6)} MB`);
+ */
+export async function estimateSize(source: ImageSource, options: SizeEstimateOptions = {}): Promise<SizeEstimate> {
+  const { quality = 100, subsampling = '4:4:4', sampleBands = 16 } = options;
+  if (!LUMA_FACTORS[subsampling]) {
+    throw new Error(`Unknown chroma subsampling: ${subsampling}`);
+  }
+  if (!Number.isInteger(sampleBands) || sampleBands < 1) {
+    throw new Error(`Sample band count must be a positive integer, got ${sampleBands}`);
+  }
+
+  const strips = await openStripSource(source, options);
+  const { width, height } = strips;
+  const rowBytes = width * 4;
+  const tops = bandTops(height, sampleBands);
+  const bands = tops.map((top) => new Uint8Array(Math.min(BAND_ROWS, height - top) * rowBytes));
+
+  // Copy the rows of each strip that fall inside a band
+  for (let y = 0; y < height;) {
+    const strip = await strips.readStrip(Math.min(64, height - y));
+    if (!strip || strip.length === 0) {
+      throw new Error(`Source ended after ${y} of ${height} rows`);
+    }
+    const count = strip.length / rowBytes;
+    tops.forEach((top, index) => {
+      const from = Math.max(top, y);
+      const to = Math.min(top + bands[index].length / rowBytes, y + count);
+      if (from < to) {
+        bands[index].set(strip.subarray((from - y) * rowBytes, (to - y) * rowBytes), (from - top) * rowBytes);
+      }
+    });
+    y += count;
+  }
+
+  const quantTables = qualityQuantTables(quality, options.qualityScaling);
+  const counter = new SymbolCounter();
+  let sampledMcuRows = 0;
+  bands.forEach((band) => {
+    sampledMcuRows += modelBand(band, width, band.length / rowBytes, subsampling, quantTables, counter);
+  });
+
+  // Subsampled output is re-coded with tables built from the image, as optimizeHuffman does
+  const optimized = options.optimizeHuffman || subsampling !== '4:4:4';
+  const specs = optimized
+    ? counter.frequencies.map((frequencies, index) => optimalHuffmanSpec(frequencies, index % 2, index >> 1))
+    : STANDARD_TABLES;
+  const codes = optimized ? specs.map(buildHuffmanCodes) : sharedStandardCodes();
+  let bits = counter.extraBits;
+  counter.frequencies.forEach((frequencies, index) => {
+    frequencies.forEach((count, symbol) => {
+      bits += count * codes[index].lengths[symbol];
+    });
+  });
+
+  const mcuRows = Math.ceil(height / (8 * LUMA_FACTORS[subsampling][1]));
+  const scanBytes = (bits / 8) * (mcuRows / sampledMcuRows) * (1 + STUFFING);
+  const headerBytes = FIXED_HEADER_BYTES + specs.reduce((sum, spec) => sum + 4 + 1 + 16 + spec.symbols.length, 0);
+  return {
+    bytes: Math.round(headerBytes + scanBytes),
+    sampledRows: bands.reduce((sum, band) => sum + band.length / rowBytes, 0),
+  };
+}
@@ -0,0 +1,274 @@
+/**
+ * GIF decoding
+ * Parses GIF87a/89a files into animation frames (frame rectangles with their
+ * delay and disposal), decoding one image at a time as frames are requested.
+ */
+
+import type { AnimationFrame, FrameDisposal } from './animation.js';
+import { concatBytes } from './pipeline.js';
+
+export interface GifInfo {
+  width: number;
+  height: number;
+  background?: [number, number, number]; // Global palette background color, if any
+}
+
+const DISPOSALS: FrameDisposal[] = ['none', 'none', 'background', 'previous'];
+
+/**
+ * Return true if the bytes start with a GIF signature
+ */
+export function isGif(bytes: Uint8Array): boolean {
+  return bytes.length >= 6 && String.fromCharCode(...bytes.subarray(0, 6)).match(/^GIF8[79]a$/) !== null;
+}
+
+class GifReader {
+  pos = 0;
+  readonly bytes: Uint8Array;
+
+  constructor(bytes: Uint8Array) {
+    this.bytes = bytes;
+  }
+
+  byte(): number {
+    if (this.pos >= this.bytes.length) {
+      throw new Error('GIF data is truncated');
+    }
+    return this.bytes[this.pos++];
+  }
+
+  uint16(): number {
+    return this.byte() | (this.byte() << 8);
+  }
+
+  take(count: number): Uint8Array {
+    if (this.pos + count > this.bytes.length) {
+      throw new Error('GIF data is truncated');
+    }
+    const data = this.bytes.subarray(this.pos, this.pos + count);
+    this.pos += count;
+    return data;
+  }
+
+  /**
+   * Concatenate a chain of data sub-blocks
+   */
+  subBlocks(): Uint8Array {
+    const parts: Uint8Array[] = [];
+    for (let size = this.byte(); size > 0; size = this.byte()) {
+      parts.push(this.take(size));
+    }
+    return concatBytes(parts);
+  }
+}
+
+/**
+ * Decode GIF LZW data into palette indices
+ */
+export function lzwDecode(data: Uint8Array, minCodeSize: number, pixelCount: number): Uint8Array {
+  const output = new Uint8Array(pixelCount);
+  const clearCode = 1 << minCodeSize;
+  const endCode = clearCode + 1;
+  const prefix = new Uint16Array(4096);
+  const suffix = new Uint8Array(4096);
+  const lengths = new Uint16Array(4096);
+  for (let i = 0; i < clearCode; i++) {
+    suffix[i] = i;
+    lengths[i] = 1;
+  }
+
+  let codeSize = minCodeSize + 1;
+  let nextCode = endCode + 1;
+  let previous = -1;
+  let written = 0;
+  let bitBuffer = 0;
+  let bitCount = 0;
+  let pos = 0;
+
+  // Write the string for `code` at `at`, returning its first symbol
+  const emit = (code: number, at: number): number => {
+    const length = lengths[code];
+    let c = code;
+    for (let i = length - 1; i >= 0; i--) {
+      if (at + i < pixelCount) {
+        output[at + i] = suffix[c];
+      }
+      c = prefix[c];
+    }
+    return output[at] ?? suffix[c];
+  };
+
+  while (written < pixelCount) {
+    while (bitCount < codeSize) {
+      if (pos >= data.length) {
+        return output; // Missing data leaves the remaining pixels at index 0
+      }
+      bitBuffer |= data[pos++] << bitCount;
+      bitCount += 8;
+    }
+    const code = bitBuffer & ((1 << codeSize) - 1);
+    bitBuffer >>>= codeSize;
+    bitCount -= codeSize;
+
+    if (code === clearCode) {
+      codeSize = minCodeSize + 1;
+      nextCode = endCode + 1;
+      previous = -1;
+      continue;
+    }
+    if (code === endCode) {
+      break;
+    }
+
+    if (previous < 0) {
+      if (code >= clearCode) {
+        throw new Error(`Invalid LZW code ${code}`);
+      }
+      emit(code, written);
+      written += 1;
+      previous = code;
+      continue;
+    }
+
+    let first: number;
+    if (code < nextCode) {
+      first = emit(code, written);
+      written += lengths[code];
+    } else if (code === nextCode) {
+      // KwKwK case: previous string plus its own first symbol
+      first = emit(previous, written);
+      if (written + lengths[previous] < pixelCount) {
+        output[written + lengths[previous]] = first;
+      }
+      written += lengths[previous] + 1;
+    } else {
+      throw new Error(`Invalid LZW code ${code}`);
+    }
+
+    if (nextCode < 4096) {
+      prefix[nextCode] = previous;
+      suffix[nextCode] = first;
+      lengths[nextCode] = lengths[previous] + 1;
+      nextCode++;
+      if (nextCode === 1 << codeSize && codeSize < 12) {
+        codeSize++;
+      }
+    }
+    previous = code;
+  }
+  return output;
+}
+
+function readPalette(reader: GifReader, packed: number): Uint8Array {
+  return reader.take(3 * (1 << ((packed & 0x07) + 1)));
+}
+
+/**
+ * Read the logical screen descriptor
+ */
+export function readGifInfo(bytes: Uint8Array): GifInfo {
+  if (!isGif(bytes)) {
+    throw new Error('Not a GIF file: missing signature');
+  }
+  const reader = new GifReader(bytes);
+  reader.pos = 6;
+  const width = reader.uint16();
+  const height = reader.uint16();
+  const packed = reader.byte();
+  const backgroundIndex = reader.byte();
+  reader.byte(); // Pixel aspect ratio
+  if (!(packed & 0x80)) {
+    return { width, height };
+  }
+  const palette = readPalette(reader, packed);
+  return {
+    width,
+    height,
+    background: [palette[backgroundIndex * 3], palette[backgroundIndex * 3 + 1], palette[backgroundIndex * 3 + 2]],
+  };
+}
+
+/**
+ * Iterate over the frames of a GIF file
+ * Each frame is decoded only when the iterator reaches it.
+ *
+ * @param bytes Complete GIF file
+ */
+export function* gifFrames(bytes: Uint8Array): Generator<AnimationFrame> {
+  readGifInfo(bytes);
+  const reader = new GifReader(bytes);
+  reader.pos = 10;
+  const screenPacked = reader.byte();
+  reader.pos = 13;
+  const globalPalette = screenPacked & 0x80 ? readPalette(reader, screenPacked) : undefined;
+
+  let delay = 0;
+  let disposal: FrameDisposal = 'none';
+  let transparent = -1;
+
+  while (reader.pos < bytes.length) {
+    const introducer = reader.byte();
+    if (introducer === 0x3b) {
+      return;
+    }
+    if (introducer === 0x21) {
+      const label = reader.byte();
+      const data = reader.subBlocks();
+      if (label === 0xf9 && data.length >= 4) {
+        disposal = DISPOSALS[(data[0] >> 2) & 0x07] ?? 'none';
+        delay = (data[1] | (data[2] << 8)) * 10;
+        transparent = data[0] & 0x01 ? data[3] : -1;
+      }
+      continue;
+    }
+    if (introducer !== 0x2c) {
+      throw new Error(`Unexpected GIF block 0x${introducer.toString(16)} at offset ${reader.pos - 1}`);
+    }
+
+    const left = reader.uint16();
+    const top = reader.uint16();
+    const width = reader.uint16();
+    const height = reader.uint16();
+    const packed = reader.byte();
+    const palette = packed & 0x80 ? readPalette(reader, packed) : globalPalette;
+    if (!palette) {
+      throw new Error('GIF frame has no color table');
+    }
+    const minCodeSize = reader.byte();
+    const indices = lzwDecode(reader.subBlocks(), minCodeSize, width * height);
+
+    // Interlaced images store rows in four passes
+    const rowOrder: number[] = [];
+    if (packed & 0x40) {
+      for (const [start, step] of [[0, 8], [4, 8], [2, 4], [1, 2]]) {
+        for (let y = start; y < height; y += step) {
+          rowOrder.push(y);
+        }
+      }
+    } else {
+      for (let y = 0; y < height; y++) {
+        rowOrder.push(y);
+      }
+    }
+
+    const data = new Uint8Array(width * height * 4);
+    rowOrder.forEach((y, row) => {
+      for (let x = 0; x < width; x++) {
+        const index = indices[row * width + x];
+        const offset = (y * width + x) * 4;
+        if (index === transparent) {
+          continue;
+        }
+        data[offset] = palette[index * 3];
+        data[offset + 1] = palette[index * 3 + 1];
+        data[offset + 2] = palette[index * 3 + 2];
+        data[offset + 3] = 255;
+      }
+    });
+
+    yield { data, left, top, width, height, delay, disposal };
+    delay = 0;
+    disposal = 'none';
+    transparent = -1;
+  }
+}
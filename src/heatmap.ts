@@ -0,0 +1,71 @@
+/**
+ * Quality-loss heatmap
+ * Measures the distortion an encode introduced in each 8x8 block by decoding
+ * the output and comparing it with the source pixels.
+ */
+
+import { decode } from './decoder.js';
+
+/**
+ * One value per 8x8 pixel block, row-major
+ * - mse: mean squared error over the RGB channels of the block
+ * - data: grayscale rendering of `mse`, scaled so the worst block is 255
+ */
+export interface DistortionHeatmap {
+  width: number;          // Blocks per row
+  height: number;         // Block rows
+  mse: Float64Array;
+  data: Uint8Array;
+}
+
+/**
+ * Compute a per-block distortion map for an encoded image
+ *
+ * @param pixels Source RGBA pixels that were encoded
+ * @param jpeg Encoded JPEG bytes produced from `pixels`
+ *
+ * @example
+ * const jpeg = await encode(rgba, { width, height, quality: 70 }) as Uint8Array;
+ * const heatmap = distortionHeatmap(rgba, jpeg);
+ */
+export function distortionHeatmap(pixels: Uint8Array, jpeg: Uint8Array): DistortionHeatmap {
+  const decoded = decode(jpeg);
+  const { width, height } = decoded;
+  if (pixels.length < width * height * 4) {
+    throw new Error(
+      `Buffer too small: expected at least ${width * height * 4} bytes for ${width}x${height} RGBA image, got ${pixels.length}`
+    );
+  }
+
+  const blocksPerLine = Math.ceil(width / 8);
+  const blocksPerColumn = Math.ceil(height / 8);
+  const mse = new Float64Array(blocksPerLine * blocksPerColumn);
+  const counts = new Uint32Array(mse.length);
+
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const offset = (y * width + x) * 4;
+      const block = (y >> 3) * blocksPerLine + (x >> 3);
+      for (let c = 0; c < 3; c++) {
+        const diff = pixels[offset + c] - decoded.data[offset + c];
+        mse[block] += diff * diff;
+      }
+      counts[block] += 3;
+    }
+  }
+
+  let worst = 0;
+  for (let i = 0; i < mse.length; i++) {
+    mse[i] /= counts[i];
+    worst = Math.max(worst, mse[i]);
+  }
+
+  const data = new Uint8Array(mse.length);
+  if (worst > 0) {
+    for (let i = 0; i < mse.length; i++) {
+      data[i] = Math.round((mse[i] / worst) * 255);
+    }
+  }
+
+  return { width: blocksPerLine, height: blocksPerColumn, mse, data };
+}
@@ -0,0 +1,94 @@
+/**
+ * Huffman symbol statistics
+ * Counts the DC category and AC run/size symbols coded for each component, the
+ * raw material for building custom Huffman tables or classifying content.
+ */
+
+import { parseJpeg } from './jpeg.js';
+import type { HuffmanSpec } from './jpeg.js';
+import { walkScan } from './entropy.js';
+import { optimalHuffmanSpec } from './huffman.js';
+
+/**
+ * Symbol frequencies for one component
+ * - dc: indexed by DC category (0-11 for 8-bit images)
+ * - ac: indexed by the run/size byte (run << 4 | size), including EOB (0x00) and ZRL (0xF0)
+ * - dcTable/acTable: Huffman table ids the component was coded with, so callers
+ *   can merge histograms of components that share a table
+ */
+export interface ComponentHistogram {
+  componentId: number;
+  dcTable: number;
+  acTable: number;
+  dc: Uint32Array;
+  ac: Uint32Array;
+}
+
+/**
+ * Collect per-component Huffman symbol histograms from an encoded JPEG
+ *
+ * @param jpeg Encoded JPEG bytes (e.g. the Buffer/Uint8Array returned by `encode`)
+ * @returns One histogram per frame component, in frame order
+ *
+ * @example
+ * const jpeg = await encode(buffer, { width, height });
+ * const [luma, cb, cr] = huffmanHistogram(jpeg as Uint8Array);
+ * console.log(`EOB symbols in luma: ${luma.ac[0x00]}`);
+ */
+export function huffmanHistogram(jpeg: Uint8Array): ComponentHistogram[] {
+  const file = parseJpeg(jpeg);
+  const histograms: ComponentHistogram[] = file.frame.components.map((component) => ({
+    componentId: component.id,
+    dcTable: -1,
+    acTable: -1,
+    dc: new Uint32Array(256),
+    ac: new Uint32Array(256),
+  }));
+
+  for (const scan of file.scans) {
+    for (const sc of scan.header.components) {
+      const histogram = histograms.find((h) => h.componentId === sc.id);
+      if (histogram) {
+        histogram.dcTable = sc.dcTable;
+        histogram.acTable = sc.acTable;
+      }
+    }
+    walkScan(file, scan, {
+      onSymbol(componentIndex, tableClass, symbol) {
+        const histogram = histograms[componentIndex];
+        (tableClass === 0 ? histogram.dc : histogram.ac)[symbol]++;
+      },
+    });
+  }
+
+  return histograms;
+}
+
+/**
+ * Optimal DC and AC tables for the statistics gathered by `huffmanHistogram`
+ * Components coded with the same table have their counts merged. Tables come
+ * out DC then AC, each ordered by id, ready for `huffmanTablePayload` or as
+ * `writeBaseline`'s `huffmanTables` for a second coding pass. Only meaningful
+ * for a sequential JPEG, whose single scan the counts describe.
+ *
+ * @example
+ * const tables = optimalHuffmanTables(huffmanHistogram(jpeg));
+ * const dht = createSegment(Marker.DHT, huffmanTablePayload(tables));
+ */
+export function optimalHuffmanTables(histograms: ComponentHistogram[]): HuffmanSpec[] {
+  const merged = [new Map<number, Uint32Array>(), new Map<number, Uint32Array>()];
+  function add(tableClass: number, id: number, counts: Uint32Array): void {
+    if (id < 0) {
+      return;
+    }
+    const total = merged[tableClass].get(id) ?? new Uint32Array(256);
+    counts.forEach((count, symbol) => { total[symbol] += count; });
+    merged[tableClass].set(id, total);
+  }
+  for (const histogram of histograms) {
+    add(0, histogram.dcTable, histogram.dc);
+    add(1, histogram.acTable, histogram.ac);
+  }
+  return merged.flatMap((tables, tableClass) =>
+    [...tables.entries()].sort(([a], [b]) => a - b).map(([id, counts]) => optimalHuffmanSpec(counts, tableClass, id)));
+}
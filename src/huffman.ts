@@ -0,0 +1,230 @@
+/**
+ * Huffman encoding
+ * Bit writer with byte stuffing, canonical code assignment, and optimal table
+ * construction from symbol statistics (ITU T.81 Annex K.2), for rewriting
+ * entropy-coded data outside the engine.
+ */
+
+import type { HuffmanSpec } from './jpeg.js';
+
+/**
+ * Code and length of every symbol of a Huffman table
+ */
+export interface HuffmanCodeTable {
+  codes: Uint16Array;
+  lengths: Uint8Array;   // 0 = symbol not in table
+}
+
+/**
+ * Assign canonical codes to the symbols of a table specification (T.81 C.2)
+ */
+export function buildHuffmanCodes(spec: HuffmanSpec): HuffmanCodeTable {
+  const codes = new Uint16Array(256);
+  const lengths = new Uint8Array(256);
+  let code = 0;
+  let k = 0;
+  for (let length = 1; length <= 16; length++) {
+    for (let i = 0; i < spec.counts[length - 1]; i++, k++) {
+      codes[spec.symbols[k]] = code++;
+      lengths[spec.symbols[k]] = length;
+    }
+    code <<= 1;
+  }
+  return { codes, lengths };
+}
+
+/**
+ * Build an optimal table, limited to 16-bit codes, from symbol frequencies
+ * Follows the procedure of T.81 K.2 including the reserved all-ones code point.
+ *
+ * @param frequencies Occurrence count of each of the 256 symbols
+ */
+export function optimalHuffmanSpec(frequencies: ArrayLike<number>, tableClass: number, id: number): HuffmanSpec {
+  const freq = new Float64Array(257);
+  for (let i = 0; i < 256; i++) {
+    freq[i] = frequencies[i] ?? 0;
+  }
+  freq[256] = 1; // Reserved so that no code consists entirely of 1-bits
+  const codeSize = new Int32Array(257);
+  const others = new Int32Array(257).fill(-1);
+
+  for (;;) {
+    // Least frequent symbol, preferring the highest index on ties, then the next least
+    let v1 = -1;
+    let v2 = -1;
+    for (let i = 0; i < 257; i++) {
+      if (freq[i] > 0 && (v1 < 0 || freq[i] <= freq[v1])) {
+        v1 = i;
+      }
+    }
+    for (let i = 0; i < 257; i++) {
+      if (freq[i] > 0 && i !== v1 && (v2 < 0 || freq[i] <= freq[v2])) {
+        v2 = i;
+      }
+    }
+    if (v2 < 0) {
+      break;
+    }
+
+    freq[v1] += freq[v2];
+    freq[v2] = 0;
+    codeSize[v1]++;
+    while (others[v1] >= 0) {
+      v1 = others[v1];
+      codeSize[v1]++;
+    }
+    others[v1] = v2;
+    codeSize[v2]++;
+    while (others[v2] >= 0) {
+      v2 = others[v2];
+      codeSize[v2]++;
+    }
+  }
+
+  const bits = new Int32Array(33);
+  for (let i = 0; i < 257; i++) {
+    if (codeSize[i] > 0) {
+      bits[codeSize[i]]++;
+    }
+  }
+  // Limit code lengths to 16 bits (K.3 Adjust_BITS)
+  for (let i = 32; i > 16; i--) {
+    while (bits[i] > 0) {
+      let j = i - 2;
+      while (bits[j] === 0) {
+        j--;
+      }
+      bits[i] -= 2;
+      bits[i - 1]++;
+      bits[j + 1] += 2;
+      bits[j]--;
+    }
+  }
+  // Drop the reserved code point from the longest length
+  let longest = 16;
+  while (longest > 0 && bits[longest] === 0) {
+    longest--;
+  }
+  if (longest > 0) {
+    bits[longest]--;
+  }
+
+  const symbols: number[] = [];
+  for (let size = 1; size <= 32; size++) {
+    for (let i = 0; i < 256; i++) {
+      if (codeSize[i] === size) {
+        symbols.push(i);
+      }
+    }
+  }
+  return { tableClass, id, counts: Uint8Array.from(bits.subarray(1, 17)), symbols: Uint8Array.from(symbols) };
+}
+
+/**
+ * Serialize table specifications as a DHT payload
+ */
+export function huffmanTablePayload(specs: HuffmanSpec[]): Uint8Array {
+  const payload = new Uint8Array(specs.reduce((sum, spec) => sum + 17 + spec.symbols.length, 0));
+  let offset = 0;
+  for (const spec of specs) {
+    payload[offset] = (spec.tableClass << 4) | spec.id;
+    payload.set(spec.counts, offset + 1);
+    payload.set(spec.symbols, offset + 17);
+    offset += 17 + spec.symbols.length;
+  }
+  return payload;
+}
+
+/**
+ * Number of magnitude bits needed for a coefficient (its JPEG category)
+ */
+export function magnitudeCategory(value: number): number {
+  let magnitude = Math.abs(value);
+  let size = 0;
+  while (magnitude > 0) {
+    size++;
+    magnitude >>= 1;
+  }
+  return size;
+}
+
+// Coefficient ranges of 8-bit DCT data; anything outside came from a malformed
+// source and would need magnitude categories the Huffman tables cannot express
+const DC_MIN = -1024;
+const DC_MAX = 1023;
+const AC_LIMIT = 1023;
+
+/**
+ * Clamp a DC coefficient to the 8-bit range, keeping differences within category 11
+ */
+export function clampDc(value: number): number {
+  return Math.max(DC_MIN, Math.min(DC_MAX, value));
+}
+
+/**
+ * Clamp an AC coefficient to the 8-bit range, keeping it within category 10
+ */
+export function clampAc(value: number): number {
+  return Math.max(-AC_LIMIT, Math.min(AC_LIMIT, value));
+}
+
+/**
+ * Bit writer producing entropy-coded bytes
+ * Inserts a stuffed zero after every 0xFF and pads the final byte with 1-bits.
+ */
+export class BitWriter {
+  private bytes = new Uint8Array(1024);
+  private length = 0;
+  private buffer = 0;
+  private count = 0;
+
+  private push(byte: number): void {
+    if (this.length + 2 > this.bytes.length) {
+      const grown = new Uint8Array(this.bytes.length * 2);
+      grown.set(this.bytes);
+      this.bytes = grown;
+    }
+    this.bytes[this.length++] = byte;
+    if (byte === 0xff) {
+      this.bytes[this.length++] = 0x00;
+    }
+  }
+
+  writeBits(value: number, count: number): void {
+    for (let i = count - 1; i >= 0; i--) {
+      this.buffer = (this.buffer << 1) | ((value >> i) & 1);
+      if (++this.count === 8) {
+        this.push(this.buffer);
+        this.buffer = 0;
+        this.count = 0;
+      }
+    }
+  }
+
+  /**
+   * Write a coefficient's magnitude bits (the inverse of EXTEND)
+   */
+  writeMagnitude(value: number, size: number): void {
+    this.writeBits(value < 0 ? value + (1 << size) - 1 : value, size);
+  }
+
+  /**
+   * Return the complete bytes written since the last call, keeping a partial byte
+   * Lets an encoder hand out entropy-coded data as it goes.
+   */
+  take(): Uint8Array {
+    const bytes = this.bytes.slice(0, this.length);
+    this.length = 0;
+    return bytes;
+  }
+
+  /**
+   * Pad to a byte boundary with 1-bits and return the bytes written so far
+   */
+  finish(): Uint8Array {
+    if (this.count > 0) {
+      this.writeBits((1 << (8 - this.count)) - 1, 8 - this.count);
+    }
+    return this.bytes.slice(0, this.length);
+  }
+}
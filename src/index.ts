@@ -5,124 +5,21 @@
 
 // @ts-ignore - WASM package may not have perfect types
 import { StreamingJpegEncoder, WasmColorType } from 'jpeg-encoder-wasm/pkg/jpeg_encoder.js';
-import type { Readable } from 'stream';
+import { encodeSegmentedToBytes, encodeStripsToBytes, encodeToBytes, toEncodeResult } from './encoder.js';
+import { rawFileSource } from './rawfile.js';
+import { thumbnailToBytes } from './thumbnailer.js';
+import type { ThumbnailerOptions } from './thumbnailer.js';
+import type { RawFileOptions } from './rawfile.js';
+import type { EncodeOptions, EncodeResult, ImageSource, SegmentedEncodeOptions } from './types.js';
+import { addJpsDescriptor, sideBySide } from './jps.js';
+import type { JpsOptions } from './jps.js';
 
-/**
- * Universal image source type
- * - Uint8Array: Raw RGBA buffer (works everywhere)
- * - string: File path (Node.js only)
- * - Buffer: Node.js buffer (Node.js only)
- * - Readable: Node.js stream (Node.js only)
- * - HTMLCanvasElement: Browser canvas (browser/jsdom only)
- * - ImageData: Canvas image data (browser/jsdom only)
- */
-export type ImageSource =
-  | Uint8Array
-  | string
-  | Buffer
-  | Readable
-  | HTMLCanvasElement
-  | ImageData;
-
-export interface EncodeOptions {
-  width?: number;   // Required for raw buffer (Uint8Array/Buffer)
-  height?: number;  // Required for raw buffer (Uint8Array/Buffer)
-  quality?: number; // JPEG quality (1-100), defaults to 100
-}
-
-/**
- * Universal return type - actual type depends on environment
- * - Browser: Blob
- * - Node.js: Buffer
- * - Fallback: Uint8Array
- */
-export type EncodeResult = Blob | Buffer | Uint8Array;
-
-/**
- * Runtime environment detection
- */
-const runtime = {
-  hasNodeFS: typeof process !== 'undefined' &&
-             process.versions != null &&
-             process.versions.node != null,
-  hasBuffer: typeof Buffer !== 'undefined',
-  hasBlob: typeof Blob !== 'undefined',
-  hasCanvas: typeof HTMLCanvasElement !== 'undefined',
-  hasImageData: typeof ImageData !== 'undefined',
-};
-
-/**
- * Convert any supported image source to a Uint8Array buffer
- */
-async function sourceToBuffer(source: ImageSource): Promise<{
-  buffer: Uint8Array;
-  width?: number;
-  height?: number;
-}> {
-  // Handle HTMLCanvasElement (browser/jsdom)
-  if (runtime.hasCanvas && source instanceof HTMLCanvasElement) {
-    const ctx = source.getContext('2d');
-    if (!ctx) {
-      throw new Error('Failed to get canvas 2D context');
-    }
-    const imageData = ctx.getImageData(0, 0, source.width, source.height);
-    return {
-      buffer: new Uint8Array(imageData.data),
-      width: source.width,
-      height: source.height,
-    };
-  }
-
-  // Handle ImageData (browser/jsdom)
-  if (runtime.hasImageData && source instanceof ImageData) {
-    return {
-      buffer: new Uint8Array(source.data),
-      width: source.width,
-      height: source.height,
-    };
-  }
-
-  // Handle file path (Node.js only)
-  if (typeof source === 'string') {
-    if (!runtime.hasNodeFS) {
-      throw new Error('File path sources are only supported in Node.js environments');
-    }
-    // Dynamic import to avoid bundling fs in browser builds
-    const { readFile } = await import('fs/promises');
-    const buffer = await readFile(source);
-    return { buffer: new Uint8Array(buffer) };
-  }
-
-  // Handle Node.js Buffer
-  if (runtime.hasBuffer && Buffer.isBuffer(source)) {
-    return { buffer: new Uint8Array(source) };
-  }
-
-  // Handle Uint8Array
-  if (source instanceof Uint8Array) {
-    return { buffer: source };
-  }
-
-  // Handle Node.js Readable stream
-  if (runtime.hasNodeFS && source && typeof (source as any).read === 'function') {
-    // Dynamic import to avoid bundling stream in browser builds
-    const { Readable } = await import('stream');
-    if (source instanceof Readable) {
-      const chunks: Buffer[] = [];
-      for await (const chunk of source) {
-        chunks.push(chunk);
-      }
-      return { buffer: new Uint8Array(Buffer.concat(chunks)) };
-    }
-  }
-
-  throw new Error('Unsupported image source type');
-}
+export type { EncodeOptions, EncodeResult, EncodeStats, ImageSource, SegmentedEncodeOptions } from './types.js';
 
 /**
  * Encode an image to JPEG
  *
- * @param source Image source (file path, Buffer, Uint8Array, Readable stream, Canvas, or ImageData)
+ * @param source Image source (file path, Buffer, Uint8Array, Readable stream, Canvas, ImageData, or ImageBitmap)
  * @param options Encoding options
  * @returns Promise resolving to encoded JPEG data
  *   - Browser: Returns Blob with type 'image/jpeg'
@@ -147,80 +44,229 @@ async function sourceToBuffer(source: ImageSource): Promise<{
  * // Browser/Node with ImageData (jsdom)
  * const imageData = ctx.getImageData(0, 0, 100, 100);
  * const jpeg = await encode(imageData, { quality: 85 });
+ *
+ * @example
+ * // Browser with a decoded bitmap
+ * const jpeg = await encode(await createImageBitmap(blob), { quality: 85 });
  */
 export async function encode(
   source: ImageSource,
   options: EncodeOptions = {}
 ): Promise<EncodeResult> {
-  const { quality = 100 } = options;
-
-  // Convert source to buffer and extract dimensions if available
-  const { buffer: imageData, width: detectedWidth, height: detectedHeight } =
-    await sourceToBuffer(source);
-
-  // Determine final dimensions
-  const width = options.width ?? detectedWidth;
-  const height = options.height ?? detectedHeight;
-
-  if (!width || !height) {
-    throw new Error('Width and height are required (provide in options or use Canvas/ImageData source)');
-  }
-
-  // Validate buffer size
-  const expectedSize = width * height * 4; // RGBA
-  if (imageData.length < expectedSize) {
-    throw new Error(
-      `Buffer too small: expected at least ${expectedSize} bytes for ${width}x${height} RGBA image, got ${imageData.length}`
-    );
-  }
-
-  // Create WASM encoder
-  const encoder = new StreamingJpegEncoder(width, height, WasmColorType.Rgba, quality);
-
-  // Collect output chunks
-  const chunks: Uint8Array[] = [];
-
-  // Process in 8-scanline strips
-  const stripHeight = 8;
-  const bytesPerRow = width * 4; // RGBA
+  return toEncodeResult(await encodeToBytes(source, options));
+}
 
-  for (let y = 0; y < height; y += stripHeight) {
-    const actualStripHeight = Math.min(stripHeight, height - y);
-    const stripSize = actualStripHeight * bytesPerRow;
-    const stripStart = y * bytesPerRow;
-    const stripData = imageData.slice(stripStart, stripStart + stripSize);
+/**
+ * Encode an ImageBitmap to JPEG
+ * The bitmap is drawn onto an OffscreenCanvas and read back one strip at a time.
+ *
+ * @example
+ * const jpeg = await encodeImageBitmap(await createImageBitmap(file), { quality: 80 });
+ */
+export async function encodeImageBitmap(
+  bitmap: ImageBitmap,
+  options: Omit<EncodeOptions, 'width' | 'height'> = {}
+): Promise<EncodeResult> {
+  return toEncodeResult(await encodeToBytes(bitmap, options));
+}
 
-    const output = encoder.encode_strip(stripData);
-    if (output && output.length > 0) {
-      chunks.push(output);
-    }
+/**
+ * Encode a raw pixel file to JPEG (Node.js only)
+ * Rows are read from disk strip by strip, so the frame is never resident in full.
+ *
+ * @example
+ * const jpeg = await encodeRawFile('scan.raw', { width: 60000, height: 40000, format: 'rgb', quality: 85 });
+ */
+export async function encodeRawFile(
+  path: string,
+  options: RawFileOptions & Omit<EncodeOptions, 'width' | 'height'>
+): Promise<EncodeResult> {
+  const strips = await rawFileSource(path, options);
+  try {
+    return toEncodeResult(await encodeStripsToBytes(strips, options));
+  } finally {
+    await strips.close();
   }
+}
 
-  // Finish encoding
-  const finalOutput = encoder.finish();
-  if (finalOutput && finalOutput.length > 0) {
-    chunks.push(finalOutput);
-  }
+/**
+ * Make a JPEG thumbnail of a JPEG in one call
+ * Large sources are decoded at 1/8 scale from their DC coefficients, then
+ * box-filtered to `maxSize` and re-encoded.
+ *
+ * @example
+ * const thumb = await thumbnail(request.body, 256, 80, { metadata: true });
+ */
+export async function thumbnail(
+  source: ImageSource | ReadableStream<Uint8Array>,
+  maxSize: number,
+  quality = 85,
+  options: ThumbnailerOptions = {}
+): Promise<EncodeResult> {
+  return toEncodeResult(await thumbnailToBytes(source, maxSize, quality, options));
+}
 
-  // Combine all chunks into a single buffer
-  const totalLength = chunks.reduce((sum, chunk) => sum + chunk.length, 0);
-  const jpegBuffer = new Uint8Array(totalLength);
-  let offset = 0;
-  for (const chunk of chunks) {
-    jpegBuffer.set(chunk, offset);
-    offset += chunk.length;
-  }
+/**
+ * Encode an image to JPEG as independent restart segments
+ *
+ * Horizontal bands of `segmentHeight` rows are encoded separately and joined with
+ * restart markers. Bands share no entropy-coder state, so they can be encoded in
+ * parallel; use `stitchRestartSegments` to join bands produced elsewhere (e.g. workers),
+ * or `RestartStitcher` to write them out as they finish in any order.
+ *
+ * @param source Image source (see `encode`)
+ * @param options Encoding options plus the segment height
+ *
+ * @example
+ * const jpeg = await encodeSegmented(buffer, { width: 4096, height: 4096, segmentHeight: 128 });
+ */
+export async function encodeSegmented(
+  source: ImageSource,
+  options: SegmentedEncodeOptions = {}
+): Promise<EncodeResult> {
+  return toEncodeResult(await encodeSegmentedToBytes(source, options));
+}
 
-  // Return appropriate type based on environment
-  // Prioritize Buffer in Node.js, even if Blob is available (e.g., via polyfill)
-  if (runtime.hasBuffer && runtime.hasNodeFS) {
-    return Buffer.from(jpegBuffer);
-  } else if (runtime.hasBlob) {
-    return new Blob([jpegBuffer], { type: 'image/jpeg' });
-  } else {
-    return jpegBuffer;
-  }
+/**
+ * Encode a stereo pair as a side-by-side JPS image
+ *
+ * @param left Left-eye RGBA pixels
+ * @param right Right-eye RGBA pixels
+ * @param options Size of one view, quality, and JPS descriptor settings
+ *
+ * @example
+ * const jps = await encodeStereo(leftRgba, rightRgba, { width: 1280, height: 720 });
+ * await writeFile('frame.jps', jps);
+ */
+export async function encodeStereo(
+  left: Uint8Array,
+  right: Uint8Array,
+  options: EncodeOptions & JpsOptions & { width: number; height: number }
+): Promise<EncodeResult> {
+  const { width, height, quality } = options;
+  const pixels = sideBySide(left, right, width, height, options.leftFirst);
+  const jpeg = await encodeToBytes(pixels, { width: width * 2, height, quality });
+  return toEncodeResult(addJpsDescriptor(jpeg, options));
 }
 
 // Export WasmColorType for advanced usage
 export { WasmColorType, StreamingJpegEncoder };
+
+// Analysis helpers operating on encoded output
+export { huffmanHistogram, optimalHuffmanTables } from './histogram.js';
+export type { ComponentHistogram } from './histogram.js';
+export { debugOverlay } from './overlay.js';
+export type { DebugOverlay, DebugOverlayOptions } from './overlay.js';
+export { validate } from './validate.js';
+export type { ValidationIssue, ValidationReport, ValidationSeverity } from './validate.js';
+export { decode, decodeDc, readCoefficients } from './decoder.js';
+export type { ComponentCoefficients, DecodedImage } from './decoder.js';
+export { psnr, roundtrip, selfTest } from './selftest.js';
+export type { RoundtripOptions, RoundtripResult, SelfTestCase, SelfTestReport } from './selftest.js';
+export { annotate, formatAnnotations, markerName } from './annotate.js';
+export type { Annotation } from './annotate.js';
+export { distortionHeatmap } from './heatmap.js';
+export type { DistortionHeatmap } from './heatmap.js';
+export { RestartStitcher, renumberRestartMarkers, stitchRestartSegments } from './restart.js';
+export type { RenumberedScanData } from './restart.js';
+export { createSegment, insertSegments } from './markers.js';
+export { createMpo, splitMpo } from './mpo.js';
+export type { MpoOptions, MpoType } from './mpo.js';
+export { addJpsDescriptor, jpsSegment, sideBySide } from './jps.js';
+export type { JpsOptions } from './jps.js';
+export { ThumbnailBuilder, jfxxJpegSegment, jfxxRgbSegment } from './thumbnail.js';
+export type { ThumbnailFormat, ThumbnailOptions } from './thumbnail.js';
+export { toSpiff } from './spiff.js';
+export type { SpiffOptions, SpiffResolutionUnit } from './spiff.js';
+export { toDicomBaseline } from './dicom.js';
+export type { DicomOptions, DicomRestartPolicy } from './dicom.js';
+export { createJpegStripEncoder } from './encoder.js';
+export { imageBitmapSource } from './bitmap.js';
+export { CollectingSink, PartSink, bufferSource, iterableSource, readAllStrips, pipelineStream, readableStreamSource, runPipeline, standaloneChunk, transferList, transferableEncoder } from './pipeline.js';
+export type { OutputPart, OutputSink, PipelineHooks, StripEncoder, StripSource } from './pipeline.js';
+export { decodePng, isPng, pngSource, readPngHeader } from './png.js';
+export type { PngHeader } from './png.js';
+export { isPnm, pnmSource, readPnmHeader } from './pnm.js';
+export type { PnmHeader } from './pnm.js';
+export { isQoi, qoiSource, readQoiHeader } from './qoi.js';
+export type { QoiHeader } from './qoi.js';
+export { FrameCompositor } from './animation.js';
+export type { AnimationFrame, FrameDisposal } from './animation.js';
+export { gifFrames, isGif, readGifInfo } from './gif.js';
+export type { GifInfo } from './gif.js';
+export { captureMjpeg, encodeMjpeg, gifToMjpeg, mjpegFrames, trackMjpeg } from './mjpeg.js';
+export type { MjpegFrame, MjpegOptions } from './mjpeg.js';
+export { rawVideoFrames } from './rawvideo.js';
+export { encodeResumable } from './resumable.js';
+export type { EncodeCheckpoint, ResumableEncodeOptions } from './resumable.js';
+export type { RawVideoOptions } from './rawvideo.js';
+export { FrameClock, captureCanvasFrames, captureVideoFrames } from './capture.js';
+export type { CaptureOptions, CapturedFrame, FrameDropPolicy, TrackCaptureOptions, VideoFrameLike } from './capture.js';
+export { detectWasmFeatures, loadEngine, selectEngineVariant } from './capabilities.js';
+export type { EngineVariant, EngineVariants, LoadedEngine, WasmFeatures } from './capabilities.js';
+export { JpegEncoderStream, encodeReadableStream, encodeToDataUrl, encodeToResponse, encodeToStream, reencodeResponse } from './stream.js';
+export type { StreamEncodeOptions } from './stream.js';
+export { defaultProgressiveScript, toProgressive } from './progressive.js';
+export type { ProgressiveOptions, ProgressiveScanInfo, ProgressiveScanSpec } from './progressive.js';
+export { encodeToSink, encodeToWritable, nodeWritableSink, webWritableSink } from './sinks.js';
+export type { WritableSinkOptions } from './sinks.js';
+export { rawFileSource, rawRowsToRgba } from './rawfile.js';
+export type { RawFileOptions, RawPixelFormat } from './rawfile.js';
+export type { ThumbnailerOptions } from './thumbnailer.js';
+export { copyMetadata, readMetadata, readOrientation, stripMetadata } from './metadata.js';
+export type { JpegMetadata, MetadataCopyOptions, StripMetadataOptions } from './metadata.js';
+export { RowDownscaler } from './downscale.js';
+export { encodePyramid } from './pyramid.js';
+export type { PyramidLevel, PyramidOptions } from './pyramid.js';
+export { deepZoomManifest, encodeDeepZoom } from './deepzoom.js';
+export type { DeepZoomOptions, DeepZoomResult, DeepZoomTile } from './deepzoom.js';
+export { dqtSegment, naturalToZigzag, normalizeQuantTable, quantTablePayload, zigzagToNatural } from './quant.js';
+export type { NaturalQuantTable, QuantTableMode } from './quant.js';
+export { toSubsampled } from './subsample.js';
+export type { ChromaSubsampling } from './subsample.js';
+export { forwardDct, optimizeHuffman, quantizeBlock, writeBaseline } from './transcode.js';
+export type { BaselineSpec, CodedComponent } from './transcode.js';
+export { buildHuffmanCodes, huffmanTablePayload, optimalHuffmanSpec } from './huffman.js';
+export type { HuffmanCodeTable } from './huffman.js';
+export type { HuffmanSpec } from './jpeg.js';
+export { ISLOW_TRANSFORM, LIBJPEG_QUANTIZER, createLibjpegStripEncoder, createLibjpegYuvEncoder, encodeYuv, islowForwardDct, libjpegQuantTables, scaledQuantTables } from './libjpeg.js';
+export type { BlockQuantizer, BlockTransform, LibjpegEncoderOptions, YuvStrip, YuvStripEncoder } from './libjpeg.js';
+export { DimensionError, MAX_JPEG_DIMENSION, checkDimensions } from './dimensions.js';
+export { EncodeError } from './errors.js';
+export type { EncodeErrorCode } from './errors.js';
+export { encodeTiled } from './tiling.js';
+export type { ImageTile, TileManifest, TiledEncodeOptions, TiledEncodeResult } from './tiling.js';
+export { requantize, requantizeRows } from './requantize.js';
+export type { CustomQuantTables, RequantizeOptions, StripQuality } from './requantize.js';
+export { CHROMA_DETAIL_THRESHOLD, ChromaAnalyzer } from './chroma.js';
+export type { ChromaAnalysis } from './chroma.js';
+export { encodeToTarget } from './target.js';
+export type { TargetQualityOptions, TargetQualityResult } from './target.js';
+export { ssim } from './ssim.js';
+export { encodeQualityLadder } from './ladder.js';
+export type { QualityLadderOptions, QualityRung } from './ladder.js';
+export { ENCODER_PRESETS, applyPreset } from './presets.js';
+export type { EncoderPreset } from './presets.js';
+export { AlphaCollector, mergeAlpha, packBits, unpackBits } from './alpha.js';
+export type { AlphaMask, AlphaMaskFormat, AlphaOutputOptions } from './alpha.js';
+export { applyFrameLayout, validateFrameLayout } from './layout.js';
+export type { ComponentLayout, FrameLayout } from './layout.js';
+export { addStripIndex, buildStripIndex, readStripIndex } from './stripindex.js';
+export type { StripIndex, StripIndexSegment } from './stripindex.js';
+export { decodeRegion } from './seekable.js';
+export type { ImageRegion, RangeFetch, RegionDecodeOptions } from './seekable.js';
+export { addRecoveryPoints, decodePartial } from './recovery.js';
+export type { RecoveryPointsResult, PartialImage } from './recovery.js';
+export { createRowFlushEncoder } from './lowlatency.js';
+export type { BandEncoderFactory } from './lowlatency.js';
+export { qualityQuantTables, qualityScaleFactor } from './scaling.js';
+export type { QualityCurve, QualityScaling } from './scaling.js';
+export { Base64Encoder, Base64EncoderStream } from './base64.js';
+export { limitMcuBits } from './budget.js';
+export type { McuBudgetResult } from './budget.js';
+export { estimateSize } from './estimate.js';
+export type { SizeEstimate, SizeEstimateOptions } from './estimate.js';
+export { ChecksumSink, Crc32, Sha256, createChecksum, toBase64, toHex } from './checksum.js';
+export type { Checksum, ChecksumAlgorithm } from './checksum.js';
+export { BatchEncoder } from './batch.js';
+export type { BatchEncoderOptions, BatchItem, BatchResult } from './batch.js';
@@ -0,0 +1,29 @@
+/**
+ * Encoded input formats
+ * Recognizes image files passed as byte sources and opens a strip source that
+ * decodes them incrementally.
+ */
+
+import type { StripSource } from './pipeline.js';
+import { isPng, pngSource } from './png.js';
+import { isPnm, pnmSource } from './pnm.js';
+import { isQoi, qoiSource } from './qoi.js';
+
+/**
+ * Open a strip source for a recognized image file, or return undefined for raw RGBA
+ * Netpbm and QOI magic numbers are short enough to occur in raw pixel data ("qoif"
+ * is a single RGBA pixel), so they are only recognized when the caller has not
+ * supplied raw dimensions.
+ */
+export function decodedSource(bytes: Uint8Array, rawDimensions = false): StripSource | undefined {
+  if (isPng(bytes)) {
+    return pngSource(bytes);
+  }
+  if (!rawDimensions && isQoi(bytes)) {
+    return qoiSource(bytes);
+  }
+  if (!rawDimensions && isPnm(bytes)) {
+    return pnmSource(bytes);
+  }
+  return undefined;
+}
@@ -0,0 +1,401 @@
+/**
+ * JPEG stream parsing
+ * Splits an encoded stream into marker segments and decodes the headers the
+ * inspection and analysis helpers rely on (frame, tables, scans, restarts).
+ */
+
+/**
+ * Marker codes (the byte following 0xFF)
+ */
+export const Marker = {
+  SOF0: 0xc0,
+  SOF1: 0xc1,
+  SOF2: 0xc2,
+  DHT: 0xc4,
+  RST0: 0xd0,
+  RST7: 0xd7,
+  SOI: 0xd8,
+  EOI: 0xd9,
+  SOS: 0xda,
+  DQT: 0xdb,
+  DRI: 0xdd,
+  APP0: 0xe0,
+  APP15: 0xef,
+  COM: 0xfe,
+} as const;
+
+/**
+ * Natural (row-major) index of each zig-zag position
+ */
+export const ZIGZAG = new Uint8Array([
+  0, 1, 8, 16, 9, 2, 3, 10,
+  17, 24, 32, 25, 18, 11, 4, 5,
+  12, 19, 26, 33, 40, 48, 41, 34,
+  27, 20, 13, 6, 7, 14, 21, 28,
+  35, 42, 49, 56, 57, 50, 43, 36,
+  29, 22, 15, 23, 30, 37, 44, 51,
+  58, 59, 52, 45, 38, 31, 39, 46,
+  53, 60, 61, 54, 47, 55, 62, 63,
+]);
+
+/**
+ * A marker segment as found in the stream
+ * - offset: position of the 0xFF byte
+ * - length: total size in bytes including the marker itself
+ * - data: segment payload (excludes the marker and the length field)
+ */
+export interface Segment {
+  marker: number;
+  offset: number;
+  length: number;
+  data: Uint8Array;
+}
+
+export interface FrameComponent {
+  id: number;
+  h: number;             // Horizontal sampling factor
+  v: number;             // Vertical sampling factor
+  quantTable: number;
+}
+
+export interface FrameHeader {
+  marker: number;        // SOFn marker that introduced the frame
+  precision: number;
+  width: number;
+  height: number;
+  components: FrameComponent[];
+}
+
+export interface HuffmanSpec {
+  tableClass: number;    // 0 = DC, 1 = AC
+  id: number;
+  counts: Uint8Array;    // Number of codes of each length 1-16 (BITS)
+  symbols: Uint8Array;   // Symbols in code order (HUFFVAL)
+}
+
+export interface QuantTable {
+  id: number;
+  precision: number;     // 0 = 8-bit entries, 1 = 16-bit entries
+  values: Uint16Array;   // 64 entries in zig-zag order
+}
+
+export interface ScanComponent {
+  id: number;
+  dcTable: number;
+  acTable: number;
+}
+
+export interface ScanHeader {
+  components: ScanComponent[];
+  spectralStart: number;
+  spectralEnd: number;
+  approxHigh: number;
+  approxLow: number;
+}
+
+/**
+ * A scan and the table state in effect when it starts
+ * - dataOffset/dataEnd delimit the entropy-coded bytes (restart markers included)
+ */
+export interface Scan {
+  header: ScanHeader;
+  dataOffset: number;
+  dataEnd: number;
+  restartInterval: number;
+  dcTables: Map<number, HuffmanSpec>;
+  acTables: Map<number, HuffmanSpec>;
+  quantTables: Map<number, QuantTable>;
+}
+
+export interface JpegFile {
+  bytes: Uint8Array;
+  segments: Segment[];
+  frame: FrameHeader;
+  scans: Scan[];
+}
+
+/**
+ * Malformed stream error carrying the byte offset where parsing failed
+ */
+export class JpegFormatError extends Error {
+  readonly offset: number;
+
+  constructor(message: string, offset: number) {
+    super(`${message} (offset ${offset})`);
+    this.name = 'JpegFormatError';
+    this.offset = offset;
+  }
+}
+
+export function readUint16(bytes: Uint8Array, offset: number): number {
+  return (bytes[offset] << 8) | bytes[offset + 1];
+}
+
+/**
+ * Return true for markers that stand alone without a length field
+ */
+export function isStandaloneMarker(marker: number): boolean {
+  return marker === Marker.SOI ||
+    marker === Marker.EOI ||
+    marker === 0x01 ||
+    (marker >= Marker.RST0 && marker <= Marker.RST7);
+}
+
+/**
+ * Find the end of an entropy-coded segment starting at `offset`
+ * Skips stuffed bytes (0xFF00) and restart markers, stopping at the next real marker.
+ */
+export function findScanEnd(bytes: Uint8Array, offset: number): number {
+  let pos = offset;
+  while (pos < bytes.length - 1) {
+    if (bytes[pos] === 0xff) {
+      const next = bytes[pos + 1];
+      if (next !== 0x00 && next !== 0xff && (next < Marker.RST0 || next > Marker.RST7)) {
+        return pos;
+      }
+    }
+    pos++;
+  }
+  return bytes.length;
+}
+
+/**
+ * Iterate over the marker segments of a JPEG stream
+ * Entropy-coded data is skipped; each SOS segment is followed by its scan data.
+ * Segments are yielded as they are found, so callers see everything preceding a
+ * malformed segment before the error is thrown.
+ */
+export function* iterateSegments(bytes: Uint8Array): Generator<Segment> {
+  if (bytes.length < 2 || bytes[0] !== 0xff || bytes[1] !== Marker.SOI) {
+    throw new JpegFormatError('Not a JPEG stream: missing SOI marker', 0);
+  }
+
+  let pos = 0;
+  while (pos < bytes.length) {
+    if (bytes[pos] !== 0xff) {
+      throw new JpegFormatError(`Expected marker, found 0x${bytes[pos].toString(16)}`, pos);
+    }
+    // Skip fill bytes preceding a marker
+    let markerPos = pos;
+    while (markerPos + 1 < bytes.length && bytes[markerPos + 1] === 0xff) {
+      markerPos++;
+    }
+    if (markerPos + 1 >= bytes.length) {
+      throw new JpegFormatError('Truncated marker', pos);
+    }
+    const marker = bytes[markerPos + 1];
+
+    if (isStandaloneMarker(marker)) {
+      yield { marker, offset: markerPos, length: 2, data: bytes.subarray(markerPos + 2, markerPos + 2) };
+      pos = markerPos + 2;
+      if (marker === Marker.EOI) {
+        return;
+      }
+      continue;
+    }
+
+    if (markerPos + 4 > bytes.length) {
+      throw new JpegFormatError(`Truncated segment length for marker 0x${marker.toString(16)}`, markerPos);
+    }
+    const length = readUint16(bytes, markerPos + 2);
+    const end = markerPos + 2 + length;
+    if (length < 2 || end > bytes.length) {
+      throw new JpegFormatError(`Segment 0x${marker.toString(16)} overruns the stream`, markerPos);
+    }
+    yield { marker, offset: markerPos, length: length + 2, data: bytes.subarray(markerPos + 4, end) };
+    pos = end;
+
+    if (marker === Marker.SOS) {
+      pos = findScanEnd(bytes, pos);
+    }
+  }
+}
+
+/**
+ * Split a JPEG stream into its marker segments
+ */
+export function readSegments(bytes: Uint8Array): Segment[] {
+  return Array.from(iterateSegments(bytes));
+}
+
+/**
+ * Decode a SOFn payload
+ */
+export function parseFrameHeader(marker: number, data: Uint8Array): FrameHeader {
+  if (data.length < 6) {
+    throw new Error('Frame header is too short');
+  }
+  const count = data[5];
+  if (data.length < 6 + count * 3) {
+    throw new Error(`Frame header declares ${count} components but is only ${data.length} bytes`);
+  }
+  const components: FrameComponent[] = [];
+  for (let i = 0; i < count; i++) {
+    const base = 6 + i * 3;
+    components.push({
+      id: data[base],
+      h: data[base + 1] >> 4,
+      v: data[base + 1] & 0x0f,
+      quantTable: data[base + 2],
+    });
+  }
+  return {
+    marker,
+    precision: data[0],
+    height: readUint16(data, 1),
+    width: readUint16(data, 3),
+    components,
+  };
+}
+
+/**
+ * Decode a DHT payload (which may carry several tables)
+ */
+export function parseHuffmanTables(data: Uint8Array): HuffmanSpec[] {
+  const tables: HuffmanSpec[] = [];
+  let pos = 0;
+  while (pos < data.length) {
+    if (pos + 17 > data.length) {
+      throw new Error('Huffman table definition is truncated');
+    }
+    const tableClass = data[pos] >> 4;
+    const id = data[pos] & 0x0f;
+    const counts = data.slice(pos + 1, pos + 17);
+    const total = counts.reduce((sum, count) => sum + count, 0);
+    if (pos + 17 + total > data.length) {
+      throw new Error('Huffman table symbols are truncated');
+    }
+    const symbols = data.slice(pos + 17, pos + 17 + total);
+    tables.push({ tableClass, id, counts, symbols });
+    pos += 17 + total;
+  }
+  return tables;
+}
+
+/**
+ * Decode a DQT payload (which may carry several tables)
+ */
+export function parseQuantTables(data: Uint8Array): QuantTable[] {
+  const tables: QuantTable[] = [];
+  let pos = 0;
+  while (pos < data.length) {
+    const precision = data[pos] >> 4;
+    const id = data[pos] & 0x0f;
+    const entrySize = precision === 0 ? 1 : 2;
+    if (pos + 1 + 64 * entrySize > data.length) {
+      throw new Error('Quantization table definition is truncated');
+    }
+    const values = new Uint16Array(64);
+    for (let i = 0; i < 64; i++) {
+      values[i] = precision === 0
+        ? data[pos + 1 + i]
+        : readUint16(data, pos + 1 + i * 2);
+    }
+    tables.push({ id, precision, values });
+    pos += 1 + 64 * entrySize;
+  }
+  return tables;
+}
+
+/**
+ * Decode a SOS payload
+ */
+export function parseScanHeader(data: Uint8Array): ScanHeader {
+  const count = data[0];
+  if (data.length < 1 + count * 2 + 3) {
+    throw new Error('Scan header is truncated');
+  }
+  const components: ScanComponent[] = [];
+  for (let i = 0; i < count; i++) {
+    const base = 1 + i * 2;
+    components.push({
+      id: data[base],
+      dcTable: data[base + 1] >> 4,
+      acTable: data[base + 1] & 0x0f,
+    });
+  }
+  const tail = 1 + count * 2;
+  return {
+    components,
+    spectralStart: data[tail],
+    spectralEnd: data[tail + 1],
+    approxHigh: data[tail + 2] >> 4,
+    approxLow: data[tail + 2] & 0x0f,
+  };
+}
+
+/**
+ * Return true if the marker starts a frame (SOF0-SOF15, excluding DHT/JPG/DAC)
+ */
+export function isFrameMarker(marker: number): boolean {
+  return marker >= Marker.SOF0 && marker <= 0xcf &&
+    marker !== Marker.DHT && marker !== 0xc8 && marker !== 0xcc;
+}
+
+/**
+ * Parse a complete JPEG stream into its frame header, tables, and scans
+ */
+export function parseJpeg(bytes: Uint8Array): JpegFile {
+  const segments = readSegments(bytes);
+  const dcTables = new Map<number, HuffmanSpec>();
+  const acTables = new Map<number, HuffmanSpec>();
+  const quantTables = new Map<number, QuantTable>();
+  let restartInterval = 0;
+  let frame: FrameHeader | undefined;
+  const scans: Scan[] = [];
+
+  for (const segment of segments) {
+    try {
+      if (isFrameMarker(segment.marker)) {
+        if (frame) {
+          throw new Error('Multiple frames are not supported');
+        }
+        frame = parseFrameHeader(segment.marker, segment.data);
+      } else if (segment.marker === Marker.DHT) {
+        for (const table of parseHuffmanTables(segment.data)) {
+          (table.tableClass === 0 ? dcTables : acTables).set(table.id, table);
+        }
+      } else if (segment.marker === Marker.DQT) {
+        for (const table of parseQuantTables(segment.data)) {
+          quantTables.set(table.id, table);
+        }
+      } else if (segment.marker === Marker.DRI) {
+        restartInterval = readUint16(segment.data, 0);
+      } else if (segment.marker === Marker.SOS) {
+        if (!frame) {
+          throw new Error('Scan found before frame header');
+        }
+        const dataOffset = segment.offset + segment.length;
+        scans.push({
+          header: parseScanHeader(segment.data),
+          dataOffset,
+          dataEnd: findScanEnd(bytes, dataOffset),
+          restartInterval,
+          dcTables: new Map(dcTables),
+          acTables: new Map(acTables),
+          quantTables: new Map(quantTables),
+        });
+      }
+    } catch (error) {
+      if (error instanceof JpegFormatError) {
+        throw error;
+      }
+      throw new JpegFormatError((error as Error).message, segment.offset);
+    }
+  }
+
+  if (!frame) {
+    throw new JpegFormatError('No frame header found', bytes.length);
+  }
+  return { bytes, segments, frame, scans };
+}
+
+/**
+ * Complete APPn and COM segments ahead of the first scan, for copying into a re-coded file
+ */
+export function metadataSegments(file: JpegFile): Uint8Array[] {
+  return file.segments
+    .filter((s) => s.offset < file.scans[0].dataOffset &&
+      ((s.marker >= Marker.APP0 && s.marker <= Marker.APP15) || s.marker === Marker.COM))
+    .map((s) => file.bytes.subarray(s.offset, s.offset + s.length));
+}
@@ -0,0 +1,75 @@
+/**
+ * JPS stereoscopic JPEG support
+ * A JPS file is an ordinary JPEG holding both views side by side, tagged with an
+ * APP3 "_JPSJPS_" descriptor so stereo viewers know how to split it.
+ */
+
+import { Marker } from './jpeg.js';
+import { createSegment, insertSegments } from './markers.js';
+
+export interface JpsOptions {
+  leftFirst?: boolean;    // Place the left view on the left (parallel); JPS defaults to right first (cross-eyed)
+  separation?: number;    // Recommended separation in pixels (0-255), defaults to 0
+  comment?: string;       // Optional ASCII comment block
+}
+
+const JPS_IDENTIFIER = '_JPSJPS_';
+const MEDIA_TYPE_STEREO = 0x01;
+const LAYOUT_SIDE_BY_SIDE = 0x02;
+const FLAG_LEFT_FIELD_FIRST = 0x04;
+
+/**
+ * Build the APP3 JPS descriptor segment for a side-by-side stereo image
+ */
+export function jpsSegment(options: JpsOptions = {}): Uint8Array {
+  const comment = options.comment ?? '';
+  const payload = new Uint8Array(8 + 2 + 4 + (comment ? 2 + comment.length : 0));
+  for (let i = 0; i < JPS_IDENTIFIER.length; i++) {
+    payload[i] = JPS_IDENTIFIER.charCodeAt(i);
+  }
+  const view = new DataView(payload.buffer);
+  view.setUint16(8, 4); // Descriptor block length
+  // Descriptor: separation, layout, media flags, media type (most to least significant)
+  payload[10] = Math.max(0, Math.min(255, Math.round(options.separation ?? 0)));
+  payload[11] = LAYOUT_SIDE_BY_SIDE;
+  payload[12] = options.leftFirst ? FLAG_LEFT_FIELD_FIRST : 0;
+  payload[13] = MEDIA_TYPE_STEREO;
+  if (comment) {
+    view.setUint16(14, comment.length);
+    for (let i = 0; i < comment.length; i++) {
+      payload[16 + i] = comment.charCodeAt(i) & 0x7f;
+    }
+  }
+  return createSegment(Marker.APP0 + 3, payload);
+}
+
+/**
+ * Place two RGBA views of equal size next to each other
+ *
+ * @param left Left-eye RGBA pixels
+ * @param right Right-eye RGBA pixels
+ * @param width Width of one view
+ * @param height Height of one view
+ * @param leftFirst Put the left view on the left; otherwise the right view goes first
+ */
+export function sideBySide(left: Uint8Array, right: Uint8Array, width: number, height: number, leftFirst = false): Uint8Array {
+  const viewSize = width * height * 4;
+  if (left.length < viewSize || right.length < viewSize) {
+    throw new Error(`Buffer too small: expected at least ${viewSize} bytes per ${width}x${height} RGBA view`);
+  }
+  const [first, second] = leftFirst ? [left, right] : [right, left];
+  const rowBytes = width * 4;
+  const output = new Uint8Array(viewSize * 2);
+  for (let y = 0; y < height; y++) {
+    output.set(first.subarray(y * rowBytes, (y + 1) * rowBytes), y * rowBytes * 2);
+    output.set(second.subarray(y * rowBytes, (y + 1) * rowBytes), y * rowBytes * 2 + rowBytes);
+  }
+  return output;
+}
+
+/**
+ * Tag an encoded side-by-side JPEG as JPS
+ */
+export function addJpsDescriptor(jpeg: Uint8Array, options: JpsOptions = {}): Uint8Array {
+  return insertSegments(jpeg, [jpsSegment(options)]);
+}
@@ -0,0 +1,66 @@
+/**
+ * Quality ladders
+ * Encodes an image once at quality 100, where every quantizer is 1 and the
+ * coefficients are the rounded DCT output, then quantizes and entropy-codes
+ * those coefficients again for each requested quality. The source is read and
+ * transformed a single time however many outputs are produced, and chroma
+ * subsampling is done once for all of them.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { createJpegStripEncoder, openStripSource, reshapeEncoded } from './encoder.js';
+import { qualityQuantTables } from './scaling.js';
+import { applyPreset } from './presets.js';
+import { toSubsampled } from './subsample.js';
+import { ChromaAnalyzer } from './chroma.js';
+import { CollectingSink, runPipeline } from './pipeline.js';
+import { EncodeError } from './errors.js';
+
+export interface QualityLadderOptions extends Omit<EncodeOptions, 'quality' | 'quantTables' | 'compat' | 'thumbnail'> {
+  qualities: number[];   // 1-100 each; outputs come back in this order
+}
+
+export interface QualityRung {
+  quality: number;
+  jpeg: Uint8Array;
+}
+
+/**
+ * Encode an image at several qualities from one pass over the source
+ * Each rung uses the tables the engine would scale for its quality (under
+ * `qualityScaling`, when given), so files
+ * match a direct encode to within a rounding step per coefficient. Subsampling,
+ * progressive output, and the other reshaping options apply to every rung.
+ *
+ * @example
+ * const rungs = await encodeQualityLadder('photo.png', { qualities: [40, 60, 80], preset: 'web' });
+ * for (const { quality, jpeg } of rungs) await writeFile(`photo-q${quality}.jpg`, jpeg);
+ */
+export async function encodeQualityLadder(source: ImageSource, ladderOptions: QualityLadderOptions): Promise<QualityRung[]> {
+  const options = applyPreset(ladderOptions);
+  const { qualities } = options;
+  if (!Array.isArray(qualities) || qualities.length === 0) {
+    throw new Error('At least one quality is required');
+  }
+  for (const quality of qualities) {
+    if (!Number.isInteger(quality) || quality < 1 || quality > 100) {
+      throw new EncodeError('bad-quality', `Quality must be an integer 1-100, got ${quality}`);
+    }
+  }
+
+  const strips = await openStripSource(source, options);
+  const chroma = options.subsampling === 'auto' ? new ChromaAnalyzer(strips.width) : undefined;
+  const sink = new CollectingSink();
+  await runPipeline(strips, createJpegStripEncoder(strips.width, strips.height, 100), sink, {
+    onStrip: (strip) => chroma?.addStrip(strip),
+  });
+
+  // Chroma is resampled once, at full precision, and shared by every rung
+  const subsampling = (options.subsampling === 'auto' ? chroma?.finish().subsampling : options.subsampling) ?? '4:4:4';
+  const base = subsampling === '4:4:4' ? sink.toBytes() : toSubsampled(sink.toBytes(), subsampling);
+
+  return qualities.map((quality) => {
+    const [luma, chroma] = qualityQuantTables(quality, options.qualityScaling);
+    return { quality, jpeg: reshapeEncoded(base, { ...options, subsampling: undefined, quantTables: { luma, chroma } }) };
+  });
+}
@@ -0,0 +1,182 @@
+/**
+ * Custom frame layouts
+ * Lets advanced users spell out the frame the encoder writes: component count,
+ * identifiers, sampling factors, and table slots, for example a Y-only image
+ * with a nonstandard component ID for a legacy consumer. The layout is checked
+ * for consistency up front, then the encoded image is re-coded to match it.
+ */
+
+import { Marker, metadataSegments, parseJpeg } from './jpeg.js';
+import type { QuantTable } from './jpeg.js';
+import { readCoefficients } from './decoder.js';
+import { writeBaseline } from './transcode.js';
+import type { CodedComponent } from './transcode.js';
+import { subsamplingForRatio, toSubsampled } from './subsample.js';
+import type { ChromaSubsampling } from './subsample.js';
+
+export interface ComponentLayout {
+  id: number;             // Identifier written in SOF and SOS, 0-255
+  h?: number;             // Horizontal sampling factor 1-4, defaults to 1
+  v?: number;             // Vertical sampling factor 1-4, defaults to 1
+  quantTable?: number;    // Quantization table slot 0-3, defaults to 0 for luma and 1 for chroma
+  huffmanTable?: number;  // DC and AC table slot 0-1, defaults to 0 for luma and 1 for chroma
+}
+
+/**
+ * - components: Y alone, or Y, Cb, Cr in that order
+ */
+export interface FrameLayout {
+  components: ComponentLayout[];
+}
+
+// Baseline limit on data units in one MCU (T.81 B.2.3)
+const MAX_MCU_BLOCKS = 10;
+
+function checkRange(value: number, min: number, max: number, what: string): void {
+  if (!Number.isInteger(value) || value < min || value > max) {
+    throw new Error(`${what} must be an integer ${min}-${max}, got ${value}`);
+  }
+}
+
+/**
+ * Check a layout for consistency
+ *
+ * @returns The chroma subsampling the sampling factors describe ('4:4:4' for Y alone)
+ */
+export function validateFrameLayout(layout: FrameLayout): ChromaSubsampling {
+  const { components } = layout;
+  if (components.length !== 1 && components.length !== 3) {
+    throw new Error(`A frame layout needs 1 (Y) or 3 (Y, Cb, Cr) components, got ${components.length}`);
+  }
+  const ids = new Set<number>();
+  components.forEach((c, index) => {
+    const name = `Component ${index}`;
+    checkRange(c.id, 0, 255, `${name} id`);
+    if (ids.has(c.id)) {
+      throw new Error(`${name} repeats id ${c.id}`);
+    }
+    ids.add(c.id);
+    checkRange(c.h ?? 1, 1, 4, `${name} horizontal sampling factor`);
+    checkRange(c.v ?? 1, 1, 4, `${name} vertical sampling factor`);
+    checkRange(c.quantTable ?? 0, 0, 3, `${name} quantization table`);
+    checkRange(c.huffmanTable ?? 0, 0, 1, `${name} Huffman table`);
+  });
+  if (components.length === 1) {
+    return '4:4:4';
+  }
+
+  const blocks = components.reduce((sum, c) => sum + (c.h ?? 1) * (c.v ?? 1), 0);
+  if (blocks > MAX_MCU_BLOCKS) {
+    throw new Error(`An MCU would hold ${blocks} blocks; at most ${MAX_MCU_BLOCKS} are allowed`);
+  }
+  const [luma, cb, cr] = components.map((c) => [c.h ?? 1, c.v ?? 1]);
+  if (cb[0] !== cr[0] || cb[1] !== cr[1]) {
+    throw new Error('Cb and Cr must have the same sampling factors');
+  }
+  const mode = luma[0] % cb[0] === 0 && luma[1] % cb[1] === 0
+    ? subsamplingForRatio(luma[0] / cb[0], luma[1] / cb[1])
+    : undefined;
+  if (!mode) {
+    throw new Error(`Sampling factors ${luma.join('x')} for Y and ${cb.join('x')} for chroma match no supported subsampling`);
+  }
+  return mode;
+}
+
+/**
+ * Re-code an encoded image with the given frame layout
+ * Y alone keeps only the luma coefficients. Chroma is subsampled when the
+ * factors ask for it, and component grids are widened to the layout's MCUs.
+ * Components given the same quantization slot share the table of the first
+ * of them, the others being re-quantized to it. APPn and COM segments are
+ * kept and restart intervals are dropped.
+ *
+ * @param jpeg Sequential 8-bit JPEG, 4:4:4 or already subsampled to the layout's ratio
+ */
+export function applyFrameLayout(jpeg: Uint8Array, layout: FrameLayout): Uint8Array {
+  const mode = validateFrameLayout(layout);
+  let file = parseJpeg(jpeg);
+  const check = (): void => {
+    if ((file.frame.marker !== Marker.SOF0 && file.frame.marker !== Marker.SOF1) || file.frame.precision !== 8 || file.scans.length !== 1) {
+      throw new Error('A frame layout needs an 8-bit sequential JPEG with a single scan');
+    }
+  };
+  check();
+  if (file.frame.components.length < layout.components.length) {
+    throw new Error(`The image has ${file.frame.components.length} component(s); the layout needs ${layout.components.length}`);
+  }
+  if (layout.components.length === 3) {
+    const [y, cb] = file.frame.components;
+    const current = y.h % cb.h === 0 && y.v % cb.v === 0 ? subsamplingForRatio(y.h / cb.h, y.v / cb.v) : undefined;
+    if (current !== mode) {
+      if (current !== '4:4:4') {
+        throw new Error(`The image has ${current ?? 'nonstandard'} subsampling; the layout needs ${mode}`);
+      }
+      jpeg = toSubsampled(jpeg, mode);
+      file = parseJpeg(jpeg);
+      check();
+    }
+  }
+
+  const { frame } = file;
+  const { width, height } = frame;
+  const coefficients = readCoefficients(file);
+  const single = layout.components.length === 1;
+  const maxH = Math.max(...layout.components.map((c) => c.h ?? 1));
+  const maxV = Math.max(...layout.components.map((c) => c.v ?? 1));
+  const mcusPerLine = Math.ceil(width / (8 * maxH));
+  const mcusPerColumn = Math.ceil(height / (8 * maxV));
+  const sourceMaxH = Math.max(...frame.components.map((c) => c.h));
+  const sourceMaxV = Math.max(...frame.components.map((c) => c.v));
+
+  const slots = new Map<number, Uint16Array>();
+  const components: CodedComponent[] = layout.components.map((c, index) => {
+    const source = coefficients[index];
+    const h = c.h ?? 1;
+    const v = c.v ?? 1;
+
+    // Blocks holding image samples, the same for the source and the layout since the ratios match
+    const sourceComponent = frame.components[index];
+    const usedLine = Math.ceil(Math.ceil((width * sourceComponent.h) / sourceMaxH) / 8);
+    const usedColumn = Math.ceil(Math.ceil((height * sourceComponent.v) / sourceMaxV) / 8);
+    const blocksPerLine = single ? usedLine : mcusPerLine * h;
+    const blocksPerColumn = single ? usedColumn : mcusPerColumn * v;
+
+    // Components sharing a slot are re-quantized to the first one's table
+    const slot = c.quantTable ?? (index === 0 ? 0 : 1);
+    const table = slots.get(slot) ?? Uint16Array.from(source.quantTable);
+    slots.set(slot, table);
+
+    // Padding blocks repeat the edge DC, as when subsampling
+    const blocks = new Int32Array(blocksPerLine * blocksPerColumn * 64);
+    for (let row = 0; row < blocksPerColumn; row++) {
+      for (let col = 0; col < blocksPerLine; col++) {
+        const target = (row * blocksPerLine + col) * 64;
+        const from = (Math.min(row, usedColumn - 1) * source.blocksPerLine + Math.min(col, usedLine - 1)) * 64;
+        const count = row < usedColumn && col < usedLine ? 64 : 1;
+        for (let k = 0; k < count; k++) {
+          blocks[target + k] = table[k] === source.quantTable[k]
+            ? source.blocks[from + k]
+            : Math.round((source.blocks[from + k] * source.quantTable[k]) / table[k]);
+        }
+      }
+    }
+
+    return {
+      id: c.id,
+      h,
+      v,
+      quantId: slot,
+      huffmanId: c.huffmanTable ?? (index === 0 ? 0 : 1),
+      blocksPerLine,
+      blocksPerColumn,
+      blocks,
+    };
+  });
+
+  const quantTables: QuantTable[] = [...slots]
+    .sort(([a], [b]) => a - b)
+    .map(([id, values]) => ({ id, precision: values.some((value) => value > 255) ? 1 : 0, values }));
+  const segments = metadataSegments(file);
+
+  return writeBaseline({ width, height, components, quantTables, segments });
+}
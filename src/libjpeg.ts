@@ -0,0 +1,775 @@
+/**
+ * libjpeg-compatible encoding
+ * A strip encoder that reproduces libjpeg's default compressor bit for bit:
+ * fixed-point YCbCr conversion, its downsampling filters and edge padding, the
+ * islow integer DCT, jpeg_set_quality table scaling, and the Annex K Huffman
+ * tables. Output matches `cjpeg -quality Q -sample HxV` (and, through
+ * toProgressive, `-progressive`), so existing libjpeg pipelines can check parity.
+ */
+
+import { Marker, ZIGZAG } from './jpeg.js';
+import type { HuffmanSpec } from './jpeg.js';
+import { BitWriter, buildHuffmanCodes, huffmanTablePayload, magnitudeCategory, optimalHuffmanSpec } from './huffman.js';
+import type { HuffmanCodeTable } from './huffman.js';
+import { createSegment } from './markers.js';
+import { dqtSegment, normalizeQuantTable } from './quant.js';
+import { checkDimensions } from './dimensions.js';
+import { concatBytes } from './pipeline.js';
+import type { StripEncoder } from './pipeline.js';
+import { LUMA_FACTORS } from './subsample.js';
+import type { ChromaSubsampling } from './subsample.js';
+import type { CustomQuantTables } from './requantize.js';
+
+/** T.81 Table K.1, natural order */
+const LUMA_QUANT = [
+  16, 11, 10, 16, 24, 40, 51, 61,
+  12, 12, 14, 19, 26, 58, 60, 55,
+  14, 13, 16, 24, 40, 57, 69, 56,
+  14, 17, 22, 29, 51, 87, 80, 62,
+  18, 22, 37, 56, 68, 109, 103, 77,
+  24, 35, 55, 64, 81, 104, 113, 92,
+  49, 64, 78, 87, 103, 121, 120, 101,
+  72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/** T.81 Table K.2, natural order */
+const CHROMA_QUANT = [
+  17, 18, 24, 47, 99, 99, 99, 99,
+  18, 21, 26, 66, 99, 99, 99, 99,
+  24, 26, 56, 99, 99, 99, 99, 99,
+  47, 66, 99, 99, 99, 99, 99, 99,
+  99, 99, 99, 99, 99, 99, 99, 99,
+  99, 99, 99, 99, 99, 99, 99, 99,
+  99, 99, 99, 99, 99, 99, 99, 99,
+  99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+/** T.81 Tables K.3-K.6: luma DC, luma AC, chroma DC, chroma AC */
+export const STANDARD_TABLES: HuffmanSpec[] = [
+  {
+    tableClass: 0,
+    id: 0,
+    counts: Uint8Array.from([0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0]),
+    symbols: Uint8Array.from([0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b]),
+  },
+  {
+    tableClass: 1,
+    id: 0,
+    counts: Uint8Array.from([0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 125]),
+    symbols: Uint8Array.from([
+      0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+      0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+      0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+      0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+      0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+      0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+      0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+      0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+      0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+      0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+      0xf9, 0xfa,
+    ]),
+  },
+  {
+    tableClass: 0,
+    id: 1,
+    counts: Uint8Array.from([0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0]),
+    symbols: Uint8Array.from([0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b]),
+  },
+  {
+    tableClass: 1,
+    id: 1,
+    counts: Uint8Array.from([0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 119]),
+    symbols: Uint8Array.from([
+      0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+      0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+      0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+      0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+      0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+      0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+      0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+      0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+      0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+      0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+      0xf9, 0xfa,
+    ]),
+  },
+];
+
+// Code lookups for the standard tables, built once and shared by every encoder
+let standardCodes: HuffmanCodeTable[] | undefined;
+export const sharedStandardCodes = (): HuffmanCodeTable[] => (standardCodes ??= STANDARD_TABLES.map(buildHuffmanCodes));
+
+/**
+ * Annex K tables scaled by `scale` percent, as jpeg_set_linear_quality(scale, TRUE) would install
+ * Returns the luma and chroma tables in natural order, clamped to 1-255.
+ */
+export function scaledQuantTables(scale: number): [Uint16Array, Uint16Array] {
+  const scaled = (table: number[]): Uint16Array =>
+    Uint16Array.from(table, (value) => Math.max(1, Math.min(255, Math.floor((value * scale + 50) / 100))));
+  return [scaled(LUMA_QUANT), scaled(CHROMA_QUANT)];
+}
+
+/**
+ * Quantization tables jpeg_set_quality(quality, TRUE) would install
+ * Returns the luma and chroma tables in natural order, clamped to 1-255.
+ */
+export function libjpegQuantTables(quality: number): [Uint16Array, Uint16Array] {
+  const q = Math.max(1, Math.min(100, Math.round(quality)));
+  return scaledQuantTables(q < 50 ? Math.floor(5000 / q) : 200 - q * 2);
+}
+
+// jfdctint.c constants: 13 fraction bits, 2 extra bits kept between passes
+const CONST_BITS = 13;
+const PASS1_BITS = 2;
+const FIX_0_298631336 = 2446;
+const FIX_0_390180644 = 3196;
+const FIX_0_541196100 = 4433;
+const FIX_0_765366865 = 6270;
+const FIX_0_899976223 = 7373;
+const FIX_1_175875602 = 9633;
+const FIX_1_501321110 = 12299;
+const FIX_1_847759065 = 15137;
+const FIX_1_961570560 = 16069;
+const FIX_2_053119869 = 16819;
+const FIX_2_562915447 = 20995;
+const FIX_3_072711026 = 25172;
+
+const descale = (value: number, shift: number): number => (value + (1 << (shift - 1))) >> shift;
+
+/**
+ * libjpeg's islow forward DCT, in place on 64 level-shifted samples
+ * Results are in natural order and scaled up by 8, as jpeg_fdct_islow leaves them.
+ */
+export function islowForwardDct(data: Int32Array): void {
+  // Pass 1 works on rows, pass 2 on columns; only the scaling differs
+  for (let pass = 0; pass < 2; pass++) {
+    const step = pass === 0 ? 1 : 8;
+    const stride = pass === 0 ? 8 : 1;
+    const evenShift = pass === 0 ? 0 : PASS1_BITS;
+    const oddShift = pass === 0 ? CONST_BITS - PASS1_BITS : CONST_BITS + PASS1_BITS;
+    for (let line = 0; line < 8; line++) {
+      const at = line * stride;
+      const d0 = data[at];
+      const d1 = data[at + step];
+      const d2 = data[at + 2 * step];
+      const d3 = data[at + 3 * step];
+      const d4 = data[at + 4 * step];
+      const d5 = data[at + 5 * step];
+      const d6 = data[at + 6 * step];
+      const d7 = data[at + 7 * step];
+
+      const tmp0 = d0 + d7;
+      const tmp7 = d0 - d7;
+      const tmp1 = d1 + d6;
+      const tmp6 = d1 - d6;
+      const tmp2 = d2 + d5;
+      const tmp5 = d2 - d5;
+      const tmp3 = d3 + d4;
+      const tmp4 = d3 - d4;
+
+      const tmp10 = tmp0 + tmp3;
+      const tmp13 = tmp0 - tmp3;
+      const tmp11 = tmp1 + tmp2;
+      const tmp12 = tmp1 - tmp2;
+
+      if (pass === 0) {
+        data[at] = (tmp10 + tmp11) << PASS1_BITS;
+        data[at + 4 * step] = (tmp10 - tmp11) << PASS1_BITS;
+      } else {
+        data[at] = descale(tmp10 + tmp11, evenShift);
+        data[at + 4 * step] = descale(tmp10 - tmp11, evenShift);
+      }
+      let z1 = (tmp12 + tmp13) * FIX_0_541196100;
+      data[at + 2 * step] = descale(z1 + tmp13 * FIX_0_765366865, oddShift);
+      data[at + 6 * step] = descale(z1 - tmp12 * FIX_1_847759065, oddShift);
+
+      z1 = tmp4 + tmp7;
+      let z2 = tmp5 + tmp6;
+      let z3 = tmp4 + tmp6;
+      let z4 = tmp5 + tmp7;
+      const z5 = (z3 + z4) * FIX_1_175875602;
+      const odd4 = tmp4 * FIX_0_298631336;
+      const odd5 = tmp5 * FIX_2_053119869;
+      const odd6 = tmp6 * FIX_3_072711026;
+      const odd7 = tmp7 * FIX_1_501321110;
+      z1 *= -FIX_0_899976223;
+      z2 *= -FIX_2_562915447;
+      z3 = z3 * -FIX_1_961570560 + z5;
+      z4 = z4 * -FIX_0_390180644 + z5;
+
+      data[at + 7 * step] = descale(odd4 + z1 + z3, oddShift);
+      data[at + 5 * step] = descale(odd5 + z2 + z4, oddShift);
+      data[at + 3 * step] = descale(odd6 + z2 + z3, oddShift);
+      data[at + step] = descale(odd7 + z1 + z4, oddShift);
+    }
+  }
+}
+
+// jccolor.c fixed-point coefficients, 16 fraction bits
+const SCALE = 1 << 16;
+const HALF = 1 << 15;
+const fix = (value: number): number => Math.floor(value * SCALE + 0.5);
+const Y_R = fix(0.299);
+const Y_G = fix(0.587);
+const Y_B = fix(0.114);
+const CB_R = fix(0.16874);
+const CB_G = fix(0.33126);
+const CR_G = fix(0.41869);
+const CR_B = fix(0.08131);
+const C_HALF = fix(0.5);
+const C_OFFSET = 128 * SCALE + HALF - 1;
+
+interface Component {
+  h: number;
+  v: number;
+  table: number;
+  blocksPerLine: number;    // Blocks holding image data
+  blocksPerColumn: number;
+  rows: Uint8Array;         // One iMCU row of downsampled samples, blocksPerLine * 8 wide
+  blocks: Int32Array;       // Quantized zig-zag coefficients of the iMCU row, MCU-padded
+  blockStride: number;      // Blocks per line in `blocks`
+}
+
+/**
+ * Forward transform of one block
+ * Works in place on 64 level-shifted samples in natural order and leaves
+ * coefficients in natural order scaled up by 8, as `islowForwardDct` does.
+ */
+export interface BlockTransform {
+  forward(data: Int32Array): void;
+}
+
+/**
+ * Quantization of one transformed block
+ * Reads the natural-order output of the transform and writes 64 zig-zag
+ * values to `out` from `offset`. `table` is the component's natural-order
+ * quantization table and `component` its index (0 for luma).
+ */
+export interface BlockQuantizer {
+  quantize(coefficients: Int32Array, table: ArrayLike<number>, out: Int32Array, offset: number, component: number): void;
+}
+
+/**
+ * libjpeg's integer DCT (jfdctint.c)
+ */
+export const ISLOW_TRANSFORM: BlockTransform = { forward: islowForwardDct };
+
+/**
+ * libjpeg's rounding division (forward_DCT in jcdctmgr.c)
+ */
+export const LIBJPEG_QUANTIZER: BlockQuantizer = {
+  quantize(coefficients, table, out, offset): void {
+    for (let k = 0; k < 64; k++) {
+      const natural = ZIGZAG[k];
+      const divisor = table[natural] << 3;
+      const value = coefficients[natural];
+      const magnitude = Math.floor((Math.abs(value) + (divisor >> 1)) / divisor);
+      out[offset + k] = value < 0 ? -magnitude : magnitude;
+    }
+  },
+};
+
+export interface LibjpegEncoderOptions {
+  quality?: number;                  // 1-100, scaled as jpeg_set_quality does; defaults to 100
+  subsampling?: ChromaSubsampling;   // Defaults to libjpeg's 4:2:0
+  grayscale?: boolean;               // Luma only, as jpeg_set_colorspace(JCS_GRAYSCALE): one component, subsampling ignored
+  quantTables?: CustomQuantTables;   // Installed as jpeg_add_quant_table would, instead of scaling by quality
+  optimizeCoding?: boolean;          // Build Huffman tables from the image (optimize_coding); output is held until finish
+  transform?: BlockTransform;        // Replaces the islow DCT; output then no longer matches libjpeg
+  quantizer?: BlockQuantizer;        // Replaces libjpeg's rounding; output then no longer matches libjpeg
+}
+
+/**
+ * Everything after color conversion and downsampling: tables, header,
+ * transform, quantization, and entropy coding of iMCU rows
+ * Callers fill each component's `rows` and call `codeRow`.
+ */
+interface LibjpegCore {
+  components: Component[];
+  stripHeight: number;
+  codeRow(): void;                        // Transform, quantize and code the filled rows as the next iMCU row
+  take(): Uint8Array;                     // Output written so far, the header first
+  finish(rowsSeen: number): Uint8Array;   // Remaining output and EOI; `rowsSeen` is for the error message
+}
+
+function createLibjpegCore(
+  width: number,
+  height: number,
+  options: LibjpegEncoderOptions,
+  sampling: number[][]
+): LibjpegCore {
+  const { quality = 100, optimizeCoding = false } = options;
+  const { transform: transformer = ISLOW_TRANSFORM, quantizer = LIBJPEG_QUANTIZER } = options;
+  checkDimensions(width, height);
+  const [maxH, maxV] = sampling[0];
+  const grayscale = sampling.length === 1;
+  const stripHeight = 8 * maxV;
+  const mcusPerLine = Math.ceil(width / (8 * maxH));
+  const mcusPerColumn = Math.ceil(height / stripHeight);
+
+  const components: Component[] = sampling.map(([h, v], index) => {
+    const blocksPerLine = Math.ceil((width * h) / (maxH * 8));
+    return {
+      h,
+      v,
+      table: index === 0 ? 0 : 1,
+      blocksPerLine,
+      blocksPerColumn: Math.ceil((height * v) / (maxV * 8)),
+      rows: new Uint8Array(blocksPerLine * 8 * 8 * v),
+      blocks: new Int32Array(mcusPerLine * h * v * 64),
+      blockStride: mcusPerLine * h,
+    };
+  });
+
+  const { quantTables } = options;
+  const [lumaQuant, chromaQuant] = quantTables
+    ? [quantTables.luma, quantTables.chroma ?? quantTables.luma].map((table) => normalizeQuantTable(table, quantTables.mode, quantTables.precision))
+    : libjpegQuantTables(quality);
+  // Like libjpeg without force_baseline, entries above 255 make the frame extended sequential
+  const extended = (grayscale ? [lumaQuant] : [lumaQuant, chromaQuant]).some((table) => table.some((value) => value > 255));
+  const divisors = [lumaQuant, chromaQuant];
+  // Grayscale frames write and use only the luma tables, the first DC and AC pair
+  const standardTables = grayscale ? STANDARD_TABLES.slice(0, 2) : STANDARD_TABLES;
+  let tables = standardTables;
+  let codes = sharedStandardCodes();
+  const storedRows: Int32Array[][] = [];
+
+  let mcuRow = 0;
+  let started = false;
+  const predictors = new Int32Array(3);
+  const writer = new BitWriter();
+  const workspace = new Int32Array(64);
+
+  const header = (): Uint8Array => {
+    const sof = [8, height >> 8, height & 0xff, width >> 8, width & 0xff, components.length];
+    const sos = [components.length];
+    components.forEach((c, index) => {
+      sof.push(index + 1, (c.h << 4) | c.v, c.table);
+      sos.push(index + 1, (c.table << 4) | c.table);
+    });
+    sos.push(0, 63, 0);
+    const chunks = [
+      new Uint8Array([0xff, Marker.SOI]),
+      createSegment(Marker.APP0, new Uint8Array([0x4a, 0x46, 0x49, 0x46, 0x00, 1, 1, 0, 0, 1, 0, 1, 0, 0])),
+      dqtSegment([{ id: 0, values: lumaQuant }]),
+      ...(grayscale ? [] : [dqtSegment([{ id: 1, values: chromaQuant }])]),
+      createSegment(extended ? Marker.SOF1 : Marker.SOF0, new Uint8Array(sof)),
+      ...tables.map((spec) => createSegment(Marker.DHT, huffmanTablePayload([spec]))),
+      createSegment(Marker.SOS, new Uint8Array(sos)),
+    ];
+    return concatBytes(chunks);
+  };
+
+  // Transform and quantize one iMCU row; blocks past the image get the DC of their neighbour (jccoefct)
+  const transform = (component: Component, index: number): void => {
+    const { h, v, rows, blocks, blockStride, blocksPerLine } = component;
+    const columns = blocksPerLine * 8;
+    const quant = divisors[component.table];
+    const realRows = Math.min(v, component.blocksPerColumn - mcuRow * v);
+    for (let by = 0; by < v; by++) {
+      for (let bx = 0; bx < blockStride; bx++) {
+        const base = (by * blockStride + bx) * 64;
+        if (by >= realRows) {
+          blocks.fill(0, base, base + 64);
+          const mcuLast = bx - (bx % h) + h - 1;
+          blocks[base] = blocks[((by - 1) * blockStride + mcuLast) * 64];
+          continue;
+        }
+        if (bx >= blocksPerLine) {
+          blocks.fill(0, base, base + 64);
+          blocks[base] = blocks[(by * blockStride + blocksPerLine - 1) * 64];
+          continue;
+        }
+        for (let y = 0; y < 8; y++) {
+          const input = (by * 8 + y) * columns + bx * 8;
+          for (let x = 0; x < 8; x++) {
+            workspace[y * 8 + x] = rows[input + x] - 128;
+          }
+        }
+        transformer.forward(workspace);
+        quantizer.quantize(workspace, quant, blocks, base, index);
+      }
+    }
+  };
+
+  // Symbols of one block (encode_one_block, or htest_one_block when only counting);
+  // tables are indexed as in `codes`, DC then AC for each table id
+  const codeBlock = (
+    blocks: Int32Array,
+    base: number,
+    index: number,
+    table: number,
+    symbol: (tableIndex: number, value: number) => void,
+    bits: (value: number, size: number) => void
+  ): void => {
+    const dc = table * 2;
+    const ac = dc + 1;
+    const diff = blocks[base] - predictors[index];
+    predictors[index] = blocks[base];
+    const dcSize = magnitudeCategory(diff);
+    symbol(dc, dcSize);
+    bits(diff, dcSize);
+    let run = 0;
+    for (let k = 1; k < 64; k++) {
+      const value = blocks[base + k];
+      if (value === 0) {
+        run++;
+        continue;
+      }
+      while (run >= 16) {
+        symbol(ac, 0xf0);
+        run -= 16;
+      }
+      const size = magnitudeCategory(value);
+      symbol(ac, (run << 4) | size);
+      bits(value, size);
+      run = 0;
+    }
+    if (run > 0) {
+      symbol(ac, 0);
+    }
+  };
+
+  const writeSymbol = (tableIndex: number, value: number): void => {
+    writer.writeBits(codes[tableIndex].codes[value], codes[tableIndex].lengths[value]);
+  };
+  const writeBits = (value: number, size: number): void => writer.writeMagnitude(value, size);
+
+  // Code the MCUs of one iMCU row, given each component's blocks
+  const codeMcuRow = (
+    rowBlocks: Int32Array[],
+    symbol: (tableIndex: number, value: number) => void,
+    bits: (value: number, size: number) => void
+  ): void => {
+    for (let mcu = 0; mcu < mcusPerLine; mcu++) {
+      components.forEach((c, index) => {
+        for (let y = 0; y < c.v; y++) {
+          for (let x = 0; x < c.h; x++) {
+            codeBlock(rowBlocks[index], (y * c.blockStride + mcu * c.h + x) * 64, index, c.table, symbol, bits);
+          }
+        }
+      });
+    }
+  };
+
+  // Replace the standard tables with ones built from the stored coefficients, then code them
+  const codeStoredRows = (): void => {
+    const frequencies = standardTables.map(() => new Uint32Array(256));
+    const count = (tableIndex: number, value: number): void => { frequencies[tableIndex][value]++; };
+    for (const rowBlocks of storedRows) {
+      codeMcuRow(rowBlocks, count, () => {});
+    }
+    tables = standardTables.map((spec, i) => optimalHuffmanSpec(frequencies[i], spec.tableClass, spec.id));
+    codes = tables.map(buildHuffmanCodes);
+    predictors.fill(0);
+    for (const rowBlocks of storedRows) {
+      codeMcuRow(rowBlocks, writeSymbol, writeBits);
+    }
+  };
+
+  const output = (data: Uint8Array): Uint8Array => {
+    if (started) {
+      return data;
+    }
+    started = true;
+    const head = header();
+    const bytes = new Uint8Array(head.length + data.length);
+    bytes.set(head);
+    bytes.set(data, head.length);
+    return bytes;
+  };
+
+  return {
+    components,
+    stripHeight,
+    codeRow: (): void => {
+      components.forEach((c, index) => transform(c, index));
+      if (optimizeCoding) {
+        storedRows.push(components.map((c) => c.blocks.slice()));
+      } else {
+        codeMcuRow(components.map((c) => c.blocks), writeSymbol, writeBits);
+      }
+      mcuRow++;
+    },
+    take: (): Uint8Array => (optimizeCoding ? new Uint8Array(0) : output(writer.take())),
+    finish: (rowsSeen: number): Uint8Array => {
+      if (mcuRow < mcusPerColumn) {
+        throw new Error(`Image ended after ${rowsSeen} of ${height} rows`);
+      }
+      if (optimizeCoding) {
+        codeStoredRows();
+      }
+      const tail = writer.finish();
+      const bytes = new Uint8Array(tail.length + 2);
+      bytes.set(tail);
+      bytes.set([0xff, Marker.EOI], tail.length);
+      return output(bytes);
+    },
+  };
+}
+
+/**
+ * Strip encoder that matches libjpeg's baseline output byte for byte
+ * Strips are one iMCU row (16 rows for 4:2:0, 8 otherwise); each produces the
+ * entropy-coded data of its MCUs straight away. Shorter strips are buffered.
+ * In grayscale mode only luma is computed and coded, in a one-component frame.
+ * With `optimizeCoding` the quantized coefficients of the whole image are kept
+ * for the statistics pass, and everything is written by `finish`.
+ */
+export function createLibjpegStripEncoder(width: number, height: number, options: LibjpegEncoderOptions = {}): StripEncoder {
+  const { subsampling = '4:2:0', grayscale = false } = options;
+  const factors = LUMA_FACTORS[subsampling];
+  if (!factors) {
+    throw new Error(`Unknown chroma subsampling: ${subsampling}`);
+  }
+  const [maxH, maxV] = grayscale ? [1, 1] : factors;
+  const core = createLibjpegCore(width, height, options, grayscale ? [[1, 1]] : [[maxH, maxV], [1, 1], [1, 1]]);
+  const { components, stripHeight } = core;
+
+  // Color-converted rows of one row group, padded to the widest downsampling input
+  const paddedWidth = Math.max(...components.map((c) => (c.blocksPerLine * 8 * maxH) / c.h));
+  const planes = components.map(() => new Uint8Array(paddedWidth * maxV));
+
+  const pending = new Uint8Array(width * stripHeight * 4);
+  let pendingRows = 0;
+  let rowsSeen = 0;
+
+  // Convert up to maxV rows into the planes, repeating the last row and column to pad (jcprepct)
+  const convertRowGroup = (rgba: Uint8Array, first: number, count: number): void => {
+    for (let r = 0; r < maxV; r++) {
+      const source = Math.min(r, count - 1);
+      const input = (first + source) * width * 4;
+      const out = r * paddedWidth;
+      for (let x = 0; x < width; x++) {
+        const red = rgba[input + x * 4];
+        const green = rgba[input + x * 4 + 1];
+        const blue = rgba[input + x * 4 + 2];
+        planes[0][out + x] = (Y_R * red + Y_G * green + Y_B * blue + HALF) >> 16;
+        if (grayscale) {
+          continue;
+        }
+        planes[1][out + x] = (-CB_R * red - CB_G * green + C_HALF * blue + C_OFFSET) >> 16;
+        planes[2][out + x] = (C_HALF * red - CR_G * green - CR_B * blue + C_OFFSET) >> 16;
+      }
+      for (const plane of planes) {
+        plane.fill(plane[out + width - 1], out + width, out + paddedWidth);
+      }
+    }
+  };
+
+  // Downsample the row group into the component's iMCU rows (jcsample)
+  const downsample = (component: Component, plane: Uint8Array, group: number): void => {
+    const { h, v, rows } = component;
+    const columns = component.blocksPerLine * 8;
+    const hExpand = maxH / h;
+    const vExpand = maxV / v;
+    for (let r = 0; r < v; r++) {
+      const out = (group * v + r) * columns;
+      if (hExpand === 1 && vExpand === 1) {
+        rows.set(plane.subarray(r * paddedWidth, r * paddedWidth + columns), out);
+      } else if (hExpand === 2 && vExpand === 1) {
+        // h2v1: alternating bias 0, 1
+        const input = r * paddedWidth;
+        for (let x = 0; x < columns; x++) {
+          rows[out + x] = (plane[input + 2 * x] + plane[input + 2 * x + 1] + (x & 1)) >> 1;
+        }
+      } else if (hExpand === 2 && vExpand === 2) {
+        // h2v2: alternating bias 1, 2
+        const above = 2 * r * paddedWidth;
+        const below = above + paddedWidth;
+        for (let x = 0; x < columns; x++) {
+          const sum = plane[above + 2 * x] + plane[above + 2 * x + 1] + plane[below + 2 * x] + plane[below + 2 * x + 1];
+          rows[out + x] = (sum + 1 + (x & 1)) >> 2;
+        }
+      } else {
+        // Generic box filter with rounding, as int_downsample
+        const count = hExpand * vExpand;
+        for (let x = 0; x < columns; x++) {
+          let sum = 0;
+          for (let dy = 0; dy < vExpand; dy++) {
+            const input = (r * vExpand + dy) * paddedWidth + x * hExpand;
+            for (let dx = 0; dx < hExpand; dx++) {
+              sum += plane[input + dx];
+            }
+          }
+          rows[out + x] = Math.floor((sum + (count >> 1)) / count);
+        }
+      }
+    }
+  };
+
+  // Encode the buffered rows as one iMCU row
+  const encodeMcuRow = (): void => {
+    const available = pendingRows;
+    for (let group = 0; group < 8; group++) {
+      const first = group * maxV;
+      if (first < available) {
+        convertRowGroup(pending, first, Math.min(maxV, available - first));
+        components.forEach((c, index) => downsample(c, planes[index], group));
+      } else {
+        // Past the bottom of the image: repeat the last downsampled row
+        for (const c of components) {
+          const columns = c.blocksPerLine * 8;
+          const last = (group * c.v - 1) * columns;
+          for (let r = 0; r < c.v; r++) {
+            c.rows.copyWithin((group * c.v + r) * columns, last, last + columns);
+          }
+        }
+      }
+    }
+    core.codeRow();
+    pendingRows = 0;
+  };
+
+  return {
+    stripHeight,
+    encodeStrip: (strip: Uint8Array): Uint8Array => {
+      const rowBytes = width * 4;
+      const rows = Math.floor(strip.length / rowBytes);
+      if (rowsSeen + rows > height) {
+        throw new Error(`Received more than the ${height} rows of the image`);
+      }
+      for (let row = 0; row < rows; row++) {
+        pending.set(strip.subarray(row * rowBytes, (row + 1) * rowBytes), pendingRows * rowBytes);
+        pendingRows++;
+        rowsSeen++;
+        if (pendingRows === stripHeight || rowsSeen === height) {
+          encodeMcuRow();
+        }
+      }
+      return core.take();
+    },
+    finish: (): Uint8Array => core.finish(rowsSeen),
+  };
+}
+
+/**
+ * Planes of one strip of 4:2:0 YCbCr, as video decoders produce them
+ * Either `u` and `v` (I420) or `uv` with Cb and Cr interleaved (NV12). Chroma
+ * has one row and column per two of luma, rounded up. Samples are full-range
+ * JFIF YCbCr; limited-range video must be expanded first.
+ */
+export interface YuvStrip {
+  y: Uint8Array;
+  u?: Uint8Array;
+  v?: Uint8Array;
+  uv?: Uint8Array;
+  yStride?: number;        // Bytes from one luma row to the next, defaults to the width
+  chromaStride?: number;   // Bytes from one chroma row to the next, defaults to the chroma width (twice that for uv)
+}
+
+/**
+ * Encodes strips of planar YCbCr instead of RGBA
+ */
+export interface YuvStripEncoder {
+  readonly stripHeight: number;
+  encodeStrip(strip: YuvStrip): Uint8Array;
+  finish(): Uint8Array;
+}
+
+/**
+ * 4:2:0 strip encoder fed planar YCbCr, skipping color conversion and downsampling
+ * The planes fill the iMCU rows directly, their last row and column repeated
+ * to pad, and are transformed, quantized and coded as `createLibjpegStripEncoder`
+ * codes its own. Every strip but the last must hold a multiple of 16 rows;
+ * the luma plane's length gives the row count.
+ *
+ * @example
+ * const encoder = createLibjpegYuvEncoder(frame.width, frame.height, { quality: 80 });
+ * const head = encoder.encodeStrip({ y: lumaRows, uv: chromaRows });
+ */
+export function createLibjpegYuvEncoder(
+  width: number,
+  height: number,
+  options: Omit<LibjpegEncoderOptions, 'subsampling' | 'grayscale'> = {}
+): YuvStripEncoder {
+  const core = createLibjpegCore(width, height, options, [[2, 2], [1, 1], [1, 1]]);
+  const { components, stripHeight } = core;
+  const chromaWidth = Math.ceil(width / 2);
+  let rowsSeen = 0;
+
+  // Copy `count` plane rows into the component's iMCU rows, repeating the last column and row to pad
+  const fill = (component: Component, plane: Uint8Array, stride: number, step: number, offset: number, columns: number, count: number): void => {
+    const paddedColumns = component.blocksPerLine * 8;
+    const rowCount = component.v * 8;
+    for (let r = 0; r < rowCount; r++) {
+      const out = r * paddedColumns;
+      if (r >= count) {
+        component.rows.copyWithin(out, out - paddedColumns, out);
+        continue;
+      }
+      const input = r * stride + offset;
+      for (let x = 0; x < columns; x++) {
+        component.rows[out + x] = plane[input + x * step];
+      }
+      component.rows.fill(component.rows[out + columns - 1], out + columns, out + paddedColumns);
+    }
+  };
+
+  return {
+    stripHeight,
+    encodeStrip: (strip: YuvStrip): Uint8Array => {
+      const { y, u, v, uv } = strip;
+      const yStride = strip.yStride ?? width;
+      const interleaved = uv !== undefined;
+      const chromaStride = strip.chromaStride ?? chromaWidth * (interleaved ? 2 : 1);
+      if (interleaved ? u !== undefined || v !== undefined : u === undefined || v === undefined) {
+        throw new Error('A YUV strip has either u and v planes or an interleaved uv plane');
+      }
+      if (yStride < width || chromaStride < chromaWidth * (interleaved ? 2 : 1)) {
+        throw new Error(`Strides of ${yStride} and ${chromaStride} bytes are shorter than a ${width}-pixel row`);
+      }
+      // The last row of a plane needs no padding after it
+      const rows = y.length < width ? 0 : Math.floor((y.length - width) / yStride) + 1;
+      const last = rowsSeen + rows === height;
+      if (rows === 0 || rowsSeen + rows > height || (!last && rows % stripHeight !== 0)) {
+        throw new Error(`A strip of ${rows} rows does not continue the image at row ${rowsSeen}; strips hold multiples of ${stripHeight} rows until the last`);
+      }
+      const chromaRows = Math.ceil(rows / 2);
+      const chromaBytes = (chromaRows - 1) * chromaStride + chromaWidth * (interleaved ? 2 : 1);
+      if ([uv ?? u!, uv ?? v!].some((plane) => plane.length < chromaBytes)) {
+        throw new Error(`Chroma planes for ${rows} rows need at least ${chromaBytes} bytes`);
+      }
+
+      const [luma, cb, cr] = components;
+      for (let top = 0; top < rows; top += stripHeight) {
+        const count = Math.min(stripHeight, rows - top);
+        const chromaTop = top / 2;
+        fill(luma, y.subarray(top * yStride), yStride, 1, 0, width, count);
+        if (uv) {
+          fill(cb, uv.subarray(chromaTop * chromaStride), chromaStride, 2, 0, chromaWidth, Math.ceil(count / 2));
+          fill(cr, uv.subarray(chromaTop * chromaStride), chromaStride, 2, 1, chromaWidth, Math.ceil(count / 2));
+        } else {
+          fill(cb, u!.subarray(chromaTop * chromaStride), chromaStride, 1, 0, chromaWidth, Math.ceil(count / 2));
+          fill(cr, v!.subarray(chromaTop * chromaStride), chromaStride, 1, 0, chromaWidth, Math.ceil(count / 2));
+        }
+        core.codeRow();
+      }
+      rowsSeen += rows;
+      return core.take();
+    },
+    finish: (): Uint8Array => core.finish(rowsSeen),
+  };
+}
+
+/**
+ * Encode a whole 4:2:0 frame from its planes
+ * Planes may run past the frame, as decoders aligning the height leave them.
+ *
+ * @example
+ * const jpeg = encodeYuv({ width: 1920, height: 1080, y: frame.y, uv: frame.uv, yStride: 2048, chromaStride: 2048 }, { quality: 80 });
+ */
+export function encodeYuv(
+  frame: YuvStrip & { width: number; height: number },
+  options: Omit<LibjpegEncoderOptions, 'subsampling' | 'grayscale'> = {}
+): Uint8Array {
+  const encoder = createLibjpegYuvEncoder(frame.width, frame.height, options);
+  const rowBytes = (frame.height - 1) * (frame.yStride ?? frame.width) + frame.width;
+  const head = encoder.encodeStrip({ ...frame, y: frame.y.subarray(0, rowBytes) });
+  const tail = encoder.finish();
+  const jpeg = new Uint8Array(head.length + tail.length);
+  jpeg.set(head);
+  jpeg.set(tail, head.length);
+  return jpeg;
+}
@@ -0,0 +1,129 @@
+/**
+ * Low-latency row flushing
+ * A strip encoder can hold back output until its internal buffers fill, so a
+ * remote display may wait for several strips before anything changes on
+ * screen. Here every band of MCU rows is encoded as a complete JPEG of its own
+ * and only its entropy-coded data is passed on, ended by a restart marker: all
+ * bytes for a band are out as soon as its rows are in, and each band ends
+ * byte-aligned so a receiver can draw it straight away.
+ */
+
+import { Marker, parseJpeg } from './jpeg.js';
+import { frameGeometry } from './entropy.js';
+import { stitchedHeader } from './restart.js';
+import { concatBytes } from './pipeline.js';
+import type { StripEncoder } from './pipeline.js';
+
+/**
+ * Opens an encoder for one band of `rows` rows, configured like every other band
+ */
+export type BandEncoderFactory = (width: number, rows: number) => StripEncoder;
+
+/**
+ * Strip encoder that flushes complete output after every band of rows
+ * The first chunk carries the header, with the frame height and a restart
+ * interval of one band; every chunk then holds one band's data followed by
+ * RSTn, or by EOI for the last band. Output is one sequential JPEG, delayed by
+ * at most one band from its input.
+ *
+ * @param openBand Encoder for a single band; all bands must share its tables
+ * @param rows Rows per band, a multiple of the MCU height; defaults to one 8-row MCU row
+ *
+ * @example
+ * const encoder = createRowFlushEncoder(width, height, (w, rows) => createJpegStripEncoder(w, rows, 70));
+ * await runPipeline(frameSource, encoder, { write: (chunk) => socket.send(chunk) });
+ */
+export function createRowFlushEncoder(
+  width: number,
+  height: number,
+  openBand: BandEncoderFactory,
+  rows = 8
+): StripEncoder {
+  if (!Number.isInteger(rows) || rows <= 0 || rows % 8 !== 0) {
+    throw new Error(`Rows per band must be a positive multiple of 8, got ${rows}`);
+  }
+  const rowBytes = width * 4;
+  let y = 0;
+  let restartIndex = 0;
+
+  const encodeBand = (strip: Uint8Array): Uint8Array => {
+    const bandRows = strip.length / rowBytes;
+    const band = openBand(width, bandRows);
+    const chunks: Uint8Array[] = [];
+    for (let row = 0; row < bandRows; row += band.stripHeight) {
+      chunks.push(band.encodeStrip(strip.subarray(row * rowBytes, Math.min(row + band.stripHeight, bandRows) * rowBytes)));
+    }
+    chunks.push(band.finish());
+    const file = parseJpeg(concatBytes(chunks));
+    const scan = file.scans[0];
+    if (file.scans.length !== 1 || scan.restartInterval !== 0) {
+      throw new Error('Band encoders must write a single scan without restart markers');
+    }
+
+    const out: Uint8Array[] = [];
+    if (y === 0) {
+      const geometry = frameGeometry(file.frame);
+      const mcuHeight = 8 * geometry.maxV;
+      if (rows % mcuHeight !== 0) {
+        throw new Error(`Bands of ${rows} rows do not cover whole ${mcuHeight}-row MCUs`);
+      }
+      const interval = geometry.mcusPerLine * (rows / mcuHeight);
+      if (interval > 0xffff) {
+        throw new Error(`Restart interval of ${interval} MCUs exceeds 65535; use fewer rows per band`);
+      }
+      out.push(...stitchedHeader(file, height, interval));
+    }
+    out.push(file.bytes.subarray(scan.dataOffset, scan.dataEnd));
+    y += bandRows;
+    if (y < height) {
+      out.push(new Uint8Array([0xff, Marker.RST0 + restartIndex]));
+      restartIndex = (restartIndex + 1) % 8;
+    } else {
+      out.push(new Uint8Array([0xff, Marker.EOI]));
+    }
+    return concatBytes(out);
+  };
+
+  // Rows of a band not yet complete, when a source returns short strips
+  const pending = new Uint8Array(rows * rowBytes);
+  let filled = 0;
+
+  return {
+    stripHeight: rows,
+    encodeStrip: (strip: Uint8Array): Uint8Array => {
+      if (strip.length % rowBytes !== 0 || y * rowBytes + filled + strip.length > height * rowBytes) {
+        throw new Error(`Strip of ${strip.length} bytes does not fit the remaining rows of a ${width}x${height} image`);
+      }
+      const chunks: Uint8Array[] = [];
+      let offset = 0;
+      if (filled === 0) {
+        // Whole bands straight from the strip, without copying
+        for (; strip.length - offset >= pending.length; offset += pending.length) {
+          chunks.push(encodeBand(strip.subarray(offset, offset + pending.length)));
+        }
+      }
+      while (offset < strip.length) {
+        const take = Math.min(strip.length - offset, pending.length - filled);
+        pending.set(strip.subarray(offset, offset + take), filled);
+        filled += take;
+        offset += take;
+        if (filled === pending.length) {
+          chunks.push(encodeBand(pending));
+          filled = 0;
+        }
+      }
+      // The last band may be short
+      if (filled > 0 && y * rowBytes + filled === height * rowBytes) {
+        chunks.push(encodeBand(pending.subarray(0, filled)));
+        filled = 0;
+      }
+      return chunks.length === 1 ? chunks[0] : concatBytes(chunks);
+    },
+    finish: (): Uint8Array => {
+      if (y < height) {
+        throw new Error(`Encoder finished after ${y + filled / rowBytes} of ${height} rows`);
+      }
+      return new Uint8Array(0);
+    },
+  };
+}
@@ -0,0 +1,69 @@
+/**
+ * Marker segment writing
+ * Builds application segments and splices them into encoded streams without
+ * touching the entropy-coded data.
+ */
+
+import { Marker, JpegFormatError, iterateSegments } from './jpeg.js';
+
+/**
+ * Build a complete marker segment (marker, length field, payload)
+ */
+export function createSegment(marker: number, payload: Uint8Array): Uint8Array {
+  if (payload.length > 0xffff - 2) {
+    throw new Error(`Segment payload of ${payload.length} bytes exceeds 65533`);
+  }
+  const segment = new Uint8Array(payload.length + 4);
+  segment[0] = 0xff;
+  segment[1] = marker;
+  segment[2] = (payload.length + 2) >> 8;
+  segment[3] = (payload.length + 2) & 0xff;
+  segment.set(payload, 4);
+  return segment;
+}
+
+/**
+ * Find where a new APPn segment belongs
+ * Application segments are kept in ascending order right after SOI, so the
+ * position is after every leading APPn segment with a marker <= `marker`.
+ */
+export function applicationInsertOffset(jpeg: Uint8Array, marker: number): number {
+  let offset = 2;
+  for (const segment of iterateSegments(jpeg)) {
+    if (segment.marker === Marker.SOI) {
+      continue;
+    }
+    if (segment.marker < Marker.APP0 || segment.marker > Marker.APP15 || segment.marker > marker) {
+      break;
+    }
+    offset = segment.offset + segment.length;
+  }
+  return offset;
+}
+
+/**
+ * Insert complete segments into a JPEG stream
+ *
+ * @param jpeg Encoded JPEG bytes
+ * @param segments Segments built with `createSegment`, inserted in order
+ * @param offset Byte offset to insert at; defaults to the APPn position of the first segment's marker
+ */
+export function insertSegments(jpeg: Uint8Array, segments: Uint8Array[], offset?: number): Uint8Array {
+  if (segments.length === 0) {
+    return jpeg;
+  }
+  const at = offset ?? applicationInsertOffset(jpeg, segments[0][1]);
+  if (at < 2 || at > jpeg.length) {
+    throw new JpegFormatError('Insert position is outside the stream', at);
+  }
+  const total = segments.reduce((sum, segment) => sum + segment.length, 0);
+  const output = new Uint8Array(jpeg.length + total);
+  output.set(jpeg.subarray(0, at), 0);
+  let pos = at;
+  for (const segment of segments) {
+    output.set(segment, pos);
+    pos += segment.length;
+  }
+  output.set(jpeg.subarray(at), pos);
+  return output;
+}
@@ -0,0 +1,231 @@
+/**
+ * EXIF and ICC metadata carry-over
+ * Lifts the EXIF (APP1) and ICC profile (APP2) segments out of a source JPEG
+ * and splices them into a re-encoded one, updating the EXIF tags that describe
+ * the pixels so they match the new image, or strips metadata for delivery.
+ */
+
+import { Marker, readSegments } from './jpeg.js';
+import { insertSegments } from './markers.js';
+import { concatBytes } from './pipeline.js';
+
+export interface MetadataCopyOptions {
+  exif?: boolean;  // Copy the EXIF segment, defaults to true
+  icc?: boolean;   // Copy the ICC profile, defaults to true
+  /**
+   * 'keep' leaves the Orientation tag as is (pixels were not rotated);
+   * 'reset' sets it to 1 for pipelines that already applied the rotation.
+   * Defaults to 'keep'.
+   */
+  orientation?: 'keep' | 'reset';
+}
+
+export interface StripMetadataOptions {
+  icc?: boolean;   // Keep the ICC profile, defaults to true
+}
+
+export interface JpegMetadata {
+  exif?: Uint8Array;  // Complete APP1 Exif segment
+  icc: Uint8Array[];  // Complete APP2 ICC_PROFILE segments in chunk order
+}
+
+const EXIF_IDENTIFIER = 'Exif\0\0';
+const ICC_IDENTIFIER = 'ICC_PROFILE\0';
+const JFIF_IDENTIFIER = 'JFIF\0';
+const SPIFF_IDENTIFIER = 'SPIFF\0';
+const ADOBE_IDENTIFIER = 'Adobe';
+
+const TAG_ORIENTATION = 0x0112;
+const TAG_EXIF_IFD = 0x8769;
+const TAG_PIXEL_X_DIMENSION = 0xa002;
+const TAG_PIXEL_Y_DIMENSION = 0xa003;
+const TYPE_SHORT = 3;
+const TYPE_LONG = 4;
+
+function startsWith(data: Uint8Array, identifier: string): boolean {
+  if (data.length < identifier.length) {
+    return false;
+  }
+  for (let i = 0; i < identifier.length; i++) {
+    if (data[i] !== identifier.charCodeAt(i)) {
+      return false;
+    }
+  }
+  return true;
+}
+
+/**
+ * Find the EXIF and ICC profile segments of a JPEG
+ */
+export function readMetadata(jpeg: Uint8Array): JpegMetadata {
+  const metadata: JpegMetadata = { icc: [] };
+  const icc: { sequence: number; segment: Uint8Array }[] = [];
+  for (const segment of readSegments(jpeg)) {
+    if (segment.marker === Marker.SOS) {
+      break;
+    }
+    const bytes = jpeg.subarray(segment.offset, segment.offset + segment.length);
+    if (segment.marker === Marker.APP0 + 1 && !metadata.exif && startsWith(segment.data, EXIF_IDENTIFIER)) {
+      metadata.exif = bytes;
+    } else if (segment.marker === Marker.APP0 + 2 && startsWith(segment.data, ICC_IDENTIFIER)) {
+      icc.push({ sequence: segment.data[ICC_IDENTIFIER.length], segment: bytes });
+    }
+  }
+  metadata.icc = icc.sort((a, b) => a.sequence - b.sequence).map((chunk) => chunk.segment);
+  return metadata;
+}
+
+interface ExifEntry {
+  view: DataView;
+  offset: number;        // Offset of the 12-byte entry within the TIFF data
+  tag: number;
+  type: number;
+  count: number;
+  littleEndian: boolean;
+}
+
+/**
+ * Visit the entries of IFD0 and the Exif IFD of an APP1 Exif segment
+ * Malformed structures end the walk early rather than throwing.
+ */
+function forEachExifEntry(segment: Uint8Array, visit: (entry: ExifEntry) => void): void {
+  const tiff = 4 + EXIF_IDENTIFIER.length;
+  if (segment.length < tiff + 8) {
+    return;
+  }
+  const view = new DataView(segment.buffer, segment.byteOffset + tiff, segment.length - tiff);
+  const order = view.getUint16(0);
+  if (order !== 0x4949 && order !== 0x4d4d) {
+    return;
+  }
+  const littleEndian = order === 0x4949;
+
+  const walk = (ifd: number, depth: number): void => {
+    if (ifd < 8 || ifd + 2 > view.byteLength || depth > 1) {
+      return;
+    }
+    const count = view.getUint16(ifd, littleEndian);
+    for (let i = 0; i < count; i++) {
+      const offset = ifd + 2 + i * 12;
+      if (offset + 12 > view.byteLength) {
+        return;
+      }
+      const tag = view.getUint16(offset, littleEndian);
+      if (tag === TAG_EXIF_IFD) {
+        walk(view.getUint32(offset + 8, littleEndian), depth + 1);
+        continue;
+      }
+      visit({
+        view,
+        offset,
+        tag,
+        type: view.getUint16(offset + 2, littleEndian),
+        count: view.getUint32(offset + 4, littleEndian),
+        littleEndian,
+      });
+    }
+  };
+  walk(view.getUint32(4, littleEndian), 0);
+}
+
+/**
+ * Rewrite single-valued SHORT or LONG tags of an APP1 Exif segment in place
+ */
+function patchExif(segment: Uint8Array, tags: Map<number, number>): void {
+  forEachExifEntry(segment, ({ view, offset, tag, type, count, littleEndian }) => {
+    const value = tags.get(tag);
+    if (value === undefined || count !== 1) {
+      return;
+    }
+    if (type === TYPE_SHORT) {
+      view.setUint16(offset + 8, value, littleEndian);
+    } else if (type === TYPE_LONG) {
+      view.setUint32(offset + 8, value, littleEndian);
+    }
+  });
+}
+
+/**
+ * Read the EXIF Orientation tag (1-8) of a JPEG, or undefined when absent
+ */
+export function readOrientation(jpeg: Uint8Array): number | undefined {
+  const { exif } = readMetadata(jpeg);
+  let orientation: number | undefined;
+  if (exif) {
+    forEachExifEntry(exif, ({ view, offset, tag, type, littleEndian }) => {
+      if (tag === TAG_ORIENTATION && type === TYPE_SHORT) {
+        orientation = view.getUint16(offset + 8, littleEndian);
+      }
+    });
+  }
+  return orientation;
+}
+
+/**
+ * Copy EXIF and ICC metadata from a source JPEG into a re-encoded one
+ * The copied EXIF gets the target's pixel dimensions and, with
+ * `orientation: 'reset'`, an Orientation of 1. Segments already present in the
+ * target are kept.
+ *
+ * @param source JPEG the metadata comes from
+ * @param target Re-encoded JPEG to receive it
+ */
+export function copyMetadata(source: Uint8Array, target: Uint8Array, options: MetadataCopyOptions = {}): Uint8Array {
+  const { exif: copyExif = true, icc: copyIcc = true, orientation = 'keep' } = options;
+  const metadata = readMetadata(source);
+  const existing = readMetadata(target);
+  let output = target;
+
+  if (copyExif && metadata.exif && !existing.exif) {
+    const exif = metadata.exif.slice();
+    const frame = readSegments(target).find((s) => s.marker === Marker.SOF0 || s.marker === Marker.SOF1 || s.marker === Marker.SOF2);
+    const tags = new Map<number, number>();
+    if (frame) {
+      tags.set(TAG_PIXEL_Y_DIMENSION, (frame.data[1] << 8) | frame.data[2]);
+      tags.set(TAG_PIXEL_X_DIMENSION, (frame.data[3] << 8) | frame.data[4]);
+    }
+    if (orientation === 'reset') {
+      tags.set(TAG_ORIENTATION, 1);
+    }
+    patchExif(exif, tags);
+    output = insertSegments(output, [exif]);
+  }
+
+  if (copyIcc && metadata.icc.length > 0 && existing.icc.length === 0) {
+    output = insertSegments(output, metadata.icc);
+  }
+  return output;
+}
+
+/**
+ * Remove metadata segments before the first scan
+ * EXIF, XMP, comments, JFXX thumbnails, and other APPn segments are dropped.
+ * Segments that decoders rely on stay: the JFIF or SPIFF header, the Adobe
+ * color transform flag, and the ICC profile unless `icc` is false.
+ */
+export function stripMetadata(jpeg: Uint8Array, options: StripMetadataOptions = {}): Uint8Array {
+  const { icc: keepIcc = true } = options;
+  const keep = (marker: number, data: Uint8Array): boolean =>
+    (marker === Marker.APP0 && startsWith(data, JFIF_IDENTIFIER)) ||
+    (marker === Marker.APP0 + 2 && keepIcc && startsWith(data, ICC_IDENTIFIER)) ||
+    (marker === Marker.APP0 + 8 && startsWith(data, SPIFF_IDENTIFIER)) ||
+    (marker === Marker.APP0 + 14 && startsWith(data, ADOBE_IDENTIFIER));
+
+  const chunks: Uint8Array[] = [];
+  let start = 0;
+  for (const segment of readSegments(jpeg)) {
+    if (segment.marker === Marker.SOS) {
+      break;
+    }
+    const metadata = (segment.marker >= Marker.APP0 && segment.marker <= Marker.APP15) || segment.marker === Marker.COM;
+    if (metadata && !keep(segment.marker, segment.data)) {
+      chunks.push(jpeg.subarray(start, segment.offset));
+      start = segment.offset + segment.length;
+    }
+  }
+  if (chunks.length === 0) {
+    return jpeg;
+  }
+  chunks.push(jpeg.subarray(start));
+  return concatBytes(chunks);
+}
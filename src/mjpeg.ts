@@ -0,0 +1,107 @@
+/**
+ * Animation to MJPEG conversion
+ * Composites animation frames onto a canvas and encodes each displayed frame
+ * as a standalone JPEG, yielding them one at a time.
+ */
+
+import { FrameCompositor } from './animation.js';
+import type { AnimationFrame } from './animation.js';
+import { encodeToBytes } from './encoder.js';
+import { concatBytes } from './pipeline.js';
+import { gifFrames, readGifInfo } from './gif.js';
+import { captureCanvasFrames, captureVideoFrames } from './capture.js';
+import type { CaptureOptions, TrackCaptureOptions, VideoFrameLike } from './capture.js';
+
+export interface MjpegOptions {
+  width: number;
+  height: number;
+  quality?: number;
+  background?: [number, number, number]; // Canvas color behind transparent pixels
+}
+
+export interface MjpegFrame {
+  index: number;
+  delay: number;          // Display time in milliseconds
+  jpeg: Uint8Array;
+}
+
+/**
+ * Encode a sequence of animation frames, one JPEG per frame
+ * Frames are pulled from the iterable only as the previous JPEG is consumed.
+ */
+export async function* mjpegFrames(
+  frames: Iterable<AnimationFrame> | AsyncIterable<AnimationFrame>,
+  options: MjpegOptions
+): AsyncGenerator<MjpegFrame> {
+  const { width, height, quality = 90 } = options;
+  const compositor = new FrameCompositor(width, height, options.background);
+  let index = 0;
+  for await (const frame of frames) {
+    const canvas = compositor.composite(frame);
+    const jpeg = await encodeToBytes(canvas, { width, height, quality });
+    yield { index: index++, delay: frame.delay, jpeg };
+  }
+}
+
+/**
+ * Encode every frame and concatenate the JPEGs into a raw MJPEG stream
+ */
+export async function encodeMjpeg(
+  frames: Iterable<AnimationFrame> | AsyncIterable<AnimationFrame>,
+  options: MjpegOptions
+): Promise<Uint8Array> {
+  const parts: Uint8Array[] = [];
+  for await (const { jpeg } of mjpegFrames(frames, options)) {
+    parts.push(jpeg);
+  }
+  return concatBytes(parts);
+}
+
+/**
+ * Convert a GIF file into a sequence of JPEG frames
+ * The canvas size and background come from the GIF's logical screen.
+ */
+export function gifToMjpeg(
+  gif: Uint8Array,
+  options: Omit<MjpegOptions, 'width' | 'height'> = {}
+): AsyncGenerator<MjpegFrame> {
+  const info = readGifInfo(gif);
+  return mjpegFrames(gifFrames(gif), {
+    width: info.width,
+    height: info.height,
+    quality: options.quality,
+    background: options.background ?? info.background,
+  });
+}
+
+/**
+ * Capture a canvas at a fixed rate and encode each sampled frame
+ * Frames the encoder cannot keep up with are dropped, not queued.
+ */
+export function captureMjpeg(
+  canvas: HTMLCanvasElement,
+  options: CaptureOptions & Omit<MjpegOptions, 'width' | 'height'> = {}
+): AsyncGenerator<MjpegFrame> {
+  return mjpegFrames(captureCanvasFrames(canvas, options), {
+    width: canvas.width,
+    height: canvas.height,
+    quality: options.quality,
+    background: options.background,
+  });
+}
+
+/**
+ * Encode frames from a video track (e.g. a webcam) as they arrive
+ * Each frame is encoded at its own size; see `captureVideoFrames` for the drop policy.
+ */
+export async function* trackMjpeg(
+  source: MediaStreamTrack | ReadableStream<VideoFrameLike>,
+  options: TrackCaptureOptions & { quality?: number } = {}
+): AsyncGenerator<MjpegFrame> {
+  const { quality = 90 } = options;
+  let index = 0;
+  for await (const frame of captureVideoFrames(source, options)) {
+    const jpeg = await encodeToBytes(frame.data, { width: frame.width, height: frame.height, quality });
+    yield { index: index++, delay: frame.delay, jpeg };
+  }
+}
@@ -0,0 +1,177 @@
+/**
+ * Multi Picture Format (MPO) output
+ * Packs several encoded JPEGs into one .mpo file (CIPA DC-007): the first image
+ * carries an APP2 "MPF" index describing where every image starts.
+ */
+
+import { Marker, iterateSegments } from './jpeg.js';
+import { applicationInsertOffset, createSegment, insertSegments } from './markers.js';
+
+/**
+ * MP type of the individual images
+ * - disparity: stereoscopic pair or set
+ * - panorama: multi-frame panorama
+ * - multi-angle: multiple views of one subject (also used for bursts)
+ * - baseline: a primary image followed by unrelated images
+ */
+export type MpoType = 'disparity' | 'panorama' | 'multi-angle' | 'baseline';
+
+export interface MpoOptions {
+  type?: MpoType;         // Defaults to 'disparity' for two images, otherwise 'multi-angle'
+}
+
+const MP_TYPE_CODES: Record<MpoType, number> = {
+  baseline: 0x030000,
+  panorama: 0x020001,
+  disparity: 0x020002,
+  'multi-angle': 0x020003,
+};
+
+const MPF_IDENTIFIER = [0x4d, 0x50, 0x46, 0x00]; // "MPF\0"
+const TAG_VERSION = 0xb000;
+const TAG_NUMBER_OF_IMAGES = 0xb001;
+const TAG_MP_ENTRY = 0xb002;
+const TAG_INDIVIDUAL_NUM = 0xb101;
+const REPRESENTATIVE_IMAGE = 0x20000000;
+
+/**
+ * Big-endian TIFF-style IFD writer
+ */
+function writeIfd(view: DataView, offset: number, entries: Array<[number, number, number, number]>): number {
+  view.setUint16(offset, entries.length);
+  let pos = offset + 2;
+  for (const [tag, type, count, value] of entries) {
+    view.setUint16(pos, tag);
+    view.setUint16(pos + 2, type);
+    view.setUint32(pos + 4, count);
+    view.setUint32(pos + 8, value);
+    pos += 12;
+  }
+  view.setUint32(pos, 0); // No next IFD
+  return pos + 4;
+}
+
+// "0100" as an UNDEFINED[4] value stored inline
+const VERSION_0100 = 0x30313030;
+
+function mpfPayload(tiffLength: number): { payload: Uint8Array; view: DataView } {
+  const payload = new Uint8Array(4 + tiffLength);
+  payload.set(MPF_IDENTIFIER, 0);
+  const view = new DataView(payload.buffer, 4);
+  view.setUint16(0, 0x4d4d); // "MM"
+  view.setUint16(2, 0x002a);
+  view.setUint32(4, 8);      // First IFD
+  return { payload, view };
+}
+
+/**
+ * Combine encoded JPEGs into a single MPO file
+ *
+ * @param images Encoded JPEGs; the first becomes the representative image
+ * @param options MP type of the set
+ *
+ * @example
+ * const left = await encode(leftRgba, { width, height }) as Uint8Array;
+ * const right = await encode(rightRgba, { width, height }) as Uint8Array;
+ * await writeFile('stereo.mpo', createMpo([left, right]));
+ */
+export function createMpo(images: Uint8Array[], options: MpoOptions = {}): Uint8Array {
+  if (images.length < 2) {
+    throw new Error('An MPO file needs at least two images');
+  }
+  const typeCode = MP_TYPE_CODES[options.type ?? (images.length === 2 ? 'disparity' : 'multi-angle')];
+
+  // Attribute IFD for every image after the first
+  const rest = images.slice(1).map((image, index) => {
+    const { payload, view } = mpfPayload(8 + 2 + 2 * 12 + 4);
+    writeIfd(view, 8, [
+      [TAG_VERSION, 7, 4, VERSION_0100],
+      [TAG_INDIVIDUAL_NUM, 4, 1, index + 2],
+    ]);
+    return insertSegments(image, [createSegment(Marker.APP0 + 2, payload)]);
+  });
+
+  // Index IFD for the first image; entry offsets are relative to its TIFF header
+  const entriesOffset = 8 + 2 + 3 * 12 + 4;
+  const { payload, view } = mpfPayload(entriesOffset + 16 * images.length);
+  writeIfd(view, 8, [
+    [TAG_VERSION, 7, 4, VERSION_0100],
+    [TAG_NUMBER_OF_IMAGES, 4, 1, images.length],
+    [TAG_MP_ENTRY, 7, 16 * images.length, entriesOffset],
+  ]);
+
+  const insertAt = applicationInsertOffset(images[0], Marker.APP0 + 2);
+  const tiffStart = insertAt + 4 + MPF_IDENTIFIER.length;
+  const firstLength = images[0].length + 4 + payload.length;
+  let imageOffset = firstLength;
+  [firstLength, ...rest.map((image) => image.length)].forEach((size, index) => {
+    const entry = entriesOffset + 16 * index;
+    view.setUint32(entry, typeCode | (index === 0 ? REPRESENTATIVE_IMAGE : 0));
+    view.setUint32(entry + 4, size);
+    view.setUint32(entry + 8, index === 0 ? 0 : imageOffset - tiffStart);
+    view.setUint16(entry + 12, 0);
+    view.setUint16(entry + 14, 0);
+    if (index > 0) {
+      imageOffset += size;
+    }
+  });
+  const first = insertSegments(images[0], [createSegment(Marker.APP0 + 2, payload)], insertAt);
+
+  const output = new Uint8Array(imageOffset);
+  output.set(first, 0);
+  let pos = first.length;
+  for (const image of rest) {
+    output.set(image, pos);
+    pos += image.length;
+  }
+  return output;
+}
+
+/**
+ * Split an MPO file into its individual JPEGs using the MP index
+ *
+ * @param mpo MPO file bytes
+ */
+export function splitMpo(mpo: Uint8Array): Uint8Array[] {
+  for (const segment of iterateSegments(mpo)) {
+    if (segment.marker === Marker.SOS) {
+      break;
+    }
+    const { data } = segment;
+    if (segment.marker !== Marker.APP0 + 2 || data.length < 12 ||
+        !MPF_IDENTIFIER.every((byte, i) => data[i] === byte)) {
+      continue;
+    }
+    const tiffStart = segment.offset + 4 + MPF_IDENTIFIER.length;
+    const view = new DataView(data.buffer, data.byteOffset + 4, data.length - 4);
+    const little = view.getUint16(0) === 0x4949;
+    const ifd = view.getUint32(4, little);
+    const count = view.getUint16(ifd, little);
+    let entries = -1;
+    let number = 0;
+    for (let i = 0; i < count; i++) {
+      const entry = ifd + 2 + i * 12;
+      const tag = view.getUint16(entry, little);
+      if (tag === TAG_NUMBER_OF_IMAGES) {
+        number = view.getUint32(entry + 8, little);
+      } else if (tag === TAG_MP_ENTRY) {
+        entries = view.getUint32(entry + 8, little);
+      }
+    }
+    if (entries < 0) {
+      throw new Error('MPF segment has no MP entries');
+    }
+    const images: Uint8Array[] = [];
+    for (let i = 0; i < number; i++) {
+      const size = view.getUint32(entries + 16 * i + 4, little);
+      const offset = view.getUint32(entries + 16 * i + 8, little);
+      const start = i === 0 ? 0 : tiffStart + offset;
+      if (start + size > mpo.length) {
+        throw new Error(`MP entry ${i} extends past the end of the file`);
+      }
+      images.push(mpo.subarray(start, start + size));
+    }
+    return images;
+  }
+  throw new Error('No MPF index found');
+}
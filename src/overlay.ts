@@ -0,0 +1,133 @@
+/**
+ * Debug visualization overlay
+ * Renders block structure, restart intervals, and quantization impact of an
+ * encoded JPEG into an RGBA buffer that can be drawn over the decoded image.
+ */
+
+import { parseJpeg } from './jpeg.js';
+import { frameGeometry, walkScan } from './entropy.js';
+
+export interface DebugOverlayOptions {
+  blocks?: boolean;       // Draw 8x8 block (thin) and MCU (thick) boundaries, defaults to true
+  restarts?: boolean;     // Mark the first MCU of every restart interval, defaults to true
+  quantization?: boolean; // Tint blocks by the AC coefficients surviving quantization, defaults to true
+  opacity?: number;       // Overlay alpha (0-255), defaults to 160
+}
+
+/**
+ * RGBA overlay with the same dimensions as the encoded image
+ * `data` can be passed straight to `new ImageData(data, width, height)`.
+ */
+export interface DebugOverlay {
+  width: number;
+  height: number;
+  data: Uint8ClampedArray;
+}
+
+/**
+ * Render a diagnostic overlay for an encoded JPEG
+ *
+ * Quantization tint runs from blue (every AC coefficient quantized to zero) to
+ * red (all 63 retained), based on the first component. Block and MCU edges are
+ * drawn in grey and white; restart interval starts are marked in red.
+ *
+ * @param jpeg Encoded JPEG bytes
+ * @param options Layer selection and opacity
+ *
+ * @example
+ * const overlay = debugOverlay(jpeg as Uint8Array);
+ * ctx.putImageData(new ImageData(overlay.data, overlay.width, overlay.height), 0, 0);
+ */
+export function debugOverlay(jpeg: Uint8Array, options: DebugOverlayOptions = {}): DebugOverlay {
+  const { blocks = true, restarts = true, quantization = true, opacity = 160 } = options;
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  const { width, height } = frame;
+  const geometry = frameGeometry(frame);
+  const data = new Uint8ClampedArray(width * height * 4);
+
+  const fillRect = (x0: number, y0: number, w: number, h: number, r: number, g: number, b: number, a: number): void => {
+    const x1 = Math.min(width, x0 + w);
+    const y1 = Math.min(height, y0 + h);
+    for (let y = Math.max(0, y0); y < y1; y++) {
+      for (let x = Math.max(0, x0); x < x1; x++) {
+        const offset = (y * width + x) * 4;
+        data[offset] = r;
+        data[offset + 1] = g;
+        data[offset + 2] = b;
+        data[offset + 3] = a;
+      }
+    }
+  };
+
+  // Pixel footprint of one block of each component
+  const blockSize = frame.components.map((c) => ({
+    w: (8 * geometry.maxH) / c.h,
+    h: (8 * geometry.maxV) / c.v,
+  }));
+  const mcuWidth = 8 * geometry.maxH;
+  const mcuHeight = 8 * geometry.maxV;
+  const restartOrigins: Array<{ x: number; y: number; w: number; h: number }> = [];
+
+  for (const scan of file.scans) {
+    const single = scan.header.components.length === 1;
+    const firstIndex = frame.components.findIndex((c) => c.id === scan.header.components[0].id);
+
+    walkScan(file, scan, {
+      onBlock(componentIndex, blockRow, blockCol, coefficients) {
+        if (!quantization || componentIndex !== 0) {
+          return;
+        }
+        let retained = 0;
+        for (let k = 1; k < 64; k++) {
+          if (coefficients[k] !== 0) {
+            retained++;
+          }
+        }
+        const heat = Math.round((retained / 63) * 255);
+        const size = blockSize[0];
+        fillRect(blockCol * size.w, blockRow * size.h, size.w, size.h, heat, 0, 255 - heat, Math.round(opacity / 2));
+      },
+      onRestart(mcu) {
+        if (single) {
+          const blocksPerLine = geometry.components[firstIndex].blocksPerLine;
+          const size = blockSize[firstIndex];
+          restartOrigins.push({
+            x: (mcu % blocksPerLine) * size.w,
+            y: Math.floor(mcu / blocksPerLine) * size.h,
+            w: size.w,
+            h: size.h,
+          });
+        } else {
+          restartOrigins.push({
+            x: (mcu % geometry.mcusPerLine) * mcuWidth,
+            y: Math.floor(mcu / geometry.mcusPerLine) * mcuHeight,
+            w: mcuWidth,
+            h: mcuHeight,
+          });
+        }
+      },
+    });
+  }
+
+  if (blocks) {
+    const { w: blockWidth, h: blockHeight } = blockSize[0];
+    for (let x = 0; x < width; x += blockWidth) {
+      const mcuEdge = x % mcuWidth === 0;
+      fillRect(x, 0, 1, height, mcuEdge ? 255 : 128, mcuEdge ? 255 : 128, mcuEdge ? 255 : 128, opacity);
+    }
+    for (let y = 0; y < height; y += blockHeight) {
+      const mcuEdge = y % mcuHeight === 0;
+      fillRect(0, y, width, 1, mcuEdge ? 255 : 128, mcuEdge ? 255 : 128, mcuEdge ? 255 : 128, opacity);
+    }
+  }
+
+  if (restarts) {
+    for (const origin of restartOrigins) {
+      fillRect(origin.x, origin.y, 2, origin.h, 255, 0, 0, 255);
+      fillRect(origin.x, origin.y, origin.w, 2, 255, 0, 0, 255);
+    }
+  }
+
+  return { width, height, data };
+}
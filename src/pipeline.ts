@@ -0,0 +1,375 @@
+/**
+ * Strip pipeline
+ * Codec-neutral interfaces for the streaming orchestration: a source yields
+ * RGBA strips, an encoder turns strips into output bytes, and a sink receives
+ * them. Any strip-based encoder can reuse `runPipeline` by implementing
+ * `StripEncoder`.
+ */
+
+import { BYTES_PER_PIXEL, rawRowsToRgba } from './rawfile.js';
+import type { RawPixelFormat } from './rawfile.js';
+
+/**
+ * Produces an image top to bottom as RGBA strips
+ * `readStrip` returns up to `rows` rows, or null (or an empty array) once the image is exhausted.
+ */
+export interface StripSource {
+  readonly width: number;
+  readonly height: number;
+  readStrip(rows: number): Promise<Uint8Array | null> | Uint8Array | null;
+}
+
+/**
+ * Encodes strips of RGBA rows
+ * - stripHeight: preferred rows per strip (the last strip may be shorter)
+ * - encodeStrip/finish return whatever output became available (possibly empty)
+ */
+export interface StripEncoder {
+  readonly stripHeight: number;
+  encodeStrip(strip: Uint8Array): Uint8Array;
+  finish(): Uint8Array;
+}
+
+/**
+ * Receives encoded output in order
+ */
+export interface OutputSink {
+  write(chunk: Uint8Array): Promise<void> | void;
+  close?(): Promise<void> | void;
+}
+
+export interface PipelineHooks {
+  onStrip?(strip: Uint8Array, y: number): void; // Called with each strip before it is encoded
+}
+
+/**
+ * Return a chunk that exclusively owns a plain ArrayBuffer
+ * Views into WASM memory, shared memory, or a larger buffer are copied, so the
+ * result's buffer can be listed in a postMessage transfer without detaching
+ * anything else.
+ */
+export function standaloneChunk(chunk: Uint8Array): Uint8Array {
+  const { buffer } = chunk;
+  const owned = buffer instanceof ArrayBuffer && chunk.byteOffset === 0 && chunk.byteLength === buffer.byteLength;
+  return owned ? chunk : chunk.slice();
+}
+
+/**
+ * Wrap an encoder so every chunk it returns is standalone and transferable
+ */
+export function transferableEncoder(encoder: StripEncoder): StripEncoder {
+  return {
+    stripHeight: encoder.stripHeight,
+    encodeStrip: (strip: Uint8Array): Uint8Array => standaloneChunk(encoder.encodeStrip(strip)),
+    finish: (): Uint8Array => standaloneChunk(encoder.finish()),
+  };
+}
+
+/**
+ * Buffers to list as the transfer argument of postMessage
+ * Each chunk must be standalone (see `standaloneChunk`); transferring detaches it.
+ *
+ * @example
+ * const chunks = sink.chunks.map(standaloneChunk);
+ * self.postMessage(chunks, transferList(chunks));
+ */
+export function transferList(chunks: Uint8Array[]): ArrayBuffer[] {
+  return chunks.map((chunk) => {
+    if (standaloneChunk(chunk) !== chunk) {
+      throw new Error('Chunk is a view into a larger or shared buffer; pass it through standaloneChunk first');
+    }
+    return chunk.buffer as ArrayBuffer;
+  });
+}
+
+/**
+ * Strip source over an in-memory pixel buffer
+ * Tightly packed RGBA rows are passed on as views into `data`; other formats
+ * and padded rows are converted to RGBA a strip at a time, so canvases,
+ * decoder output and framebuffers need no repacking first.
+ *
+ * @param format Channel order of `data`, defaults to 'rgba'
+ * @param stride Bytes from one row to the next, defaults to width × bytes per pixel
+ */
+export function bufferSource(
+  data: Uint8Array,
+  width: number,
+  height: number,
+  format: RawPixelFormat = 'rgba',
+  stride = width * BYTES_PER_PIXEL[format]
+): StripSource {
+  let y = 0;
+  return {
+    width,
+    height,
+    readStrip(rows: number): Uint8Array | null {
+      if (y >= height) {
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      const strip = rawRowsToRgba(data.subarray(y * stride), width, count, stride, format);
+      y += count;
+      return strip;
+    },
+  };
+}
+
+/**
+ * Strip source over raw RGBA chunks of any size, pulled one at a time
+ * Only the bytes of the strip being assembled are held in memory.
+ */
+function chunkSource(
+  pull: () => Promise<IteratorResult<Uint8Array>>,
+  release: () => Promise<unknown>,
+  width: number,
+  height: number
+): StripSource {
+  const bytesPerRow = width * 4;
+  let pending: Uint8Array = new Uint8Array(0);
+  let y = 0;
+  let done = false;
+
+  return {
+    width,
+    height,
+    async readStrip(rows: number): Promise<Uint8Array | null> {
+      if (y >= height) {
+        await release();
+        return null;
+      }
+      const needed = Math.min(rows, height - y) * bytesPerRow;
+      const strip = new Uint8Array(needed);
+      let filled = 0;
+      while (filled < needed) {
+        if (pending.length === 0) {
+          if (done) {
+            break;
+          }
+          const result = await pull();
+          if (result.done) {
+            done = true;
+            continue;
+          }
+          pending = result.value;
+        }
+        const take = Math.min(pending.length, needed - filled);
+        strip.set(pending.subarray(0, take), filled);
+        pending = pending.subarray(take);
+        filled += take;
+      }
+      // Partial rows at the end of the input are dropped; runPipeline reports the short image
+      const complete = filled - (filled % bytesPerRow);
+      y += complete / bytesPerRow;
+      return complete === 0 ? null : strip.subarray(0, complete);
+    },
+  };
+}
+
+/**
+ * Strip source over a stream of raw RGBA bytes
+ * Only the bytes of the strip being assembled are held in memory.
+ */
+export function readableStreamSource(stream: ReadableStream<Uint8Array>, width: number, height: number): StripSource {
+  const reader = stream.getReader();
+  return chunkSource(() => reader.read() as Promise<IteratorResult<Uint8Array>>, () => reader.cancel(), width, height);
+}
+
+/**
+ * Strip source over an async or sync iterable of raw RGBA chunks
+ * Chunks may be any size, not only whole strips. The next chunk is requested
+ * only when the encoder needs more rows, so an async generator producing them
+ * is paused while earlier rows are encoded; it is closed once the image is complete.
+ *
+ * @example
+ * async function* rows() { for await (const band of pngDecoder) yield band.rgba; }
+ * const jpeg = await encode(rows(), { width, height });
+ */
+export function iterableSource(
+  chunks: AsyncIterable<Uint8Array> | Iterable<Uint8Array>,
+  width: number,
+  height: number
+): StripSource {
+  const iterator = Symbol.asyncIterator in chunks
+    ? (chunks as AsyncIterable<Uint8Array>)[Symbol.asyncIterator]()
+    : (chunks as Iterable<Uint8Array>)[Symbol.iterator]();
+  return chunkSource(async () => iterator.next(), async () => iterator.return?.(), width, height);
+}
+
+/**
+ * Read a source to the end into a single RGBA buffer
+ */
+export async function readAllStrips(source: StripSource, rows = 64): Promise<Uint8Array> {
+  const data = new Uint8Array(source.width * source.height * 4);
+  let offset = 0;
+  for (let strip = await source.readStrip(rows); strip && strip.length > 0; strip = await source.readStrip(rows)) {
+    data.set(strip, offset);
+    offset += strip.length;
+  }
+  return data;
+}
+
+/**
+ * Join byte chunks into one array
+ */
+export function concatBytes(chunks: Uint8Array[]): Uint8Array {
+  const output = new Uint8Array(chunks.reduce((sum, chunk) => sum + chunk.length, 0));
+  let offset = 0;
+  for (const chunk of chunks) {
+    output.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return output;
+}
+
+/**
+ * Sink that keeps every chunk in memory
+ */
+export class CollectingSink implements OutputSink {
+  readonly chunks: Uint8Array[] = [];
+
+  write(chunk: Uint8Array): void {
+    this.chunks.push(chunk);
+  }
+
+  /**
+   * Concatenate everything written so far
+   */
+  toBytes(): Uint8Array {
+    return concatBytes(this.chunks);
+  }
+}
+
+export interface OutputPart {
+  index: number;    // 0-based; S3 part numbers are index + 1
+  offset: number;   // Position of the part's first byte in the JPEG
+  data: Uint8Array; // Exactly partSize bytes, except for a shorter final part
+}
+
+/**
+ * Sink that regroups output into fixed-size parts for multipart uploads
+ * Each part is handed to `onPart` as soon as it is full and awaited before more
+ * output is accepted, so at most one part is buffered.
+ *
+ * @example
+ * const sink = new PartSink(8 * 1024 * 1024, (part) => uploadPart(uploadId, part.index + 1, part.data));
+ */
+export class PartSink implements OutputSink {
+  readonly partSize: number;
+  private readonly onPart: (part: OutputPart) => Promise<void> | void;
+  private readonly part: Uint8Array;
+  private filled = 0;
+  private index = 0;
+  private offset = 0;
+
+  constructor(partSize: number, onPart: (part: OutputPart) => Promise<void> | void) {
+    if (!Number.isInteger(partSize) || partSize < 1) {
+      throw new Error(`Part size must be a positive integer, got ${partSize}`);
+    }
+    this.partSize = partSize;
+    this.onPart = onPart;
+    this.part = new Uint8Array(partSize);
+  }
+
+  async write(chunk: Uint8Array): Promise<void> {
+    let position = 0;
+    while (position < chunk.length) {
+      const take = Math.min(chunk.length - position, this.partSize - this.filled);
+      this.part.set(chunk.subarray(position, position + take), this.filled);
+      this.filled += take;
+      position += take;
+      if (this.filled === this.partSize) {
+        await this.emit();
+      }
+    }
+  }
+
+  async close(): Promise<void> {
+    if (this.filled > 0 || this.index === 0) {
+      await this.emit();
+    }
+  }
+
+  private async emit(): Promise<void> {
+    const data = this.part.slice(0, this.filled);
+    const part = { index: this.index++, offset: this.offset, data };
+    this.offset += this.filled;
+    this.filled = 0;
+    await this.onPart(part);
+  }
+}
+
+/**
+ * Drive a source through an encoder into a sink
+ *
+ * @returns Total number of bytes written to the sink
+ */
+export async function runPipeline(
+  source: StripSource,
+  encoder: StripEncoder,
+  sink: OutputSink,
+  hooks: PipelineHooks = {}
+): Promise<number> {
+  let written = 0;
+  const emit = async (chunk: Uint8Array): Promise<void> => {
+    if (chunk && chunk.length > 0) {
+      written += chunk.length;
+      await sink.write(chunk);
+    }
+  };
+
+  let y = 0;
+  for (;;) {
+    const strip = await source.readStrip(encoder.stripHeight);
+    if (!strip || strip.length === 0) {
+      break;
+    }
+    hooks.onStrip?.(strip, y);
+    y += strip.length / (source.width * 4);
+    await emit(encoder.encodeStrip(strip));
+  }
+  if (y < source.height) {
+    throw new Error(`Source ended after ${y} of ${source.height} rows`);
+  }
+
+  await emit(encoder.finish());
+  await sink.close?.();
+  return written;
+}
+
+/**
+ * Drive a source through an encoder as a pull-based ReadableStream
+ * A strip is only read and encoded when the consumer pulls, so a slow reader
+ * (e.g. a fetch upload on a slow network) pauses the encoder.
+ */
+export function pipelineStream(
+  source: StripSource,
+  encoder: StripEncoder,
+  hooks: PipelineHooks = {}
+): ReadableStream<Uint8Array> {
+  let y = 0;
+  return new ReadableStream<Uint8Array>({
+    async pull(controller): Promise<void> {
+      for (;;) {
+        const strip = await source.readStrip(encoder.stripHeight);
+        if (!strip || strip.length === 0) {
+          if (y < source.height) {
+            throw new Error(`Source ended after ${y} of ${source.height} rows`);
+          }
+          const tail = encoder.finish();
+          if (tail.length > 0) {
+            controller.enqueue(tail);
+          }
+          controller.close();
+          return;
+        }
+        hooks.onStrip?.(strip, y);
+        y += strip.length / (source.width * 4);
+        const chunk = encoder.encodeStrip(strip);
+        if (chunk.length > 0) {
+          controller.enqueue(chunk);
+          return;
+        }
+      }
+    },
+  }, { highWaterMark: 0 });
+}
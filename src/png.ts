@@ -0,0 +1,267 @@
+/**
+ * PNG input
+ * Decodes PNG images scanline by scanline into RGBA strips, inflating IDAT data
+ * only as far as the encoder has asked for, so the full frame is never buffered.
+ */
+
+import { readAllStrips } from './pipeline.js';
+import type { StripSource } from './pipeline.js';
+
+const PNG_SIGNATURE = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+export interface PngHeader {
+  width: number;
+  height: number;
+  bitDepth: number;
+  colorType: number;     // 0 gray, 2 RGB, 3 palette, 4 gray+alpha, 6 RGBA
+  interlace: number;
+}
+
+const CHANNELS: Record<number, number> = { 0: 1, 2: 3, 3: 1, 4: 2, 6: 4 };
+
+/**
+ * Return true if the bytes start with the PNG signature
+ */
+export function isPng(bytes: Uint8Array): boolean {
+  return bytes.length >= 8 && PNG_SIGNATURE.every((byte, i) => bytes[i] === byte);
+}
+
+interface PngChunks {
+  header: PngHeader;
+  palette?: Uint8Array;
+  transparency?: Uint8Array;
+  data: Uint8Array[];
+}
+
+function readChunks(bytes: Uint8Array): PngChunks {
+  if (!isPng(bytes)) {
+    throw new Error('Not a PNG file: missing signature');
+  }
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  let header: PngHeader | undefined;
+  let palette: Uint8Array | undefined;
+  let transparency: Uint8Array | undefined;
+  const data: Uint8Array[] = [];
+
+  let pos = 8;
+  while (pos + 8 <= bytes.length) {
+    const length = view.getUint32(pos);
+    const type = String.fromCharCode(...bytes.subarray(pos + 4, pos + 8));
+    const body = bytes.subarray(pos + 8, pos + 8 + length);
+    if (body.length < length) {
+      throw new Error(`PNG chunk ${type} is truncated`);
+    }
+    if (type === 'IHDR') {
+      header = {
+        width: view.getUint32(pos + 8),
+        height: view.getUint32(pos + 12),
+        bitDepth: body[8],
+        colorType: body[9],
+        interlace: body[12],
+      };
+    } else if (type === 'PLTE') {
+      palette = body;
+    } else if (type === 'tRNS') {
+      transparency = body;
+    } else if (type === 'IDAT') {
+      data.push(body);
+    } else if (type === 'IEND') {
+      break;
+    }
+    pos += 12 + length;
+  }
+
+  if (!header) {
+    throw new Error('PNG file has no IHDR chunk');
+  }
+  if (CHANNELS[header.colorType] === undefined) {
+    throw new Error(`Unsupported PNG color type ${header.colorType}`);
+  }
+  if (header.interlace !== 0) {
+    throw new Error('Interlaced PNG images are not supported');
+  }
+  if (header.colorType === 3 && !palette) {
+    throw new Error('Palette PNG has no PLTE chunk');
+  }
+  return { header, palette, transparency, data };
+}
+
+/**
+ * Read the PNG header without decoding any image data
+ */
+export function readPngHeader(bytes: Uint8Array): PngHeader {
+  return readChunks(bytes).header;
+}
+
+function paeth(a: number, b: number, c: number): number {
+  const p = a + b - c;
+  const pa = Math.abs(p - a);
+  const pb = Math.abs(p - b);
+  const pc = Math.abs(p - c);
+  if (pa <= pb && pa <= pc) {
+    return a;
+  }
+  return pb <= pc ? b : c;
+}
+
+/**
+ * Reverse the filter of one scanline in place
+ */
+function unfilter(filter: number, row: Uint8Array, previous: Uint8Array, bpp: number): void {
+  for (let i = 0; i < row.length; i++) {
+    const left = i >= bpp ? row[i - bpp] : 0;
+    const up = previous[i];
+    const upLeft = i >= bpp ? previous[i - bpp] : 0;
+    switch (filter) {
+      case 0:
+        return;
+      case 1:
+        row[i] = (row[i] + left) & 0xff;
+        break;
+      case 2:
+        row[i] = (row[i] + up) & 0xff;
+        break;
+      case 3:
+        row[i] = (row[i] + ((left + up) >> 1)) & 0xff;
+        break;
+      case 4:
+        row[i] = (row[i] + paeth(left, up, upLeft)) & 0xff;
+        break;
+      default:
+        throw new Error(`Invalid PNG filter type ${filter}`);
+    }
+  }
+}
+
+/**
+ * Streaming zlib inflater over in-memory IDAT chunks
+ */
+function inflater(chunks: Uint8Array[]): () => Promise<Uint8Array | null> {
+  const stream = new DecompressionStream('deflate');
+  const writer = stream.writable.getWriter();
+  const reader = stream.readable.getReader();
+  (async () => {
+    for (const chunk of chunks) {
+      await writer.write(chunk);
+    }
+    await writer.close();
+  })().catch(() => {
+    // Errors surface through the reader
+  });
+  return async () => {
+    const { value, done } = await reader.read();
+    return done ? null : value;
+  };
+}
+
+/**
+ * Create a strip source that decodes a PNG file on demand
+ *
+ * @param bytes Complete PNG file
+ */
+export function pngSource(bytes: Uint8Array): StripSource {
+  const { header, palette, transparency, data } = readChunks(bytes);
+  const { width, height, bitDepth, colorType } = header;
+  const channels = CHANNELS[colorType];
+  const bitsPerPixel = channels * bitDepth;
+  const stride = Math.ceil((width * bitsPerPixel) / 8);
+  const bpp = Math.max(1, bitsPerPixel >> 3);
+  const maxSample = (1 << bitDepth) - 1;
+  const next = inflater(data);
+
+  // Inflated bytes not yet consumed
+  let pending = new Uint8Array(0);
+  let previous = new Uint8Array(stride);
+  let y = 0;
+
+  const readRow = async (): Promise<Uint8Array> => {
+    while (pending.length < stride + 1) {
+      const chunk = await next();
+      if (!chunk) {
+        throw new Error(`PNG image data ended at row ${y} of ${height}`);
+      }
+      const merged = new Uint8Array(pending.length + chunk.length);
+      merged.set(pending, 0);
+      merged.set(chunk, pending.length);
+      pending = merged;
+    }
+    const row = pending.slice(1, stride + 1);
+    unfilter(pending[0], row, previous, bpp);
+    pending = pending.subarray(stride + 1);
+    previous = row;
+    return row;
+  };
+
+  const sample = (row: Uint8Array, index: number): number => {
+    if (bitDepth === 8) {
+      return row[index];
+    }
+    if (bitDepth === 16) {
+      return (row[index * 2] << 8) | row[index * 2 + 1];
+    }
+    const bit = index * bitDepth;
+    return (row[bit >> 3] >> (8 - bitDepth - (bit & 7))) & maxSample;
+  };
+  const scale = (value: number): number => bitDepth === 16 ? value >> 8 : Math.round((value * 255) / maxSample);
+
+  const toRgba = (row: Uint8Array, out: Uint8Array, offset: number): void => {
+    for (let x = 0; x < width; x++) {
+      const o = offset + x * 4;
+      if (colorType === 3) {
+        const index = sample(row, x);
+        out[o] = palette![index * 3];
+        out[o + 1] = palette![index * 3 + 1];
+        out[o + 2] = palette![index * 3 + 2];
+        out[o + 3] = transparency && index < transparency.length ? transparency[index] : 255;
+        continue;
+      }
+      const values: number[] = [];
+      for (let c = 0; c < channels; c++) {
+        values.push(sample(row, x * channels + c));
+      }
+      let alpha = 255;
+      if (colorType === 4 || colorType === 6) {
+        alpha = scale(values[channels - 1]);
+      } else if (transparency) {
+        // tRNS holds a 16-bit color key per channel
+        const key = colorType === 0 ? [0] : [0, 1, 2];
+        if (key.every((c) => ((transparency[c * 2] << 8) | transparency[c * 2 + 1]) === values[c])) {
+          alpha = 0;
+        }
+      }
+      if (colorType === 0 || colorType === 4) {
+        out[o] = out[o + 1] = out[o + 2] = scale(values[0]);
+      } else {
+        out[o] = scale(values[0]);
+        out[o + 1] = scale(values[1]);
+        out[o + 2] = scale(values[2]);
+      }
+      out[o + 3] = alpha;
+    }
+  };
+
+  return {
+    width,
+    height,
+    async readStrip(rows: number): Promise<Uint8Array | null> {
+      if (y >= height) {
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      const strip = new Uint8Array(count * width * 4);
+      for (let r = 0; r < count; r++) {
+        toRgba(await readRow(), strip, r * width * 4);
+        y++;
+      }
+      return strip;
+    },
+  };
+}
+
+/**
+ * Decode a complete PNG file to RGBA
+ */
+export async function decodePng(bytes: Uint8Array): Promise<{ data: Uint8Array; width: number; height: number }> {
+  const source = pngSource(bytes);
+  return { data: await readAllStrips(source), width: source.width, height: source.height };
+}
@@ -0,0 +1,160 @@
+/**
+ * Netpbm input (binary PGM, PPM, and PAM)
+ * Rows are converted to RGBA strip by strip straight from the input bytes.
+ */
+
+import type { StripSource } from './pipeline.js';
+
+export interface PnmHeader {
+  format: 'P5' | 'P6' | 'P7';
+  width: number;
+  height: number;
+  depth: number;          // Channels per pixel: 1 gray, 2 gray+alpha, 3 RGB, 4 RGBA
+  maxval: number;
+  dataOffset: number;     // Byte offset of the first row
+}
+
+const PAM_DEPTHS: Record<string, number> = {
+  BLACKANDWHITE: 1,
+  GRAYSCALE: 1,
+  GRAYSCALE_ALPHA: 2,
+  RGB: 3,
+  RGB_ALPHA: 4,
+};
+
+/**
+ * Return true if the bytes start with a binary PGM, PPM, or PAM magic number
+ */
+export function isPnm(bytes: Uint8Array): boolean {
+  return bytes.length >= 3 && bytes[0] === 0x50 && bytes[1] >= 0x35 && bytes[1] <= 0x37 &&
+    (bytes[2] === 0x0a || bytes[2] === 0x0d || bytes[2] === 0x20 || bytes[2] === 0x09);
+}
+
+const isSpace = (byte: number): boolean => byte === 0x20 || byte === 0x09 || byte === 0x0a || byte === 0x0d;
+
+/**
+ * Parse the header of a binary PGM (P5), PPM (P6), or PAM (P7) file
+ */
+export function readPnmHeader(bytes: Uint8Array): PnmHeader {
+  if (!isPnm(bytes)) {
+    throw new Error('Not a binary PGM/PPM/PAM file');
+  }
+  const format = String.fromCharCode(bytes[0], bytes[1]) as PnmHeader['format'];
+  let pos = 2;
+
+  if (format === 'P7') {
+    const fields: Record<string, string> = {};
+    for (;;) {
+      const end = bytes.indexOf(0x0a, pos);
+      if (end < 0) {
+        throw new Error('PAM header is missing ENDHDR');
+      }
+      const line = String.fromCharCode(...bytes.subarray(pos, end)).trim();
+      pos = end + 1;
+      if (line === 'ENDHDR') {
+        break;
+      }
+      if (line && !line.startsWith('#')) {
+        const [key, ...value] = line.split(/\s+/);
+        fields[key] = value.join(' ');
+      }
+    }
+    const depth = Number(fields.DEPTH);
+    const tupleDepth = fields.TUPLTYPE ? PAM_DEPTHS[fields.TUPLTYPE] : depth;
+    if (!(depth >= 1 && depth <= 4) || tupleDepth !== depth) {
+      throw new Error(`Unsupported PAM tuple type ${fields.TUPLTYPE ?? '(none)'} with depth ${fields.DEPTH}`);
+    }
+    return {
+      format,
+      width: Number(fields.WIDTH),
+      height: Number(fields.HEIGHT),
+      depth,
+      maxval: Number(fields.MAXVAL),
+      dataOffset: pos,
+    };
+  }
+
+  // P5/P6: whitespace-separated width, height, maxval with '#' comments, then one whitespace byte
+  const values: number[] = [];
+  while (values.length < 3) {
+    while (pos < bytes.length && (isSpace(bytes[pos]) || bytes[pos] === 0x23)) {
+      if (bytes[pos] === 0x23) {
+        while (pos < bytes.length && bytes[pos] !== 0x0a) {
+          pos++;
+        }
+      }
+      pos++;
+    }
+    const start = pos;
+    while (pos < bytes.length && bytes[pos] >= 0x30 && bytes[pos] <= 0x39) {
+      pos++;
+    }
+    if (pos === start) {
+      throw new Error('Netpbm header is truncated');
+    }
+    values.push(Number(String.fromCharCode(...bytes.subarray(start, pos))));
+  }
+  return {
+    format,
+    width: values[0],
+    height: values[1],
+    depth: format === 'P5' ? 1 : 3,
+    maxval: values[2],
+    dataOffset: pos + 1,
+  };
+}
+
+/**
+ * Create a strip source over a binary PGM, PPM, or PAM file
+ *
+ * @param bytes Complete Netpbm file
+ */
+export function pnmSource(bytes: Uint8Array): StripSource {
+  const header = readPnmHeader(bytes);
+  const { width, height, depth, maxval, dataOffset } = header;
+  if (!(maxval >= 1 && maxval <= 65535) || !(width > 0) || !(height > 0)) {
+    throw new Error(`Invalid Netpbm header: ${width}x${height}, maxval ${maxval}`);
+  }
+  const sampleSize = maxval > 255 ? 2 : 1;
+  const rowBytes = width * depth * sampleSize;
+  if (dataOffset + rowBytes * height > bytes.length) {
+    throw new Error(`Netpbm data is truncated: expected ${rowBytes * height} bytes of pixels`);
+  }
+
+  const sample = (offset: number): number => {
+    const value = sampleSize === 2 ? (bytes[offset] << 8) | bytes[offset + 1] : bytes[offset];
+    return maxval === 255 ? value : Math.round((Math.min(value, maxval) * 255) / maxval);
+  };
+
+  let y = 0;
+  return {
+    width,
+    height,
+    readStrip(rows: number): Uint8Array | null {
+      if (y >= height) {
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      const strip = new Uint8Array(count * width * 4);
+      for (let r = 0; r < count; r++, y++) {
+        let source = dataOffset + y * rowBytes;
+        for (let x = 0; x < width; x++) {
+          const o = (r * width + x) * 4;
+          const values: number[] = [];
+          for (let c = 0; c < depth; c++, source += sampleSize) {
+            values.push(sample(source));
+          }
+          if (depth <= 2) {
+            strip[o] = strip[o + 1] = strip[o + 2] = values[0];
+          } else {
+            strip[o] = values[0];
+            strip[o + 1] = values[1];
+            strip[o + 2] = values[2];
+          }
+          strip[o + 3] = depth === 2 || depth === 4 ? values[depth - 1] : 255;
+        }
+      }
+      return strip;
+    },
+  };
+}
@@ -0,0 +1,46 @@
+/**
+ * Encoder presets
+ * Named bundles of the settings that trade encoding time against file size, so
+ * a caller can pick one by name instead of weighing each option.
+ */
+
+import type { EncodeOptions } from './types.js';
+
+/**
+ * - fast: the engine's output as it comes (4:4:4, standard Huffman tables)
+ * - balanced: 4:2:0 chroma and Huffman tables built from the image
+ * - smallest: 4:2:0 chroma as a progressive JPEG with successive approximation;
+ *   the extra scans cost a little on small images, so it pays off on large ones
+ * - web: what mozjpeg-based optimizers deliver, the smallest settings with all
+ *   metadata but the ICC profile stripped
+ */
+export type EncoderPreset = 'fast' | 'balanced' | 'smallest' | 'web';
+
+export const ENCODER_PRESETS: Record<EncoderPreset, Readonly<EncodeOptions>> = {
+  fast: { subsampling: '4:4:4', optimizeHuffman: false, progressive: false },
+  balanced: { subsampling: '4:2:0', optimizeHuffman: true, progressive: false },
+  smallest: { subsampling: '4:2:0', optimizeHuffman: true, progressive: true },
+  web: { subsampling: '4:2:0', optimizeHuffman: true, progressive: true, stripMetadata: true },
+};
+
+/**
+ * Expand `options.preset` into the settings it stands for
+ * Options given explicitly take precedence over the preset's.
+ */
+export function applyPreset<T extends EncodeOptions>(options: T): T {
+  const { preset } = options;
+  if (preset === undefined) {
+    return options;
+  }
+  const settings = ENCODER_PRESETS[preset];
+  if (!settings) {
+    throw new Error(`Unknown encoder preset: ${preset}`);
+  }
+  const resolved: EncodeOptions = { ...settings };
+  for (const [key, value] of Object.entries(options)) {
+    if (value !== undefined) {
+      (resolved as Record<string, unknown>)[key] = value;
+    }
+  }
+  return resolved as T;
+}
@@ -0,0 +1,357 @@
+/**
+ * Progressive output
+ * Transcodes a sequential JPEG into a progressive one losslessly: the quantized
+ * coefficients are regrouped into spectral-selection and successive-approximation
+ * scans, each with its own optimal Huffman tables. Runs of empty bands are coded
+ * as EOBn symbols, with refinement bits buffered across the run as T.81 G.1.2.3
+ * requires. The DC scan comes first so a coarse preview is available as soon as
+ * it has been written.
+ */
+
+import { Marker, parseJpeg } from './jpeg.js';
+import type { HuffmanSpec, Segment } from './jpeg.js';
+import { readCoefficients } from './decoder.js';
+import { frameGeometry } from './entropy.js';
+import { BitWriter, buildHuffmanCodes, clampAc, clampDc, huffmanTablePayload, magnitudeCategory, optimalHuffmanSpec } from './huffman.js';
+import { createSegment } from './markers.js';
+import { concatBytes } from './pipeline.js';
+
+/**
+ * One scan of a progressive script
+ * - components: frame component indices; AC scans must name exactly one
+ * - DC scans use spectralStart = spectralEnd = 0
+ * - approxHigh/approxLow are Ah/Al: a first scan (Ah = 0) sends coefficients
+ *   shifted right by Al, and each refinement sends the next bit with Al = Ah - 1
+ */
+export interface ProgressiveScanSpec {
+  components: number[];
+  spectralStart: number;
+  spectralEnd: number;
+  approxHigh?: number;   // Defaults to 0
+  approxLow?: number;    // Defaults to 0
+}
+
+/**
+ * Where a written scan sits in the output
+ * offset/length cover the scan's DHT (none for DC refinement), SOS, and entropy-coded data.
+ */
+export interface ProgressiveScanInfo {
+  index: number;
+  componentIds: number[];
+  spectralStart: number;
+  spectralEnd: number;
+  approxHigh: number;
+  approxLow: number;
+  offset: number;
+  length: number;
+}
+
+export interface ProgressiveOptions {
+  scans?: ProgressiveScanSpec[];                 // Scan script, defaults to defaultProgressiveScript
+  onScan?(info: ProgressiveScanInfo): void;      // Called as each scan is written
+  /**
+   * Called once the first DC scan of every component has been written
+   * `preview` is the output so far plus EOI, a complete blurry image.
+   */
+  onDcScan?(info: ProgressiveScanInfo, preview: Uint8Array): void;
+}
+
+const scan = (components: number[], spectralStart: number, spectralEnd: number, approxHigh: number, approxLow: number): ProgressiveScanSpec =>
+  ({ components, spectralStart, spectralEnd, approxHigh, approxLow });
+
+/**
+ * Default scan script, the one libjpeg's jpeg_simple_progression uses
+ * DC at half precision first, then luma AC in two bands and chroma AC with the
+ * low bits held back, then refinement scans for the remaining bits.
+ */
+export function defaultProgressiveScript(componentCount: number): ProgressiveScanSpec[] {
+  const all = Array.from({ length: componentCount }, (_, i) => i);
+  if (componentCount !== 3) {
+    return [
+      scan(all, 0, 0, 0, 1),
+      ...all.map((c) => scan([c], 1, 5, 0, 2)),
+      ...all.map((c) => scan([c], 6, 63, 0, 2)),
+      ...all.map((c) => scan([c], 1, 63, 2, 1)),
+      scan(all, 0, 0, 1, 0),
+      ...all.map((c) => scan([c], 1, 63, 1, 0)),
+    ];
+  }
+  return [
+    scan(all, 0, 0, 0, 1),
+    scan([0], 1, 5, 0, 2),
+    scan([2], 1, 63, 0, 1),
+    scan([1], 1, 63, 0, 1),
+    scan([0], 6, 63, 0, 2),
+    scan([0], 1, 63, 2, 1),
+    scan(all, 0, 0, 1, 0),
+    scan([2], 1, 63, 1, 0),
+    scan([1], 1, 63, 1, 0),
+    scan([0], 1, 63, 1, 0),
+  ];
+}
+
+// Correction bits buffered behind an EOB run before it is flushed early (libjpeg's MAX_CORR_BITS)
+const MAX_CORRECTION_BITS = 1000;
+
+/**
+ * Rewrite a sequential JPEG as a progressive JPEG with the same coefficients
+ * Application, comment, and quantization segments are kept; restart intervals are dropped.
+ * Coefficients outside the 8-bit range are clamped, so DC differences stay within category 11.
+ *
+ * @param jpeg Baseline or extended sequential JPEG with 8-bit samples
+ * @param options Scan script and progress callbacks
+ */
+export function toProgressive(jpeg: Uint8Array, options: ProgressiveOptions = {}): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8) {
+    throw new Error(`Progressive transcoding needs an 8-bit sequential Huffman frame, found marker 0x${frame.marker.toString(16)}`);
+  }
+
+  const script = options.scans ?? defaultProgressiveScript(frame.components.length);
+  // Lowest bit position sent so far for each coefficient of each component, -1 before its first scan
+  const sent = frame.components.map(() => new Int8Array(64).fill(-1));
+  for (const spec of script) {
+    const { components, spectralStart: ss, spectralEnd: se, approxHigh: ah = 0, approxLow: al = 0 } = spec;
+    const dc = ss === 0 && se === 0;
+    if (components.length === 0 || new Set(components).size !== components.length ||
+        components.some((c) => c < 0 || c >= frame.components.length) ||
+        (!dc && (ss < 1 || se > 63 || ss > se || components.length !== 1)) ||
+        al < 0 || al > 13 || (ah !== 0 && ah !== al + 1)) {
+      throw new Error(`Invalid progressive scan: components [${components}], Ss=${ss}, Se=${se}, Ah=${ah}, Al=${al}`);
+    }
+    for (const c of components) {
+      if (!dc && sent[c][0] < 0) {
+        throw new Error('AC scans must follow the DC scan of their component');
+      }
+      for (let k = ss; k <= se; k++) {
+        if (sent[c][k] !== (ah === 0 ? -1 : ah)) {
+          throw new Error(`Scan Ss=${ss}, Se=${se}, Ah=${ah} does not continue coefficient ${k} of component ${c}`);
+        }
+        sent[c][k] = al;
+      }
+    }
+  }
+  if (sent.some((bits) => bits.some((al) => al !== 0))) {
+    throw new Error('Progressive scan script does not send every coefficient in full');
+  }
+
+  const geometry = frameGeometry(frame);
+  const coefficients = readCoefficients(file);
+
+  // Header: SOI, APPn/COM/DQT segments from the source, then the frame as SOF2
+  const chunks: Uint8Array[] = [new Uint8Array([0xff, Marker.SOI])];
+  const sof = file.segments.find((s) => s.marker === frame.marker) as Segment;
+  for (const segment of file.segments) {
+    if (segment.offset >= sof.offset) {
+      break;
+    }
+    if ((segment.marker >= Marker.APP0 && segment.marker <= Marker.APP15) || segment.marker === Marker.COM || segment.marker === Marker.DQT) {
+      chunks.push(jpeg.subarray(segment.offset, segment.offset + segment.length));
+    }
+  }
+  chunks.push(createSegment(Marker.SOF2, sof.data));
+  let written = chunks.reduce((sum, chunk) => sum + chunk.length, 0);
+
+  const dcDone = new Set<number>();
+  let previewSent = false;
+
+  script.forEach((spec, index) => {
+    const { components, spectralStart: ss, spectralEnd: se, approxHigh: ah = 0, approxLow: al = 0 } = spec;
+    const dc = ss === 0;
+    const refine = ah > 0;
+    // Table 0 for the first component and table 1 for the rest, as libjpeg assigns them
+    const tableOf = (component: number): number => (component > 0 ? 1 : 0);
+
+    const visitBlocks = (visit: (component: number, block: number) => void): void => {
+      if (components.length === 1) {
+        const c = components[0];
+        const { blocksPerLine, blocksPerColumn } = geometry.components[c];
+        for (let row = 0; row < blocksPerColumn; row++) {
+          for (let col = 0; col < blocksPerLine; col++) {
+            visit(c, row * coefficients[c].blocksPerLine + col);
+          }
+        }
+        return;
+      }
+      for (let mcuRow = 0; mcuRow < geometry.mcusPerColumn; mcuRow++) {
+        for (let mcuCol = 0; mcuCol < geometry.mcusPerLine; mcuCol++) {
+          for (const c of components) {
+            const { h, v } = frame.components[c];
+            for (let y = 0; y < v; y++) {
+              for (let x = 0; x < h; x++) {
+                visit(c, (mcuRow * v + y) * coefficients[c].blocksPerLine + mcuCol * h + x);
+              }
+            }
+          }
+        }
+      }
+    };
+
+    // Run the scan twice: once to gather symbol statistics, once to write
+    const encodeScan = (symbol: (table: number, value: number) => void, bits: (value: number, size: number) => void): void => {
+      const table = tableOf(components[0]);
+      const predictors = new Int32Array(frame.components.length);
+      const magnitudes = new Int32Array(64);
+      // Correction bits of blocks inside the current EOB run, sent after the EOBn symbol
+      const pending: number[] = [];
+      let eobrun = 0;
+      const flushEobrun = (): void => {
+        if (eobrun > 0) {
+          const size = magnitudeCategory(eobrun) - 1;
+          symbol(table, size << 4);
+          bits(eobrun - (1 << size), size);
+          eobrun = 0;
+          pending.forEach((bit) => bits(bit, 1));
+          pending.length = 0;
+        }
+      };
+
+      visitBlocks((c, block) => {
+        const values = coefficients[c].blocks;
+        const base = block * 64;
+        if (dc) {
+          const value = clampDc(values[base]) >> al;
+          if (refine) {
+            bits(value & 1, 1);
+            return;
+          }
+          const diff = value - predictors[c];
+          predictors[c] = value;
+          const size = magnitudeCategory(diff);
+          symbol(tableOf(c), size);
+          bits(diff < 0 ? diff + (1 << size) - 1 : diff, size);
+          return;
+        }
+
+        // Magnitudes at this scan's precision; signs come from the coefficients
+        let lastNew = 0;
+        for (let k = ss; k <= se; k++) {
+          magnitudes[k] = Math.abs(clampAc(values[base + k])) >> al;
+          if (magnitudes[k] === 1) {
+            lastNew = k;
+          }
+        }
+
+        if (!refine) {
+          let run = 0;
+          for (let k = ss; k <= se; k++) {
+            const magnitude = magnitudes[k];
+            if (magnitude === 0) {
+              run++;
+              continue;
+            }
+            flushEobrun();
+            while (run >= 16) {
+              symbol(table, 0xf0);
+              run -= 16;
+            }
+            const size = magnitudeCategory(magnitude);
+            symbol(table, (run << 4) | size);
+            bits(values[base + k] < 0 ? (1 << size) - 1 - magnitude : magnitude, size);
+            run = 0;
+          }
+          if (run > 0) {
+            eobrun++;
+            if (eobrun === 0x7fff) {
+              flushEobrun();
+            }
+          }
+          return;
+        }
+
+        // Refinement (G.1.2.3): coefficients becoming nonzero are coded with their
+        // zero run, while already-nonzero ones contribute one correction bit each,
+        // sent after the next symbol or with the EOB run that covers the block
+        let run = 0;
+        const corrections: number[] = [];
+        for (let k = ss; k <= se; k++) {
+          const magnitude = magnitudes[k];
+          if (magnitude === 0) {
+            run++;
+            continue;
+          }
+          while (run > 15 && k <= lastNew) {
+            flushEobrun();
+            symbol(table, 0xf0);
+            run -= 16;
+            corrections.forEach((bit) => bits(bit, 1));
+            corrections.length = 0;
+          }
+          if (magnitude > 1) {
+            corrections.push(magnitude & 1);
+            continue;
+          }
+          flushEobrun();
+          symbol(table, (run << 4) | 1);
+          bits(values[base + k] < 0 ? 0 : 1, 1);
+          corrections.forEach((bit) => bits(bit, 1));
+          corrections.length = 0;
+          run = 0;
+        }
+        if (run > 0 || corrections.length > 0) {
+          eobrun++;
+          pending.push(...corrections);
+          if (eobrun === 0x7fff || pending.length > MAX_CORRECTION_BITS - 64 + 1) {
+            flushEobrun();
+          }
+        }
+      });
+      flushEobrun();
+    };
+
+    const frequencies = [new Uint32Array(256), new Uint32Array(256)];
+    encodeScan((table, value) => { frequencies[table][value]++; }, () => {});
+    // DC refinement scans are raw bits and need no tables
+    const tableIds = refine && dc ? [] : [...new Set(components.map(tableOf))];
+    const specs: HuffmanSpec[] = tableIds.map((id) => optimalHuffmanSpec(frequencies[id], dc ? 0 : 1, id));
+    const codes = specs.map((s) => buildHuffmanCodes(s));
+
+    const writer = new BitWriter();
+    encodeScan(
+      (table, value) => {
+        const code = codes[tableIds.indexOf(table)];
+        writer.writeBits(code.codes[value], code.lengths[value]);
+      },
+      (value, size) => writer.writeBits(value, size)
+    );
+
+    // Selectors a scan does not use are written as 0; each table gets its own DHT like libjpeg's
+    const sos = [components.length];
+    for (const c of components) {
+      sos.push(frame.components[c].id, dc ? (refine ? 0 : tableOf(c) << 4) : tableOf(c));
+    }
+    sos.push(ss, se, (ah << 4) | al);
+    const scanChunks = [
+      ...specs.map((spec) => createSegment(Marker.DHT, huffmanTablePayload([spec]))),
+      createSegment(Marker.SOS, new Uint8Array(sos)),
+      writer.finish(),
+    ];
+    chunks.push(...scanChunks);
+
+    const length = scanChunks.reduce((sum, chunk) => sum + chunk.length, 0);
+    const info: ProgressiveScanInfo = {
+      index,
+      componentIds: components.map((c) => frame.components[c].id),
+      spectralStart: ss,
+      spectralEnd: se,
+      approxHigh: ah,
+      approxLow: al,
+      offset: written,
+      length,
+    };
+    written += length;
+    options.onScan?.(info);
+
+    if (dc && !refine) {
+      components.forEach((c) => dcDone.add(c));
+      if (!previewSent && dcDone.size === frame.components.length && options.onDcScan) {
+        previewSent = true;
+        options.onDcScan(info, concatBytes([...chunks, new Uint8Array([0xff, Marker.EOI])]));
+      }
+    }
+  });
+
+  chunks.push(new Uint8Array([0xff, Marker.EOI]));
+  return concatBytes(chunks);
+}
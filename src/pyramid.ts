@@ -0,0 +1,119 @@
+/**
+ * Multi-resolution output
+ * Encodes several sizes of one image from a single pass over its strips. Each
+ * level is box-downsampled row by row from the next larger level and fed to its
+ * own strip encoder, so no level is ever held in full.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { createJpegStripEncoder, openStripSource } from './encoder.js';
+import { CollectingSink } from './pipeline.js';
+import type { StripEncoder } from './pipeline.js';
+import { RowDownscaler } from './downscale.js';
+
+export interface PyramidOptions extends Pick<EncodeOptions, 'width' | 'height' | 'quality'> {
+  sizes: number[]; // Longest edge of each output in pixels; sizes at or above the source size give the source size
+}
+
+export interface PyramidLevel {
+  size: number;     // Requested size
+  width: number;
+  height: number;
+  jpeg: Uint8Array;
+}
+
+interface Level {
+  size: number;
+  width: number;
+  height: number;
+  scaler?: RowDownscaler;   // Absent for a level at the size of its parent
+  encoder: StripEncoder;
+  sink: CollectingSink;
+  strip: Uint8Array;
+  rows: number;
+  children: Level[];
+}
+
+function pushRow(level: Level, row: Uint8Array): void {
+  const forward = (output: Uint8Array): void => {
+    level.strip.set(output, level.rows * level.width * 4);
+    if (++level.rows === level.encoder.stripHeight) {
+      level.sink.write(level.encoder.encodeStrip(level.strip));
+      level.rows = 0;
+    }
+    for (const child of level.children) {
+      pushRow(child, output);
+    }
+  };
+  if (level.scaler) {
+    level.scaler.push(row, forward);
+  } else {
+    forward(row);
+  }
+}
+
+/**
+ * Encode several resolutions of an image in one pass
+ * Levels are cascaded: each is downsampled from the smallest larger level,
+ * sharing the work of reading and converting the source.
+ *
+ * @example
+ * const [large, medium, small] = await encodePyramid(file, { sizes: [1600, 800, 320], quality: 82 });
+ *
+ * @returns One independent JPEG per requested size, in the order requested
+ */
+export async function encodePyramid(source: ImageSource, options: PyramidOptions): Promise<PyramidLevel[]> {
+  const { sizes, quality } = options;
+  if (sizes.length === 0 || sizes.some((size) => !Number.isInteger(size) || size < 1)) {
+    throw new Error(`Pyramid sizes must be positive integers, got [${sizes}]`);
+  }
+  const strips = await openStripSource(source, options);
+  const { width: sourceWidth, height: sourceHeight } = strips;
+  const longest = Math.max(sourceWidth, sourceHeight);
+
+  // Largest first, so each level can hang off the previous one
+  const order = sizes.map((size, index) => ({ size, index })).sort((a, b) => b.size - a.size);
+  const levels: Level[] = new Array(sizes.length);
+  let parent: Level | undefined;
+  for (const { size, index } of order) {
+    const scale = Math.min(1, size / longest);
+    const width = Math.max(1, Math.round(sourceWidth * scale));
+    const height = Math.max(1, Math.round(sourceHeight * scale));
+    const from = parent ?? { width: sourceWidth, height: sourceHeight };
+    const encoder = createJpegStripEncoder(width, height, quality);
+    const level: Level = {
+      size,
+      width,
+      height,
+      scaler: width === from.width && height === from.height ? undefined : new RowDownscaler(from.width, from.height, width, height),
+      encoder,
+      sink: new CollectingSink(),
+      strip: new Uint8Array(encoder.stripHeight * width * 4),
+      rows: 0,
+      children: [],
+    };
+    parent?.children.push(level);
+    levels[index] = level;
+    parent = level;
+  }
+
+  const rowBytes = sourceWidth * 4;
+  let y = 0;
+  for (let strip = await strips.readStrip(8); strip && strip.length > 0; strip = await strips.readStrip(8)) {
+    for (let offset = 0; offset + rowBytes <= strip.length; offset += rowBytes, y++) {
+      const row = strip.subarray(offset, offset + rowBytes);
+      pushRow(levels[order[0].index], row);
+    }
+  }
+  if (y < sourceHeight) {
+    throw new Error(`Source ended after ${y} of ${sourceHeight} rows`);
+  }
+
+  return levels.map((level) => {
+    if (level.rows > 0) {
+      level.sink.write(level.encoder.encodeStrip(level.strip.subarray(0, level.rows * level.width * 4)));
+    }
+    level.sink.write(level.encoder.finish());
+    return { size: level.size, width: level.width, height: level.height, jpeg: level.sink.toBytes() };
+  });
+}
@@ -0,0 +1,140 @@
+/**
+ * QOI input
+ * Decodes "Quite OK Image" files into RGBA strips, keeping the decoder state
+ * between strips so only the requested rows are ever materialized.
+ */
+
+import type { StripSource } from './pipeline.js';
+
+export interface QoiHeader {
+  width: number;
+  height: number;
+  channels: number;      // 3 = RGB, 4 = RGBA
+  colorspace: number;    // 0 = sRGB with linear alpha, 1 = all channels linear
+}
+
+const QOI_OP_INDEX = 0x00;
+const QOI_OP_DIFF = 0x40;
+const QOI_OP_LUMA = 0x80;
+const QOI_OP_RUN = 0xc0;
+const QOI_OP_RGB = 0xfe;
+const QOI_OP_RGBA = 0xff;
+const QOI_MASK = 0xc0;
+const HEADER_SIZE = 14;
+
+/**
+ * Return true if the bytes start with the QOI magic "qoif"
+ */
+export function isQoi(bytes: Uint8Array): boolean {
+  return bytes.length >= HEADER_SIZE && bytes[0] === 0x71 && bytes[1] === 0x6f && bytes[2] === 0x69 && bytes[3] === 0x66;
+}
+
+/**
+ * Parse the 14-byte QOI header
+ */
+export function readQoiHeader(bytes: Uint8Array): QoiHeader {
+  if (!isQoi(bytes)) {
+    throw new Error('Not a QOI file: missing "qoif" magic');
+  }
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const header = {
+    width: view.getUint32(4),
+    height: view.getUint32(8),
+    channels: bytes[12],
+    colorspace: bytes[13],
+  };
+  if (header.width === 0 || header.height === 0 || (header.channels !== 3 && header.channels !== 4)) {
+    throw new Error(`Invalid QOI header: ${header.width}x${header.height}, ${header.channels} channels`);
+  }
+  return header;
+}
+
+/**
+ * Create a strip source that decodes a QOI file on demand
+ *
+ * @param bytes Complete QOI file
+ */
+export function qoiSource(bytes: Uint8Array): StripSource {
+  const { width, height } = readQoiHeader(bytes);
+  const index = new Uint8Array(64 * 4);
+  let r = 0;
+  let g = 0;
+  let b = 0;
+  let a = 255;
+  let run = 0;
+  let pos = HEADER_SIZE;
+  let y = 0;
+
+  const need = (count: number): void => {
+    if (pos + count > bytes.length) {
+      throw new Error(`QOI data ended at row ${y} of ${height}`);
+    }
+  };
+
+  const nextPixel = (): void => {
+    if (run > 0) {
+      run--;
+      return;
+    }
+    need(1);
+    const op = bytes[pos++];
+    if (op === QOI_OP_RGB) {
+      need(3);
+      r = bytes[pos];
+      g = bytes[pos + 1];
+      b = bytes[pos + 2];
+      pos += 3;
+    } else if (op === QOI_OP_RGBA) {
+      need(4);
+      r = bytes[pos];
+      g = bytes[pos + 1];
+      b = bytes[pos + 2];
+      a = bytes[pos + 3];
+      pos += 4;
+    } else if ((op & QOI_MASK) === QOI_OP_INDEX) {
+      r = index[op * 4];
+      g = index[op * 4 + 1];
+      b = index[op * 4 + 2];
+      a = index[op * 4 + 3];
+    } else if ((op & QOI_MASK) === QOI_OP_DIFF) {
+      r = (r + ((op >> 4) & 0x03) - 2) & 0xff;
+      g = (g + ((op >> 2) & 0x03) - 2) & 0xff;
+      b = (b + (op & 0x03) - 2) & 0xff;
+    } else if ((op & QOI_MASK) === QOI_OP_LUMA) {
+      need(1);
+      const next = bytes[pos++];
+      const dg = (op & 0x3f) - 32;
+      r = (r + dg - 8 + ((next >> 4) & 0x0f)) & 0xff;
+      g = (g + dg) & 0xff;
+      b = (b + dg - 8 + (next & 0x0f)) & 0xff;
+    } else if ((op & QOI_MASK) === QOI_OP_RUN) {
+      run = op & 0x3f;
+    }
+    const slot = ((r * 3 + g * 5 + b * 7 + a * 11) % 64) * 4;
+    index[slot] = r;
+    index[slot + 1] = g;
+    index[slot + 2] = b;
+    index[slot + 3] = a;
+  };
+
+  return {
+    width,
+    height,
+    readStrip(rows: number): Uint8Array | null {
+      if (y >= height) {
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      const strip = new Uint8Array(count * width * 4);
+      for (let i = 0; i < strip.length; i += 4) {
+        nextPixel();
+        strip[i] = r;
+        strip[i + 1] = g;
+        strip[i + 2] = b;
+        strip[i + 3] = a;
+      }
+      y += count;
+      return strip;
+    },
+  };
+}
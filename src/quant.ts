@@ -0,0 +1,119 @@
+/**
+ * Quantization table helpers
+ * Tables are usually written down in natural (row-major) order, but DQT
+ * segments store them in zig-zag order. These helpers convert between the two,
+ * check user-supplied tables, and serialize DQT segments from natural-order input.
+ */
+
+import { Marker, ZIGZAG } from './jpeg.js';
+import type { QuantTable } from './jpeg.js';
+import { createSegment } from './markers.js';
+import { EncodeError } from './errors.js';
+
+/**
+ * A quantization table in natural order, as printed in T.81 Annex K
+ */
+export interface NaturalQuantTable {
+  id: number;                 // Destination 0-3
+  values: ArrayLike<number>;  // 64 entries, row-major over the 8x8 block
+  precision?: 0 | 1;          // 1 forces 16-bit entries; by default they are used only when needed
+}
+
+function checkLength(values: ArrayLike<number>): void {
+  if (values.length !== 64) {
+    throw new EncodeError('bad-table', `Quantization tables have 64 entries, got ${values.length}`);
+  }
+}
+
+/**
+ * Reorder a natural-order table into zig-zag order
+ */
+export function naturalToZigzag(natural: ArrayLike<number>): Uint16Array {
+  checkLength(natural);
+  return Uint16Array.from(ZIGZAG, (index) => natural[index]);
+}
+
+/**
+ * Reorder a zig-zag table (as stored in DQT) into natural order
+ */
+export function zigzagToNatural(zigzag: ArrayLike<number>): Uint16Array {
+  checkLength(zigzag);
+  const natural = new Uint16Array(64);
+  ZIGZAG.forEach((index, k) => {
+    natural[index] = zigzag[k];
+  });
+  return natural;
+}
+
+/**
+ * What to do with quantizer values outside 1-255 (1-65535 for 16-bit tables)
+ * - 'reject': throw, naming the first bad entry
+ * - 'clamp': round to an integer and clamp into range
+ */
+export type QuantTableMode = 'reject' | 'clamp';
+
+/**
+ * Check a user-supplied table and return it as integers in range
+ * Zero, negative, fractional, and non-finite entries would make quantization
+ * divide by nothing or produce coefficients no decoder agrees on.
+ *
+ * @param values 64 entries in either order; the order is kept
+ * @param precision Entry size; 16-bit entries (Pq=1) need an extended sequential or progressive frame
+ */
+export function normalizeQuantTable(values: ArrayLike<number>, mode: QuantTableMode = 'reject', precision: 8 | 16 = 8): Uint16Array {
+  checkLength(values);
+  const max = precision === 16 ? 65535 : 255;
+  const table = new Uint16Array(64);
+  for (let i = 0; i < 64; i++) {
+    const value = values[i];
+    if (Number.isInteger(value) && value >= 1 && value <= max) {
+      table[i] = value;
+    } else if (mode === 'clamp' && !Number.isNaN(value)) {
+      table[i] = Math.max(1, Math.min(max, Math.round(value)));
+    } else {
+      throw new EncodeError(value === 0 ? 'zero-quant-entry' : 'bad-table', `Quantization table entry ${i} must be an integer 1-${max}, got ${value}`);
+    }
+  }
+  return table;
+}
+
+/**
+ * Serialize tables (zig-zag values) as a DQT payload
+ */
+export function quantTablePayload(tables: QuantTable[]): Uint8Array {
+  const payload = new Uint8Array(tables.reduce((sum, table) => sum + 1 + 64 * (table.precision ? 2 : 1), 0));
+  let offset = 0;
+  for (const table of tables) {
+    checkLength(table.values);
+    payload[offset++] = (table.precision << 4) | table.id;
+    for (const value of table.values) {
+      if (table.precision) {
+        payload[offset++] = value >> 8;
+      }
+      payload[offset++] = value & 0xff;
+    }
+  }
+  return payload;
+}
+
+/**
+ * Build a DQT segment from natural-order tables
+ * Entries are written in zig-zag order; a table uses 16-bit entries when it asks
+ * for them or a value exceeds 255.
+ *
+ * @example
+ * const dqt = dqtSegment([{ id: 0, values: ANNEX_K_LUMINANCE }]);
+ */
+export function dqtSegment(tables: NaturalQuantTable[]): Uint8Array {
+  const zigzag = tables.map(({ id, values, precision }): QuantTable => {
+    if (id < 0 || id > 3) {
+      throw new Error(`Quantization table id must be 0-3, got ${id}`);
+    }
+    const ordered = naturalToZigzag(values);
+    if (ordered.some((value) => value < 1 || value > 65535)) {
+      throw new Error(`Quantization table ${id} entries must be 1-65535`);
+    }
+    return { id, precision: precision || ordered.some((value) => value > 255) ? 1 : 0, values: ordered };
+  });
+  return createSegment(Marker.DQT, quantTablePayload(zigzag));
+}
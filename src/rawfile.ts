@@ -0,0 +1,115 @@
+/**
+ * Raw pixel files
+ * Reads uncompressed frames from disk one strip at a time at computed file
+ * offsets, so images far larger than memory can be encoded (Node.js only).
+ */
+
+import type { StripSource } from './pipeline.js';
+import { checkDimensions } from './dimensions.js';
+
+export type RawPixelFormat = 'rgba' | 'bgra' | 'rgb' | 'bgr' | 'gray';
+
+export interface RawFileOptions {
+  width: number;
+  height: number;
+  format?: RawPixelFormat; // Defaults to 'rgba'
+  stride?: number;         // Bytes per row including padding, defaults to width × bytes per pixel
+  offset?: number;         // Bytes to skip before the first row (file header), defaults to 0
+}
+
+export const BYTES_PER_PIXEL: Record<RawPixelFormat, number> = { rgba: 4, bgra: 4, rgb: 3, bgr: 3, gray: 1 };
+
+/**
+ * Convert rows of any supported raw format to RGBA
+ */
+export function rawRowsToRgba(data: Uint8Array, width: number, rows: number, stride: number, format: RawPixelFormat): Uint8Array {
+  const bpp = BYTES_PER_PIXEL[format];
+  if (format === 'rgba' && stride === width * 4) {
+    return data.subarray(0, rows * stride);
+  }
+  const rgba = new Uint8Array(width * rows * 4);
+  for (let r = 0; r < rows; r++) {
+    for (let x = 0, i = r * stride, o = r * width * 4; x < width; x++, i += bpp, o += 4) {
+      switch (format) {
+        case 'gray':
+          rgba[o] = rgba[o + 1] = rgba[o + 2] = data[i];
+          rgba[o + 3] = 255;
+          break;
+        case 'rgb':
+        case 'rgba':
+          rgba[o] = data[i];
+          rgba[o + 1] = data[i + 1];
+          rgba[o + 2] = data[i + 2];
+          rgba[o + 3] = bpp === 4 ? data[i + 3] : 255;
+          break;
+        default:
+          rgba[o] = data[i + 2];
+          rgba[o + 1] = data[i + 1];
+          rgba[o + 2] = data[i];
+          rgba[o + 3] = bpp === 4 ? data[i + 3] : 255;
+      }
+    }
+  }
+  return rgba;
+}
+
+/**
+ * Open a strip source over a raw pixel file
+ * The file stays open until the last strip has been read or `close` is called.
+ *
+ * @param path File holding `height` rows of `stride` bytes after `offset`
+ * @param options Dimensions, pixel format, row stride, and header size
+ */
+export async function rawFileSource(path: string, options: RawFileOptions): Promise<StripSource & { close(): Promise<void> }> {
+  const { width, height, format = 'rgba', offset = 0 } = options;
+  const bpp = BYTES_PER_PIXEL[format];
+  if (!bpp) {
+    throw new Error(`Unsupported raw pixel format: ${format}`);
+  }
+  // Larger than one JPEG is fine; encodeTiled splits it, and the encoders enforce the limit
+  checkDimensions(width, height, Infinity);
+  const stride = options.stride ?? width * bpp;
+  if (stride < width * bpp || offset < 0) {
+    throw new Error(`Invalid raw file layout: ${width}x${height} ${format}, stride ${stride}, offset ${offset}`);
+  }
+
+  const { open } = await import('fs/promises');
+  const file = await open(path, 'r');
+  const { size } = await file.stat();
+  const needed = offset + stride * (height - 1) + width * bpp;
+  if (size < needed) {
+    await file.close();
+    throw new Error(`Raw file is too small: expected at least ${needed} bytes, found ${size}`);
+  }
+
+  let y = 0;
+  let closed = false;
+  const close = async (): Promise<void> => {
+    if (!closed) {
+      closed = true;
+      await file.close();
+    }
+  };
+
+  return {
+    width,
+    height,
+    close,
+    async readStrip(rows: number): Promise<Uint8Array | null> {
+      if (y >= height) {
+        await close();
+        return null;
+      }
+      const count = Math.min(rows, height - y);
+      // The last row may lack its padding at the end of the file
+      const length = stride * (count - 1) + width * bpp;
+      const data = new Uint8Array(stride * count);
+      const { bytesRead } = await file.read(data, 0, length, offset + y * stride);
+      if (bytesRead < length) {
+        throw new Error(`Raw file ended at row ${y}`);
+      }
+      y += count;
+      return rawRowsToRgba(data, width, count, stride, format);
+    },
+  };
+}
@@ -0,0 +1,109 @@
+/**
+ * Raw video input
+ * Reads back-to-back RGBA frames from a byte stream, such as
+ * `ffmpeg -f rawvideo -pix_fmt rgba -` piped into stdin, and encodes each one
+ * as it arrives. Chunks may split rows and frames anywhere; only the strip
+ * being encoded is held in memory.
+ */
+
+import { createJpegStripEncoder } from './encoder.js';
+import { CollectingSink, runPipeline } from './pipeline.js';
+import type { StripSource } from './pipeline.js';
+import type { MjpegFrame } from './mjpeg.js';
+
+export interface RawVideoOptions {
+  width: number;
+  height: number;
+  quality?: number;
+  frameRate?: number;     // Frames per second, used for each frame's delay; 0 delay when absent
+}
+
+/**
+ * Reads exact byte counts from an async iterable of chunks of any size
+ */
+class ChunkReader {
+  private readonly iterator: AsyncIterator<Uint8Array>;
+  private pending: Uint8Array = new Uint8Array(0);
+  private done = false;
+
+  constructor(input: AsyncIterable<Uint8Array>) {
+    this.iterator = input[Symbol.asyncIterator]();
+  }
+
+  private async fill(): Promise<boolean> {
+    while (this.pending.length === 0 && !this.done) {
+      const result = await this.iterator.next();
+      if (result.done) {
+        this.done = true;
+      } else {
+        this.pending = new Uint8Array(result.value.buffer, result.value.byteOffset, result.value.byteLength);
+      }
+    }
+    return this.pending.length > 0;
+  }
+
+  async atEnd(): Promise<boolean> {
+    return !(await this.fill());
+  }
+
+  /**
+   * Read `length` bytes, or fewer if the input ends first
+   */
+  async read(length: number): Promise<Uint8Array> {
+    const out = new Uint8Array(length);
+    let filled = 0;
+    while (filled < length && await this.fill()) {
+      const take = Math.min(this.pending.length, length - filled);
+      out.set(this.pending.subarray(0, take), filled);
+      this.pending = this.pending.subarray(take);
+      filled += take;
+    }
+    return filled === length ? out : out.subarray(0, filled);
+  }
+}
+
+/**
+ * Encode each raw RGBA frame of a byte stream as a standalone JPEG
+ * Input is pulled only as frames are consumed, so a slow consumer applies
+ * backpressure to the pipe. Input that stops partway through a frame is an error.
+ *
+ * @example
+ * // ffmpeg -i clip.mp4 -f rawvideo -pix_fmt rgba - | node thumbs.js
+ * for await (const { index, jpeg } of rawVideoFrames(process.stdin, { width: 640, height: 360 })) {
+ *   await writeFile(`frame-${index}.jpg`, jpeg);
+ * }
+ */
+export async function* rawVideoFrames(
+  input: AsyncIterable<Uint8Array>,
+  options: RawVideoOptions
+): AsyncGenerator<MjpegFrame> {
+  const { width, height, quality = 90, frameRate } = options;
+  if (frameRate !== undefined && !(frameRate > 0)) {
+    throw new Error(`Frame rate must be positive, got ${frameRate}`);
+  }
+  const delay = frameRate ? 1000 / frameRate : 0;
+  const reader = new ChunkReader(input);
+  const rowBytes = width * 4;
+
+  for (let index = 0; !(await reader.atEnd()); index++) {
+    let y = 0;
+    const source: StripSource = {
+      width,
+      height,
+      async readStrip(rows: number): Promise<Uint8Array | null> {
+        if (y >= height) {
+          return null;
+        }
+        const strip = await reader.read(Math.min(rows, height - y) * rowBytes);
+        if (strip.length % rowBytes !== 0 || strip.length === 0) {
+          throw new Error(`Input ended partway through frame ${index}, after ${y + Math.floor(strip.length / rowBytes)} of ${height} rows`);
+        }
+        y += strip.length / rowBytes;
+        return strip;
+      },
+    };
+    const sink = new CollectingSink();
+    await runPipeline(source, createJpegStripEncoder(width, height, quality), sink);
+    yield { index, delay, jpeg: sink.toBytes() };
+  }
+}
@@ -0,0 +1,161 @@
+/**
+ * Recovery points
+ * A JPEG cut off in transit is normally discarded whole, since nothing marks
+ * where the entropy-coded data can be trusted up to. Restart markers every few
+ * MCU rows give byte-aligned points with fresh DC predictors: a receiver holding
+ * a truncated stream decodes every complete interval before the last marker it
+ * has, and shows those rows while the rest is still missing.
+ */
+
+import { Marker, metadataSegments, parseFrameHeader, parseJpeg, readUint16 } from './jpeg.js';
+import { frameGeometry } from './entropy.js';
+import { decode, readCoefficients } from './decoder.js';
+import type { DecodedImage } from './decoder.js';
+import { writeBaseline } from './transcode.js';
+import type { CodedComponent } from './transcode.js';
+import { buildStripIndex } from './stripindex.js';
+import type { StripIndex } from './stripindex.js';
+
+export interface RecoveryPointsResult {
+  jpeg: Uint8Array;
+  index: StripIndex;      // Offset and rows of every interval, the recovery points being segment starts
+}
+
+/**
+ * - fullHeight: the height the complete stream declares; `height` counts only the recovered rows
+ */
+export interface PartialImage extends DecodedImage {
+  fullHeight: number;
+}
+
+/**
+ * Re-code a JPEG with a recovery point every `mcuRows` MCU rows
+ * Coefficients are unchanged; Huffman tables are rebuilt for the new DC
+ * differences. APPn and COM segments are kept.
+ *
+ * @param jpeg Sequential 8-bit JPEG with a single scan
+ */
+export function addRecoveryPoints(jpeg: Uint8Array, mcuRows = 1): RecoveryPointsResult {
+  if (!Number.isInteger(mcuRows) || mcuRows < 1) {
+    throw new Error(`Recovery interval must be a positive number of MCU rows, got ${mcuRows}`);
+  }
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 || file.scans.length !== 1) {
+    throw new Error('Recovery points need an 8-bit sequential JPEG with a single scan');
+  }
+  const scan = file.scans[0];
+  const coefficients = readCoefficients(file);
+  const geometry = frameGeometry(frame);
+  // The DRI field is 16 bits
+  const restartInterval = mcuRows * geometry.mcusPerLine;
+  if (restartInterval > 0xffff) {
+    throw new Error(`${mcuRows} rows of ${geometry.mcusPerLine} MCUs exceed the largest restart interval`);
+  }
+
+  const components: CodedComponent[] = frame.components.map((c, index) => ({
+    id: c.id,
+    h: c.h,
+    v: c.v,
+    quantId: c.quantTable,
+    blocksPerLine: coefficients[index].blocksPerLine,
+    blocksPerColumn: coefficients[index].blocksPerColumn,
+    blocks: coefficients[index].blocks,
+  }));
+  const usedIds = new Set(components.map((c) => c.quantId));
+  const segments = metadataSegments(file);
+
+  const output = writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components,
+    quantTables: [...scan.quantTables.values()].filter((t) => usedIds.has(t.id)).sort((a, b) => a.id - b.id),
+    segments,
+    restartInterval,
+  });
+  return { jpeg: output, index: buildStripIndex(output) };
+}
+
+/**
+ * Decode as much of a possibly truncated JPEG as its recovery points allow
+ * Rows after the last complete restart interval are left out; a complete
+ * stream decodes in full. Needs the whole header, up to the start of the scan.
+ *
+ * @example
+ * socket.onmessage = (event) => {
+ *   received = concat(received, event.data);
+ *   const image = decodePartial(received);
+ *   if (image.height > 0) draw(image);
+ * };
+ */
+export function decodePartial(bytes: Uint8Array): PartialImage {
+  if (bytes.length < 2 || bytes[0] !== 0xff || bytes[1] !== Marker.SOI) {
+    throw new Error('Not a JPEG stream: missing SOI marker');
+  }
+  // Walk the header by hand, since the rest of the stream may be missing
+  let frameOffset = -1;
+  let restartInterval = 0;
+  let dataOffset = -1;
+  let frameData: Uint8Array | undefined;
+  let frameMarker = 0;
+  for (let pos = 2; pos + 4 <= bytes.length && bytes[pos] === 0xff;) {
+    const marker = bytes[pos + 1];
+    const end = pos + 2 + readUint16(bytes, pos + 2);
+    if (end > bytes.length) {
+      break;
+    }
+    if (marker === Marker.SOF2) {
+      throw new Error('Progressive JPEGs have no recovery points to decode up to');
+    }
+    if (marker >= Marker.SOF0 && marker <= Marker.SOF1) {
+      frameOffset = pos;
+      frameMarker = marker;
+      frameData = bytes.subarray(pos + 4, end);
+    } else if (marker === Marker.DRI) {
+      restartInterval = readUint16(bytes, pos + 4);
+    } else if (marker === Marker.SOS) {
+      dataOffset = end;
+      break;
+    }
+    pos = end;
+  }
+  if (dataOffset < 0 || !frameData) {
+    throw new Error('The stream ends before its scan starts');
+  }
+
+  const frame = parseFrameHeader(frameMarker, frameData);
+  const geometry = frameGeometry(frame);
+  const rowsPerInterval = restartInterval > 0 && restartInterval % geometry.mcusPerLine === 0
+    ? (restartInterval / geometry.mcusPerLine) * 8 * geometry.maxV
+    : 0;
+
+  // Count whole intervals up to the last marker received
+  let cut = dataOffset;
+  let intervals = 0;
+  for (let i = dataOffset; i + 1 < bytes.length; i++) {
+    if (bytes[i] !== 0xff) {
+      continue;
+    }
+    const next = bytes[i + 1];
+    if (next === Marker.EOI) {
+      return { ...decode(bytes.subarray(0, i + 2)), fullHeight: frame.height };
+    }
+    if (next >= Marker.RST0 && next <= Marker.RST7) {
+      cut = i;
+      intervals++;
+    }
+    i++;
+  }
+  const rows = Math.min(frame.height, intervals * rowsPerInterval);
+  if (rows === 0) {
+    return { width: frame.width, height: 0, data: new Uint8ClampedArray(0), fullHeight: frame.height };
+  }
+
+  // The received header and intervals, with the frame height cut to match
+  const band = new Uint8Array(cut + 2);
+  band.set(bytes.subarray(0, cut));
+  band.set([0xff, Marker.EOI], cut);
+  band[frameOffset + 5] = rows >> 8;
+  band[frameOffset + 6] = rows & 0xff;
+  return { ...decode(band), fullHeight: frame.height };
+}
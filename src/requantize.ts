@@ -0,0 +1,233 @@
+/**
+ * Custom quantization tables
+ * The engine only scales the Annex K tables by quality. To apply tables of the
+ * caller's choosing, the image is encoded at quality 100 (every quantizer 1),
+ * so the coefficients are the rounded DCT output, and each one is then divided
+ * by its new quantizer and entropy-coded again.
+ */
+
+import { Marker, metadataSegments, parseJpeg } from './jpeg.js';
+import { readCoefficients } from './decoder.js';
+import { naturalToZigzag, normalizeQuantTable } from './quant.js';
+import type { QuantTableMode } from './quant.js';
+import { writeBaseline } from './transcode.js';
+import { qualityQuantTables } from './scaling.js';
+import type { QualityScaling } from './scaling.js';
+import type { CodedComponent } from './transcode.js';
+import { EncodeError } from './errors.js';
+
+/**
+ * Tables for the luma and chroma components, in natural order
+ */
+export interface CustomQuantTables {
+  luma: ArrayLike<number>;
+  chroma?: ArrayLike<number>;   // Defaults to the luma table
+  mode?: QuantTableMode;        // Out-of-range entries are rejected unless this is 'clamp'
+  precision?: 8 | 16;           // 16 allows entries up to 65535, written as Pq=1 in an extended (SOF1) frame
+}
+
+export interface RequantizeOptions {
+  dither?: boolean;
+  deband?: boolean;
+}
+
+// Zigzag positions whose rounding error is diffused: DC and the first horizontal and vertical AC terms
+const DITHERED = [0, 1, 2];
+
+/**
+ * Quantize one component, diffusing the rounding error of the lowest
+ * frequencies to neighboring blocks (Floyd-Steinberg over the block grid)
+ * In a smooth gradient every block of a band rounds its DC the same way, so a
+ * coarse step shows as a visible contour; carrying the error forward makes the
+ * blocks alternate and the average level follow the source.
+ */
+function ditherQuantize(source: Int32Array, from: ArrayLike<number>, to: ArrayLike<number>, blocksPerLine: number, blocksPerColumn: number, out: Int32Array): void {
+  const errors = DITHERED.map(() => new Float64Array((blocksPerLine + 2) * (blocksPerColumn + 1)));
+  const at = (row: number, col: number): number => row * (blocksPerLine + 2) + col + 1;
+  for (let row = 0; row < blocksPerColumn; row++) {
+    for (let col = 0; col < blocksPerLine; col++) {
+      const base = (row * blocksPerLine + col) * 64;
+      for (let k = 0; k < 64; k++) {
+        out[base + k] = Math.round((source[base + k] * from[k]) / to[k]);
+      }
+      DITHERED.forEach((k, index) => {
+        const error = errors[index];
+        const value = (source[base + k] * from[k]) / to[k] + error[at(row, col)];
+        out[base + k] = Math.round(value);
+        const residual = value - out[base + k];
+        error[at(row, col + 1)] += (residual * 7) / 16;
+        error[at(row + 1, col - 1)] += (residual * 3) / 16;
+        error[at(row + 1, col)] += (residual * 5) / 16;
+        error[at(row + 1, col + 1)] += residual / 16;
+      });
+    }
+  }
+}
+
+// Zigzag positions of the lowest-frequency AC terms, which carry the slope of a gradient within a block
+const DEBANDED = [1, 2];
+
+// Factor by which their quantizers shrink in smooth blocks
+const DEBAND_DIVISOR = 4;
+
+/**
+ * Keep the lowest AC terms of smooth luma blocks at a finer step
+ * A baseline scan has one luma table, so the table written carries the fine
+ * step and every other block is rounded back to the normal step, costing it
+ * little. A block counts as smooth when every higher AC term would quantize to
+ * zero anyway: there the lowest terms are all that shapes a sky or gradient,
+ * and coarse steps flatten it into bands.
+ */
+function debandBlocks(source: { blocks: Int32Array; quantTable: ArrayLike<number> }, normal: ArrayLike<number>, fine: ArrayLike<number>, blocks: Int32Array): void {
+  for (let base = 0; base < blocks.length; base += 64) {
+    let smooth = true;
+    for (let k = DEBANDED[DEBANDED.length - 1] + 1; k < 64 && smooth; k++) {
+      smooth = Math.abs(source.blocks[base + k] * source.quantTable[k]) < normal[k] / 2;
+    }
+    if (smooth) {
+      continue;
+    }
+    for (const k of DEBANDED) {
+      blocks[base + k] = Math.round((Math.round((blocks[base + k] * fine[k]) / normal[k]) * normal[k]) / fine[k]);
+    }
+  }
+}
+
+/**
+ * Rewrite a sequential JPEG with new quantization tables
+ * Component 0 uses the luma table (id 0) and the others the chroma table (id 1).
+ * With 16-bit tables the frame is written as extended sequential (SOF1).
+ * APPn and COM segments are kept and restart intervals are dropped.
+ *
+ * @param jpeg Sequential 8-bit JPEG, ideally encoded with all quantizers 1
+ * @param options.dither Diffuse the rounding error of the lowest frequencies between blocks
+ * @param options.deband Quantize the lowest AC terms of smooth luma blocks more finely
+ */
+export function requantize(jpeg: Uint8Array, tables: CustomQuantTables, options: RequantizeOptions = {}): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8) {
+    throw new Error('Requantization needs an 8-bit sequential JPEG');
+  }
+  const { mode, precision = 8 } = tables;
+  const luma = naturalToZigzag(normalizeQuantTable(tables.luma, mode, precision));
+  const chroma = tables.chroma ? naturalToZigzag(normalizeQuantTable(tables.chroma, mode, precision)) : luma;
+  const written = luma.slice();
+  if (options.deband) {
+    for (const k of DEBANDED) {
+      written[k] = Math.max(1, Math.round(luma[k] / DEBAND_DIVISOR));
+    }
+  }
+  const pq = precision === 16 ? 1 : 0;
+  const coefficients = readCoefficients(file);
+
+  const components: CodedComponent[] = coefficients.map((source, index) => {
+    const quant = index === 0 ? written : chroma;
+    const blocks = new Int32Array(source.blocks.length);
+    if (options.dither) {
+      ditherQuantize(source.blocks, source.quantTable, quant, source.blocksPerLine, source.blocksPerColumn, blocks);
+    } else {
+      for (let i = 0; i < blocks.length; i++) {
+        const k = i % 64;
+        blocks[i] = Math.round((source.blocks[i] * source.quantTable[k]) / quant[k]);
+      }
+    }
+    if (options.deband && index === 0) {
+      debandBlocks(source, luma, written, blocks);
+    }
+    const { h, v } = frame.components[index];
+    return {
+      id: frame.components[index].id,
+      h,
+      v,
+      quantId: index === 0 ? 0 : 1,
+      blocksPerLine: source.blocksPerLine,
+      blocksPerColumn: source.blocksPerColumn,
+      blocks,
+    };
+  });
+
+  const segments = metadataSegments(file);
+
+  return writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components,
+    quantTables: [
+      { id: 0, precision: pq, values: written },
+      ...(components.length > 1 ? [{ id: 1, precision: pq, values: chroma }] : []),
+    ],
+    segments,
+  });
+}
+
+/**
+ * Quality for the MCU row covering pixel rows top to top + rows - 1, or
+ * undefined to keep the quality the image was encoded at
+ */
+export type StripQuality = (top: number, rows: number) => number | undefined;
+
+/**
+ * Lower the quality of chosen MCU rows of a sequential JPEG
+ * A baseline scan has one set of tables, so each coefficient of a row is
+ * rounded to the step its quality's table would use and stored in units of
+ * the existing table. Quantizers finer than the file's are left alone, which
+ * makes the encoded quality a ceiling. APPn and COM segments are kept and
+ * restart intervals are dropped.
+ *
+ * @param scaling How row qualities become tables, as for the encode's own quality
+ *
+ * @example
+ * // Foveated panorama: full quality in the middle third, 40 elsewhere
+ * const out = requantizeRows(jpeg, (top) => (top > height / 3 && top < height * 2 / 3 ? undefined : 40));
+ */
+export function requantizeRows(jpeg: Uint8Array, qualityOf: StripQuality, scaling: QualityScaling = 'libjpeg'): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 || file.scans.length !== 1) {
+    throw new Error('Per-strip quality needs an 8-bit sequential JPEG with a single scan');
+  }
+  const maxV = Math.max(...frame.components.map((c) => c.v));
+  const mcuRows = Math.ceil(frame.height / (8 * maxV));
+  const steps: (Uint16Array[] | undefined)[] = [];
+  for (let row = 0; row < mcuRows; row++) {
+    const top = row * 8 * maxV;
+    const quality = qualityOf(top, Math.min(8 * maxV, frame.height - top));
+    if (quality !== undefined && (!Number.isInteger(quality) || quality < 1 || quality > 100)) {
+      throw new EncodeError('bad-quality', `Quality must be an integer 1-100, got ${quality} for rows from ${top}`);
+    }
+    steps.push(quality === undefined ? undefined : qualityQuantTables(quality, scaling).map(naturalToZigzag));
+  }
+  const coefficients = readCoefficients(file);
+
+  const components: CodedComponent[] = coefficients.map((source, index) => {
+    const { id, h, v, quantTable } = frame.components[index];
+    const blocks = source.blocks.slice();
+    for (let blockRow = 0; blockRow < source.blocksPerColumn; blockRow++) {
+      const coarse = steps[Math.floor(blockRow / v)]?.[index === 0 ? 0 : 1];
+      if (!coarse) {
+        continue;
+      }
+      const end = (blockRow + 1) * source.blocksPerLine * 64;
+      for (let i = blockRow * source.blocksPerLine * 64; i < end; i++) {
+        const k = i % 64;
+        const fine = source.quantTable[k];
+        if (coarse[k] > fine) {
+          blocks[i] = Math.round((Math.round((blocks[i] * fine) / coarse[k]) * coarse[k]) / fine);
+        }
+      }
+    }
+    return { id, h, v, quantId: quantTable, blocksPerLine: source.blocksPerLine, blocksPerColumn: source.blocksPerColumn, blocks };
+  });
+
+  const segments = metadataSegments(file);
+
+  const usedIds = new Set(components.map((c) => c.quantId));
+  return writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components,
+    quantTables: [...file.scans[0].quantTables.values()].filter((t) => usedIds.has(t.id)).sort((a, b) => a.id - b.id),
+    segments,
+  });
+}
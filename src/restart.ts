@@ -0,0 +1,297 @@
+/**
+ * Restart-segment stitching
+ * Joins independently encoded horizontal bands into a single JPEG whose bands
+ * are separated by restart markers. Each band starts with fresh DC predictors
+ * and ends byte-aligned, so the entropy-coded data can be concatenated as-is.
+ */
+
+import { Marker, parseJpeg } from './jpeg.js';
+import type { JpegFile } from './jpeg.js';
+import { frameGeometry } from './entropy.js';
+import { concatBytes } from './pipeline.js';
+
+/**
+ * Bytes of the headers every band must share, with the frame height masked out
+ */
+function headerSignature(file: JpegFile): string {
+  const parts: string[] = [];
+  for (const segment of file.segments) {
+    if (segment.marker === Marker.DQT || segment.marker === Marker.DHT || segment.marker === Marker.SOS) {
+      parts.push(`${segment.marker}:${Array.from(segment.data).join(',')}`);
+    } else if (segment.marker === file.frame.marker) {
+      const data = Array.from(segment.data);
+      data[1] = data[2] = 0;
+      parts.push(`${segment.marker}:${data.join(',')}`);
+    }
+  }
+  return parts.join(';');
+}
+
+/**
+ * Entropy-coded data with its restart markers renumbered
+ * - nextRestartIndex: the RSTn number (0-7) the following marker must carry
+ */
+export interface RenumberedScanData {
+  data: Uint8Array;
+  nextRestartIndex: number;
+}
+
+/**
+ * Rewrite the RSTn markers of entropy-coded data so they count on from `firstIndex`
+ * Decoders expect RST0-RST7 in strict rotation across the whole scan, so data
+ * produced separately (by another call or another worker) must be renumbered
+ * before it is joined. Pass the returned index to the next fragment.
+ *
+ * @param scanData Entropy-coded bytes of one scan, without SOS or EOI
+ * @param firstIndex Number of restart markers that precede this data in the scan
+ */
+export function renumberRestartMarkers(scanData: Uint8Array, firstIndex = 0): RenumberedScanData {
+  if (!Number.isInteger(firstIndex) || firstIndex < 0) {
+    throw new Error(`Restart index must be a non-negative integer, got ${firstIndex}`);
+  }
+  const data = scanData.slice();
+  let index = firstIndex % 8;
+  for (let i = 0; i + 1 < data.length; i++) {
+    // Stuffed zeros and fill bytes are skipped; scan data holds no other markers
+    if (data[i] === 0xff && data[i + 1] >= Marker.RST0 && data[i + 1] <= Marker.RST7) {
+      data[++i] = Marker.RST0 + index;
+      index = (index + 1) % 8;
+    }
+  }
+  return { data, nextRestartIndex: index };
+}
+
+/**
+ * Header segments of a band, up to and including SOS, rewritten for the whole image
+ * The frame height is replaced and a DRI segment for `interval` MCUs is placed
+ * before the scan, replacing any the band had.
+ */
+export function stitchedHeader(band: JpegFile, height: number, interval: number): Uint8Array[] {
+  const chunks: Uint8Array[] = [];
+  for (const segment of band.segments) {
+    if (segment.marker === Marker.SOS) {
+      chunks.push(new Uint8Array([0xff, Marker.DRI, 0x00, 0x04, interval >> 8, interval & 0xff]));
+      chunks.push(band.bytes.subarray(segment.offset, segment.offset + segment.length));
+      break;
+    }
+    if (segment.marker === Marker.DRI) {
+      continue;
+    }
+    const bytes = band.bytes.slice(segment.offset, segment.offset + segment.length);
+    if (segment.marker === band.frame.marker) {
+      // Frame height lives at payload offset 1 (after the 4-byte marker and length)
+      bytes[5] = height >> 8;
+      bytes[6] = height & 0xff;
+    }
+    chunks.push(bytes);
+  }
+  return chunks;
+}
+
+/**
+ * Stitch horizontal bands into one JPEG separated by restart markers
+ *
+ * Bands must come from the same encoder configuration (identical tables and
+ * width), each with a single sequential scan. Every band but the last must have
+ * the same height, a multiple of the MCU height.
+ *
+ * Bands may carry restart markers of their own (e.g. output of `encodeSegmented`
+ * from several workers) if they all use the same interval and every band but the
+ * last holds a whole number of intervals; their markers are renumbered so the
+ * RSTn sequence runs on across band boundaries.
+ *
+ * @param bands Encoded bands in top-to-bottom order
+ * @returns A single JPEG with DRI set to one band's worth of MCUs, or to the bands' own interval
+ */
+export function stitchRestartSegments(bands: Uint8Array[]): Uint8Array {
+  if (bands.length === 0) {
+    throw new Error('At least one band is required');
+  }
+  const files = bands.map((band) => parseJpeg(band));
+  const first = files[0];
+  const signature = headerSignature(first);
+  const geometry = frameGeometry(first.frame);
+  const mcuHeight = 8 * geometry.maxV;
+  const bandHeight = first.frame.height;
+  const bandInterval = first.scans[0]?.restartInterval ?? 0;
+
+  let height = 0;
+  files.forEach((file, index) => {
+    if (file.scans.length !== 1 || file.scans[0].header.components.length !== file.frame.components.length) {
+      throw new Error(`Band ${index} must contain a single interleaved scan`);
+    }
+    if (file.scans[0].restartInterval !== bandInterval) {
+      throw new Error(`Band ${index} uses a restart interval of ${file.scans[0].restartInterval}, expected ${bandInterval}`);
+    }
+    if (file.frame.width !== first.frame.width) {
+      throw new Error(`Band ${index} is ${file.frame.width} pixels wide, expected ${first.frame.width}`);
+    }
+    if (headerSignature(file) !== signature) {
+      throw new Error(`Band ${index} was encoded with different tables`);
+    }
+    if (index < files.length - 1 && (file.frame.height !== bandHeight || bandHeight % mcuHeight !== 0)) {
+      throw new Error(`Band ${index} height ${file.frame.height} must equal ${bandHeight} and be a multiple of ${mcuHeight}`);
+    }
+    height += file.frame.height;
+  });
+
+  if (files.length === 1) {
+    return bands[0];
+  }
+  if (height > 0xffff) {
+    throw new Error(`Stitched height ${height} exceeds the JPEG limit of 65535`);
+  }
+  const bandMcus = geometry.mcusPerLine * (bandHeight / mcuHeight);
+  if (bandInterval > 0 && bandMcus % bandInterval !== 0) {
+    throw new Error(`Bands of ${bandMcus} MCUs must hold a whole number of ${bandInterval}-MCU restart intervals`);
+  }
+  const interval = bandInterval || bandMcus;
+  if (interval > 0xffff) {
+    throw new Error(`Restart interval of ${interval} MCUs exceeds 65535; use shorter bands`);
+  }
+
+  const chunks = stitchedHeader(first, height, interval);
+  let restartIndex = 0;
+  files.forEach((file, index) => {
+    if (index > 0) {
+      chunks.push(new Uint8Array([0xff, Marker.RST0 + restartIndex]));
+      restartIndex = (restartIndex + 1) % 8;
+    }
+    const scan = file.scans[0];
+    const renumbered = renumberRestartMarkers(file.bytes.subarray(scan.dataOffset, scan.dataEnd), restartIndex);
+    chunks.push(renumbered.data);
+    restartIndex = renumbered.nextRestartIndex;
+  });
+  chunks.push(new Uint8Array([0xff, Marker.EOI]));
+
+  return concatBytes(chunks);
+}
+
+/**
+ * Joins bands encoded out of order into one JPEG, writing each as soon as it can
+ * Workers encode bands of `bandHeight` rows (the last may be shorter) and hand
+ * them over by index in whatever order they finish. `add` returns the output
+ * that is now ready, in file order: nothing until band 0 arrives, then the
+ * header and every band up to the first one still missing, each after an RSTn
+ * marker, with EOI after the last. Later bands are held until their turn.
+ * Bands follow the same rules as for `stitchRestartSegments`.
+ *
+ * @example
+ * const stitcher = new RestartStitcher(height, 256);
+ * pool.onmessage = ({ data: { index, jpeg } }) => out.write(stitcher.add(index, jpeg));
+ */
+export class RestartStitcher {
+  private readonly height: number;
+  private readonly bandHeight: number;
+  private readonly bandCount: number;
+  private readonly held = new Map<number, JpegFile>();
+  private first: JpegFile | undefined;
+  private signature = '';
+  private bandInterval = 0;
+  private next = 0;
+  private restartIndex = 0;
+
+  constructor(height: number, bandHeight: number) {
+    if (!Number.isInteger(height) || height < 1 || height > 0xffff) {
+      throw new Error(`Image height must be 1-65535, got ${height}`);
+    }
+    if (!Number.isInteger(bandHeight) || bandHeight <= 0 || bandHeight % 8 !== 0) {
+      throw new Error(`Band height must be a positive multiple of 8, got ${bandHeight}`);
+    }
+    this.height = height;
+    this.bandHeight = bandHeight;
+    this.bandCount = Math.ceil(height / bandHeight);
+  }
+
+  /**
+   * Whether every band has been written
+   */
+  get complete(): boolean {
+    return this.next === this.bandCount;
+  }
+
+  /**
+   * Take band `index` and return the output that became ready, possibly empty
+   */
+  add(index: number, band: Uint8Array): Uint8Array {
+    if (!Number.isInteger(index) || index < 0 || index >= this.bandCount) {
+      throw new Error(`Band index ${index} is outside 0-${this.bandCount - 1}`);
+    }
+    if (index < this.next || this.held.has(index)) {
+      throw new Error(`Band ${index} was already added`);
+    }
+    const file = parseJpeg(band);
+    this.check(file, index);
+    this.held.set(index, file);
+
+    const chunks: Uint8Array[] = [];
+    for (let held = this.held.get(this.next); held; held = this.held.get(this.next)) {
+      this.held.delete(this.next);
+      if (this.next === 0) {
+        chunks.push(...stitchedHeader(held, this.height, this.interval()));
+      } else {
+        chunks.push(new Uint8Array([0xff, Marker.RST0 + this.restartIndex]));
+        this.restartIndex = (this.restartIndex + 1) % 8;
+      }
+      const scan = held.scans[0];
+      const renumbered = renumberRestartMarkers(held.bytes.subarray(scan.dataOffset, scan.dataEnd), this.restartIndex);
+      chunks.push(renumbered.data);
+      this.restartIndex = renumbered.nextRestartIndex;
+      this.next++;
+    }
+    if (this.complete) {
+      chunks.push(new Uint8Array([0xff, Marker.EOI]));
+    }
+
+    return concatBytes(chunks);
+  }
+
+  /**
+   * Check a band against the first one received and its place in the image
+   */
+  private check(file: JpegFile, index: number): void {
+    if (file.scans.length !== 1 || file.scans[0].header.components.length !== file.frame.components.length) {
+      throw new Error(`Band ${index} must contain a single interleaved scan`);
+    }
+    const rows = Math.min(this.bandHeight, this.height - index * this.bandHeight);
+    if (file.frame.height !== rows) {
+      throw new Error(`Band ${index} is ${file.frame.height} rows tall, expected ${rows}`);
+    }
+    if (!this.first) {
+      const geometry = frameGeometry(file.frame);
+      if (this.bandCount > 1 && this.bandHeight % (8 * geometry.maxV) !== 0) {
+        throw new Error(`Bands of ${this.bandHeight} rows do not cover whole ${8 * geometry.maxV}-row MCUs`);
+      }
+      this.first = file;
+      this.signature = headerSignature(file);
+      this.bandInterval = file.scans[0].restartInterval;
+      this.interval();
+      return;
+    }
+    if (file.frame.width !== this.first.frame.width) {
+      throw new Error(`Band ${index} is ${file.frame.width} pixels wide, expected ${this.first.frame.width}`);
+    }
+    if (headerSignature(file) !== this.signature) {
+      throw new Error(`Band ${index} was encoded with different tables`);
+    }
+    if (file.scans[0].restartInterval !== this.bandInterval) {
+      throw new Error(`Band ${index} uses a restart interval of ${file.scans[0].restartInterval}, expected ${this.bandInterval}`);
+    }
+  }
+
+  /**
+   * The DRI value: one band's worth of MCUs, or the bands' own interval
+   */
+  private interval(): number {
+    const geometry = frameGeometry(this.first!.frame);
+    const bandMcus = geometry.mcusPerLine * Math.ceil(this.bandHeight / (8 * geometry.maxV));
+    if (this.bandInterval > 0 && bandMcus % this.bandInterval !== 0) {
+      throw new Error(`Bands of ${bandMcus} MCUs must hold a whole number of ${this.bandInterval}-MCU restart intervals`);
+    }
+    const interval = this.bandInterval || bandMcus;
+    if (interval > 0xffff) {
+      throw new Error(`Restart interval of ${interval} MCUs exceeds 65535; use shorter bands`);
+    }
+    return interval;
+  }
+}
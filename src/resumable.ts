@@ -0,0 +1,93 @@
+/**
+ * Resumable encoding
+ * The image is encoded as independent bands, the same way `encodeSegmented`
+ * does, so the state between bands is just the finished bands and a row count.
+ * A checkpoint taken there is plain data that survives structured cloning
+ * (IndexedDB, postMessage, v8.serialize), and a job cut short by a worker
+ * restart or serverless timeout continues from its last checkpoint.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { encodeToBytes, openStripSource } from './encoder.js';
+import type { StripSource } from './pipeline.js';
+import { stitchRestartSegments } from './restart.js';
+
+export interface EncodeCheckpoint {
+  width: number;
+  height: number;
+  quality: number;
+  segmentHeight: number;
+  rows: number;           // Source rows encoded so far
+  bands: Uint8Array[];    // One encoded band per segment so far
+}
+
+export interface ResumableEncodeOptions extends Pick<EncodeOptions, 'width' | 'height' | 'quality'> {
+  segmentHeight?: number; // Rows per band (multiple of 8), defaults to 64
+  checkpoint?: EncodeCheckpoint; // Continue a previous job from this point
+  onCheckpoint?(checkpoint: EncodeCheckpoint): Promise<void> | void; // Called after each band; throw to stop
+}
+
+/**
+ * Read exactly `rows` rows, or throw if the source ends first
+ */
+async function readRows(source: StripSource, rows: number): Promise<Uint8Array> {
+  const rowBytes = source.width * 4;
+  const out = new Uint8Array(rows * rowBytes);
+  let filled = 0;
+  while (filled < out.length) {
+    const strip = await source.readStrip(rows - filled / rowBytes);
+    if (!strip || strip.length === 0) {
+      throw new Error(`Source ended ${rows - filled / rowBytes} rows early`);
+    }
+    out.set(strip, filled);
+    filled += strip.length;
+  }
+  return out;
+}
+
+/**
+ * Encode an image band by band, reporting a checkpoint after each band
+ * The result is one JPEG with restart markers between bands, byte-identical
+ * however many times the job was resumed. On resume the source must supply
+ * the same pixels; rows already encoded are read and skipped.
+ *
+ * @example
+ * const saved = await db.get('checkpoint');
+ * const jpeg = await encodeResumable(file, { quality: 85, checkpoint: saved, onCheckpoint: (c) => db.put('checkpoint', c) });
+ */
+export async function encodeResumable(source: ImageSource, options: ResumableEncodeOptions = {}): Promise<Uint8Array> {
+  const { quality = 100, segmentHeight = 64, checkpoint } = options;
+  if (!Number.isInteger(segmentHeight) || segmentHeight <= 0 || segmentHeight % 8 !== 0) {
+    throw new Error(`Segment height must be a positive multiple of 8, got ${segmentHeight}`);
+  }
+
+  const strips = await openStripSource(source, options);
+  const { width, height } = strips;
+  if (checkpoint) {
+    const expected = { width, height, quality, segmentHeight };
+    for (const [key, value] of Object.entries(expected) as [keyof typeof expected, number][]) {
+      if (checkpoint[key] !== value) {
+        throw new Error(`Checkpoint was taken with ${key} ${checkpoint[key]}, but this job uses ${value}`);
+      }
+    }
+    if (checkpoint.rows !== Math.min(height, checkpoint.bands.length * segmentHeight)) {
+      throw new Error(`Checkpoint holds ${checkpoint.bands.length} bands but claims ${checkpoint.rows} rows`);
+    }
+  }
+
+  const bands = checkpoint ? [...checkpoint.bands] : [];
+  let rows = checkpoint?.rows ?? 0;
+  for (let skipped = 0; skipped < rows; skipped += segmentHeight) {
+    await readRows(strips, Math.min(segmentHeight, rows - skipped));
+  }
+
+  while (rows < height) {
+    const bandHeight = Math.min(segmentHeight, height - rows);
+    const pixels = await readRows(strips, bandHeight);
+    bands.push(await encodeToBytes(pixels, { width, height: bandHeight, quality }));
+    rows += bandHeight;
+    await options.onCheckpoint?.({ width, height, quality, segmentHeight, rows, bands: [...bands] });
+  }
+
+  return stitchRestartSegments(bands);
+}
@@ -0,0 +1,55 @@
+/**
+ * Quality scaling
+ * The 1-100 quality knob means different things in different encoders: it is
+ * a scale factor on the Annex K tables in every common one, but the curve from
+ * quality to scale varies. Choosing the curve lets teams moving from another
+ * encoder keep their quality numbers.
+ */
+
+import { libjpegQuantTables, scaledQuantTables } from './libjpeg.js';
+
+/**
+ * Maps a quality 1-100 to a scale factor in percent of the Annex K tables
+ */
+export type QualityCurve = (quality: number) => number;
+
+/**
+ * How quality becomes quantization tables
+ * - 'libjpeg': jpeg_set_quality's curve, 5000/q below 50 and 200-2q above (the default)
+ * - 'linear': perceptually even steps; the scale changes by the same ratio per quality point,
+ *   from 5000% at 1 through 100% at 50 to about 2% at 100
+ * - a curve, or a table of 100 scale factors for qualities 1-100
+ */
+export type QualityScaling = 'libjpeg' | 'linear' | QualityCurve | ArrayLike<number>;
+
+/**
+ * Scale factor, in percent of the Annex K tables, for a quality
+ */
+export function qualityScaleFactor(quality: number, scaling: QualityScaling = 'libjpeg'): number {
+  const q = Math.max(1, Math.min(100, Math.round(quality)));
+  let scale: number;
+  if (scaling === 'libjpeg') {
+    scale = q < 50 ? Math.floor(5000 / q) : 200 - q * 2;
+  } else if (scaling === 'linear') {
+    scale = 100 * 50 ** ((50 - q) / 49);
+  } else if (typeof scaling === 'function') {
+    scale = scaling(q);
+  } else {
+    if (scaling.length !== 100) {
+      throw new Error(`A quality scaling table has 100 entries, for qualities 1-100; got ${scaling.length}`);
+    }
+    scale = scaling[q - 1];
+  }
+  if (!Number.isFinite(scale) || scale < 0) {
+    throw new Error(`Quality ${q} scales to ${scale}; scale factors must be non-negative percentages`);
+  }
+  return scale;
+}
+
+/**
+ * Luma and chroma tables, in natural order, for a quality under a scaling policy
+ * With 'libjpeg' these are the tables `libjpegQuantTables` returns.
+ */
+export function qualityQuantTables(quality: number, scaling: QualityScaling = 'libjpeg'): [Uint16Array, Uint16Array] {
+  return scaling === 'libjpeg' ? libjpegQuantTables(quality) : scaledQuantTables(qualityScaleFactor(quality, scaling));
+}
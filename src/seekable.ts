@@ -0,0 +1,108 @@
+/**
+ * Seekable decoding
+ * Decodes a region of a stored JPEG from byte ranges, using its strip index to
+ * fetch only the header and the restart segments that cover the region. A
+ * tiled viewer over plain JPEG files can then show part of a very large image
+ * without downloading or decoding the rest.
+ */
+
+import { Marker, iterateSegments } from './jpeg.js';
+import { decode } from './decoder.js';
+import type { DecodedImage } from './decoder.js';
+import { renumberRestartMarkers } from './restart.js';
+import { readStripIndex } from './stripindex.js';
+import type { StripIndex } from './stripindex.js';
+
+/**
+ * Fetch bytes `start` up to (not including) `end`, e.g. with an HTTP Range request
+ */
+export type RangeFetch = (start: number, end: number) => Promise<Uint8Array>;
+
+export interface ImageRegion {
+  x?: number;             // Left column, defaults to 0
+  y: number;              // Top row
+  width?: number;         // Defaults to the rest of the row
+  height: number;
+}
+
+export interface RegionDecodeOptions {
+  index?: StripIndex;     // Index kept as sidecar JSON; read from the file's APP9 segment when absent
+  headerBytes?: number;   // Bytes fetched to find the embedded index, defaults to 65536
+}
+
+/**
+ * Fetch a range, checking that the server returned all of it
+ */
+async function fetchExactly(fetchRange: RangeFetch, start: number, end: number): Promise<Uint8Array> {
+  const bytes = await fetchRange(start, end);
+  if (bytes.length < end - start) {
+    throw new Error(`Fetching bytes ${start}-${end} returned only ${bytes.length}`);
+  }
+  return bytes.subarray(0, end - start);
+}
+
+/**
+ * Decode a region of a JPEG from byte ranges
+ * Makes one request for the header (unless an index is given and covers it)
+ * and one for the segments spanning the region's rows. Output matches the
+ * same region of a full decode; with subsampled chroma, rows at segment edges
+ * may differ slightly where the full decode would smooth chroma across them.
+ *
+ * @example
+ * const fetchRange = async (start, end) => new Uint8Array(await (await fetch(url, {
+ *   headers: { Range: `bytes=${start}-${end - 1}` },
+ * })).arrayBuffer());
+ * const tile = await decodeRegion(fetchRange, { x: 2048, y: 4096, width: 512, height: 512 });
+ */
+export async function decodeRegion(
+  fetchRange: RangeFetch,
+  region: ImageRegion,
+  options: RegionDecodeOptions = {}
+): Promise<DecodedImage> {
+  const { headerBytes = 65536 } = options;
+  let header: Uint8Array | undefined;
+  let index = options.index;
+  if (!index) {
+    header = await fetchRange(0, headerBytes);
+    index = readStripIndex(header);
+    if (!index) {
+      throw new Error(`No strip index in the first ${header.length} bytes; pass one in options.index`);
+    }
+  }
+
+  const { x = 0, y, width = index.width - x, height } = region;
+  if (x < 0 || y < 0 || width < 1 || height < 1 || x + width > index.width || y + height > index.height) {
+    throw new Error(`Region ${width}x${height} at (${x}, ${y}) is outside the ${index.width}x${index.height} image`);
+  }
+  if (!header || header.length < index.headerLength) {
+    header = await fetchExactly(fetchRange, 0, index.headerLength);
+  }
+  header = header.subarray(0, index.headerLength);
+
+  // The segments covering the rows are contiguous, restart markers included
+  const first = index.segments[Math.floor(y / index.rowsPerSegment)];
+  const last = index.segments[Math.floor((y + height - 1) / index.rowsPerSegment)];
+  const data = await fetchExactly(fetchRange, first.offset, last.end);
+  const rows = last.top + last.rows - first.top;
+
+  // A standalone JPEG of just those rows: the header with the frame height changed
+  const band = new Uint8Array(header.length + data.length + 2);
+  band.set(header);
+  band.set(renumberRestartMarkers(data).data, header.length);
+  band.set([0xff, Marker.EOI], header.length + data.length);
+  const frame = Array.from(iterateSegments(header)).find((s) => s.marker >= Marker.SOF0 && s.marker <= Marker.SOF0 + 2);
+  if (!frame) {
+    throw new Error('The header holds no baseline or extended frame');
+  }
+  band[frame.offset + 5] = rows >> 8;
+  band[frame.offset + 6] = rows & 0xff;
+
+  const decoded = decode(band);
+  const out = new Uint8ClampedArray(width * height * 4);
+  const rowBytes = width * 4;
+  for (let row = 0; row < height; row++) {
+    const from = ((y - first.top + row) * decoded.width + x) * 4;
+    out.set(decoded.data.subarray(from, from + rowBytes), row * rowBytes);
+  }
+  return { width, height, data: out };
+}
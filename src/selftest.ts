@@ -0,0 +1,137 @@
+/**
+ * Encode/decode roundtrip self-test
+ * Lets embedders confirm at startup that the deployed WASM build produces
+ * decodable output of the expected fidelity.
+ */
+
+import { encodeToBytes } from './encoder.js';
+import { decode } from './decoder.js';
+import type { DecodedImage } from './decoder.js';
+import { validate } from './validate.js';
+
+export interface RoundtripOptions {
+  width: number;
+  height: number;
+  quality?: number; // JPEG quality (1-100), defaults to 100
+}
+
+export interface RoundtripResult {
+  jpeg: Uint8Array;
+  decoded: DecodedImage;
+  psnr: number;     // Peak signal-to-noise ratio over RGB in dB (Infinity if lossless)
+}
+
+export interface SelfTestCase {
+  name: string;
+  width: number;
+  height: number;
+  quality: number;
+  psnr: number;
+  minPsnr: number;
+  bytes: number;
+  passed: boolean;
+  error?: string;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  cases: SelfTestCase[];
+}
+
+/**
+ * Peak signal-to-noise ratio between two RGBA buffers, ignoring alpha
+ */
+export function psnr(a: ArrayLike<number>, b: ArrayLike<number>): number {
+  if (a.length !== b.length) {
+    throw new Error(`Buffer sizes differ: ${a.length} vs ${b.length}`);
+  }
+  let sum = 0;
+  let count = 0;
+  for (let i = 0; i < a.length; i += 4) {
+    for (let c = 0; c < 3; c++) {
+      const diff = a[i + c] - b[i + c];
+      sum += diff * diff;
+      count++;
+    }
+  }
+  if (sum === 0) {
+    return Infinity;
+  }
+  return 10 * Math.log10((255 * 255) / (sum / count));
+}
+
+/**
+ * Encode RGBA pixels, decode the result, and measure the reconstruction error
+ *
+ * @param pixels Raw RGBA buffer
+ * @param options Image dimensions and quality
+ */
+export async function roundtrip(pixels: Uint8Array, options: RoundtripOptions): Promise<RoundtripResult> {
+  const jpeg = await encodeToBytes(pixels, options);
+  const decoded = decode(jpeg);
+  return {
+    jpeg,
+    decoded,
+    psnr: psnr(pixels.subarray(0, options.width * options.height * 4), decoded.data),
+  };
+}
+
+// Synthetic patterns with conservative PSNR floors at quality 90
+const PATTERNS: Array<{ name: string; width: number; height: number; minPsnr: number; pixel: (x: number, y: number, seed: number) => number[] }> = [
+  { name: 'solid', width: 16, height: 16, minPsnr: 40, pixel: () => [200, 100, 50] },
+  { name: 'gradient', width: 64, height: 48, minPsnr: 35, pixel: (x, y) => [x * 4, y * 5, 255 - x * 2] },
+  { name: 'checkerboard', width: 37, height: 23, minPsnr: 20, pixel: (x, y) => ((x >> 2) + (y >> 2)) % 2 ? [255, 255, 255] : [0, 0, 0] },
+  { name: 'noise', width: 32, height: 32, minPsnr: 20, pixel: (_x, _y, seed) => [seed & 0xff, (seed >> 8) & 0xff, (seed >> 16) & 0xff] },
+  { name: 'single-pixel', width: 1, height: 1, minPsnr: 30, pixel: () => [10, 220, 130] },
+];
+
+/**
+ * Roundtrip a set of synthetic patterns through the encoder and the reference decoder
+ * Each output is also checked with `validate`; any error fails the case.
+ *
+ * @example
+ * const report = await selfTest();
+ * if (!report.passed) console.error(report.cases.filter((c) => !c.passed));
+ */
+export async function selfTest(): Promise<SelfTestReport> {
+  const quality = 90;
+  const cases: SelfTestCase[] = [];
+
+  for (const pattern of PATTERNS) {
+    const { name, width, height, minPsnr } = pattern;
+    const pixels = new Uint8Array(width * height * 4);
+    let seed = 0x2545f491;
+    for (let y = 0; y < height; y++) {
+      for (let x = 0; x < width; x++) {
+        // xorshift32 keeps the noise pattern deterministic
+        seed ^= seed << 13;
+        seed ^= seed >>> 17;
+        seed ^= seed << 5;
+        pixels.set(pattern.pixel(x, y, seed >>> 0), (y * width + x) * 4);
+        pixels[(y * width + x) * 4 + 3] = 255;
+      }
+    }
+
+    const entry: SelfTestCase = { name, width, height, quality, psnr: 0, minPsnr, bytes: 0, passed: false };
+    try {
+      const result = await roundtrip(pixels, { width, height, quality });
+      entry.psnr = result.psnr;
+      entry.bytes = result.jpeg.length;
+      const report = validate(result.jpeg);
+      if (!report.valid) {
+        entry.error = report.issues.find((issue) => issue.severity === 'error')?.message;
+      } else if (result.decoded.width !== width || result.decoded.height !== height) {
+        entry.error = `Decoded size ${result.decoded.width}x${result.decoded.height} does not match ${width}x${height}`;
+      } else if (result.psnr < minPsnr) {
+        entry.error = `PSNR ${result.psnr.toFixed(2)} dB is below ${minPsnr} dB`;
+      } else {
+        entry.passed = true;
+      }
+    } catch (error) {
+      entry.error = (error as Error).message;
+    }
+    cases.push(entry);
+  }
+
+  return { passed: cases.every((c) => c.passed), cases };
+}
@@ -0,0 +1,114 @@
+/**
+ * Writable sinks
+ * Output sinks over Node.js Writables and WHATWG WritableStreams that wait
+ * for the destination to accept each chunk, so a slow file, socket, or HTTP
+ * response pauses the encoder instead of buffering its output.
+ */
+
+import type { Writable } from 'stream';
+import type { ImageSource } from './types.js';
+import type { OutputSink } from './pipeline.js';
+import { runPipeline } from './pipeline.js';
+import { openStripSource } from './encoder.js';
+import { streamEncoder } from './stream.js';
+import type { StreamEncodeOptions } from './stream.js';
+
+export interface WritableSinkOptions {
+  end?: boolean; // Close the destination when encoding finishes, defaults to true
+}
+
+/**
+ * Sink over a Node.js Writable (file, socket, http.ServerResponse)
+ * Writes wait for 'drain' whenever the destination's buffer is full.
+ */
+export function nodeWritableSink(writable: Writable, options: WritableSinkOptions = {}): OutputSink {
+  const { end = true } = options;
+  const drained = (): Promise<void> => new Promise((resolve, reject) => {
+    const settle = (error?: Error): void => {
+      writable.off('drain', settle);
+      writable.off('error', settle);
+      if (error) {
+        reject(error);
+      } else {
+        resolve();
+      }
+    };
+    writable.on('drain', settle);
+    writable.on('error', settle);
+  });
+
+  return {
+    async write(chunk: Uint8Array): Promise<void> {
+      if (writable.destroyed) {
+        throw new Error('Destination stream was destroyed');
+      }
+      if (!writable.write(chunk)) {
+        await drained();
+      }
+    },
+    async close(): Promise<void> {
+      if (end) {
+        await new Promise<void>((resolve, reject) => {
+          writable.once('error', reject);
+          writable.end(() => {
+            writable.off('error', reject);
+            resolve();
+          });
+        });
+      }
+    },
+  };
+}
+
+/**
+ * Sink over a WHATWG WritableStream
+ * Each write waits for the stream's `ready` promise, honouring its queuing strategy.
+ */
+export function webWritableSink(stream: WritableStream<Uint8Array>, options: WritableSinkOptions = {}): OutputSink {
+  const { end = true } = options;
+  const writer = stream.getWriter();
+  return {
+    async write(chunk: Uint8Array): Promise<void> {
+      await writer.ready;
+      await writer.write(chunk);
+    },
+    async close(): Promise<void> {
+      if (end) {
+        await writer.close();
+      } else {
+        writer.releaseLock();
+      }
+    },
+  };
+}
+
+/**
+ * Encode an image source straight into a writable destination
+ * Only the strip being encoded is held in memory; each chunk is written as soon
+ * as the engine produces it.
+ *
+ * @example
+ * await encodeToWritable('photo.png', fs.createWriteStream('photo.jpg'), { quality: 85 });
+ *
+ * @returns Number of JPEG bytes written
+ */
+export async function encodeToWritable(
+  source: ImageSource,
+  destination: Writable | WritableStream<Uint8Array>,
+  options: StreamEncodeOptions & WritableSinkOptions = {}
+): Promise<number> {
+  const sink = typeof WritableStream !== 'undefined' && destination instanceof WritableStream
+    ? webWritableSink(destination, options)
+    : nodeWritableSink(destination as Writable, options);
+  return encodeToSink(source, sink, options);
+}
+
+/**
+ * Encode an image source into any output sink, e.g. a `PartSink` for multipart uploads
+ *
+ * @returns Number of JPEG bytes written
+ */
+export async function encodeToSink(source: ImageSource, sink: OutputSink, options: StreamEncodeOptions = {}): Promise<number> {
+  const strips = await openStripSource(source, options);
+  return runPipeline(strips, streamEncoder(strips.width, strips.height, options), sink);
+}
@@ -0,0 +1,103 @@
+/**
+ * SPIFF headers (ITU T.84 / ISO 10918-3)
+ * Rewrites a JFIF stream so it starts with a SPIFF header and directory instead
+ * of the JFIF APP0 segment, for interchange with systems that require SPIFF.
+ */
+
+import { Marker, parseJpeg } from './jpeg.js';
+import { createSegment } from './markers.js';
+import { concatBytes } from './pipeline.js';
+
+export type SpiffResolutionUnit = 'aspect' | 'dpi' | 'dpcm';
+
+export interface SpiffOptions {
+  resolutionUnit?: SpiffResolutionUnit; // Defaults to the JFIF header's unit, or 'aspect'
+  xDensity?: number;                    // Defaults to the JFIF header's value, or 1
+  yDensity?: number;                    // Defaults to the JFIF header's value, or 1
+}
+
+const SPIFF_IDENTIFIER = [0x53, 0x50, 0x49, 0x46, 0x46, 0x00]; // "SPIFF\0"
+const SPIFF_VERSION = 0x0200;
+const PROFILE_CONTINUOUS_TONE = 1;
+const COLOR_SPACE_YCBCR_601_RGB = 3;  // Full-range YCbCr as used by JFIF
+const COLOR_SPACE_GRAYSCALE = 8;
+const COMPRESSION_JPEG = 5;
+const ENTRY_END_OF_DIRECTORY = 1;
+const UNITS: SpiffResolutionUnit[] = ['aspect', 'dpi', 'dpcm'];
+
+/**
+ * Replace the JFIF header of an encoded stream with a SPIFF header
+ * All APP0 segments (JFIF and JFIF extensions) are removed; other segments are kept.
+ *
+ * @param jpeg Encoded JFIF stream
+ * @param options Resolution fields
+ */
+export function toSpiff(jpeg: Uint8Array, options: SpiffOptions = {}): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if (frame.components.length !== 1 && frame.components.length !== 3) {
+    throw new Error(`SPIFF output supports 1 or 3 components, found ${frame.components.length}`);
+  }
+
+  let unit = 0;
+  let xDensity = 1;
+  let yDensity = 1;
+  const jfif = file.segments.find((s) =>
+    s.marker === Marker.APP0 && s.data.length >= 12 && String.fromCharCode(...s.data.subarray(0, 5)) === 'JFIF\0'
+  );
+  if (jfif) {
+    unit = jfif.data[7];
+    xDensity = (jfif.data[8] << 8) | jfif.data[9];
+    yDensity = (jfif.data[10] << 8) | jfif.data[11];
+  }
+  if (options.resolutionUnit) {
+    unit = UNITS.indexOf(options.resolutionUnit);
+  }
+  xDensity = options.xDensity ?? xDensity;
+  yDensity = options.yDensity ?? yDensity;
+
+  const header = new Uint8Array(SPIFF_IDENTIFIER.length + 24);
+  const view = new DataView(header.buffer);
+  header.set(SPIFF_IDENTIFIER, 0);
+  view.setUint16(6, SPIFF_VERSION);
+  header[8] = PROFILE_CONTINUOUS_TONE;
+  header[9] = frame.components.length;
+  view.setUint32(10, frame.height);
+  view.setUint32(14, frame.width);
+  header[18] = frame.components.length === 1 ? COLOR_SPACE_GRAYSCALE : COLOR_SPACE_YCBCR_601_RGB;
+  header[19] = frame.precision;
+  header[20] = COMPRESSION_JPEG;
+  header[21] = unit;
+  view.setUint32(22, yDensity);
+  view.setUint32(26, xDensity);
+
+  // The end-of-directory entry ends with the SOI of the image stream itself
+  const endOfDirectory = new Uint8Array([0, 0, 0, ENTRY_END_OF_DIRECTORY, 0xff, Marker.SOI]);
+
+  const chunks: Uint8Array[] = [
+    new Uint8Array([0xff, Marker.SOI]),
+    createSegment(Marker.APP0 + 8, header),
+    createSegment(Marker.APP0 + 8, endOfDirectory),
+  ];
+  let copyFrom = -1;
+  for (const segment of file.segments) {
+    if (segment.marker === Marker.SOI) {
+      continue;
+    }
+    if (segment.marker === Marker.APP0) {
+      if (copyFrom >= 0) {
+        chunks.push(jpeg.subarray(copyFrom, segment.offset));
+        copyFrom = -1;
+      }
+      continue;
+    }
+    if (copyFrom < 0) {
+      copyFrom = segment.offset;
+    }
+  }
+  if (copyFrom >= 0) {
+    chunks.push(jpeg.subarray(copyFrom));
+  }
+
+  return concatBytes(chunks);
+}
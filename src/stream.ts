@@ -0,0 +1,233 @@
+/**
+ * Web stream encoding
+ * Exposes the encoder's output as a ReadableStream, for fetch upload bodies,
+ * Service Worker responses, and other stream consumers.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { createJpegStripEncoder, openStripSource } from './encoder.js';
+import { decodedSource } from './inputs.js';
+import { concatBytes, pipelineStream, readableStreamSource, runPipeline, transferableEncoder } from './pipeline.js';
+import { Base64Encoder } from './base64.js';
+import { createRowFlushEncoder } from './lowlatency.js';
+import type { StripEncoder, StripSource } from './pipeline.js';
+
+/**
+ * Options for stream encoding
+ * For a ReadableStream input, width and height mean the stream is raw RGBA
+ * and is encoded as it arrives; without them it must be a PNG, QOI, or
+ * Netpbm file, which is buffered before decoding.
+ */
+export type StreamEncodeOptions = Pick<EncodeOptions, 'width' | 'height' | 'quality'> & {
+  transferable?: boolean; // Give every chunk its own ArrayBuffer, ready for postMessage transfer
+  flushRows?: number; // Emit complete, byte-aligned output every this many rows (a multiple of 8), for remote displays
+};
+
+/**
+ * Strip encoder honouring the stream options' row flushing and transferable chunks
+ */
+export function streamEncoder(width: number, height: number, options: StreamEncodeOptions): StripEncoder {
+  const encoder = options.flushRows !== undefined
+    ? createRowFlushEncoder(width, height, (bandWidth, rows) => createJpegStripEncoder(bandWidth, rows, options.quality), options.flushRows)
+    : createJpegStripEncoder(width, height, options.quality);
+  return options.transferable ? transferableEncoder(encoder) : encoder;
+}
+
+/**
+ * Read a byte stream to the end
+ */
+export async function readStream(stream: ReadableStream<Uint8Array>): Promise<Uint8Array> {
+  const chunks: Uint8Array[] = [];
+  const reader = stream.getReader();
+  for (let result = await reader.read(); !result.done; result = await reader.read()) {
+    chunks.push(result.value);
+  }
+  return concatBytes(chunks);
+}
+
+/**
+ * Stream whose underlying stream is only opened on the first pull
+ */
+function deferredStream(open: () => Promise<ReadableStream<Uint8Array>>): ReadableStream<Uint8Array> {
+  let reader: ReadableStreamDefaultReader<Uint8Array> | undefined;
+  return new ReadableStream<Uint8Array>({
+    async pull(controller): Promise<void> {
+      reader ??= (await open()).getReader();
+      const { value, done } = await reader.read();
+      if (done) {
+        controller.close();
+      } else {
+        controller.enqueue(value);
+      }
+    },
+    async cancel(reason): Promise<void> {
+      await reader?.cancel(reason);
+    },
+  }, { highWaterMark: 0 });
+}
+
+/**
+ * Encode an image source to a stream of JPEG bytes
+ * Strips are encoded only as the stream is read, so passing the result as a
+ * fetch body uploads while encoding and pauses when the network is slow.
+ *
+ * @example
+ * await fetch('/upload', { method: 'POST', body: encodeToStream(canvas), duplex: 'half' });
+ */
+export function encodeToStream(source: ImageSource, options: StreamEncodeOptions = {}): ReadableStream<Uint8Array> {
+  return deferredStream(async () => {
+    const strips = await openStripSource(source, options);
+    return pipelineStream(strips, streamEncoder(strips.width, strips.height, options));
+  });
+}
+
+/**
+ * Encode an image source straight to a `data:image/jpeg;base64,` URL
+ * Output chunks are converted as the encoder produces them, so the JPEG bytes
+ * are never held whole.
+ *
+ * @example
+ * preview.src = await encodeToDataUrl(canvas, { quality: 60 });
+ */
+export async function encodeToDataUrl(
+  source: ImageSource,
+  options: Pick<EncodeOptions, 'width' | 'height' | 'quality'> = {}
+): Promise<string> {
+  const strips = await openStripSource(source, options);
+  const encoder = new Base64Encoder();
+  const parts = ['data:image/jpeg;base64,'];
+  await runPipeline(strips, createJpegStripEncoder(strips.width, strips.height, options.quality), {
+    write: (chunk: Uint8Array): void => {
+      parts.push(encoder.push(chunk));
+    },
+  });
+  parts.push(encoder.flush());
+  return parts.join('');
+}
+
+/**
+ * Encode a stream of pixels or image file bytes, producing JPEG bytes as a stream
+ */
+export function encodeReadableStream(
+  input: ReadableStream<Uint8Array>,
+  options: StreamEncodeOptions = {}
+): ReadableStream<Uint8Array> {
+  return deferredStream(async () => {
+    let source: StripSource;
+    if (options.width !== undefined && options.height !== undefined) {
+      source = readableStreamSource(input, options.width, options.height);
+    } else {
+      const decoded = decodedSource(await readStream(input));
+      if (!decoded) {
+        throw new Error('Width and height are required for raw pixel streams');
+      }
+      source = decoded;
+    }
+    return pipelineStream(source, streamEncoder(source.width, source.height, options));
+  });
+}
+
+/**
+ * Transform stream from raw RGBA bytes to JPEG bytes
+ * Follows the CompressionStream idiom, so it composes with `pipeThrough`.
+ * Input chunks may be any size; rows are buffered until a full strip is available.
+ * With `transferable`, output chunks can be posted to another worker without a copy.
+ *
+ * @example
+ * const jpeg = pixelStream.pipeThrough(new JpegEncoderStream({ width: 640, height: 480 }));
+ */
+export class JpegEncoderStream {
+  readonly readable: ReadableStream<Uint8Array>;
+  readonly writable: WritableStream<Uint8Array>;
+
+  constructor(options: StreamEncodeOptions & { width: number; height: number }) {
+    const { width, height } = options;
+    const encoder = streamEncoder(width, height, options);
+    const stripBytes = encoder.stripHeight * width * 4;
+    const total = width * height * 4;
+    const strip = new Uint8Array(stripBytes);
+    let filled = 0;
+    let received = 0;
+
+    const emit = (controller: TransformStreamDefaultController<Uint8Array>, chunk: Uint8Array): void => {
+      if (chunk.length > 0) {
+        controller.enqueue(chunk);
+      }
+    };
+
+    const { readable, writable } = new TransformStream<Uint8Array, Uint8Array>({
+      transform(chunk, controller): void {
+        if (received + chunk.length > total) {
+          throw new Error(`Received more than the ${total} bytes of a ${width}x${height} RGBA image`);
+        }
+        received += chunk.length;
+        let offset = 0;
+        while (offset < chunk.length) {
+          const take = Math.min(chunk.length - offset, stripBytes - filled);
+          strip.set(chunk.subarray(offset, offset + take), filled);
+          filled += take;
+          offset += take;
+          if (filled === stripBytes) {
+            emit(controller, encoder.encodeStrip(strip));
+            filled = 0;
+          }
+        }
+      },
+      flush(controller): void {
+        if (received < total) {
+          throw new Error(`Stream ended after ${received} of ${total} bytes`);
+        }
+        if (filled > 0) {
+          emit(controller, encoder.encodeStrip(strip.subarray(0, filled)));
+        }
+        emit(controller, encoder.finish());
+      },
+    });
+    this.readable = readable;
+    this.writable = writable;
+  }
+}
+
+/**
+ * Encode an image source as a streaming JPEG Response
+ * Headers can be sent as soon as the handler returns; later strips are encoded
+ * while the client reads the first bytes. Works with any fetch-style server
+ * (Workers, Deno, Bun, Hono, Next.js route handlers).
+ *
+ * @example
+ * app.get('/thumb', () => encodeToResponse(pixels, { width, height, quality: 80 }));
+ */
+export function encodeToResponse(
+  source: ImageSource,
+  options: StreamEncodeOptions = {},
+  init: ResponseInit = {}
+): Response {
+  const headers = new Headers(init.headers);
+  headers.set('Content-Type', 'image/jpeg');
+  return new Response(encodeToStream(source, options), { ...init, headers });
+}
+
+/**
+ * Re-encode the body of a fetch Response as a streaming JPEG Response
+ * Status and headers are kept, except that the content type becomes image/jpeg
+ * and length/encoding headers that no longer apply are removed.
+ *
+ * @example
+ * self.addEventListener('fetch', (event) => {
+ *   event.respondWith(fetch(event.request).then((r) => reencodeResponse(r, { quality: 75 })));
+ * });
+ */
+export function reencodeResponse(response: Response, options: StreamEncodeOptions = {}): Response {
+  if (!response.body) {
+    throw new Error('Response has no body to re-encode');
+  }
+  const headers = new Headers(response.headers);
+  headers.set('Content-Type', 'image/jpeg');
+  headers.delete('Content-Length');
+  headers.delete('Content-Encoding');
+  return new Response(encodeReadableStream(response.body, options), {
+    status: response.status,
+    statusText: response.statusText,
+    headers,
+  });
+}
@@ -0,0 +1,176 @@
+/**
+ * Strip index
+ * Records where each restart segment of a stored JPEG starts, so a reader can
+ * fetch and decode one band of rows without the rest of the file. The index is
+ * a plain object that can be kept as sidecar JSON, or embedded in an APP9
+ * segment ahead of the scan, where a ranged fetch of the first few kilobytes
+ * finds it.
+ */
+
+import { Marker, parseJpeg, readUint16 } from './jpeg.js';
+import { frameGeometry } from './entropy.js';
+import { createSegment, insertSegments } from './markers.js';
+
+const STRIP_INDEX_MARKER = Marker.APP0 + 9;
+const STRIP_INDEX_IDENTIFIER = 'StripIndex\0';
+const STRIP_INDEX_VERSION = 1;
+
+export interface StripIndexSegment {
+  top: number;            // First image row the segment covers
+  rows: number;
+  offset: number;         // File offset of its entropy-coded data
+  end: number;            // File offset just past its data, before the next RSTn or EOI
+}
+
+/**
+ * - headerLength: bytes before the first segment, all a decoder needs besides the data
+ * - segments: in scan order, so a segment's position is its restart interval number
+ */
+export interface StripIndex {
+  width: number;
+  height: number;
+  headerLength: number;
+  rowsPerSegment: number;
+  segments: StripIndexSegment[];
+}
+
+/**
+ * Index the restart segments of a JPEG
+ * The restart interval must cover whole MCU rows, as `encodeSegmented` output
+ * does; a file without restart markers is indexed as one segment.
+ *
+ * @param jpeg Sequential JPEG with a single interleaved scan
+ */
+export function buildStripIndex(jpeg: Uint8Array): StripIndex {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  const scan = file.scans[0];
+  if (file.scans.length !== 1 || scan.header.spectralEnd !== 63 || scan.header.components.length !== frame.components.length) {
+    throw new Error('A strip index needs a sequential JPEG with a single interleaved scan');
+  }
+  const geometry = frameGeometry(frame);
+  const mcuHeight = 8 * geometry.maxV;
+  const interval = scan.restartInterval || geometry.mcusPerLine * geometry.mcusPerColumn;
+  if (interval % geometry.mcusPerLine !== 0) {
+    throw new Error(`A restart interval of ${interval} MCUs does not cover whole rows of ${geometry.mcusPerLine} MCUs`);
+  }
+  const rowsPerSegment = (interval / geometry.mcusPerLine) * mcuHeight;
+
+  const segments: StripIndexSegment[] = [];
+  let offset = scan.dataOffset;
+  const close = (end: number): void => {
+    const top = segments.length * rowsPerSegment;
+    segments.push({ top, rows: Math.min(rowsPerSegment, frame.height - top), offset, end });
+  };
+  for (let i = scan.dataOffset; i + 1 < scan.dataEnd; i++) {
+    if (jpeg[i] === 0xff && jpeg[i + 1] >= Marker.RST0 && jpeg[i + 1] <= Marker.RST7) {
+      close(i);
+      offset = i + 2;
+      i++;
+    }
+  }
+  close(scan.dataEnd);
+
+  const expected = Math.ceil(frame.height / rowsPerSegment);
+  if (segments.length !== expected) {
+    throw new Error(`Found ${segments.length} restart segments, expected ${expected}`);
+  }
+  return { width: frame.width, height: frame.height, headerLength: scan.dataOffset, rowsPerSegment, segments };
+}
+
+/**
+ * Build the APP9 segment holding an index
+ */
+function stripIndexSegment(index: StripIndex): Uint8Array {
+  const count = index.segments.length;
+  const payload = new Uint8Array(STRIP_INDEX_IDENTIFIER.length + 13 + 4 * (count + 1));
+  for (let i = 0; i < STRIP_INDEX_IDENTIFIER.length; i++) {
+    payload[i] = STRIP_INDEX_IDENTIFIER.charCodeAt(i);
+  }
+  const view = new DataView(payload.buffer);
+  let pos = STRIP_INDEX_IDENTIFIER.length;
+  view.setUint8(pos, STRIP_INDEX_VERSION);
+  view.setUint16(pos + 1, index.width);
+  view.setUint16(pos + 3, index.height);
+  view.setUint16(pos + 5, index.rowsPerSegment);
+  view.setUint16(pos + 7, count);
+  view.setUint32(pos + 9, index.headerLength);
+  pos += 13;
+  // Segment starts, then the end of the last one; the others end 2 bytes before the next start
+  for (const segment of index.segments) {
+    view.setUint32(pos, segment.offset);
+    pos += 4;
+  }
+  view.setUint32(pos, index.segments[count - 1].end);
+  return createSegment(STRIP_INDEX_MARKER, payload);
+}
+
+/**
+ * Embed a strip index in an APP9 segment
+ * Offsets in the embedded index account for the segment itself.
+ *
+ * @returns The JPEG with the segment and the index it holds
+ */
+export function addStripIndex(jpeg: Uint8Array): { jpeg: Uint8Array; index: StripIndex } {
+  if (readStripIndex(jpeg)) {
+    throw new Error('The JPEG already holds a strip index');
+  }
+  const unshifted = buildStripIndex(jpeg);
+  const size = stripIndexSegment(unshifted).length;
+  const index: StripIndex = {
+    ...unshifted,
+    headerLength: unshifted.headerLength + size,
+    segments: unshifted.segments.map((s) => ({ ...s, offset: s.offset + size, end: s.end + size })),
+  };
+  return { jpeg: insertSegments(jpeg, [stripIndexSegment(index)]), index };
+}
+
+/**
+ * Read an embedded strip index
+ * Only the bytes up to the index segment are needed, so the start of a file
+ * fetched with a range request is enough.
+ *
+ * @returns The index, or undefined if none comes before the scan or the bytes end first
+ */
+export function readStripIndex(bytes: Uint8Array): StripIndex | undefined {
+  if (bytes.length < 2 || bytes[0] !== 0xff || bytes[1] !== Marker.SOI) {
+    throw new Error('Not a JPEG stream: missing SOI marker');
+  }
+  // Walk the header by hand, since the rest of the file may be missing
+  for (let pos = 2; pos + 4 <= bytes.length && bytes[pos] === 0xff;) {
+    const marker = bytes[pos + 1];
+    if (marker === Marker.SOS || marker === Marker.EOI) {
+      return undefined;
+    }
+    const end = pos + 2 + readUint16(bytes, pos + 2);
+    const data = bytes.subarray(pos + 4, Math.min(end, bytes.length));
+    const identified = data.length >= STRIP_INDEX_IDENTIFIER.length &&
+      Array.from(STRIP_INDEX_IDENTIFIER).every((c, i) => data[i] === c.charCodeAt(0));
+    if (marker === STRIP_INDEX_MARKER && identified) {
+      if (end > bytes.length) {
+        throw new Error(`The strip index segment ends at byte ${end}; fetch at least that much`);
+      }
+      const view = new DataView(data.buffer, data.byteOffset, data.byteLength);
+      let at = STRIP_INDEX_IDENTIFIER.length;
+      if (view.getUint8(at) !== STRIP_INDEX_VERSION) {
+        throw new Error(`Unsupported strip index version ${view.getUint8(at)}`);
+      }
+      const width = view.getUint16(at + 1);
+      const height = view.getUint16(at + 3);
+      const rowsPerSegment = view.getUint16(at + 5);
+      const count = view.getUint16(at + 7);
+      const headerLength = view.getUint32(at + 9);
+      at += 13;
+      const offsets = Array.from({ length: count + 1 }, (_, i) => view.getUint32(at + i * 4));
+      const segments = offsets.slice(0, count).map((offset, i) => ({
+        top: i * rowsPerSegment,
+        rows: Math.min(rowsPerSegment, height - i * rowsPerSegment),
+        offset,
+        end: i + 1 < count ? offsets[i + 1] - 2 : offsets[count],
+      }));
+      return { width, height, headerLength, rowsPerSegment, segments };
+    }
+    pos = end;
+  }
+  return undefined;
+}
@@ -0,0 +1,154 @@
+/**
+ * Chroma subsampling
+ * The engine always writes 4:4:4. This rewrites its output with subsampled
+ * chroma: luma coefficients are copied unchanged, while each chroma plane is
+ * reconstructed, box-filtered to the lower resolution, and transformed and
+ * quantized again with its original table.
+ */
+
+import { Marker, metadataSegments, parseJpeg } from './jpeg.js';
+import type { QuantTable } from './jpeg.js';
+import { inverseDct, readCoefficients } from './decoder.js';
+import { forwardDct, quantizeBlock, writeBaseline } from './transcode.js';
+import type { CodedComponent } from './transcode.js';
+
+export type ChromaSubsampling = '4:4:4' | '4:2:2' | '4:2:0' | '4:1:1';
+
+// Luma sampling factors for each mode; chroma is always 1x1
+export const LUMA_FACTORS: Record<ChromaSubsampling, [number, number]> = {
+  '4:4:4': [1, 1],
+  '4:2:2': [2, 1],
+  '4:2:0': [2, 2],
+  '4:1:1': [4, 1],
+};
+
+/**
+ * Subsampling mode whose luma covers h x v chroma samples, if there is one
+ */
+export function subsamplingForRatio(h: number, v: number): ChromaSubsampling | undefined {
+  return (Object.keys(LUMA_FACTORS) as ChromaSubsampling[]).find((mode) => LUMA_FACTORS[mode][0] === h && LUMA_FACTORS[mode][1] === v);
+}
+
+/**
+ * Rewrite a 4:4:4 YCbCr JPEG with subsampled chroma
+ * MCUs grow to the luma factors (32x8 pixels for 4:1:1); APPn and COM segments
+ * are kept and restart intervals are dropped.
+ *
+ * @param jpeg Sequential 8-bit three-component JPEG with 1x1 sampling
+ * @param mode Target subsampling
+ */
+export function toSubsampled(jpeg: Uint8Array, mode: ChromaSubsampling): Uint8Array {
+  const factors = LUMA_FACTORS[mode];
+  if (!factors) {
+    throw new Error(`Unknown chroma subsampling: ${mode}`);
+  }
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 ||
+      frame.components.length !== 3 || frame.components.some((c) => c.h !== 1 || c.v !== 1)) {
+    throw new Error('Chroma subsampling needs an 8-bit sequential 4:4:4 YCbCr JPEG');
+  }
+  if (mode === '4:4:4') {
+    return jpeg;
+  }
+
+  const [h, v] = factors;
+  const { width, height } = frame;
+  const mcusPerLine = Math.ceil(width / (8 * h));
+  const mcusPerColumn = Math.ceil(height / (8 * v));
+  const coefficients = readCoefficients(file);
+
+  const quantTables = new Map<number, QuantTable>();
+  for (const scan of file.scans) {
+    scan.quantTables.forEach((table, id) => quantTables.set(id, table));
+  }
+
+  // Luma: same blocks on a grid widened to whole MCUs, padding blocks repeat the edge DC
+  const luma = coefficients[0];
+  const lumaLine = mcusPerLine * h;
+  const lumaColumn = mcusPerColumn * v;
+  const lumaBlocks = new Int32Array(lumaLine * lumaColumn * 64);
+  const sourceLine = Math.ceil(width / 8);
+  const sourceColumn = Math.ceil(height / 8);
+  for (let row = 0; row < lumaColumn; row++) {
+    for (let col = 0; col < lumaLine; col++) {
+      const target = (row * lumaLine + col) * 64;
+      if (row < sourceColumn && col < sourceLine) {
+        lumaBlocks.set(luma.blocks.subarray((row * luma.blocksPerLine + col) * 64, (row * luma.blocksPerLine + col + 1) * 64), target);
+      } else {
+        const edge = (Math.min(row, sourceColumn - 1) * luma.blocksPerLine + Math.min(col, sourceLine - 1)) * 64;
+        lumaBlocks[target] = luma.blocks[edge];
+      }
+    }
+  }
+
+  const components: CodedComponent[] = [{
+    id: frame.components[0].id,
+    h,
+    v,
+    quantId: frame.components[0].quantTable,
+    blocksPerLine: lumaLine,
+    blocksPerColumn: lumaColumn,
+    blocks: lumaBlocks,
+  }];
+
+  // Chroma: reconstruct at full resolution, average h x v boxes, transform again
+  const planeWidth = sourceLine * 8;
+  const samples = new Float64Array(64);
+  const natural = new Float64Array(64);
+  for (let index = 1; index < 3; index++) {
+    const source = coefficients[index];
+    const plane = new Float64Array(planeWidth * sourceColumn * 8);
+    for (let row = 0; row < sourceColumn; row++) {
+      for (let col = 0; col < sourceLine; col++) {
+        inverseDct(source.blocks, (row * source.blocksPerLine + col) * 64, source.quantTable, samples);
+        for (let y = 0; y < 8; y++) {
+          plane.set(samples.subarray(y * 8, y * 8 + 8), (row * 8 + y) * planeWidth + col * 8);
+        }
+      }
+    }
+    // Samples past the image edge are replaced by the nearest edge sample
+    const at = (x: number, y: number): number => plane[Math.min(y, height - 1) * planeWidth + Math.min(x, width - 1)];
+
+    const quantId = frame.components[index].quantTable;
+    const quant = quantTables.get(quantId)?.values ?? source.quantTable;
+    const blocks = new Int32Array(mcusPerLine * mcusPerColumn * 64);
+    for (let row = 0; row < mcusPerColumn; row++) {
+      for (let col = 0; col < mcusPerLine; col++) {
+        for (let y = 0; y < 8; y++) {
+          for (let x = 0; x < 8; x++) {
+            let sum = 0;
+            for (let dy = 0; dy < v; dy++) {
+              for (let dx = 0; dx < h; dx++) {
+                sum += at((col * 8 + x) * h + dx, (row * 8 + y) * v + dy);
+              }
+            }
+            samples[y * 8 + x] = sum / (h * v) - 128;
+          }
+        }
+        forwardDct(samples, natural);
+        quantizeBlock(natural, quant, blocks, (row * mcusPerLine + col) * 64);
+      }
+    }
+    components.push({
+      id: frame.components[index].id,
+      h: 1,
+      v: 1,
+      quantId,
+      blocksPerLine: mcusPerLine,
+      blocksPerColumn: mcusPerColumn,
+      blocks,
+    });
+  }
+
+  const usedIds = new Set(components.map((c) => c.quantId));
+  const segments = metadataSegments(file);
+
+  return writeBaseline({
+    width,
+    height,
+    components,
+    quantTables: [...quantTables.values()].filter((t) => usedIds.has(t.id)).sort((a, b) => a.id - b.id),
+    segments,
+  });
+}
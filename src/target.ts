@@ -0,0 +1,98 @@
+/**
+ * Perceptual quality targets
+ * The same quality number gives very different fidelity on a flat graphic and
+ * on foliage. Here the caller names an SSIM score instead, and a binary search
+ * over quality runs trial encodes of a few bands sampled from the image to find
+ * the lowest quality that reaches it; only the final encode covers every row.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { encodeToBytes, openStripSource } from './encoder.js';
+import { decode } from './decoder.js';
+import { readAllStrips } from './pipeline.js';
+import { ssim } from './ssim.js';
+
+// Band height covering whole MCUs for every subsampling mode
+const BAND_ROWS = 16;
+
+export interface TargetQualityOptions extends Omit<EncodeOptions, 'quality' | 'quantTables'> {
+  targetSsim: number;     // Score to reach, 0-1; around 0.98 is visually lossless for photos
+  sampleBands?: number;   // Bands of 16 rows used for the trial encodes, defaults to 8
+}
+
+export interface TargetQualityResult {
+  jpeg: Uint8Array;
+  quality: number;        // Lowest quality whose trial encode reached the target, or 100
+  ssim: number;           // Score of the sample at that quality
+}
+
+/**
+ * Gather evenly spaced bands into one image for trial encodes
+ */
+function sampleImage(pixels: Uint8Array, width: number, height: number, bands: number): { data: Uint8Array; height: number } {
+  if (height <= bands * BAND_ROWS * 2) {
+    return { data: pixels, height };
+  }
+  const rowBytes = width * 4;
+  const data = new Uint8Array(bands * BAND_ROWS * rowBytes);
+  const spacing = Math.floor((height - BAND_ROWS) / (bands - 1 || 1));
+  for (let band = 0; band < bands; band++) {
+    const top = Math.min(band * spacing, height - BAND_ROWS);
+    data.set(pixels.subarray(top * rowBytes, (top + BAND_ROWS) * rowBytes), band * BAND_ROWS * rowBytes);
+  }
+  return { data, height: bands * BAND_ROWS };
+}
+
+/**
+ * Encode at the lowest quality that reaches an SSIM target
+ * The source is held in memory, since it is read again for the final encode.
+ *
+ * @example
+ * const { jpeg, quality } = await encodeToTarget('photo.png', { targetSsim: 0.97, subsampling: '4:2:0' });
+ */
+export async function encodeToTarget(source: ImageSource, options: TargetQualityOptions): Promise<TargetQualityResult> {
+  const { targetSsim, sampleBands = 8 } = options;
+  if (!(targetSsim > 0 && targetSsim <= 1)) {
+    throw new Error(`Target SSIM must be in (0, 1], got ${targetSsim}`);
+  }
+  if (!Number.isInteger(sampleBands) || sampleBands < 1) {
+    throw new Error(`Sample band count must be a positive integer, got ${sampleBands}`);
+  }
+
+  const strips = await openStripSource(source, options);
+  const { width, height } = strips;
+  const pixels = await readAllStrips(strips);
+  const sample = sampleImage(pixels, width, height, sampleBands);
+
+  // Trials take only the options that shape the coded image; callbacks, the
+  // alpha mask and per-row quality belong to the final encode
+  const { subsampling, qualityScaling, preset, grayscale, layout, progressive, optimizeHuffman, compat, transform, quantizer, dither, deband } = options;
+  const trialOptions: EncodeOptions = {
+    subsampling, qualityScaling, preset, grayscale, layout, optimizeHuffman, compat, transform, quantizer, dither, deband,
+    progressive: typeof progressive === 'object' ? { scans: progressive.scans } : progressive,
+  };
+
+  const score = async (quality: number): Promise<number> => {
+    const jpeg = await encodeToBytes(sample.data, { ...trialOptions, width, height: sample.height, quality });
+    return ssim(sample.data, decode(jpeg).data, width, sample.height);
+  };
+
+  // Lowest quality in 1-100 whose sample reaches the target; SSIM rises with quality
+  let low = 1;
+  let high = 100;
+  let best = await score(high);
+  while (low < high) {
+    const middle = Math.floor((low + high) / 2);
+    const value = await score(middle);
+    if (value >= targetSsim) {
+      high = middle;
+      best = value;
+    } else {
+      low = middle + 1;
+    }
+  }
+
+  // The pixels are RGBA rows now, whatever layout the source buffer had
+  const jpeg = await encodeToBytes(pixels, { ...options, width, height, quality: high, pixelFormat: undefined, stride: undefined });
+  return { jpeg, quality: high, ssim: best };
+}
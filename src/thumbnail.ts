@@ -0,0 +1,121 @@
+/**
+ * JFIF extension (JFXX) thumbnails
+ * Builds a box-filtered thumbnail incrementally from the strips fed to the
+ * encoder and packages it as an APP0 "JFXX" segment.
+ */
+
+import { Marker } from './jpeg.js';
+import { createSegment } from './markers.js';
+
+export type ThumbnailFormat = 'rgb' | 'jpeg';
+
+export interface ThumbnailOptions {
+  format?: ThumbnailFormat; // Uncompressed RGB or an embedded JPEG, defaults to 'rgb'
+  maxSize?: number;         // Longest thumbnail edge in pixels, defaults to 80
+}
+
+const JFXX_IDENTIFIER = [0x4a, 0x46, 0x58, 0x58, 0x00]; // "JFXX\0"
+const EXTENSION_JPEG = 0x10;
+const EXTENSION_RGB = 0x13;
+
+/**
+ * Largest edge an RGB thumbnail can have and still fit in one segment
+ * (3 bytes per pixel plus 8 bytes of header must stay within 65533)
+ */
+export const MAX_RGB_THUMBNAIL_SIZE = 147;
+
+/**
+ * Accumulates a downscaled copy of an image one strip at a time
+ */
+export class ThumbnailBuilder {
+  readonly width: number;
+  readonly height: number;
+  private readonly sourceWidth: number;
+  private readonly sourceHeight: number;
+  private readonly sums: Float64Array;
+  private readonly counts: Uint32Array;
+  private nextRow = 0;
+
+  constructor(sourceWidth: number, sourceHeight: number, maxSize = 80) {
+    const scale = Math.min(1, maxSize / Math.max(sourceWidth, sourceHeight));
+    this.sourceWidth = sourceWidth;
+    this.sourceHeight = sourceHeight;
+    this.width = Math.max(1, Math.round(sourceWidth * scale));
+    this.height = Math.max(1, Math.round(sourceHeight * scale));
+    this.sums = new Float64Array(this.width * this.height * 3);
+    this.counts = new Uint32Array(this.width * this.height);
+  }
+
+  /**
+   * Add the next strip of RGBA rows (strips must arrive top to bottom)
+   */
+  addStrip(strip: Uint8Array): void {
+    const rows = Math.floor(strip.length / (this.sourceWidth * 4));
+    for (let row = 0; row < rows && this.nextRow < this.sourceHeight; row++, this.nextRow++) {
+      const ty = Math.floor((this.nextRow * this.height) / this.sourceHeight);
+      for (let x = 0; x < this.sourceWidth; x++) {
+        const tx = Math.floor((x * this.width) / this.sourceWidth);
+        const source = (row * this.sourceWidth + x) * 4;
+        const target = ty * this.width + tx;
+        this.sums[target * 3] += strip[source];
+        this.sums[target * 3 + 1] += strip[source + 1];
+        this.sums[target * 3 + 2] += strip[source + 2];
+        this.counts[target]++;
+      }
+    }
+  }
+
+  /**
+   * Thumbnail pixels, 3 bytes per pixel
+   */
+  toRgb(): Uint8Array {
+    const rgb = new Uint8Array(this.width * this.height * 3);
+    for (let i = 0; i < this.counts.length; i++) {
+      const count = this.counts[i] || 1;
+      for (let c = 0; c < 3; c++) {
+        rgb[i * 3 + c] = Math.round(this.sums[i * 3 + c] / count);
+      }
+    }
+    return rgb;
+  }
+
+  /**
+   * Thumbnail pixels, 4 bytes per pixel with opaque alpha
+   */
+  toRgba(): Uint8Array {
+    const rgb = this.toRgb();
+    const rgba = new Uint8Array(this.width * this.height * 4);
+    for (let i = 0; i < this.counts.length; i++) {
+      rgba.set(rgb.subarray(i * 3, i * 3 + 3), i * 4);
+      rgba[i * 4 + 3] = 255;
+    }
+    return rgba;
+  }
+}
+
+/**
+ * Build an APP0 JFXX segment holding an uncompressed RGB thumbnail
+ */
+export function jfxxRgbSegment(width: number, height: number, rgb: Uint8Array): Uint8Array {
+  if (width > 255 || height > 255 || width * height * 3 + 8 > 0xffff - 2) {
+    throw new Error(`RGB thumbnail of ${width}x${height} does not fit in a JFXX segment`);
+  }
+  const payload = new Uint8Array(JFXX_IDENTIFIER.length + 3 + width * height * 3);
+  payload.set(JFXX_IDENTIFIER, 0);
+  payload[5] = EXTENSION_RGB;
+  payload[6] = width;
+  payload[7] = height;
+  payload.set(rgb.subarray(0, width * height * 3), 8);
+  return createSegment(Marker.APP0, payload);
+}
+
+/**
+ * Build an APP0 JFXX segment holding a JPEG-compressed thumbnail
+ */
+export function jfxxJpegSegment(jpeg: Uint8Array): Uint8Array {
+  const payload = new Uint8Array(JFXX_IDENTIFIER.length + 1 + jpeg.length);
+  payload.set(JFXX_IDENTIFIER, 0);
+  payload[5] = EXTENSION_JPEG;
+  payload.set(jpeg, 6);
+  return createSegment(Marker.APP0, payload);
+}
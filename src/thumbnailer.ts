@@ -0,0 +1,58 @@
+/**
+ * JPEG thumbnailing
+ * Decodes a JPEG at reduced scale, box-filters it to the requested size, and
+ * re-encodes it strip by strip. When the source is at least 8 times larger than
+ * the target, only DC coefficients are decoded, so memory stays proportional to
+ * the 1/8-scale image rather than the original.
+ */
+
+import type { ImageSource } from './types.js';
+import { parseJpeg } from './jpeg.js';
+import { decode, decodeDc } from './decoder.js';
+import { ThumbnailBuilder } from './thumbnail.js';
+import { encodeToBytes, sourceToBuffer } from './encoder.js';
+import { readStream } from './stream.js';
+import { copyMetadata } from './metadata.js';
+import type { MetadataCopyOptions } from './metadata.js';
+
+export interface ThumbnailerOptions {
+  metadata?: boolean | MetadataCopyOptions; // Carry EXIF and the ICC profile over from the source
+}
+
+/**
+ * Make a JPEG thumbnail of a JPEG
+ *
+ * @param source JPEG bytes, file path, Node.js stream, or web ReadableStream
+ * @param maxSize Longest edge of the thumbnail in pixels
+ * @param quality JPEG quality of the thumbnail, defaults to 85
+ * @param options Metadata to carry over
+ */
+export async function thumbnailToBytes(
+  source: ImageSource | ReadableStream<Uint8Array>,
+  maxSize: number,
+  quality = 85,
+  options: ThumbnailerOptions = {}
+): Promise<Uint8Array> {
+  if (!Number.isInteger(maxSize) || maxSize < 1) {
+    throw new Error(`Thumbnail size must be a positive integer, got ${maxSize}`);
+  }
+  const bytes = typeof ReadableStream !== 'undefined' && source instanceof ReadableStream
+    ? await readStream(source)
+    : (await sourceToBuffer(source as ImageSource)).buffer;
+
+  const { frame } = parseJpeg(bytes);
+  const image = Math.max(frame.width, frame.height) / 8 >= maxSize ? decodeDc(bytes) : decode(bytes);
+
+  const builder = new ThumbnailBuilder(image.width, image.height, maxSize);
+  const rowBytes = image.width * 4;
+  const pixels = new Uint8Array(image.data.buffer, image.data.byteOffset, image.data.byteLength);
+  for (let y = 0; y < image.height; y += 8) {
+    builder.addStrip(pixels.subarray(y * rowBytes, Math.min(y + 8, image.height) * rowBytes));
+  }
+
+  const jpeg = await encodeToBytes(builder.toRgba(), { width: builder.width, height: builder.height, quality });
+  if (!options.metadata) {
+    return jpeg;
+  }
+  return copyMetadata(bytes, jpeg, options.metadata === true ? {} : options.metadata);
+}
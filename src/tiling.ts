@@ -0,0 +1,168 @@
+/**
+ * Tiled output for oversized images
+ * Splits an image that exceeds the JPEG dimension limit into a grid of JPEG
+ * tiles plus a JSON manifest. Each tile row is encoded as its rows stream past,
+ * with one encoder per column. Strip sources such as `rawFileSource`, streams,
+ * and iterables of raw RGBA are read a strip at a time; other image sources
+ * (buffers, files by path) are read whole first, as `encode` reads them.
+ */
+
+import type { EncodeOptions, ImageSource } from './types.js';
+import { createJpegStripEncoder, openStripSource } from './encoder.js';
+import { MAX_JPEG_DIMENSION } from './dimensions.js';
+import { concatBytes } from './pipeline.js';
+import type { StripEncoder, StripSource } from './pipeline.js';
+
+export interface TiledEncodeOptions extends Pick<EncodeOptions, 'width' | 'height' | 'quality'> {
+  maxTileSize?: number;  // Largest tile edge, defaults to the JPEG limit of 65535
+  onTile?(tile: ImageTile): Promise<void> | void; // Receives tiles as they complete; otherwise they are collected
+}
+
+export interface ImageTile {
+  column: number;
+  row: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  path: string;       // "<row>_<column>.jpg"
+  jpeg: Uint8Array;
+}
+
+/**
+ * JSON description of a tile grid; tiles are listed row by row
+ */
+export interface TileManifest {
+  width: number;
+  height: number;
+  tileWidth: number;
+  tileHeight: number;
+  columns: number;
+  rows: number;
+  tiles: { path: string; x: number; y: number; width: number; height: number }[];
+}
+
+export interface TiledEncodeResult {
+  manifest: TileManifest;
+  tiles: ImageTile[];   // Empty when `onTile` was given
+}
+
+interface ColumnEncoder {
+  x: number;
+  width: number;
+  encoder: StripEncoder;
+  strip: Uint8Array;
+  chunks: Uint8Array[];
+}
+
+function isStripSource(source: ImageSource | StripSource): source is StripSource {
+  return typeof source === 'object' && source !== null && typeof (source as StripSource).readStrip === 'function';
+}
+
+/**
+ * Encode an image of any size as a grid of JPEG tiles
+ * Images within `maxTileSize` produce a single tile, so this can be used
+ * unconditionally where inputs may exceed 65535 pixels. Pass a strip source
+ * to keep only a strip of a large image in memory; its own dimensions are
+ * used, and width and height in `options` are ignored.
+ *
+ * @example
+ * const source = await rawFileSource('scene.rgb', { width: 120000, height: 80000, format: 'rgb' });
+ * const { manifest } = await encodeTiled(source, {
+ *   onTile: (tile) => writeFile(`scene/${tile.path}`, tile.jpeg),
+ * });
+ * await writeFile('scene/manifest.json', JSON.stringify(manifest));
+ */
+export async function encodeTiled(source: ImageSource | StripSource, options: TiledEncodeOptions = {}): Promise<TiledEncodeResult> {
+  const { maxTileSize = MAX_JPEG_DIMENSION, quality } = options;
+  if (!Number.isInteger(maxTileSize) || maxTileSize < 1 || maxTileSize > MAX_JPEG_DIMENSION) {
+    throw new Error(`Tile size must be 1-${MAX_JPEG_DIMENSION}, got ${maxTileSize}`);
+  }
+  const strips = isStripSource(source) ? source : await openStripSource(source, options);
+  const { width, height } = strips;
+  const tileWidth = Math.min(maxTileSize, width);
+  const tileHeight = Math.min(maxTileSize, height);
+  const manifest: TileManifest = {
+    width,
+    height,
+    tileWidth,
+    tileHeight,
+    columns: Math.ceil(width / tileWidth),
+    rows: Math.ceil(height / tileHeight),
+    tiles: [],
+  };
+  const tiles: ImageTile[] = [];
+
+  const rowBytes = width * 4;
+  let row = 0;
+  let columns: ColumnEncoder[] = [];
+  let rowStart = 0;
+  let filled = 0;
+
+  const startTileRow = (): void => {
+    rowStart = row * tileHeight;
+    columns = [];
+    for (let x = 0; x < width; x += tileWidth) {
+      const columnWidth = Math.min(tileWidth, width - x);
+      const encoder = createJpegStripEncoder(columnWidth, Math.min(tileHeight, height - rowStart), quality);
+      columns.push({ x, width: columnWidth, encoder, strip: new Uint8Array(columnWidth * encoder.stripHeight * 4), chunks: [] });
+    }
+  };
+
+  const flushStrip = (): void => {
+    for (const column of columns) {
+      column.chunks.push(column.encoder.encodeStrip(column.strip.subarray(0, filled * column.width * 4)));
+    }
+    filled = 0;
+  };
+
+  const finishTileRow = async (): Promise<void> => {
+    const rowHeight = Math.min(tileHeight, height - rowStart);
+    for (const [column, encoder] of columns.entries()) {
+      encoder.chunks.push(encoder.encoder.finish());
+      const tile: ImageTile = {
+        column,
+        row,
+        x: encoder.x,
+        y: rowStart,
+        width: encoder.width,
+        height: rowHeight,
+        path: `${row}_${column}.jpg`,
+        jpeg: concatBytes(encoder.chunks),
+      };
+      manifest.tiles.push({ path: tile.path, x: tile.x, y: tile.y, width: tile.width, height: tile.height });
+      if (options.onTile) {
+        await options.onTile(tile);
+      } else {
+        tiles.push(tile);
+      }
+    }
+    row++;
+  };
+
+  startTileRow();
+  let y = 0;
+  for (let strip = await strips.readStrip(8); strip && strip.length > 0; strip = await strips.readStrip(8)) {
+    for (let offset = 0; offset + rowBytes <= strip.length; offset += rowBytes, y++) {
+      for (const column of columns) {
+        column.strip.set(strip.subarray(offset + column.x * 4, offset + (column.x + column.width) * 4), filled * column.width * 4);
+      }
+      filled++;
+      const tileRowEnd = y + 1 === Math.min(height, rowStart + tileHeight);
+      if (filled === columns[0].encoder.stripHeight || tileRowEnd) {
+        flushStrip();
+      }
+      if (tileRowEnd) {
+        await finishTileRow();
+        if (y + 1 < height) {
+          startTileRow();
+        }
+      }
+    }
+  }
+  if (y < height) {
+    throw new Error(`Source ended after ${y} of ${height} rows`);
+  }
+
+  return { manifest, tiles };
+}
@@ -0,0 +1,286 @@
+/**
+ * Coefficient transcoding
+ * Forward DCT and quantization for blocks rebuilt outside the engine, and a
+ * baseline writer that entropy-codes quantized coefficients with optimal
+ * Huffman tables. Used to reshape the engine's 4:4:4 output (subsampling,
+ * requantization, table optimization) without a second encoder.
+ */
+
+import { Marker, ZIGZAG, metadataSegments, parseJpeg } from './jpeg.js';
+import type { HuffmanSpec, QuantTable } from './jpeg.js';
+import { DCT_BASIS, readCoefficients } from './decoder.js';
+import { BitWriter, buildHuffmanCodes, clampAc, clampDc, huffmanTablePayload, magnitudeCategory, optimalHuffmanSpec } from './huffman.js';
+import { createSegment } from './markers.js';
+import { quantTablePayload } from './quant.js';
+import { concatBytes } from './pipeline.js';
+
+/**
+ * Quantized coefficients of one component, ready to be written
+ * - blocksPerLine/blocksPerColumn cover whole MCUs
+ * - blocks holds 64 zig-zag ordered values per block, row-major
+ */
+export interface CodedComponent {
+  id: number;
+  h: number;
+  v: number;
+  quantId: number;
+  huffmanId?: number;   // DC and AC table id, 0 or 1; defaults to 0 for component 0 and 1 for the others
+  blocksPerLine: number;
+  blocksPerColumn: number;
+  blocks: Int32Array;
+}
+
+export interface BaselineSpec {
+  width: number;
+  height: number;
+  components: CodedComponent[];
+  quantTables: QuantTable[];
+  segments?: Uint8Array[];   // Complete APPn/COM segments written after SOI
+  restartInterval?: number;  // MCUs per restart interval, 0 or absent for none
+  huffmanTables?: HuffmanSpec[];  // Tables to code with instead of ones built from the statistics
+}
+
+/**
+ * Forward DCT of one block of level-shifted samples into natural-order coefficients
+ */
+export function forwardDct(samples: Float64Array, out: Float64Array): void {
+  const temp = new Float64Array(64);
+  for (let y = 0; y < 8; y++) {
+    for (let u = 0; u < 8; u++) {
+      let sum = 0;
+      for (let x = 0; x < 8; x++) {
+        sum += samples[y * 8 + x] * DCT_BASIS[u * 8 + x];
+      }
+      temp[y * 8 + u] = sum;
+    }
+  }
+  for (let u = 0; u < 8; u++) {
+    for (let v = 0; v < 8; v++) {
+      let sum = 0;
+      for (let y = 0; y < 8; y++) {
+        sum += temp[y * 8 + u] * DCT_BASIS[v * 8 + y];
+      }
+      out[v * 8 + u] = sum;
+    }
+  }
+}
+
+/**
+ * Quantize natural-order coefficients into zig-zag order
+ *
+ * @param quant Zig-zag ordered quantizer values, as stored in DQT
+ */
+export function quantizeBlock(natural: Float64Array, quant: ArrayLike<number>, out: Int32Array, offset = 0): void {
+  for (let k = 0; k < 64; k++) {
+    out[offset + k] = Math.round(natural[ZIGZAG[k]] / quant[k]);
+  }
+}
+
+/**
+ * Write quantized coefficients as a baseline (or extended, for 16-bit tables) JPEG
+ * All components share one interleaved scan; component 0 uses Huffman tables 0
+ * and the others tables 1 unless `huffmanId` says otherwise, each built from
+ * the coefficient statistics unless `huffmanTables` supplies them.
+ * Coefficients outside the 8-bit range are clamped, as in `toProgressive`.
+ */
+export function writeBaseline(spec: BaselineSpec): Uint8Array {
+  const { width, height, components, quantTables, restartInterval = 0 } = spec;
+  const maxH = Math.max(...components.map((c) => c.h));
+  const maxV = Math.max(...components.map((c) => c.v));
+  const single = components.length === 1;
+  const mcusPerLine = single ? Math.ceil(width / 8) : Math.ceil(width / (8 * maxH));
+  const mcusPerColumn = single ? Math.ceil(height / 8) : Math.ceil(height / (8 * maxV));
+  const mcuCount = mcusPerLine * mcusPerColumn;
+  const tableOf = (index: number): number => components[index].huffmanId ?? (index === 0 ? 0 : 1);
+  const tableIds = [...new Set(components.map((_, index) => tableOf(index)))].sort((a, b) => a - b);
+
+  // Visit blocks in scan order, restarting predictors at each interval
+  const encode = (symbol: (tableClass: number, table: number, value: number) => void, bits: (value: number, size: number) => void, restart: () => void): void => {
+    const predictors = new Int32Array(components.length);
+    const block = (index: number, row: number, col: number): void => {
+      const component = components[index];
+      const base = (row * component.blocksPerLine + col) * 64;
+      const values = component.blocks;
+      const table = tableOf(index);
+      const dc = clampDc(values[base]);
+      const diff = dc - predictors[index];
+      predictors[index] = dc;
+      const dcSize = magnitudeCategory(diff);
+      symbol(0, table, dcSize);
+      bits(diff < 0 ? diff + (1 << dcSize) - 1 : diff, dcSize);
+      let run = 0;
+      for (let k = 1; k < 64; k++) {
+        const value = clampAc(values[base + k]);
+        if (value === 0) {
+          run++;
+          continue;
+        }
+        while (run >= 16) {
+          symbol(1, table, 0xf0);
+          run -= 16;
+        }
+        const size = magnitudeCategory(value);
+        symbol(1, table, (run << 4) | size);
+        bits(value < 0 ? value + (1 << size) - 1 : value, size);
+        run = 0;
+      }
+      if (run > 0) {
+        symbol(1, table, 0x00);
+      }
+    };
+
+    for (let mcu = 0; mcu < mcuCount; mcu++) {
+      if (restartInterval > 0 && mcu > 0 && mcu % restartInterval === 0) {
+        restart();
+        predictors.fill(0);
+      }
+      const mcuRow = Math.floor(mcu / mcusPerLine);
+      const mcuCol = mcu % mcusPerLine;
+      if (single) {
+        block(0, mcuRow, mcuCol);
+        continue;
+      }
+      components.forEach((c, index) => {
+        for (let y = 0; y < c.v; y++) {
+          for (let x = 0; x < c.h; x++) {
+            block(index, mcuRow * c.v + y, mcuCol * c.h + x);
+          }
+        }
+      });
+    }
+  };
+
+  // Statistics pass, then the coding pass with the resulting tables
+  const frequencies = [0, 1].map(() => [new Uint32Array(256), new Uint32Array(256)]);
+  encode((tableClass, table, value) => { frequencies[tableClass][table][value]++; }, () => {}, () => {});
+  const specs: HuffmanSpec[] = [];
+  for (let tableClass = 0; tableClass < 2; tableClass++) {
+    for (const table of tableIds) {
+      const given = spec.huffmanTables?.find((t) => t.tableClass === tableClass && t.id === table);
+      if (spec.huffmanTables && !given) {
+        throw new Error(`No ${tableClass ? 'AC' : 'DC'} Huffman table ${table} was supplied`);
+      }
+      specs.push(given ?? optimalHuffmanSpec(frequencies[tableClass][table], tableClass, table));
+    }
+  }
+  const codes = specs.map((s) => buildHuffmanCodes(s));
+  if (spec.huffmanTables) {
+    specs.forEach((s, index) => {
+      const missing = frequencies[s.tableClass][s.id].findIndex((count, symbol) => count > 0 && codes[index].lengths[symbol] === 0);
+      if (missing >= 0) {
+        throw new Error(`${s.tableClass ? 'AC' : 'DC'} Huffman table ${s.id} has no code for symbol 0x${missing.toString(16).padStart(2, '0')}`);
+      }
+    });
+  }
+  const codeFor = (tableClass: number, table: number): ReturnType<typeof buildHuffmanCodes> =>
+    codes[tableClass * tableIds.length + tableIds.indexOf(table)];
+
+  const intervals: Uint8Array[] = [];
+  let writer = new BitWriter();
+  encode(
+    (tableClass, table, value) => {
+      const code = codeFor(tableClass, table);
+      writer.writeBits(code.codes[value], code.lengths[value]);
+    },
+    (value, size) => writer.writeBits(value, size),
+    () => {
+      intervals.push(writer.finish(), new Uint8Array([0xff, Marker.RST0 + ((intervals.length / 2) % 8)]));
+      writer = new BitWriter();
+    }
+  );
+  intervals.push(writer.finish());
+
+  const sofMarker = quantTables.some((t) => t.precision) ? Marker.SOF1 : Marker.SOF0;
+  const sof = [8, height >> 8, height & 0xff, width >> 8, width & 0xff, components.length];
+  for (const c of components) {
+    sof.push(c.id, (c.h << 4) | c.v, c.quantId);
+  }
+  const sos = [components.length];
+  components.forEach((c, index) => sos.push(c.id, (tableOf(index) << 4) | tableOf(index)));
+  sos.push(0, 63, 0);
+
+  const chunks: Uint8Array[] = [
+    new Uint8Array([0xff, Marker.SOI]),
+    ...(spec.segments ?? []),
+    createSegment(Marker.DQT, quantTablePayload(quantTables)),
+    createSegment(sofMarker, new Uint8Array(sof)),
+    createSegment(Marker.DHT, huffmanTablePayload(specs)),
+  ];
+  if (restartInterval > 0) {
+    chunks.push(createSegment(Marker.DRI, new Uint8Array([restartInterval >> 8, restartInterval & 0xff])));
+  }
+  chunks.push(createSegment(Marker.SOS, new Uint8Array(sos)), ...intervals, new Uint8Array([0xff, Marker.EOI]));
+
+  return concatBytes(chunks);
+}
+
+/**
+ * Re-code a sequential JPEG with Huffman tables built from its own statistics
+ * Coefficients, quantization tables, restart interval, and APPn/COM segments are
+ * kept, so the image is unchanged and the file usually shrinks by a few percent.
+ *
+ * @param jpeg Sequential 8-bit JPEG
+ */
+export function optimizeHuffman(jpeg: Uint8Array): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 || file.scans.length !== 1) {
+    throw new Error('Huffman optimization needs an 8-bit sequential JPEG with a single scan');
+  }
+  const [scan] = file.scans;
+  const coefficients = readCoefficients(file);
+
+  const components: CodedComponent[] = frame.components.map((c, index) => ({
+    id: c.id,
+    h: c.h,
+    v: c.v,
+    quantId: c.quantTable,
+    blocksPerLine: coefficients[index].blocksPerLine,
+    blocksPerColumn: coefficients[index].blocksPerColumn,
+    blocks: coefficients[index].blocks,
+  }));
+  const usedIds = new Set(components.map((c) => c.quantId));
+  const segments = metadataSegments(file);
+
+  return writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components,
+    quantTables: [...scan.quantTables.values()].filter((t) => usedIds.has(t.id)).sort((a, b) => a.id - b.id),
+    segments,
+    restartInterval: scan.restartInterval,
+  });
+}
+
+/**
+ * Keep only the luma of a sequential JPEG, giving a grayscale file
+ * The luma coefficients are copied as they are, so nothing is re-quantized;
+ * APPn and COM segments are kept.
+ */
+export function toGrayscale(jpeg: Uint8Array): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const { frame } = file;
+  if ((frame.marker !== Marker.SOF0 && frame.marker !== Marker.SOF1) || frame.precision !== 8 || file.scans.length !== 1) {
+    throw new Error('Grayscale conversion needs an 8-bit sequential JPEG with a single scan');
+  }
+  const [scan] = file.scans;
+  const [luma] = readCoefficients(file);
+  const component = frame.components[0];
+  const segments = metadataSegments(file);
+
+  return writeBaseline({
+    width: frame.width,
+    height: frame.height,
+    components: [{
+      id: component.id,
+      h: 1,
+      v: 1,
+      quantId: component.quantTable,
+      blocksPerLine: luma.blocksPerLine,
+      blocksPerColumn: luma.blocksPerColumn,
+      blocks: luma.blocks,
+    }],
+    quantTables: [...scan.quantTables.values()].filter((t) => t.id === component.quantTable),
+    segments,
+  });
+}
@@ -0,0 +1,98 @@
+/**
+ * Public types shared by the encoder entry points
+ */
+
+import type { Readable } from 'stream';
+import type { ThumbnailOptions } from './thumbnail.js';
+import type { ProgressiveOptions } from './progressive.js';
+import type { ChromaSubsampling } from './subsample.js';
+import type { CustomQuantTables, StripQuality } from './requantize.js';
+import type { EncoderPreset } from './presets.js';
+import type { AlphaOutputOptions } from './alpha.js';
+import type { FrameLayout } from './layout.js';
+import type { BlockQuantizer, BlockTransform } from './libjpeg.js';
+import type { QualityScaling } from './scaling.js';
+import type { RawPixelFormat } from './rawfile.js';
+
+/**
+ * Universal image source type
+ * - Uint8Array: Raw RGBA buffer (works everywhere)
+ * - string: File path (Node.js only)
+ * - Buffer: Node.js buffer (Node.js only)
+ * - Readable: Node.js stream (Node.js only)
+ * - HTMLCanvasElement: Browser canvas (browser/jsdom only)
+ * - ImageData: Canvas image data (browser/jsdom only)
+ * - ImageBitmap: Decoded bitmap, read back through an OffscreenCanvas (browser only)
+ *
+ * - AsyncIterable/Iterable: Chunks of raw RGBA of any size, pulled as the encoder needs rows
+ *
+ * Byte sources (Uint8Array, Buffer, file, stream) holding a PNG, QOI, or binary PNM file are
+ * detected by signature and decoded strip by strip; width and height come
+ * from the file header.
+ */
+export type ImageSource =
+  | Uint8Array
+  | string
+  | Buffer
+  | Readable
+  | HTMLCanvasElement
+  | ImageData
+  | ImageBitmap
+  | AsyncIterable<Uint8Array>
+  | Iterable<Uint8Array>;
+
+export interface EncodeOptions {
+  width?: number;   // Required for raw buffer (Uint8Array/Buffer)
+  height?: number;  // Required for raw buffer (Uint8Array/Buffer)
+  pixelFormat?: RawPixelFormat; // Channel order of a raw buffer: 'rgba' (default), 'bgra', 'rgb', 'bgr' or 'gray'
+  stride?: number;  // Bytes from one row of a raw buffer to the next, defaults to width × bytes per pixel
+  quality?: number; // JPEG quality (1-100), defaults to 100
+  qualityScaling?: QualityScaling; // How quality maps to table scaling: 'libjpeg' (default), 'linear', or a curve or 100-entry table
+  thumbnail?: ThumbnailOptions; // Embed a JFXX thumbnail built from the streamed strips
+  header?: 'jfif' | 'spiff' | 'dicom'; // File header, defaults to 'jfif'; 'dicom' writes a bare DICOM baseline stream
+  progressive?: boolean | ProgressiveOptions; // Rewrite as progressive JPEG, optionally with scan callbacks
+  subsampling?: ChromaSubsampling | 'auto'; // Chroma subsampling, defaults to '4:4:4'; others re-code the chroma planes; 'auto' picks 4:4:4 or 4:2:0 from the content
+  layout?: FrameLayout; // Component IDs, sampling factors, and table slots to write; sets the subsampling
+  grayscale?: boolean; // Write only luma, as a single-component frame; color input is converted to its Y channel
+  compat?: 'libjpeg'; // Encode exactly as libjpeg would (subsampling then defaults to '4:2:0'), bypassing the engine
+  transform?: BlockTransform; // Replace the DCT of the libjpeg-compatible encoder (requires compat: 'libjpeg')
+  quantizer?: BlockQuantizer; // Replace its quantizer (requires compat: 'libjpeg')
+  quantTables?: CustomQuantTables; // Natural-order quantization tables; replaces quality scaling
+  dither?: boolean; // Diffuse DC and low-frequency rounding error between blocks to reduce banding in smooth gradients
+  deband?: boolean; // Quantize the lowest AC terms of smooth luma blocks more finely against posterized skies
+  stripQuality?: StripQuality; // Lower the quality of chosen MCU rows; `quality` is the ceiling
+  optimizeHuffman?: boolean; // Build Huffman tables from the image instead of using the standard ones
+  recoveryInterval?: number; // MCU rows between restart markers a truncated stream can be decoded up to
+  maxMcuBits?: number; // Drop the highest-frequency coefficients of any MCU whose coded data would exceed this many bits
+  preset?: EncoderPreset; // 'fast', 'balanced', 'smallest', or 'web'; options given explicitly override it
+  stripMetadata?: boolean; // Drop APPn and COM segments except the file header and ICC profile
+  alpha?: AlphaOutputOptions; // Produce the alpha channel as a companion mask from the same pass
+  onStats?(stats: EncodeStats): void; // Receives a summary of the finished encode
+}
+
+/**
+ * Summary of a finished encode
+ * - subsampling: the mode written, as chosen when the option was 'auto'
+ * - chromaDetail: fraction of 2x2 blocks with sharp color edges, when analyzed for 'auto'
+ */
+export interface EncodeStats {
+  width: number;
+  height: number;
+  quality: number;
+  subsampling: ChromaSubsampling;
+  chromaDetail?: number;
+  bytes: number;
+}
+
+/**
+ * Universal return type - actual type depends on environment
+ * - Browser: Blob
+ * - Node.js: Buffer
+ * - Fallback: Uint8Array
+ */
+export type EncodeResult = Blob | Buffer | Uint8Array;
+
+export interface SegmentedEncodeOptions extends EncodeOptions {
+  segmentHeight?: number; // Rows per restart segment (multiple of 8), defaults to 64
+  stripIndex?: boolean; // Embed an APP9 index of the segments' byte offsets for random access
+}
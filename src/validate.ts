@@ -0,0 +1,379 @@
+/**
+ * Output conformance validation
+ * Checks an encoded stream against the structural rules of ITU T.81 and reports
+ * every problem found instead of stopping at the first one.
+ */
+
+import type { FrameHeader, HuffmanSpec, Segment } from './jpeg.js';
+import {
+  JpegFormatError,
+  Marker,
+  findScanEnd,
+  isFrameMarker,
+  iterateSegments,
+  parseFrameHeader,
+  parseHuffmanTables,
+  parseQuantTables,
+  parseScanHeader,
+  readUint16,
+} from './jpeg.js';
+import { frameGeometry, walkScan } from './entropy.js';
+
+export type ValidationSeverity = 'error' | 'warning';
+
+export interface ValidationIssue {
+  severity: ValidationSeverity;
+  offset: number;        // Byte offset the issue relates to
+  message: string;
+}
+
+export interface ValidationReport {
+  valid: boolean;        // True when no errors were found (warnings allowed)
+  issues: ValidationIssue[];
+  width?: number;
+  height?: number;
+  components?: number;
+  expectedMcus?: number; // MCU count implied by the frame header, per scan
+  decodedMcus?: number;  // MCUs successfully decoded across all scans
+}
+
+/**
+ * Check a Huffman table for over-subscription, the reserved all-ones code,
+ * and symbols that cannot occur in an 8/12-bit stream
+ * Progressive AC tables may also hold the EOBn run-length symbols (size 0).
+ */
+function checkHuffmanTable(table: HuffmanSpec, precision: number, progressive = false): string[] {
+  const problems: string[] = [];
+  const name = `${table.tableClass === 0 ? 'DC' : 'AC'} table ${table.id}`;
+
+  if (table.tableClass > 1) {
+    problems.push(`${name}: invalid table class ${table.tableClass}`);
+  }
+  if (table.id > 3) {
+    problems.push(`${name}: table id must be 0-3`);
+  }
+  if (table.symbols.length > 256) {
+    problems.push(`${name}: ${table.symbols.length} symbols exceeds 256`);
+  }
+
+  let code = 0;
+  for (let length = 1; length <= 16; length++) {
+    code += table.counts[length - 1];
+    if (code > (1 << length)) {
+      problems.push(`${name}: code lengths are over-subscribed at ${length} bits`);
+      break;
+    }
+    if (table.counts[length - 1] > 0 && code === (1 << length) && table.counts.slice(length).every((c) => c === 0)) {
+      problems.push(`${name}: uses the reserved all-ones code of length ${length}`);
+    }
+    code <<= 1;
+  }
+
+  const maxCategory = precision > 8 ? 15 : 11;
+  const seen = new Set<number>();
+  for (const symbol of table.symbols) {
+    if (seen.has(symbol)) {
+      problems.push(`${name}: duplicate symbol 0x${symbol.toString(16)}`);
+    }
+    seen.add(symbol);
+    if (table.tableClass === 0 && symbol > maxCategory) {
+      problems.push(`${name}: DC category ${symbol} exceeds ${maxCategory}`);
+    }
+    if (table.tableClass === 1) {
+      const size = symbol & 0x0f;
+      const run = symbol >> 4;
+      if (size > maxCategory - 1) {
+        problems.push(`${name}: AC symbol 0x${symbol.toString(16)} has size ${size}`);
+      } else if (size === 0 && run !== 0 && run !== 15 && !progressive) {
+        problems.push(`${name}: AC symbol 0x${symbol.toString(16)} is undefined`);
+      }
+    }
+  }
+  return problems;
+}
+
+/**
+ * Validate an encoded JPEG stream
+ *
+ * Checks marker ordering, segment lengths, table definitions (including Huffman
+ * code completeness), byte stuffing, restart marker sequencing, padding bits, and
+ * that each scan holds exactly the MCUs implied by the frame dimensions.
+ *
+ * @param bytes Encoded JPEG bytes
+ * @returns Report listing every issue found; `valid` is false if any is an error
+ *
+ * @example
+ * const report = validate(jpeg as Uint8Array);
+ * if (!report.valid) {
+ *   for (const issue of report.issues) console.error(`${issue.offset}: ${issue.message}`);
+ * }
+ */
+export function validate(bytes: Uint8Array): ValidationReport {
+  const issues: ValidationIssue[] = [];
+  const error = (offset: number, message: string): void => {
+    issues.push({ severity: 'error', offset, message });
+  };
+  const warning = (offset: number, message: string): void => {
+    issues.push({ severity: 'warning', offset, message });
+  };
+  const report = (): ValidationReport => ({
+    valid: issues.every((issue) => issue.severity !== 'error'),
+    issues,
+  });
+
+  const segments: Segment[] = [];
+  try {
+    for (const segment of iterateSegments(bytes)) {
+      segments.push(segment);
+    }
+  } catch (e) {
+    if (e instanceof JpegFormatError) {
+      error(e.offset, e.message);
+    } else {
+      throw e;
+    }
+  }
+  if (segments.length === 0) {
+    return report();
+  }
+
+  let frame: FrameHeader | undefined;
+  let restartInterval = 0;
+  let sawEoi = false;
+  let expectedMcus = 0;
+  let decodedMcus = 0;
+  const dcTables = new Map<number, HuffmanSpec>();
+  const acTables = new Map<number, HuffmanSpec>();
+  const quantIds = new Set<number>();
+
+  for (const [index, segment] of segments.entries()) {
+    const { marker, offset, data } = segment;
+    const name = `0x${marker.toString(16)}`;
+
+    if (index === 0 && marker !== Marker.SOI) {
+      error(offset, 'Stream must start with SOI');
+    }
+    if (index > 0 && marker === Marker.SOI) {
+      error(offset, 'Unexpected SOI after start of stream');
+    }
+    if (marker >= Marker.RST0 && marker <= Marker.RST7) {
+      error(offset, `Restart marker RST${marker - Marker.RST0} outside entropy-coded data`);
+      continue;
+    }
+    if (marker === Marker.EOI) {
+      sawEoi = true;
+      if (offset + 2 < bytes.length) {
+        warning(offset + 2, `${bytes.length - offset - 2} bytes of trailing data after EOI`);
+      }
+      continue;
+    }
+
+    try {
+      if (isFrameMarker(marker)) {
+        if (frame) {
+          error(offset, 'Multiple frame headers');
+          continue;
+        }
+        frame = parseFrameHeader(marker, data);
+        if (data.length !== 6 + frame.components.length * 3) {
+          error(offset, `SOF length ${data.length + 2} does not match ${frame.components.length} components`);
+        }
+        if (marker !== Marker.SOF0 && marker !== Marker.SOF1 && marker !== Marker.SOF2) {
+          warning(offset, `Frame type ${name} is not baseline, extended or progressive Huffman`);
+        }
+        if (marker === Marker.SOF0 && frame.precision !== 8) {
+          error(offset, `Baseline frames require 8-bit precision, found ${frame.precision}`);
+        }
+        if (frame.width === 0) {
+          error(offset, 'Frame width is zero');
+        }
+        if (frame.height === 0) {
+          warning(offset, 'Frame height is zero (DNL marker is not supported)');
+        }
+        if (frame.components.length < 1 || frame.components.length > 4) {
+          error(offset, `Frame has ${frame.components.length} components (expected 1-4)`);
+        }
+        const ids = new Set<number>();
+        for (const component of frame.components) {
+          if (ids.has(component.id)) {
+            error(offset, `Duplicate component id ${component.id}`);
+          }
+          ids.add(component.id);
+          if (component.h < 1 || component.h > 4 || component.v < 1 || component.v > 4) {
+            error(offset, `Component ${component.id} has invalid sampling factors ${component.h}x${component.v}`);
+          }
+          if (component.quantTable > 3) {
+            error(offset, `Component ${component.id} references quantization table ${component.quantTable}`);
+          }
+        }
+        const sampling = frame.components.reduce((sum, c) => sum + c.h * c.v, 0);
+        if (frame.components.length > 1 && sampling > 10) {
+          error(offset, `MCU would contain ${sampling} blocks (limit is 10)`);
+        }
+      } else if (marker === Marker.DQT) {
+        for (const table of parseQuantTables(data)) {
+          if (table.id > 3) {
+            error(offset, `Quantization table id ${table.id} must be 0-3`);
+          }
+          if (table.precision > 1) {
+            error(offset, `Quantization table ${table.id} has invalid precision ${table.precision}`);
+          }
+          if (table.precision === 1 && frame?.marker === Marker.SOF0) {
+            error(offset, `16-bit quantization table ${table.id} is not allowed in a baseline frame`);
+          }
+          if (table.values.some((value) => value === 0)) {
+            error(offset, `Quantization table ${table.id} contains a zero entry`);
+          }
+          quantIds.add(table.id);
+        }
+      } else if (marker === Marker.DHT) {
+        for (const table of parseHuffmanTables(data)) {
+          for (const problem of checkHuffmanTable(table, frame?.precision ?? 8, frame?.marker === Marker.SOF2)) {
+            error(offset, problem);
+          }
+          if (table.id > 1 && frame?.marker === Marker.SOF0) {
+            error(offset, `Baseline frames allow Huffman table ids 0-1, found ${table.id}`);
+          }
+          (table.tableClass === 0 ? dcTables : acTables).set(table.id, table);
+        }
+      } else if (marker === Marker.DRI) {
+        if (data.length !== 2) {
+          error(offset, `DRI length ${data.length + 2} should be 4`);
+        } else {
+          restartInterval = readUint16(data, 0);
+        }
+      } else if (marker === Marker.SOS) {
+        if (!frame) {
+          error(offset, 'Scan found before frame header');
+          continue;
+        }
+        const header = parseScanHeader(data);
+        if (data.length !== 4 + header.components.length * 2) {
+          error(offset, `SOS length ${data.length + 2} does not match ${header.components.length} components`);
+        }
+        if (header.components.length < 1 || header.components.length > 4) {
+          error(offset, `Scan has ${header.components.length} components (expected 1-4)`);
+        }
+        // Progressive DC scans need no AC table, and AC or DC refinement scans no DC table
+        const needsDc = header.spectralStart === 0 && (frame.marker !== Marker.SOF2 || header.approxHigh === 0);
+        const needsAc = header.spectralEnd > 0;
+        let tablesReady = true;
+        for (const sc of header.components) {
+          const component = frame.components.find((c) => c.id === sc.id);
+          if (!component) {
+            error(offset, `Scan references unknown component ${sc.id}`);
+            tablesReady = false;
+            continue;
+          }
+          if (!quantIds.has(component.quantTable)) {
+            error(offset, `Quantization table ${component.quantTable} for component ${sc.id} is not defined`);
+          }
+          if ((needsDc && !dcTables.has(sc.dcTable)) || (needsAc && !acTables.has(sc.acTable))) {
+            error(offset, `Huffman tables for component ${sc.id} are not defined`);
+            tablesReady = false;
+          }
+        }
+
+        const dataOffset = offset + segment.length;
+        const dataEnd = findScanEnd(bytes, dataOffset);
+        for (let pos = dataOffset; pos < dataEnd - 1; pos++) {
+          if (bytes[pos] !== 0xff) {
+            continue;
+          }
+          const next = bytes[pos + 1];
+          if (next >= Marker.RST0 && next <= Marker.RST7 && restartInterval === 0) {
+            error(pos, `Restart marker RST${next - Marker.RST0} without a restart interval`);
+          } else if (next === 0xff) {
+            warning(pos, 'Fill byte inside entropy-coded data');
+          }
+          pos++;
+        }
+
+        const sequential = header.spectralStart === 0 && header.spectralEnd === 63 &&
+          header.approxHigh === 0 && header.approxLow === 0;
+        if (frame.marker !== Marker.SOF2 && !sequential) {
+          error(offset, 'Sequential frames require Ss=0, Se=63, Ah=Al=0');
+        }
+        if (!sequential) {
+          warning(offset, 'Progressive scan data is not checked');
+          continue;
+        }
+        if (!tablesReady) {
+          continue;
+        }
+
+        const geometry = frameGeometry(frame);
+        const scanFile = { bytes, segments, frame, scans: [] };
+        const scan = {
+          header,
+          dataOffset,
+          dataEnd,
+          restartInterval,
+          dcTables: new Map(dcTables),
+          acTables: new Map(acTables),
+          quantTables: new Map(),
+        };
+        const single = header.components.length === 1;
+        if (single) {
+          const index = frame.components.findIndex((c) => c.id === header.components[0].id);
+          const component = geometry.components[index];
+          expectedMcus += component.blocksPerLine * component.blocksPerColumn;
+        } else {
+          expectedMcus += geometry.mcusPerLine * geometry.mcusPerColumn;
+        }
+
+        try {
+          const result = walkScan(scanFile, scan);
+          if (result.paddedBytes > 0) {
+            error(dataEnd, `Scan data ends ${result.paddedBytes} byte(s) before all ${result.mcuCount} MCUs were decoded`);
+          } else {
+            decodedMcus += result.mcuCount;
+          }
+          if (result.extraneousBytes > 0) {
+            warning(dataEnd, `${result.extraneousBytes} byte(s) of extraneous data after the last MCU of an interval`);
+          }
+          if (result.invalidPadding > 0) {
+            warning(dataEnd, `${result.invalidPadding} interval(s) not padded with 1-bits`);
+          }
+        } catch (e) {
+          if (e instanceof JpegFormatError) {
+            error(e.offset, e.message);
+          } else {
+            throw e;
+          }
+        }
+      } else if (marker >= Marker.APP0 && marker <= Marker.APP15) {
+        if (frame && marker === Marker.APP0) {
+          warning(offset, 'APP0 segment after frame header');
+        }
+      } else if (marker !== Marker.SOI && marker !== Marker.COM && marker !== 0xcc) {
+        warning(offset, `Unknown marker ${name}`);
+      }
+    } catch (e) {
+      if (e instanceof JpegFormatError) {
+        error(e.offset, e.message);
+      } else {
+        error(offset, `Malformed segment ${name}: ${(e as Error).message}`);
+      }
+    }
+  }
+
+  if (!frame) {
+    error(bytes.length, 'No frame header found');
+  }
+  if (!segments.some((segment) => segment.marker === Marker.SOS)) {
+    error(bytes.length, 'No scan found');
+  }
+  if (!sawEoi) {
+    error(bytes.length, 'Stream does not end with EOI');
+  }
+
+  return {
+    ...report(),
+    width: frame?.width,
+    height: frame?.height,
+    components: frame?.components.length,
+    expectedMcus,
+    decodedMcus,
+  };
+}
@@ -0,0 +1,63 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, mergeAlpha, validate } from '../../src/index.js';
+import type { AlphaMask } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+
+// Gradient with a feathered transparent hole in the middle
+function cutout(width: number, height: number): Uint8Array {
+  const buffer = new Uint8Array(width * height * 4);
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const distance = Math.hypot(x - width / 2, y - height / 2);
+      const alpha = Math.round(Math.max(0, Math.min(1, (distance - 12) / 4)) * 255);
+      buffer.set([x * 3, y * 3, 128, alpha], (y * width + x) * 4);
+    }
+  }
+  return buffer;
+}
+
+describe('companion alpha masks', () => {
+  it('should produce a grayscale JPEG mask alongside the image', async () => {
+    const buffer = cutout(64, 48);
+    let mask: AlphaMask | undefined;
+
+    const jpeg = await encode(buffer, { width: 64, height: 48, quality: 85, alpha: { onAlpha: (m) => { mask = m; } } }) as Buffer;
+
+    assert.ok(mask);
+    assert.strictEqual(mask.format, 'jpeg');
+    assert.strictEqual(validate(mask.data).valid, true);
+    assert.strictEqual(parseJpeg(mask.data).frame.components.length, 1);
+    const { data, info } = await sharp(mask.data).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [64, 48]);
+
+    const merged = mergeAlpha(new Uint8Array(jpeg), mask);
+    for (let i = 0; i < 64 * 48; i++) {
+      assert.ok(Math.abs(merged.data[i * 4 + 3] - buffer[i * 4 + 3]) <= 16, `pixel ${i}`);
+      assert.ok(Math.abs(data[i * info.channels] - buffer[i * 4 + 3]) <= 16, `mask pixel ${i}`);
+    }
+  });
+
+  it('should restore a run-length mask exactly', async () => {
+    const buffer = cutout(40, 40);
+    let mask: AlphaMask | undefined;
+
+    const jpeg = await encode(buffer, { width: 40, height: 40, subsampling: '4:2:0', alpha: { format: 'rle', onAlpha: (m) => { mask = m; } } }) as Buffer;
+
+    assert.ok(mask && mask.data.length < 40 * 40);
+    const merged = mergeAlpha(new Uint8Array(jpeg), mask);
+    for (let i = 0; i < 40 * 40; i++) {
+      assert.strictEqual(merged.data[i * 4 + 3], buffer[i * 4 + 3]);
+    }
+  });
+
+  it('should leave the image unchanged', async () => {
+    const buffer = cutout(32, 32);
+
+    const plain = await encode(buffer, { width: 32, height: 32, quality: 80 }) as Buffer;
+    const withAlpha = await encode(buffer, { width: 32, height: 32, quality: 80, alpha: { format: 'raw', onAlpha: () => {} } }) as Buffer;
+
+    assert.deepStrictEqual(withAlpha, plain);
+  });
+});
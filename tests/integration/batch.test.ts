@@ -0,0 +1,35 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { BatchEncoder } from '../../src/index.js';
+import type { BatchResult } from '../../src/index.js';
+
+function solid(width: number, height: number, value: number): Uint8Array {
+  return new Uint8Array(width * height * 4).fill(value);
+}
+
+describe('BatchEncoder', () => {
+  it('should encode every item and isolate failures', async () => {
+    const finished: BatchResult[] = [];
+    const batch = new BatchEncoder({ concurrency: 2, defaults: { quality: 70 }, onResult: (result) => finished.push(result) });
+
+    const results = await batch.encode([
+      { source: solid(8, 8, 200), options: { width: 8, height: 8 } },
+      { source: solid(4, 4, 10), options: { width: 5, height: 5 } },
+      { source: solid(16, 8, 90), options: { width: 16, height: 8 } },
+    ]);
+
+    assert.deepStrictEqual(results.map((r) => [r.index, r.ok]), [[0, true], [1, false], [2, true]]);
+    assert.strictEqual(finished.length, 3);
+    const failed = results[1];
+    assert.ok(!failed.ok && /too small/.test(failed.error.message), 'Size mismatch should be reported on its own item');
+    const third = results[2];
+    assert.ok(third.ok);
+    const metadata = await sharp(third.jpeg).metadata();
+    assert.deepStrictEqual([metadata.width, metadata.height], [16, 8]);
+  });
+
+  it('should reject a non-positive concurrency', () => {
+    assert.throws(() => new BatchEncoder({ concurrency: 0 }), /positive integer/);
+  });
+});
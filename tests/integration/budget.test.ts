@@ -0,0 +1,43 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// Seeded noise; the generator also steps over each alpha byte
+function noisy(): () => number[] {
+  let seed = 3;
+  return () => [0, 1, 2, 3].map(() => (seed = (seed * 1103515245 + 12345) >>> 0) >>> 24).slice(0, 3);
+}
+
+describe('Per-MCU bit budget', () => {
+  const width = 64;
+  const height = 48;
+  const pixels = rgbaPattern(width, height, noisy());
+
+  it('should bound the scan size and stay decodable', async () => {
+    const maxMcuBits = 256;
+    const full = await encode(pixels, { width, height, quality: 90 });
+    const limited = await encode(pixels, { width, height, quality: 90, maxMcuBits, subsampling: '4:2:0' });
+
+    const scan = parseJpeg(limited).scans[0];
+    const mcus = Math.ceil(width / 16) * Math.ceil(height / 16);
+    assert.ok(limited.length < full.length);
+    // Bits per MCU, plus at most one stuffed byte per coded byte
+    assert.ok(scan.dataEnd - scan.dataOffset <= 2 * Math.ceil((mcus * maxMcuBits) / 8));
+    const { info } = await sharp(limited).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+  });
+
+  it('should replace a preset\'s progressive and Huffman settings', async () => {
+    const jpeg = await encode(pixels, { width, height, quality: 80, preset: 'web', maxMcuBits: 512 });
+    assert.strictEqual(parseJpeg(jpeg).scans.length, 1);
+  });
+
+  it('should reject options that would re-code the scan afterwards', async () => {
+    await assert.rejects(encode(pixels, { width, height, maxMcuBits: 512, progressive: true }), /progressive scans are not available/);
+    await assert.rejects(encode(pixels, { width, height, maxMcuBits: 512, optimizeHuffman: true }), /optimized tables/);
+    await assert.rejects(encode(pixels, { width, height, maxMcuBits: 512, compat: 'libjpeg' }), /libjpeg-compatible/);
+  });
+});
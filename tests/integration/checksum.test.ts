@@ -0,0 +1,22 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { createHash } from 'node:crypto';
+import { ChecksumSink, CollectingSink, encodeToSink, toBase64 } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('Streaming checksums', () => {
+  it('should hash an encode to the same digest as hashing the finished file', async () => {
+    const width = 96;
+    const height = 80;
+    const pixels = rgbaPattern(width, height, (x, y) => [(y * width + x) % 251, ((y * width + x) * 7) % 256, 90]);
+    const inner = new CollectingSink();
+    const sink = new ChecksumSink(inner, 'sha256');
+
+    const written = await encodeToSink(pixels, sink, { width, height, quality: 80 });
+
+    const jpeg = inner.toBytes();
+    assert.strictEqual(written, jpeg.length);
+    assert.strictEqual(sink.length, jpeg.length);
+    assert.strictEqual(toBase64(sink.digest()), createHash('sha256').update(jpeg).digest('base64'));
+  });
+});
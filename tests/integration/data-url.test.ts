@@ -0,0 +1,17 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { encode, encodeToDataUrl } from '../../src/index.js';
+
+describe('encodeToDataUrl', () => {
+  it('should hold the same JPEG that encode produces', async () => {
+    const width = 40;
+    const height = 24;
+    const pixels = Uint8Array.from({ length: width * height * 4 }, (_, i) => ((i & 3) === 3 ? 255 : (i * 7) & 0xff));
+
+    const url = await encodeToDataUrl(pixels, { width, height, quality: 70 });
+    const jpeg = await encode(pixels, { width, height, quality: 70 });
+
+    assert.ok(url.startsWith('data:image/jpeg;base64,'));
+    assert.deepStrictEqual(Buffer.from(url.slice(url.indexOf(',') + 1), 'base64'), Buffer.from(jpeg));
+  });
+});
@@ -0,0 +1,33 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encodeDeepZoom } from '../../src/index.js';
+import type { DeepZoomTile } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('encodeDeepZoom', () => {
+  it('should cut overlapping tiles at every level', async () => {
+    const width = 300;
+    const height = 200;
+    const buffer = rgbaPattern(width, height, (x, y) => [(x * 255) / width, (y * 255) / height, 80]);
+    const tiles: DeepZoomTile[] = [];
+
+    const result = await encodeDeepZoom(buffer, { width, height, tileSize: 128, overlap: 1, quality: 90, onTile: (tile) => { tiles.push(tile); } });
+
+    assert.strictEqual(result.maxLevel, 9);
+    assert.strictEqual(result.tiles.length, 0);
+    assert.match(result.manifest, /Overlap="1" TileSize="128"/);
+    assert.match(result.manifest, /<Size Width="300" Height="200"\/>/);
+    const byPath = new Map(tiles.map((tile) => [tile.path, tile]));
+    assert.deepStrictEqual(['9/0_0.jpg', '9/1_0.jpg', '9/2_1.jpg', '8/1_0.jpg', '0/0_0.jpg'].map((p) => {
+      const tile = byPath.get(p);
+      return tile && [tile.width, tile.height];
+    }), [[129, 129], [130, 129], [45, 73], [23, 100], [1, 1]]);
+
+    const middle = byPath.get('9/1_1.jpg')!;
+    const { data, info } = await sharp(middle.jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [130, 73]);
+    assert.ok(Math.abs(data[0] - (127 * 255) / 300) < 6, 'Tile should start one overlap pixel left of its column');
+    assert.ok(Math.abs(data[1] - (127 * 255) / 200) < 6, 'Tile should start one overlap pixel above its row');
+  });
+});
@@ -0,0 +1,78 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, libjpegQuantTables, naturalToZigzag, validate } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// Slow diagonal sky gradient, a few levels over the whole image
+const sky = (x: number, y: number, width: number, height: number): number[] => {
+  const level = 90 + (20 * (x + y)) / (width + height);
+  return [level, level + 30, level + 90];
+};
+
+// Mean error of the image averaged over 16x16 areas, which is what banding shifts
+async function areaError(jpeg: Buffer, buffer: Uint8Array, width: number, height: number): Promise<number> {
+  const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+  let total = 0;
+  let areas = 0;
+  for (let top = 0; top < height; top += 16) {
+    for (let left = 0; left < width; left += 16) {
+      let difference = 0;
+      for (let y = top; y < top + 16; y++) {
+        for (let x = left; x < left + 16; x++) {
+          difference += data[(y * width + x) * info.channels] - buffer[(y * width + x) * 4];
+        }
+      }
+      total += Math.abs(difference / 256);
+      areas++;
+    }
+  }
+  return total / areas;
+}
+
+describe('dither option', () => {
+  it('should follow a smooth gradient more closely at low quality', async () => {
+    const buffer = rgbaPattern(128, 128, sky);
+
+    const plain = await encode(buffer, { width: 128, height: 128, quality: 15 }) as Buffer;
+    const dithered = await encode(buffer, { width: 128, height: 128, quality: 15, dither: true }) as Buffer;
+
+    assert.strictEqual(validate(dithered).valid, true);
+    assert.deepStrictEqual(parseJpeg(dithered).scans[0].quantTables.get(0)!.values, naturalToZigzag(libjpegQuantTables(15)[0]));
+    const plainError = await areaError(plain, buffer, 128, 128);
+    const ditheredError = await areaError(dithered, buffer, 128, 128);
+    assert.ok(ditheredError < plainError, `${ditheredError} should be below ${plainError}`);
+  });
+
+  it('should reject libjpeg compatibility', async () => {
+    await assert.rejects(
+      () => encode(rgbaPattern(16, 16, sky), { width: 16, height: 16, compat: 'libjpeg', dither: true }),
+      /cannot be dithered/,
+    );
+  });
+});
+
+describe('deband option', () => {
+  it('should reproduce the slope of a sky within blocks at low quality', async () => {
+    const buffer = rgbaPattern(128, 128, sky);
+    const pixelError = async (jpeg: Buffer): Promise<number> => {
+      const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      let total = 0;
+      for (let i = 0; i < 128 * 128; i++) {
+        total += Math.abs(data[i * info.channels] - buffer[i * 4]);
+      }
+      return total / (128 * 128);
+    };
+
+    const plain = await encode(buffer, { width: 128, height: 128, quality: 40 }) as Buffer;
+    const debanded = await encode(buffer, { width: 128, height: 128, quality: 40, deband: true }) as Buffer;
+
+    assert.strictEqual(validate(debanded).valid, true);
+    const table = parseJpeg(debanded).scans[0].quantTables.get(0)!.values;
+    assert.ok(table[1] < naturalToZigzag(libjpegQuantTables(40)[0])[1]);
+    const plainError = await pixelError(plain);
+    const debandedError = await pixelError(debanded);
+    assert.ok(debandedError < plainError, `${debandedError} should be below ${plainError}`);
+  });
+});
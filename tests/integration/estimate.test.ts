@@ -0,0 +1,62 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { encode, estimateSize } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+/**
+ * Image with smooth regions, hard edges, and noisy texture that changes down the frame
+ */
+function scene(): (x: number, y: number, width: number, height: number) => number[] {
+  let seed = 7;
+  return (x, y, _width, height) => {
+    const texture = 10 + (40 * y) / height;
+    seed = (seed * 1103515245 + 12345) >>> 0;
+    const noise = ((seed >>> 24) / 255 - 0.5) * texture;
+    const edge = (Math.floor(x / 24) + Math.floor(y / 40)) % 2 ? 60 : 0;
+    return [
+      Math.max(0, Math.min(255, 60 + x / 2 + edge + noise)),
+      Math.max(0, Math.min(255, 90 + y / 8 + noise)),
+      Math.max(0, Math.min(255, 150 - edge / 2 + noise / 2)),
+    ];
+  };
+}
+
+describe('estimateSize', () => {
+  const width = 160;
+  const height = 960;
+  const pixels = rgbaPattern(width, height, scene());
+
+  for (const options of [
+    { quality: 50 },
+    { quality: 90 },
+    { quality: 80, subsampling: '4:2:0' as const },
+    { quality: 85, optimizeHuffman: true },
+  ]) {
+    it(`should predict the encoded size within 10% for ${JSON.stringify(options)}`, async () => {
+      const estimate = await estimateSize(pixels, { width, height, ...options });
+      const actual = (await encode(pixels, { width, height, ...options })).length;
+
+      assert.ok(estimate.sampledRows < height / 2, `sampled ${estimate.sampledRows} rows`);
+      assert.ok(Math.abs(estimate.bytes - actual) / actual < 0.1, `estimated ${estimate.bytes}, encoded ${actual}`);
+    });
+  }
+
+  it('should model every row of a short image', async () => {
+    const short = rgbaPattern(64, 40, scene());
+    const estimate = await estimateSize(short, { width: 64, height: 40, quality: 75 });
+    const actual = (await encode(short, { width: 64, height: 40, quality: 75 })).length;
+
+    assert.strictEqual(estimate.sampledRows, 40);
+    assert.ok(Math.abs(estimate.bytes - actual) / actual < 0.1, `estimated ${estimate.bytes}, encoded ${actual}`);
+  });
+
+  it('should grow as quality rises', async () => {
+    const low = await estimateSize(pixels, { width, height, quality: 30 });
+    const high = await estimateSize(pixels, { width, height, quality: 95 });
+    assert.ok(high.bytes > low.bytes * 1.5);
+  });
+
+  it('should reject a bad sample count', async () => {
+    await assert.rejects(estimateSize(pixels, { width, height, sampleBands: 0 }), /positive integer/);
+  });
+});
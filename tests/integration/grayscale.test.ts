@@ -0,0 +1,53 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, encodeSegmented, validate } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const WIDTH = 48;
+const HEIGHT = 40;
+
+// A scanned page: dark strokes on an off-white background, tinted slightly
+const page = (): Uint8Array => rgbaPattern(WIDTH, HEIGHT, (x, y) => ((x * 7 + y * 3) % 11 < 3 ? [30, 32, 40] : [235, 230, 220]));
+
+describe('Grayscale output', () => {
+  it('should write a single-component frame other decoders read', async () => {
+    const jpeg = await encode(page(), { width: WIDTH, height: HEIGHT, quality: 85, grayscale: true }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    const file = parseJpeg(jpeg);
+    assert.deepStrictEqual(file.frame.components.map((c) => [c.h, c.v]), [[1, 1]]);
+    assert.strictEqual(file.scans[0].header.components.length, 1);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [WIDTH, HEIGHT]);
+    // Off-white has luma 0.299 * 235 + 0.587 * 230 + 0.114 * 220 = 230
+    const background = (0 * WIDTH + 4) * info.channels;
+    assert.ok(Math.abs(data[background] - 230) < 12, `luma ${data[background]} should be near 230`);
+  });
+
+  it('should be smaller than color output at the same quality', async () => {
+    const rgba = page();
+    const color = await encode(rgba, { width: WIDTH, height: HEIGHT, quality: 85 }) as Buffer;
+    const gray = await encode(rgba, { width: WIDTH, height: HEIGHT, quality: 85, grayscale: true }) as Buffer;
+
+    assert.ok(gray.length < color.length, `${gray.length} should be below ${color.length}`);
+  });
+
+  it('should combine with libjpeg compatibility, progressive output, and presets', async () => {
+    const rgba = page();
+    const libjpeg = await encode(rgba, { width: WIDTH, height: HEIGHT, quality: 85, grayscale: true, compat: 'libjpeg' }) as Buffer;
+    const progressive = await encode(rgba, { width: WIDTH, height: HEIGHT, quality: 85, grayscale: true, progressive: true }) as Buffer;
+    const preset = await encode(rgba, { width: WIDTH, height: HEIGHT, grayscale: true, preset: 'smallest' }) as Buffer;
+
+    for (const jpeg of [libjpeg, progressive, preset]) {
+      assert.strictEqual(parseJpeg(jpeg).frame.components.length, 1);
+    }
+  });
+
+  it('should reject chroma settings and restart segments', async () => {
+    const rgba = page();
+    await assert.rejects(encode(rgba, { width: WIDTH, height: HEIGHT, grayscale: true, subsampling: '4:2:0' }), /single luma component/);
+    await assert.rejects(encodeSegmented(rgba, { width: WIDTH, height: HEIGHT, grayscale: true }), /restart segments cannot be kept/);
+  });
+});
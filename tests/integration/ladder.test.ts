@@ -0,0 +1,50 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, encodeQualityLadder, libjpegQuantTables, naturalToZigzag, validate } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const scene = (x: number, y: number): number[] => [128 + 100 * Math.sin(x / 9) * Math.cos(y / 13), 128 + 90 * Math.sin((x + y) / 17), (x * y / 50) & 0xff];
+
+describe('encodeQualityLadder', () => {
+  it('should return one file per quality with that quality\'s tables', async () => {
+    const buffer = rgbaPattern(80, 64, scene);
+
+    const rungs = await encodeQualityLadder(buffer, { width: 80, height: 64, qualities: [90, 30, 60], subsampling: '4:2:0' });
+
+    assert.deepStrictEqual(rungs.map((r) => r.quality), [90, 30, 60]);
+    for (const { quality, jpeg } of rungs) {
+      assert.strictEqual(validate(jpeg).valid, true);
+      const file = parseJpeg(jpeg);
+      assert.deepStrictEqual(file.frame.components.map((c) => [c.h, c.v]), [[2, 2], [1, 1], [1, 1]]);
+      assert.deepStrictEqual(file.scans[0].quantTables.get(0)!.values, naturalToZigzag(libjpegQuantTables(quality)[0]));
+      const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [80, 64]);
+    }
+    assert.ok(rungs[1].jpeg.length < rungs[2].jpeg.length && rungs[2].jpeg.length < rungs[0].jpeg.length);
+  });
+
+  it('should come within a few percent of direct encodes with optimized tables', async () => {
+    const buffer = rgbaPattern(96, 96, scene);
+
+    const [rung] = await encodeQualityLadder(buffer, { width: 96, height: 96, qualities: [75] });
+    const direct = await encode(buffer, { width: 96, height: 96, quality: 75, optimizeHuffman: true }) as Buffer;
+
+    assert.ok(Math.abs(rung.jpeg.length - direct.length) < direct.length * 0.05, `${rung.jpeg.length} vs ${direct.length}`);
+    const [a, b] = await Promise.all([rung.jpeg, direct].map((jpeg) => sharp(jpeg).raw().toBuffer()));
+    let error = 0;
+    for (let i = 0; i < a.length; i++) {
+      error = Math.max(error, Math.abs(a[i] - b[i]));
+    }
+    assert.ok(error < 8, `Largest difference ${error}`);
+  });
+
+  it('should apply presets to every rung and reject invalid qualities', async () => {
+    const rungs = await encodeQualityLadder(rgbaPattern(32, 32, scene), { width: 32, height: 32, qualities: [50, 80], preset: 'web' });
+
+    assert.ok(rungs.every(({ jpeg }) => parseJpeg(jpeg).frame.marker === 0xc2));
+    await assert.rejects(encodeQualityLadder(rgbaPattern(8, 8, scene), { width: 8, height: 8, qualities: [] }), /At least one quality/);
+    await assert.rejects(encodeQualityLadder(rgbaPattern(8, 8, scene), { width: 8, height: 8, qualities: [0] }), /integer 1-100, got 0/);
+  });
+});
@@ -0,0 +1,43 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, validate } from '../../src/index.js';
+import type { EncodeStats } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const scene = (x: number, y: number): number[] => [x * 4, y * 4, 255 - x * 2];
+
+describe('layout option', () => {
+  it('should write a Y-only frame with a nonstandard component id', async () => {
+    const jpeg = await encode(rgbaPattern(40, 30, scene), { width: 40, height: 30, quality: 90, layout: { components: [{ id: 0x59 }] } }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    assert.deepStrictEqual(parseJpeg(jpeg).frame.components.map((c) => c.id), [0x59]);
+    const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [40, 30]);
+  });
+
+  it('should take subsampling from the layout over a preset, and report it', async () => {
+    let stats: EncodeStats | undefined;
+
+    const jpeg = await encode(rgbaPattern(48, 32, scene), {
+      width: 48, height: 32, preset: 'smallest',
+      layout: { components: [{ id: 1, h: 2 }, { id: 2 }, { id: 3 }] },
+      onStats: (s) => { stats = s; },
+    }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    const file = parseJpeg(jpeg);
+    assert.strictEqual(file.scans.length > 1, true, 'the preset still makes it progressive');
+    assert.deepStrictEqual(file.frame.components.map((c) => [c.h, c.v]), [[2, 1], [1, 1], [1, 1]]);
+    assert.strictEqual(stats?.subsampling, '4:2:2');
+  });
+
+  it('should reject an explicit subsampling alongside a layout', async () => {
+    await assert.rejects(
+      () => encode(rgbaPattern(8, 8, scene), { width: 8, height: 8, subsampling: '4:2:0', layout: { components: [{ id: 1 }] } }),
+      /leave subsampling unset/,
+    );
+  });
+});
@@ -0,0 +1,45 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encodeMjpeg, gifToMjpeg, validate } from '../../src/index.js';
+import { gifFile } from '../utils/gif-fixtures.js';
+
+const PALETTE = [0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255];
+
+describe('MJPEG conversion', () => {
+  it('should encode each composited GIF frame as a JPEG', async () => {
+    const gif = gifFile(16, 16, PALETTE, [
+      { indices: new Array(256).fill(1), width: 16, height: 16, delay: 10 },
+      { indices: new Array(64).fill(3), left: 8, top: 8, width: 8, height: 8, delay: 20 },
+    ]);
+
+    const frames = [];
+    for await (const frame of gifToMjpeg(gif, { quality: 90 })) {
+      frames.push(frame);
+    }
+
+    assert.deepStrictEqual(frames.map((f) => [f.index, f.delay]), [[0, 100], [1, 200]]);
+    for (const { jpeg } of frames) {
+      assert.strictEqual(validate(jpeg).valid, true);
+    }
+
+    const { data, info } = await sharp(frames[1].jpeg).raw().toBuffer({ resolveWithObject: true });
+    const pixel = (x: number, y: number) => Array.from(data.subarray((y * info.width + x) * 3, (y * info.width + x) * 3 + 3));
+    assert.ok(pixel(2, 2)[0] > 200 && pixel(2, 2)[2] < 50, 'First frame should remain outside the update');
+    assert.ok(pixel(12, 12)[2] > 200 && pixel(12, 12)[0] < 50, 'Second frame should be drawn in place');
+  });
+
+  it('should concatenate frames into a raw MJPEG stream', async () => {
+    const data = new Uint8Array(8 * 8 * 4).fill(200);
+    const frame = { data, left: 0, top: 0, width: 8, height: 8, delay: 40, disposal: 'none' as const };
+    const stream = await encodeMjpeg([frame, frame, frame], { width: 8, height: 8 });
+
+    let starts = 0;
+    for (let i = 0; i + 2 < stream.length; i++) {
+      if (stream[i] === 0xff && stream[i + 1] === 0xd8 && stream[i + 2] === 0xff) {
+        starts++;
+      }
+    }
+    assert.strictEqual(starts, 3);
+  });
+});
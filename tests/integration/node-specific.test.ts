@@ -197,6 +197,42 @@ describe('Node.js-specific Integration Tests (no browser APIs)', () => {
     });
   });
 
+  describe('Iterable input', () => {
+    const width = 24;
+    const height = 20;
+    const buffer = new Uint8Array(width * height * 4);
+    for (let i = 0; i < buffer.length; i += 4) {
+      buffer.set([(i / 4) % 256, 90, 200, 255], i);
+    }
+
+    it('should encode an async iterable of chunks like the whole buffer', async () => {
+      let pulled = 0;
+      async function* chunks(): AsyncGenerator<Uint8Array> {
+        for (let offset = 0; offset < buffer.length; offset += 700) {
+          pulled++;
+          yield buffer.slice(offset, offset + 700);
+        }
+      }
+
+      const result = await encode(chunks(), { width, height, quality: 90 });
+
+      assert.deepStrictEqual(result, await encode(buffer, { width, height, quality: 90 }));
+      assert.strictEqual(pulled, Math.ceil(buffer.length / 700));
+    });
+
+    it('should encode a sync iterable, and an image file given without dimensions', async () => {
+      const rows = Array.from({ length: height }, (_, y) => buffer.subarray(y * width * 4, (y + 1) * width * 4));
+      const fromRows = await encode(rows, { width, height });
+      const { info } = await sharp(fromRows).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [width, height]);
+
+      const header = new TextEncoder().encode(`P6\n${width} ${height}\n255\n`);
+      const rgb = buffer.filter((_, i) => i % 4 !== 3);
+      const fromPnm = await encode([header, rgb]);
+      assert.strictEqual((await sharp(fromPnm).metadata()).width, width);
+    });
+  });
+
   describe('Error handling', () => {
     it('should require width and height for raw buffer', async () => {
       const buffer = Buffer.alloc(100);
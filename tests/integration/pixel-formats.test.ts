@@ -0,0 +1,65 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { encode, encodeSegmented } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const WIDTH = 21;
+const HEIGHT = 13;
+
+const pattern = (x: number, y: number, width: number): number[] => {
+  const i = y * width + x;
+  return [i * 7, i * 3, i * 11];
+};
+
+// Repack RGBA rows into another channel order with `padding` bytes after each row
+function repack(data: Uint8Array, order: number[], padding: number): Uint8Array {
+  const stride = WIDTH * order.length + padding;
+  const out = new Uint8Array(stride * HEIGHT).fill(0x5a);
+  for (let y = 0; y < HEIGHT; y++) {
+    for (let x = 0; x < WIDTH; x++) {
+      order.forEach((channel, i) => {
+        out[y * stride + x * order.length + i] = data[(y * WIDTH + x) * 4 + channel];
+      });
+    }
+  }
+  return out;
+}
+
+describe('Pixel formats and strides', () => {
+  const pixels = rgbaPattern(WIDTH, HEIGHT, pattern);
+
+  it('should encode BGRA, RGB and BGR buffers as the same image as RGBA', async () => {
+    const expected = await encode(pixels, { width: WIDTH, height: HEIGHT, quality: 80 }) as Buffer;
+
+    const bgra = await encode(repack(pixels, [2, 1, 0, 3], 0), { width: WIDTH, height: HEIGHT, quality: 80, pixelFormat: 'bgra' }) as Buffer;
+    const rgb = await encode(repack(pixels, [0, 1, 2], 0), { width: WIDTH, height: HEIGHT, quality: 80, pixelFormat: 'rgb' }) as Buffer;
+    const bgr = await encode(repack(pixels, [2, 1, 0], 0), { width: WIDTH, height: HEIGHT, quality: 80, pixelFormat: 'bgr' }) as Buffer;
+
+    for (const jpeg of [bgra, rgb, bgr]) {
+      assert.deepStrictEqual(Buffer.from(jpeg), Buffer.from(expected));
+    }
+  });
+
+  it('should skip the padding of rows with a wider stride', async () => {
+    const expected = await encode(pixels, { width: WIDTH, height: HEIGHT, quality: 80 }) as Buffer;
+    const padded = repack(pixels, [0, 1, 2, 3], 12);
+
+    const jpeg = await encode(padded, { width: WIDTH, height: HEIGHT, quality: 80, stride: WIDTH * 4 + 12 }) as Buffer;
+    const segmented = await encodeSegmented(repack(pixels, [2, 1, 0], 5), {
+      width: WIDTH, height: HEIGHT, quality: 80, pixelFormat: 'bgr', stride: WIDTH * 3 + 5, segmentHeight: 8,
+    }) as Buffer;
+
+    assert.deepStrictEqual(Buffer.from(jpeg), Buffer.from(expected));
+    assert.deepStrictEqual(
+      Buffer.from(segmented),
+      Buffer.from(await encodeSegmented(pixels, { width: WIDTH, height: HEIGHT, quality: 80, segmentHeight: 8 }) as Buffer)
+    );
+  });
+
+  it('should reject short strides, short buffers and unknown formats', async () => {
+    await assert.rejects(encode(pixels, { width: WIDTH, height: HEIGHT, stride: WIDTH * 4 - 1 }), /shorter than a row of 21 rgba pixels/);
+    await assert.rejects(encode(pixels, { width: WIDTH, height: HEIGHT, stride: WIDTH * 5 }), /Buffer too small/);
+    await assert.rejects(encode(pixels, { width: WIDTH, height: HEIGHT, pixelFormat: 'argb' as 'rgba' }), /Unsupported pixel format: argb/);
+    await assert.rejects(encode([pixels], { width: WIDTH, height: HEIGHT, pixelFormat: 'rgba' }), /apply to buffers/);
+  });
+});
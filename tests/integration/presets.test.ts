@@ -0,0 +1,57 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, validate } from '../../src/index.js';
+import { Marker, parseJpeg, readSegments } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const scene = (x: number, y: number): number[] => [x * 4, y * 3 + x, (x ^ y) * 2];
+
+describe('Encoder presets', () => {
+  it('should write smaller files than the fast preset', async () => {
+    const buffer = rgbaPattern(96, 64, scene);
+    const sizes: number[] = [];
+
+    for (const preset of ['fast', 'balanced', 'smallest'] as const) {
+      const jpeg = await encode(buffer, { width: 96, height: 64, quality: 85, preset }) as Buffer;
+
+      assert.strictEqual(validate(jpeg).valid, true, preset);
+      const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [96, 64]);
+      sizes.push(jpeg.length);
+    }
+
+    assert.ok(sizes[0] > sizes[1] && sizes[0] > sizes[2], `Sizes should shrink: ${sizes.join(', ')}`);
+  });
+
+  it('should apply explicit options over the preset', async () => {
+    const jpeg = await encode(rgbaPattern(32, 32, scene), { width: 32, height: 32, preset: 'smallest', progressive: false, subsampling: '4:2:2' }) as Buffer;
+
+    const { frame } = parseJpeg(jpeg);
+    assert.strictEqual(frame.marker, Marker.SOF0);
+    assert.deepStrictEqual(frame.components.map((c) => [c.h, c.v]), [[2, 1], [1, 1], [1, 1]]);
+  });
+
+  it('should shrink 4:4:4 output with optimizeHuffman alone', async () => {
+    const buffer = rgbaPattern(48, 48, scene);
+
+    const standard = await encode(buffer, { width: 48, height: 48, quality: 90 }) as Buffer;
+    const optimized = await encode(buffer, { width: 48, height: 48, quality: 90, optimizeHuffman: true }) as Buffer;
+
+    assert.ok(optimized.length < standard.length);
+    const [a, b] = await Promise.all([standard, optimized].map((jpeg) => sharp(jpeg).raw().toBuffer()));
+    assert.deepStrictEqual(a, b);
+  });
+
+  it('should write progressive 4:2:0 output without extra segments for the web preset', async () => {
+    const jpeg = await encode(rgbaPattern(64, 48, scene), { width: 64, height: 48, quality: 80, preset: 'web' }) as Buffer;
+
+    const { frame } = parseJpeg(jpeg);
+    assert.strictEqual(frame.marker, Marker.SOF2);
+    assert.deepStrictEqual(frame.components.map((c) => [c.h, c.v]), [[2, 2], [1, 1], [1, 1]]);
+    assert.deepStrictEqual(readSegments(jpeg).filter((s) => s.marker >= 0xe0 && s.marker <= 0xef).map((s) => s.marker), [0xe0]);
+    await assert.rejects(encode(rgbaPattern(64, 48, scene), { width: 64, height: 48, preset: 'web', thumbnail: {} }), /set stripMetadata to false/);
+    const kept = await encode(rgbaPattern(64, 48, scene), { width: 64, height: 48, preset: 'web', thumbnail: {}, stripMetadata: false }) as Buffer;
+    assert.strictEqual(readSegments(kept).filter((s) => s.marker === 0xe0).length, 2);
+  });
+});
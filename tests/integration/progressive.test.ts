@@ -0,0 +1,34 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, validate } from '../../src/index.js';
+
+describe('Progressive encoding', () => {
+  it('should produce a progressive JPEG and report the DC scan early', async () => {
+    const width = 48;
+    const height = 32;
+    const buffer = new Uint8Array(width * height * 4);
+    for (let i = 0; i < width * height; i++) {
+      buffer[i * 4] = (i % width) * 5;
+      buffer[i * 4 + 1] = Math.floor(i / width) * 7;
+      buffer[i * 4 + 2] = 90;
+      buffer[i * 4 + 3] = 255;
+    }
+    let preview: Uint8Array | undefined;
+
+    const jpeg = await encode(buffer, {
+      width,
+      height,
+      quality: 85,
+      progressive: { onDcScan: (_info, bytes) => { preview = bytes; } },
+    }) as Buffer;
+
+    const report = validate(jpeg);
+    assert.strictEqual(report.valid, true, JSON.stringify(report.issues));
+    const metadata = await sharp(jpeg).metadata();
+    assert.strictEqual(metadata.isProgressive, true);
+    assert.ok(preview && preview.length < jpeg.length);
+    const blurry = await sharp(preview!).metadata();
+    assert.deepStrictEqual([blurry.width, blurry.height], [width, height]);
+  });
+});
@@ -0,0 +1,29 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encodePyramid } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('encodePyramid', () => {
+  it('should encode every requested size from one pass', async () => {
+    const width = 200;
+    const height = 130;
+    const buffer = rgbaPattern(width, height, (x, y) => [(x * 255) / width, (y * 255) / height, 80]);
+
+    const levels = await encodePyramid(buffer, { width, height, sizes: [50, 400, 100], quality: 90 });
+
+    assert.deepStrictEqual(levels.map((l) => [l.size, l.width, l.height]), [[50, 50, 33], [400, 200, 130], [100, 100, 65]]);
+    for (const level of levels) {
+      const { data, info } = await sharp(level.jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [level.width, level.height]);
+      assert.ok(data[0] < 12 && data[1] < 12, `Level ${level.size} should keep the dark corner`);
+      const last = (info.width * info.height - 1) * info.channels;
+      assert.ok(data[last] > 240 && data[last + 1] > 240, `Level ${level.size} should keep the bright corner`);
+    }
+  });
+
+  it('should reject empty or invalid size lists', async () => {
+    await assert.rejects(encodePyramid(new Uint8Array(64), { width: 4, height: 4, sizes: [] }), /positive integers/);
+    await assert.rejects(encodePyramid(new Uint8Array(64), { width: 4, height: 4, sizes: [0] }), /positive integers/);
+  });
+});
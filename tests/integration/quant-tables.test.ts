@@ -0,0 +1,81 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, qualityQuantTables, zigzagToNatural } from '../../src/index.js';
+import { Marker, parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const gradient = (x: number, y: number, width: number, height: number): number[] => [(x * 255) / width, (y * 255) / height, 120];
+
+describe('quantTables option', () => {
+  for (const compat of [undefined, 'libjpeg'] as const) {
+    it(`should write the given tables${compat ? ' in libjpeg mode' : ''}`, async () => {
+      const width = 48;
+      const height = 40;
+      const luma = Array.from({ length: 64 }, (_, i) => 4 + (i >> 3));
+      const chroma = new Array(64).fill(12);
+
+      const jpeg = await encode(rgbaPattern(width, height, gradient), { width, height, quantTables: { luma, chroma }, compat }) as Buffer;
+
+      const tables = parseJpeg(jpeg).scans[0].quantTables;
+      assert.deepStrictEqual(Array.from(zigzagToNatural(tables.get(0)!.values)), luma);
+      assert.deepStrictEqual(Array.from(zigzagToNatural(tables.get(1)!.values)), chroma);
+      const { data } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.ok(Math.abs(data[(20 * width + 24) * 3] - (24 * 255) / width) < 8, 'Pixels should survive the custom tables');
+    });
+  }
+
+  for (const compat of [undefined, 'libjpeg'] as const) {
+    it(`should write 16-bit tables in an extended frame${compat ? ' in libjpeg mode' : ''}`, async () => {
+      const luma = Array.from({ length: 64 }, (_, i) => (i === 63 ? 1000 : 2));
+
+      const jpeg = await encode(rgbaPattern(16, 16, gradient), { width: 16, height: 16, quantTables: { luma, precision: 16 }, compat }) as Buffer;
+
+      const file = parseJpeg(jpeg);
+      assert.strictEqual(file.frame.marker, Marker.SOF1);
+      assert.deepStrictEqual([file.scans[0].quantTables.get(0)!.precision, file.scans[0].quantTables.get(0)!.values[63]], [1, 1000]);
+      const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [16, 16]);
+    });
+  }
+
+  it('should reject invalid tables unless asked to clamp', async () => {
+    const luma = new Array(64).fill(300);
+
+    await assert.rejects(encode(rgbaPattern(8, 8, gradient), { width: 8, height: 8, quantTables: { luma } }), /1-255, got 300/);
+    const jpeg = await encode(rgbaPattern(8, 8, gradient), { width: 8, height: 8, quantTables: { luma, mode: 'clamp' } }) as Buffer;
+    assert.strictEqual(parseJpeg(jpeg).scans[0].quantTables.get(0)!.values[0], 255);
+  });
+});
+
+describe('qualityScaling option', () => {
+  for (const compat of [undefined, 'libjpeg'] as const) {
+    it(`should write the tables the chosen curve gives${compat ? ' in libjpeg mode' : ''}`, async () => {
+      const width = 32;
+      const height = 24;
+
+      const jpeg = await encode(rgbaPattern(width, height, gradient), { width, height, quality: 80, qualityScaling: 'linear', compat }) as Buffer;
+
+      const [luma, chroma] = qualityQuantTables(80, 'linear');
+      const tables = parseJpeg(jpeg).scans[0].quantTables;
+      assert.deepStrictEqual(Array.from(zigzagToNatural(tables.get(0)!.values)), Array.from(luma));
+      assert.deepStrictEqual(Array.from(zigzagToNatural(tables.get(1)!.values)), Array.from(chroma));
+      const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [width, height]);
+    });
+  }
+
+  it('should leave the default output unchanged with libjpeg scaling', async () => {
+    const pixels = rgbaPattern(16, 16, gradient);
+    const plain = await encode(pixels, { width: 16, height: 16, quality: 60 }) as Buffer;
+    const scaled = await encode(pixels, { width: 16, height: 16, quality: 60, qualityScaling: 'libjpeg' }) as Buffer;
+    assert.deepStrictEqual(scaled, plain);
+  });
+
+  it('should reject explicit tables alongside a scaling', async () => {
+    await assert.rejects(
+      encode(rgbaPattern(8, 8, gradient), { width: 8, height: 8, quantTables: { luma: new Array(64).fill(2) }, qualityScaling: 'linear' }),
+      /leave qualityScaling unset/
+    );
+  });
+});
@@ -0,0 +1,51 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { Readable } from 'stream';
+import sharp from 'sharp';
+import { rawVideoFrames, validate } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const frame = (width: number, height: number, value: number): Uint8Array => rgbaPattern(width, height, () => [value, 255 - value, 128]);
+
+// Split bytes into chunks of awkward, varying sizes, as a pipe delivers them
+function* chunked(bytes: Uint8Array): Generator<Buffer> {
+  for (let offset = 0, size = 1; offset < bytes.length; offset += size, size = (size * 7 + 3) % 997 + 1) {
+    yield Buffer.from(bytes.subarray(offset, offset + size));
+  }
+}
+
+describe('rawVideoFrames', () => {
+  it('should encode each frame of a chunked byte stream', async () => {
+    const frames = [frame(24, 20, 20), frame(24, 20, 120), frame(24, 20, 230)];
+    const input = Readable.from(chunked(new Uint8Array(frames.flatMap((f) => Array.from(f)))));
+
+    const output = [];
+    for await (const encoded of rawVideoFrames(input, { width: 24, height: 20, quality: 90, frameRate: 25 })) {
+      output.push(encoded);
+    }
+
+    assert.deepStrictEqual(output.map((f) => [f.index, f.delay]), [[0, 40], [1, 40], [2, 40]]);
+    for (const [index, { jpeg }] of output.entries()) {
+      assert.strictEqual(validate(jpeg).valid, true);
+      const { data } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.ok(Math.abs(data[0] - frames[index][0]) < 4, `frame ${index} red ${data[0]}`);
+    }
+  });
+
+  it('should yield nothing for empty input', async () => {
+    const output = [];
+    for await (const encoded of rawVideoFrames(Readable.from([]), { width: 8, height: 8 })) {
+      output.push(encoded);
+    }
+
+    assert.strictEqual(output.length, 0);
+  });
+
+  it('should reject input that stops partway through a frame', async () => {
+    const bytes = new Uint8Array(frame(16, 16, 50).length + 16 * 4 * 5 + 7);
+    const frames = rawVideoFrames(Readable.from(chunked(bytes)), { width: 16, height: 16 });
+
+    assert.strictEqual((await frames.next()).value.index, 0);
+    await assert.rejects(() => frames.next(), /partway through frame 1, after 5 of 16 rows/);
+  });
+});
@@ -0,0 +1,41 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { decodePartial, encode } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const gradient = (x: number, y: number): number[] => [x * 4, y * 2, x + y];
+
+describe('Recovery points', () => {
+  const width = 64;
+  const height = 80;
+  const pixels = rgbaPattern(width, height, gradient);
+
+  it('should write a restart interval of whole MCU rows that decoders accept', async () => {
+    const jpeg = await encode(pixels, { width, height, quality: 85, recoveryInterval: 2 });
+
+    assert.strictEqual(parseJpeg(jpeg).scans[0].restartInterval, 16);
+    const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+  });
+
+  it('should let a truncated stream decode up to its last recovery point', async () => {
+    const jpeg = await encode(pixels, { width, height, quality: 85, recoveryInterval: 1 });
+
+    const partial = decodePartial(jpeg.subarray(0, Math.floor(jpeg.length / 2)));
+
+    assert.strictEqual(partial.fullHeight, height);
+    assert.ok(partial.height > 0 && partial.height < height && partial.height % 8 === 0, `recovered ${partial.height} rows`);
+  });
+
+  it('should replace a preset\'s progressive setting', async () => {
+    const jpeg = await encode(pixels, { width, height, preset: 'web', recoveryInterval: 2 });
+    assert.strictEqual(parseJpeg(jpeg).scans.length, 1);
+  });
+
+  it('should reject progressive and libjpeg-compatible output', async () => {
+    await assert.rejects(encode(pixels, { width, height, recoveryInterval: 1, progressive: true }), /progressive output cannot carry them/);
+    await assert.rejects(encode(pixels, { width, height, recoveryInterval: 1, compat: 'libjpeg' }), /recovery points are not available/);
+  });
+});
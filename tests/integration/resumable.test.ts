@@ -0,0 +1,55 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encodeResumable, encodeSegmented, validate } from '../../src/index.js';
+import type { EncodeCheckpoint } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const scene = (x: number, y: number): number[] => [x * 5, y * 3, x ^ y];
+
+describe('encodeResumable', () => {
+  it('should match the segmented encode and report a checkpoint per band', async () => {
+    const buffer = rgbaPattern(40, 100, scene);
+    const rows: number[] = [];
+
+    const jpeg = await encodeResumable(buffer, { width: 40, height: 100, quality: 80, segmentHeight: 32, onCheckpoint: (c) => { rows.push(c.rows); } });
+
+    assert.deepStrictEqual(rows, [32, 64, 96, 100]);
+    assert.strictEqual(validate(jpeg).valid, true);
+    const segmented = await encodeSegmented(buffer, { width: 40, height: 100, quality: 80, segmentHeight: 32 }) as Buffer;
+    assert.deepStrictEqual(Buffer.from(jpeg), segmented);
+  });
+
+  it('should resume from a cloned checkpoint to the same bytes', async () => {
+    const buffer = rgbaPattern(48, 80, scene);
+    const options = { width: 48, height: 80, quality: 75, segmentHeight: 16 };
+    let saved: EncodeCheckpoint | undefined;
+
+    await assert.rejects(() => encodeResumable(buffer, {
+      ...options,
+      onCheckpoint: (checkpoint) => {
+        saved = structuredClone(checkpoint);
+        if (checkpoint.rows === 32) {
+          throw new Error('worker restarted');
+        }
+      },
+    }), /worker restarted/);
+    const resumed = await encodeResumable(buffer, { ...options, checkpoint: saved });
+
+    assert.strictEqual(saved!.bands.length, 2);
+    assert.deepStrictEqual(resumed, await encodeResumable(buffer, options));
+    const { info } = await sharp(resumed).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [48, 80]);
+  });
+
+  it('should reject a checkpoint from a different job', async () => {
+    const buffer = rgbaPattern(16, 32, scene);
+    let saved: EncodeCheckpoint | undefined;
+    await encodeResumable(buffer, { width: 16, height: 32, quality: 60, segmentHeight: 16, onCheckpoint: (c) => { saved ??= c; } });
+
+    await assert.rejects(
+      () => encodeResumable(buffer, { width: 16, height: 32, quality: 90, segmentHeight: 16, checkpoint: saved }),
+      /taken with quality 60, but this job uses 90/,
+    );
+  });
+});
@@ -0,0 +1,66 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { RestartStitcher, encode, encodeSegmented, validate } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('Segmented encoding', () => {
+  it('should produce a single decodable JPEG with restart markers', async () => {
+    const width = 40;
+    const height = 50;
+    const buffer = new Uint8Array(width * height * 4);
+    for (let y = 0; y < height; y++) {
+      for (let x = 0; x < width; x++) {
+        const offset = (y * width + x) * 4;
+        buffer[offset] = x * 6;
+        buffer[offset + 1] = y * 5;
+        buffer[offset + 2] = 128;
+        buffer[offset + 3] = 255;
+      }
+    }
+
+    const jpeg = await encodeSegmented(buffer, { width, height, quality: 90, segmentHeight: 16 }) as Buffer;
+
+    const report = validate(jpeg);
+    assert.strictEqual(report.valid, true, JSON.stringify(report.issues));
+
+    const decoded = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.strictEqual(decoded.info.width, width);
+    assert.strictEqual(decoded.info.height, height);
+    const last = ((height - 1) * width + (width - 1)) * 3;
+    assert.ok(Math.abs(decoded.data[last + 1] - (height - 1) * 5) < 10, 'Bottom band should decode in place');
+  });
+
+  it('should reject segment heights that are not a multiple of 8', async () => {
+    await assert.rejects(
+      encodeSegmented(new Uint8Array(64), { width: 4, height: 4, segmentHeight: 12 }),
+      /multiple of 8/
+    );
+  });
+});
+
+describe('RestartStitcher', () => {
+  it('should join bands encoded out of order into one decodable JPEG', async () => {
+    const width = 32;
+    const height = 72;
+    const buffer = rgbaPattern(width, height, (x, y) => [x * 8, y * 3, 60]);
+    const bandHeight = 32;
+    const tops = Array.from({ length: Math.ceil(height / bandHeight) }, (_, index) => index * bandHeight);
+
+    // Encoded in parallel and handed over in reverse order
+    const bands = await Promise.all(tops.map((top) => {
+      const rows = Math.min(bandHeight, height - top);
+      return encode(buffer.subarray(top * width * 4, (top + rows) * width * 4), { width, height: rows, quality: 85 });
+    }));
+    const stitcher = new RestartStitcher(height, bandHeight);
+    const chunks = [...bands.keys()].reverse().map((index) => stitcher.add(index, bands[index] as Uint8Array));
+
+    assert.strictEqual(stitcher.complete, true);
+    const jpeg = Buffer.concat(chunks);
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+    const last = ((height - 1) * width) * info.channels;
+    assert.ok(Math.abs(data[last + 1] - (height - 1) * 3) < 10, 'Bottom band should decode in place');
+  });
+});
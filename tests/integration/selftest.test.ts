@@ -0,0 +1,38 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { decode, roundtrip, selfTest } from '../../src/index.js';
+
+describe('Roundtrip self-test', () => {
+  it('should pass every built-in pattern', async () => {
+    const report = await selfTest();
+
+    for (const entry of report.cases) {
+      assert.ok(entry.passed, `${entry.name}: ${entry.error}`);
+    }
+    assert.strictEqual(report.passed, true);
+  });
+
+  it('should agree with an independent decoder', async () => {
+    const width = 24;
+    const height = 16;
+    const pixels = new Uint8Array(width * height * 4);
+    for (let i = 0; i < pixels.length; i += 4) {
+      pixels[i] = (i / 4) % 256;
+      pixels[i + 1] = 128;
+      pixels[i + 2] = 255 - ((i / 4) % 256);
+      pixels[i + 3] = 255;
+    }
+
+    const result = await roundtrip(pixels, { width, height, quality: 95 });
+    const reference = await sharp(result.jpeg).ensureAlpha().raw().toBuffer();
+
+    assert.ok(result.psnr > 30, `PSNR ${result.psnr} should exceed 30 dB`);
+    let maxDiff = 0;
+    const ours = decode(result.jpeg).data;
+    for (let i = 0; i < ours.length; i++) {
+      maxDiff = Math.max(maxDiff, Math.abs(ours[i] - reference[i]));
+    }
+    assert.ok(maxDiff <= 3, `Decoders differ by up to ${maxDiff}`);
+  });
+});
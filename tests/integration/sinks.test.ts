@@ -0,0 +1,62 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { Writable } from 'stream';
+import sharp from 'sharp';
+import { encodeToSink, encodeToWritable, validate } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const gradient = (x: number, y: number): number[] => [x * 4, y * 4, 100];
+
+describe('Writable sinks', () => {
+  it('should wait for a slow Node.js Writable to drain', async () => {
+    const chunks: Buffer[] = [];
+    let pending = 0;
+    let maxPending = 0;
+    const slow = new Writable({
+      highWaterMark: 1,
+      write(chunk: Buffer, _encoding, callback): void {
+        pending++;
+        maxPending = Math.max(maxPending, pending);
+        chunks.push(chunk);
+        setTimeout(() => { pending--; callback(); }, 1);
+      },
+    });
+
+    const written = await encodeToWritable(rgbaPattern(64, 64, gradient), slow, { width: 64, height: 64, quality: 80 });
+
+    const jpeg = Buffer.concat(chunks);
+    assert.strictEqual(written, jpeg.length);
+    assert.strictEqual(maxPending, 1);
+    assert.ok(slow.writableFinished);
+    assert.strictEqual(slow.listenerCount('error'), 0);
+    const metadata = await sharp(jpeg).metadata();
+    assert.deepStrictEqual([metadata.width, metadata.height], [64, 64]);
+  });
+
+  it('should write into a WritableStream and leave it open on request', async () => {
+    const chunks: Uint8Array[] = [];
+    const stream = new WritableStream<Uint8Array>({ write: (chunk) => { chunks.push(chunk); } });
+
+    const written = await encodeToWritable(rgbaPattern(16, 16, gradient), stream, { width: 16, height: 16, end: false });
+
+    assert.strictEqual(written, chunks.reduce((sum, chunk) => sum + chunk.length, 0));
+    const writer = stream.getWriter();
+    await writer.write(new Uint8Array([1]));
+    await writer.close();
+  });
+
+  it('should honour flushRows and transferable for any sink', async () => {
+    const chunks: Uint8Array[] = [];
+    const sink = { write: (chunk: Uint8Array): void => { chunks.push(chunk); } };
+
+    await encodeToSink(rgbaPattern(32, 36, gradient), sink, { width: 32, height: 36, flushRows: 8, transferable: true });
+
+    // Five bands: four end in RST0-RST3, the last in EOI
+    assert.strictEqual(chunks.length, 5);
+    chunks.slice(0, 4).forEach((chunk, index) => {
+      assert.deepStrictEqual(Array.from(chunk.subarray(-2)), [0xff, 0xd0 + index]);
+      assert.strictEqual(chunk.byteLength, chunk.buffer.byteLength);
+    });
+    assert.strictEqual(validate(Buffer.concat(chunks)).valid, true);
+  });
+});
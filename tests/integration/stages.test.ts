@@ -0,0 +1,38 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { LIBJPEG_QUANTIZER, encode, validate } from '../../src/index.js';
+import type { BlockQuantizer } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const noise = (x: number, y: number, width: number): number[] => [0, 1, 2].map((c) => (((y * width + x) * 4 + c) * 7919) % 253);
+
+// Dead-zone quantizer: terms within 1.5 steps of zero are dropped instead of rounded
+const deadZone: BlockQuantizer = {
+  quantize(coefficients, table, out, offset, component): void {
+    LIBJPEG_QUANTIZER.quantize(coefficients, table, out, offset, component);
+    for (let k = 1; k < 64; k++) {
+      if (Math.abs(out[offset + k]) === 1) {
+        out[offset + k] = 0;
+      }
+    }
+  },
+};
+
+describe('custom encoder stages', () => {
+  it('should plug a quantizer into libjpeg-compatible encoding', async () => {
+    const buffer = rgbaPattern(64, 48, noise);
+
+    const standard = await encode(buffer, { width: 64, height: 48, quality: 75, compat: 'libjpeg' }) as Buffer;
+    const custom = await encode(buffer, { width: 64, height: 48, quality: 75, compat: 'libjpeg', quantizer: deadZone }) as Buffer;
+
+    assert.strictEqual(validate(custom).valid, true);
+    assert.ok(custom.length < standard.length, `${custom.length} should be below ${standard.length}`);
+  });
+
+  it('should require the TypeScript encoder', async () => {
+    await assert.rejects(
+      () => encode(rgbaPattern(8, 8, noise), { width: 8, height: 8, quantizer: deadZone }),
+      /set compat to 'libjpeg'/,
+    );
+  });
+});
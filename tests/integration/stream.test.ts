@@ -0,0 +1,136 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { JpegEncoderStream, encodeToResponse, encodeToStream, reencodeResponse, validate } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('Stream re-encoding', () => {
+  it('should re-encode a raw pixel response body as a streaming JPEG', async () => {
+    const width = 24;
+    const height = 20;
+    const pixels = new Uint8Array(width * height * 4);
+    for (let i = 0; i < pixels.length; i += 4) {
+      pixels[i] = 200;
+      pixels[i + 1] = 100;
+      pixels[i + 2] = 50;
+      pixels[i + 3] = 255;
+    }
+    // Deliver the pixels in uneven chunks, as a network body would
+    const body = new ReadableStream<Uint8Array>({
+      start(controller) {
+        for (let offset = 0; offset < pixels.length; offset += 500) {
+          controller.enqueue(pixels.slice(offset, offset + 500));
+        }
+        controller.close();
+      },
+    });
+    const upstream = new Response(body, { headers: { 'Content-Type': 'application/octet-stream', 'Content-Length': String(pixels.length) } });
+
+    const response = reencodeResponse(upstream, { width, height, quality: 90 });
+    assert.strictEqual(response.headers.get('Content-Type'), 'image/jpeg');
+    assert.strictEqual(response.headers.get('Content-Length'), null);
+
+    const jpeg = new Uint8Array(await response.arrayBuffer());
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+    assert.ok(Math.abs(data[0] - 200) < 6);
+  });
+
+  it('should error the body stream when the input is not a known image format', async () => {
+    const response = reencodeResponse(new Response(new Uint8Array([1, 2, 3, 4])));
+
+    await assert.rejects(response.arrayBuffer(), /Width and height are required/);
+  });
+
+  it('should stream an in-memory image as an upload body', async () => {
+    const width = 16;
+    const height = 40;
+    const pixels = new Uint8Array(width * height * 4).fill(128);
+
+    const stream = encodeToStream(pixels, { width, height, quality: 80 });
+    const chunks: Uint8Array[] = [];
+    for (let reader = stream.getReader(), result = await reader.read(); !result.done; result = await reader.read()) {
+      chunks.push(result.value);
+    }
+
+    const jpeg = new Uint8Array(Buffer.concat(chunks));
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+  });
+
+  it('should serve an encode as a streaming Response', async () => {
+    const pixels = new Uint8Array(24 * 24 * 4).fill(60);
+
+    const response = encodeToResponse(pixels, { width: 24, height: 24 }, { status: 201, headers: { 'Cache-Control': 'no-store' } });
+
+    assert.strictEqual(response.status, 201);
+    assert.strictEqual(response.headers.get('Content-Type'), 'image/jpeg');
+    assert.strictEqual(response.headers.get('Cache-Control'), 'no-store');
+    const jpeg = new Uint8Array(await response.arrayBuffer());
+    assert.strictEqual(validate(jpeg).valid, true);
+  });
+
+  it('should compose with pipeThrough like CompressionStream', async () => {
+    const width = 10;
+    const height = 12;
+    const pixels = new Uint8Array(width * height * 4).fill(90);
+    const input = new ReadableStream<Uint8Array>({
+      start(controller) {
+        // Odd chunk sizes split rows and strips
+        for (let offset = 0; offset < pixels.length; offset += 77) {
+          controller.enqueue(pixels.slice(offset, offset + 77));
+        }
+        controller.close();
+      },
+    });
+
+    const output = input.pipeThrough(new JpegEncoderStream({ width, height, quality: 90 }));
+    const jpeg = new Uint8Array(await new Response(output).arrayBuffer());
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+    assert.ok(Math.abs(data[data.length - 1] - 90) < 4);
+  });
+
+  it('should reject input that stops short of the image size', async () => {
+    const input = new ReadableStream<Uint8Array>({
+      start(controller) {
+        controller.enqueue(new Uint8Array(16));
+        controller.close();
+      },
+    });
+
+    const output = input.pipeThrough(new JpegEncoderStream({ width: 4, height: 4 }));
+    await assert.rejects(new Response(output).arrayBuffer(), /ended after 16 of 64 bytes/);
+  });
+});
+
+describe('Low-latency streaming', () => {
+  it('should flush every band of rows as it is encoded', async () => {
+    const width = 32;
+    const height = 36;
+    const pixels = rgbaPattern(width, height, (x, y) => {
+      const i = (y * width + x) * 4;
+      return [i & 0xff, (i >> 4) & 0xff, 90];
+    });
+
+    const chunks: Uint8Array[] = [];
+    const reader = encodeToStream(pixels, { width, height, quality: 80, flushRows: 8 }).getReader();
+    for (let result = await reader.read(); !result.done; result = await reader.read()) {
+      chunks.push(result.value);
+    }
+
+    // Five bands: four end in RST0-RST3, the last in EOI
+    assert.strictEqual(chunks.length, 5);
+    chunks.slice(0, 4).forEach((chunk, index) => {
+      assert.deepStrictEqual(Array.from(chunk.subarray(-2)), [0xff, 0xd0 + index]);
+    });
+    const jpeg = new Uint8Array(chunks.flatMap((chunk) => Array.from(chunk)));
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+  });
+});
@@ -0,0 +1,48 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { decode, decodeRegion, encodeSegmented, readStripIndex } from '../../src/index.js';
+
+describe('Strip index', () => {
+  const width = 64;
+  const height = 200;
+  const pixels = Uint8Array.from({ length: width * height * 4 }, (_, i) => ((i & 3) === 3 ? 255 : (i * 5) & 0xff));
+
+  it('should embed an index of the restart segments in segmented output', async () => {
+    const jpeg = new Uint8Array(await encodeSegmented(pixels, { width, height, quality: 80, segmentHeight: 64, stripIndex: true }) as Buffer);
+
+    const index = readStripIndex(jpeg.subarray(0, 1024));
+    assert.ok(index);
+    assert.deepStrictEqual(index.segments.map((s) => [s.top, s.rows]), [[0, 64], [64, 64], [128, 64], [192, 8]]);
+    assert.strictEqual(index.segments[3].end, jpeg.length - 2);
+    const { info } = await sharp(Buffer.from(jpeg)).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+  });
+
+  it('should reject non-JFIF headers', async () => {
+    await assert.rejects(encodeSegmented(pixels, { width, height, stripIndex: true, header: 'spiff' }), /JFIF output only/);
+  });
+});
+
+describe('decodeRegion', () => {
+  it('should decode a tile of segmented output from byte ranges', async () => {
+    const width = 96;
+    const height = 256;
+    const pixels = Uint8Array.from({ length: width * height * 4 }, (_, i) => ((i & 3) === 3 ? 255 : (i * 3) & 0xff));
+    const jpeg = new Uint8Array(await encodeSegmented(pixels, { width, height, quality: 85, segmentHeight: 32, stripIndex: true }) as Buffer);
+    let fetched = 0;
+    const fetchRange = async (start: number, end: number): Promise<Uint8Array> => {
+      fetched += Math.min(end, jpeg.length) - start;
+      return jpeg.slice(start, end);
+    };
+
+    const tile = await decodeRegion(fetchRange, { x: 32, y: 100, width: 48, height: 40 }, { headerBytes: 1024 });
+
+    const full = decode(jpeg);
+    for (let row = 0; row < 40; row++) {
+      const from = ((100 + row) * width + 32) * 4;
+      assert.deepStrictEqual(tile.data.subarray(row * 48 * 4, (row + 1) * 48 * 4), full.data.subarray(from, from + 48 * 4));
+    }
+    assert.ok(fetched < jpeg.length, `fetched ${fetched} of ${jpeg.length} bytes`);
+  });
+});
@@ -0,0 +1,56 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, validate } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const panorama = (width: number, height: number): Uint8Array => rgbaPattern(width, height, (x, y) => [
+  128 + 100 * Math.sin(x / 5) * Math.cos(y / 7), 128 + 90 * Math.sin((x + y) / 6), (x * y / 20) & 0xff,
+]);
+
+async function rowError(jpeg: Buffer, buffer: Uint8Array, width: number, from: number, to: number): Promise<number> {
+  const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+  let total = 0;
+  for (let y = from; y < to; y++) {
+    for (let x = 0; x < width; x++) {
+      for (let c = 0; c < 3; c++) {
+        total += Math.abs(data[(y * width + x) * info.channels + c] - buffer[(y * width + x) * 4 + c]);
+      }
+    }
+  }
+  return total / ((to - from) * width * 3);
+}
+
+describe('per-strip quality', () => {
+  it('should spend fewer bits on the outer strips and keep the center', async () => {
+    const buffer = panorama(96, 96);
+    const foveated = (top: number): number | undefined => (top >= 32 && top < 64 ? undefined : 25);
+
+    const plain = await encode(buffer, { width: 96, height: 96, quality: 90 }) as Buffer;
+    const jpeg = await encode(buffer, { width: 96, height: 96, quality: 90, stripQuality: foveated }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    assert.ok(jpeg.length < plain.length * 0.8, `${jpeg.length} should be well below ${plain.length}`);
+    assert.ok(await rowError(jpeg, buffer, 96, 40, 56) < (await rowError(plain, buffer, 96, 40, 56)) + 0.5);
+    assert.ok(await rowError(jpeg, buffer, 96, 0, 24) > await rowError(plain, buffer, 96, 0, 24));
+  });
+
+  it('should apply to subsampled output in MCU rows of 16', async () => {
+    const rows: number[] = [];
+
+    const jpeg = await encode(panorama(32, 40), {
+      width: 32, height: 40, quality: 80, subsampling: '4:2:0',
+      stripQuality: (top) => { rows.push(top); return 50; },
+    }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    assert.deepStrictEqual(rows, [0, 16, 32]);
+  });
+
+  it('should reject libjpeg compatibility', async () => {
+    await assert.rejects(
+      () => encode(panorama(16, 16), { width: 16, height: 16, compat: 'libjpeg', stripQuality: () => 50 }),
+      /per-strip quality is not available/,
+    );
+  });
+});
@@ -0,0 +1,50 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encode, validate } from '../../src/index.js';
+import type { EncodeStats } from '../../src/index.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('Chroma subsampling', () => {
+  it('should write 4:1:1 output that other decoders read', async () => {
+    const width = 70;
+    const height = 30;
+    const buffer = rgbaPattern(width, height, (x) => [200, x * 3, 60]);
+
+    const jpeg = await encode(buffer, { width, height, quality: 90, subsampling: '4:1:1' }) as Buffer;
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    assert.deepStrictEqual(parseJpeg(jpeg).frame.components.map((c) => [c.h, c.v]), [[4, 1], [1, 1], [1, 1]]);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+    const middle = (15 * width + 35) * info.channels;
+    assert.ok(Math.abs(data[middle] - 200) < 12 && Math.abs(data[middle + 1] - 105) < 12 && Math.abs(data[middle + 2] - 60) < 12);
+  });
+
+  it('should be smaller than 4:4:4 for the same quality', async () => {
+    const buffer = rgbaPattern(64, 64, (x, y, width) => [0, 1, 2].map((c) => (((y * width + x) * 4 + c) * 13) % 256));
+
+    const full = await encode(buffer, { width: 64, height: 64, quality: 85 }) as Buffer;
+    const reduced = await encode(buffer, { width: 64, height: 64, quality: 85, subsampling: '4:2:0' }) as Buffer;
+
+    assert.ok(reduced.length < full.length);
+  });
+
+  it('should pick 4:4:4 for colored text and 4:2:0 for photos with auto', async () => {
+    const width = 64;
+    const height = 32;
+    const text = rgbaPattern(width, height, (x, y) => (x % 5 === 2 && y > 4 && y < 28 ? [200, 0, 0] : [255, 255, 255]));
+    const photo = rgbaPattern(width, height, (x, y) => [90 + x, 120 + y, 160 - x]);
+    const stats: EncodeStats[] = [];
+
+    const crisp = await encode(text, { width, height, subsampling: 'auto', onStats: (s) => stats.push(s) }) as Buffer;
+    const smooth = await encode(photo, { width, height, subsampling: 'auto', onStats: (s) => stats.push(s) }) as Buffer;
+
+    assert.deepStrictEqual(parseJpeg(crisp).frame.components.map((c) => [c.h, c.v]), [[1, 1], [1, 1], [1, 1]]);
+    assert.deepStrictEqual(parseJpeg(smooth).frame.components.map((c) => [c.h, c.v]), [[2, 2], [1, 1], [1, 1]]);
+    assert.deepStrictEqual(stats.map((s) => s.subsampling), ['4:4:4', '4:2:0']);
+    assert.ok(stats[0].chromaDetail! > stats[1].chromaDetail!);
+    assert.deepStrictEqual([stats[1].width, stats[1].height, stats[1].bytes], [width, height, smooth.length]);
+  });
+});
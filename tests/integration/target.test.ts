@@ -0,0 +1,62 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { decode, encodeToTarget, ssim } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const scene = (x: number, y: number): number[] => [128 + 100 * Math.sin(x / 5) * Math.cos(y / 7), 128 + 90 * Math.sin((x + y) / 11), (x * y / 20) & 0xff];
+
+describe('encodeToTarget', () => {
+  it('should pick a higher quality for a stricter target and reach it on the whole image', async () => {
+    const width = 96;
+    const height = 400;
+    const buffer = rgbaPattern(width, height, scene);
+
+    const loose = await encodeToTarget(buffer, { width, height, targetSsim: 0.9 });
+    const strict = await encodeToTarget(buffer, { width, height, targetSsim: 0.99, sampleBands: 4 });
+
+    assert.ok(loose.quality < strict.quality, `${loose.quality} should be below ${strict.quality}`);
+    assert.ok(loose.jpeg.length < strict.jpeg.length);
+    assert.ok(strict.ssim >= 0.99);
+    assert.ok(ssim(buffer, decode(strict.jpeg).data, width, height) > 0.985);
+  });
+
+  it('should read BGR buffers with a padded stride', async () => {
+    const width = 24;
+    const height = 16;
+    const rgba = rgbaPattern(width, height, scene);
+    const stride = width * 3 + 4;
+    const bgr = new Uint8Array(stride * height);
+    for (let i = 0; i < width * height; i++) {
+      const offset = Math.floor(i / width) * stride + (i % width) * 3;
+      bgr.set([rgba[i * 4 + 2], rgba[i * 4 + 1], rgba[i * 4]], offset);
+    }
+
+    const expected = await encodeToTarget(rgba, { width, height, targetSsim: 0.95 });
+    const result = await encodeToTarget(bgr, { width, height, targetSsim: 0.95, pixelFormat: 'bgr', stride });
+
+    assert.strictEqual(result.quality, expected.quality);
+    assert.deepStrictEqual(Buffer.from(result.jpeg), Buffer.from(expected.jpeg));
+  });
+
+  it('should report stats and scans for the final encode only', async () => {
+    const width = 32;
+    const height = 24;
+    const stats: number[] = [];
+    let dcScans = 0;
+
+    const result = await encodeToTarget(rgbaPattern(width, height, scene), {
+      width, height, targetSsim: 0.95,
+      onStats: (s) => stats.push(s.height),
+      progressive: { onDcScan: () => dcScans++ },
+    });
+
+    assert.deepStrictEqual(stats, [height]);
+    assert.strictEqual(dcScans, 1);
+    assert.strictEqual(decode(result.jpeg).height, height);
+  });
+
+  it('should reject targets outside (0, 1]', async () => {
+    await assert.rejects(encodeToTarget(rgbaPattern(8, 8, scene), { width: 8, height: 8, targetSsim: 1.5 }), /in \(0, 1\], got 1.5/);
+    await assert.rejects(encodeToTarget(rgbaPattern(8, 8, scene), { width: 8, height: 8, targetSsim: 0.9, sampleBands: 0 }), /positive integer/);
+  });
+});
@@ -0,0 +1,58 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { thumbnail } from '../../src/index.js';
+
+async function gradientJpeg(width: number, height: number): Promise<Buffer> {
+  const rgb = Buffer.alloc(width * height * 3);
+  for (let i = 0; i < width * height; i++) {
+    rgb[i * 3] = Math.floor(((i % width) * 255) / width);
+    rgb[i * 3 + 1] = Math.floor((Math.floor(i / width) * 255) / height);
+    rgb[i * 3 + 2] = 200;
+  }
+  return sharp(rgb, { raw: { width, height, channels: 3 } }).jpeg({ quality: 90, chromaSubsampling: '4:2:0' }).toBuffer();
+}
+
+describe('thumbnail', () => {
+  for (const maxSize of [40, 100]) {
+    it(`should shrink a JPEG to fit ${maxSize} pixels`, async () => {
+      const source = await gradientJpeg(400, 300);
+
+      const thumb = await thumbnail(new Uint8Array(source), maxSize, 90) as Buffer;
+
+      const { data, info } = await sharp(thumb).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [maxSize, maxSize * 3 / 4]);
+      const last = (info.width * info.height - 1) * info.channels;
+      assert.ok(data[0] < 20 && data[1] < 20, 'Top-left should stay dark in red and green');
+      assert.ok(data[last] > 235 && data[last + 1] > 235, 'Bottom-right should stay bright in red and green');
+      assert.ok(Math.abs(data[2] - 200) < 10);
+    });
+  }
+
+  it('should carry EXIF orientation and the ICC profile over on request', async () => {
+    const source = await sharp(await gradientJpeg(96, 64)).withMetadata({ orientation: 6 }).jpeg().toBuffer();
+
+    const plain = await sharp(await thumbnail(new Uint8Array(source), 24) as Buffer).metadata();
+    const kept = await sharp(await thumbnail(new Uint8Array(source), 24, 85, { metadata: true }) as Buffer).metadata();
+
+    assert.strictEqual(plain.orientation, undefined);
+    assert.strictEqual(kept.orientation, 6);
+    assert.ok(kept.icc && kept.icc.length > 0);
+  });
+
+  it('should read the source from a web stream', async () => {
+    const source = await gradientJpeg(64, 64);
+    const stream = new ReadableStream<Uint8Array>({
+      start(controller): void {
+        controller.enqueue(new Uint8Array(source.subarray(0, 100)));
+        controller.enqueue(new Uint8Array(source.subarray(100)));
+        controller.close();
+      },
+    });
+
+    const thumb = await thumbnail(stream, 16) as Buffer;
+
+    const metadata = await sharp(thumb).metadata();
+    assert.deepStrictEqual([metadata.width, metadata.height], [16, 16]);
+  });
+});
@@ -0,0 +1,59 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { mkdtemp, rm, writeFile } from 'fs/promises';
+import { tmpdir } from 'os';
+import { join } from 'path';
+import sharp from 'sharp';
+import { DimensionError, encode, encodeTiled, rawFileSource } from '../../src/index.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+describe('encodeTiled', () => {
+  it('should split an image into a grid of tiles with a manifest', async () => {
+    const width = 80;
+    const height = 50;
+    const buffer = rgbaPattern(width, height, (x, y) => [(x * 255) / width, (y * 255) / height, 80]);
+
+    const { manifest, tiles } = await encodeTiled(buffer, { width, height, maxTileSize: 32, quality: 90 });
+
+    assert.deepStrictEqual([manifest.columns, manifest.rows, manifest.tileWidth, manifest.tileHeight], [3, 2, 32, 32]);
+    assert.deepStrictEqual(manifest.tiles.map((t) => [t.path, t.x, t.y, t.width, t.height]), [
+      ['0_0.jpg', 0, 0, 32, 32], ['0_1.jpg', 32, 0, 32, 32], ['0_2.jpg', 64, 0, 16, 32],
+      ['1_0.jpg', 0, 32, 32, 18], ['1_1.jpg', 32, 32, 32, 18], ['1_2.jpg', 64, 32, 16, 18],
+    ]);
+    const corner = tiles[5];
+    const { data, info } = await sharp(corner.jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [16, 18]);
+    assert.ok(Math.abs(data[0] - (64 * 255) / width) < 6, 'Tile should start at its column offset');
+    assert.ok(Math.abs(data[1] - (32 * 255) / height) < 6, 'Tile should start at its row offset');
+  });
+
+  it('should reject oversized images from the single-file encoder', async () => {
+    const width = 70000;
+    await assert.rejects(encode(new Uint8Array(width * 4), { width, height: 1 }), DimensionError);
+
+    const { manifest } = await encodeTiled(new Uint8Array(width * 4), { width, height: 1 });
+    assert.deepStrictEqual(manifest.tiles.map((t) => [t.x, t.width]), [[0, 65535], [65535, 4465]]);
+  });
+
+  it('should tile a raw file source strip by strip', async () => {
+    const dir = await mkdtemp(join(tmpdir(), 'tiled-'));
+    try {
+      const path = join(dir, 'scene.gray');
+      const width = 40;
+      const height = 24;
+      const pixels = new Uint8Array(width * height);
+      for (let i = 0; i < pixels.length; i++) pixels[i] = i % width < 20 ? 40 : 200;
+      await writeFile(path, pixels);
+
+      const source = await rawFileSource(path, { width, height, format: 'gray' });
+      const { manifest, tiles } = await encodeTiled(source, { maxTileSize: 20, quality: 90 });
+      await source.close();
+
+      assert.deepStrictEqual([manifest.width, manifest.height, manifest.columns, manifest.rows], [40, 24, 2, 2]);
+      const { data } = await sharp(tiles[3].jpeg).raw().toBuffer({ resolveWithObject: true });
+      assert.ok(Math.abs(data[0] - 200) < 6, 'Right-hand tiles should come from the right half of the file');
+    } finally {
+      await rm(dir, { recursive: true });
+    }
+  });
+});
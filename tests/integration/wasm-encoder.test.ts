@@ -1,7 +1,7 @@
 import { describe, it } from 'node:test';
 import assert from 'node:assert';
 import sharp from 'sharp';
-import { encode } from '../../src/index.js';
+import { DimensionError, EncodeError, createJpegStripEncoder, encode } from '../../src/index.js';
 
 describe('WASM JPEG Encoder Integration', () => {
   it('should encode a simple solid color image', async () => {
@@ -173,4 +173,47 @@ describe('WASM JPEG Encoder Integration', () => {
     assert.strictEqual(jpegBuffer[1], 0xD8);
     assert.ok(jpegBuffer.length > 50, 'Even 1x1 JPEG should have headers');
   });
+
+  it('should replicate edges of images narrower or shorter than one block', async () => {
+    for (const [width, height] of [[1, 17], [17, 1], [3, 2]]) {
+      const buffer = new Uint8Array(width * height * 4);
+      for (let i = 0; i < width * height; i++) {
+        buffer.set([200, 40, 90, 255], i * 4);
+      }
+
+      const jpegBuffer = await encode(buffer, { width, height, quality: 95 }) as Buffer;
+
+      const { data, info } = await sharp(jpegBuffer).raw().toBuffer({ resolveWithObject: true });
+      assert.deepStrictEqual([info.width, info.height], [width, height]);
+      for (let i = 0; i < data.length; i += info.channels) {
+        assert.ok(Math.abs(data[i] - 200) < 6 && Math.abs(data[i + 1] - 40) < 6 && Math.abs(data[i + 2] - 90) < 6,
+          `${width}x${height} pixel ${i / info.channels} should keep the colour`);
+      }
+    }
+  });
+
+  it('should reject zero dimensions with a DimensionError', async () => {
+    await assert.rejects(encode(new Uint8Array(0), { width: 0, height: 8 }), DimensionError);
+    await assert.rejects(encode(new Uint8Array(0), { width: 8, height: 0 }), /positive integers, got 8x0/);
+  });
+
+  it('should report bad quality and mismatched strips as EncodeErrors', async () => {
+    await assert.rejects(encode(new Uint8Array(8 * 8 * 4), { width: 8, height: 8, quality: 0 }), (error: unknown) => {
+      assert.ok(error instanceof EncodeError);
+      assert.strictEqual(error.code, 'bad-quality');
+      return true;
+    });
+
+    const isMismatch = (error: unknown): boolean => error instanceof EncodeError && error.code === 'size-mismatch';
+    const encoder = createJpegStripEncoder(8, 16, 80);
+    assert.throws(() => encoder.encodeStrip(new Uint8Array(8 * 4 + 1)), isMismatch);
+    assert.throws(() => encoder.encodeStrip(new Uint8Array(8 * 17 * 4)), isMismatch);
+    encoder.encodeStrip(new Uint8Array(8 * 8 * 4));
+    assert.throws(() => encoder.finish(), /after 8 of 16 rows/);
+
+    // A rejected strip leaves the encoder usable
+    encoder.encodeStrip(new Uint8Array(8 * 8 * 4));
+    const jpeg = new Uint8Array(encoder.finish());
+    assert.deepStrictEqual(Array.from(jpeg.subarray(-2)), [0xff, 0xd9]);
+  });
 });
@@ -0,0 +1,26 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import sharp from 'sharp';
+import { encodeYuv, validate } from '../../src/index.js';
+
+describe('Planar YUV input', () => {
+  it('should encode an NV12 frame that other decoders read as the same color', async () => {
+    const width = 40;
+    const height = 30;
+    // Full-range BT.601 red: Y 76, Cb 85, Cr 255
+    const y = new Uint8Array(width * height).fill(76);
+    const uv = new Uint8Array(width * (height / 2));
+    for (let i = 0; i < uv.length; i += 2) {
+      uv.set([85, 255], i);
+    }
+
+    const jpeg = encodeYuv({ width, height, y, uv }, { quality: 90 });
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    const { data, info } = await sharp(jpeg).raw().toBuffer({ resolveWithObject: true });
+    assert.deepStrictEqual([info.width, info.height], [width, height]);
+    const middle = (15 * width + 20) * info.channels;
+    assert.ok(data[middle] > 240 && data[middle + 1] < 15 && data[middle + 2] < 15,
+      `pixel ${Array.from(data.subarray(middle, middle + 3))} should be red`);
+  });
+});
@@ -0,0 +1,117 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { AlphaCollector, mergeAlpha, packBits, unpackBits } from '../../src/alpha.js';
+import type { AlphaMaskFormat } from '../../src/alpha.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { decode } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+
+// Opaque disc on a transparent background, with a soft edge
+function disc(width: number, height: number): Uint8Array {
+  const rgba = new Uint8Array(width * height * 4);
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const distance = Math.hypot(x - width / 2, y - height / 2);
+      const alpha = Math.round(Math.max(0, Math.min(1, width / 3 - distance)) * 255);
+      rgba.set([200, 100, 50, alpha], (y * width + x) * 4);
+    }
+  }
+  return rgba;
+}
+
+function collect(rgba: Uint8Array, width: number, height: number, format: AlphaMaskFormat, rowsPerStrip: number) {
+  const encoder = format === 'jpeg' ? createLibjpegStripEncoder(width, height, { quality: 95, subsampling: '4:4:4' }) : undefined;
+  const collector = new AlphaCollector(width, height, format, encoder);
+  for (let y = 0; y < height; y += rowsPerStrip) {
+    collector.addStrip(rgba.subarray(y * width * 4, Math.min(height, y + rowsPerStrip) * width * 4));
+  }
+  return collector.finish();
+}
+
+function alphaOf(rgba: Uint8Array): Uint8Array {
+  return rgba.filter((_, i) => i % 4 === 3);
+}
+
+describe('packBits', () => {
+  it('should round-trip runs and literals', () => {
+    const data = new Uint8Array([...new Array(300).fill(0), 1, 2, 3, 3, 4, ...new Array(200).fill(255), 9]);
+
+    const packed = packBits(data);
+
+    assert.ok(packed.length < 20, `${packed.length} bytes`);
+    assert.deepStrictEqual(unpackBits(packed, data.length), data);
+  });
+
+  it('should split long literal stretches', () => {
+    const data = new Uint8Array(300).map((_, i) => i & 0xff);
+
+    assert.deepStrictEqual(unpackBits(packBits(data), data.length), data);
+  });
+
+  it('should reject truncated data', () => {
+    assert.throws(() => unpackBits(packBits(new Uint8Array(10)), 20), /ended after 10 of 20 bytes/);
+  });
+});
+
+describe('AlphaCollector', () => {
+  it('should collect the raw mask across strips', () => {
+    const rgba = disc(20, 13);
+
+    const mask = collect(rgba, 20, 13, 'raw', 5);
+
+    assert.deepStrictEqual([mask.format, mask.width, mask.height], ['raw', 20, 13]);
+    assert.deepStrictEqual(mask.data, alphaOf(rgba));
+  });
+
+  it('should run-length code the mask', () => {
+    const rgba = disc(64, 64);
+
+    const mask = collect(rgba, 64, 64, 'rle', 8);
+
+    assert.ok(mask.data.length < 64 * 64 / 4, `${mask.data.length} bytes`);
+    assert.deepStrictEqual(unpackBits(mask.data, 64 * 64), alphaOf(rgba));
+  });
+
+  it('should encode the mask as a grayscale JPEG', () => {
+    const rgba = disc(24, 20);
+
+    const mask = collect(rgba, 24, 20, 'jpeg', 3);
+    const gray = decode(mask.data);
+
+    assert.strictEqual(parseJpeg(mask.data).frame.components.length, 1);
+    const alpha = alphaOf(rgba);
+    for (let i = 0; i < alpha.length; i++) {
+      assert.ok(Math.abs(gray.data[i * 4] - alpha[i]) <= 24, `pixel ${i}: ${gray.data[i * 4]} vs ${alpha[i]}`);
+    }
+  });
+
+  it('should require an encoder for JPEG masks', () => {
+    assert.throws(() => new AlphaCollector(8, 8, 'jpeg'), /need an encoder/);
+  });
+
+  it('should reject a mask with missing rows', () => {
+    const collector = new AlphaCollector(8, 8, 'raw');
+    collector.addStrip(new Uint8Array(8 * 4 * 4));
+
+    assert.throws(() => collector.finish(), /ended after 4 of 8 rows/);
+  });
+});
+
+describe('mergeAlpha', () => {
+  it('should restore the alpha channel of a decoded image', () => {
+    const rgba = disc(16, 16);
+    const encoder = createLibjpegStripEncoder(16, 16, { quality: 90 });
+    const jpeg = new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+
+    const merged = mergeAlpha(jpeg, collect(rgba, 16, 16, 'rle', 16));
+
+    assert.deepStrictEqual(Array.from(alphaOf(new Uint8Array(merged.data))), Array.from(alphaOf(rgba)));
+  });
+
+  it('should reject a mask of another size', () => {
+    const encoder = createLibjpegStripEncoder(8, 8, { quality: 90 });
+    const jpeg = new Uint8Array([...encoder.encodeStrip(new Uint8Array(256)), ...encoder.finish()]);
+
+    assert.throws(() => mergeAlpha(jpeg, { format: 'raw', width: 4, height: 4, data: new Uint8Array(16) }), /Mask is 4x4 but the image is 8x8/);
+  });
+});
@@ -0,0 +1,45 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { FrameCompositor } from '../../src/animation.js';
+import type { AnimationFrame } from '../../src/animation.js';
+
+function solid(left: number, top: number, width: number, height: number, value: number, disposal: AnimationFrame['disposal']): AnimationFrame {
+  const data = new Uint8Array(width * height * 4);
+  for (let i = 0; i < data.length; i += 4) {
+    data[i] = data[i + 1] = data[i + 2] = value;
+    data[i + 3] = 255;
+  }
+  return { data, left, top, width, height, delay: 100, disposal };
+}
+
+const reds = (canvas: Uint8Array): number[] => Array.from(canvas).filter((_, i) => i % 4 === 0);
+
+describe('FrameCompositor', () => {
+  it('should draw partial frames over the previous canvas', () => {
+    const compositor = new FrameCompositor(3, 1, [7, 7, 7]);
+    assert.deepStrictEqual(reds(compositor.composite(solid(0, 0, 3, 1, 10, 'none'))), [10, 10, 10]);
+    assert.deepStrictEqual(reds(compositor.composite(solid(1, 0, 1, 1, 20, 'none'))), [10, 20, 10]);
+  });
+
+  it('should restore the background after a background-disposed frame', () => {
+    const compositor = new FrameCompositor(3, 1, [7, 7, 7]);
+    compositor.composite(solid(0, 0, 3, 1, 10, 'none'));
+    compositor.composite(solid(0, 0, 2, 1, 20, 'background'));
+    assert.deepStrictEqual(reds(compositor.composite(solid(2, 0, 1, 1, 30, 'none'))), [7, 7, 30]);
+  });
+
+  it('should restore the previous canvas after a previous-disposed frame', () => {
+    const compositor = new FrameCompositor(3, 1);
+    compositor.composite(solid(0, 0, 3, 1, 10, 'none'));
+    compositor.composite(solid(0, 0, 3, 1, 20, 'previous'));
+    assert.deepStrictEqual(reds(compositor.composite(solid(0, 0, 1, 1, 30, 'none'))), [30, 10, 10]);
+  });
+
+  it('should keep the canvas under transparent pixels and clip to the canvas', () => {
+    const compositor = new FrameCompositor(2, 1);
+    compositor.composite(solid(0, 0, 2, 1, 10, 'none'));
+    const frame = solid(1, 0, 2, 1, 40, 'none');
+    frame.data[3] = 0;
+    assert.deepStrictEqual(Array.from(compositor.composite(frame)), [10, 10, 10, 255, 10, 10, 10, 255]);
+  });
+});
@@ -0,0 +1,50 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { annotate, formatAnnotations, markerName } from '../../src/annotate.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('annotate', () => {
+  it('should list segments in stream order with decoded details', () => {
+    const jpeg = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+
+    const annotations = annotate(jpeg);
+
+    assert.deepStrictEqual(
+      annotations.map((a) => a.name),
+      ['SOI', 'APP0', 'DQT', 'SOF0 (baseline DCT)', 'DHT', 'DHT', 'SOS', 'entropy-coded data', 'EOI']
+    );
+    assert.deepStrictEqual(annotations[1].details, ['identifier "JFIF"']);
+    assert.strictEqual(annotations[3].details[0], '16x8, 8-bit, 1 component(s)');
+    assert.strictEqual(annotations[5].details[0], 'AC table 0: 3 symbols');
+    assert.strictEqual(annotations[8].offset, jpeg.length - 2);
+  });
+
+  it('should report restart markers between entropy-coded intervals', () => {
+    const jpeg = grayscaleJpeg(16, 8, ['00' + '0', '00' + '0'], { restartInterval: 1 });
+
+    const annotations = annotate(jpeg);
+    const scanIndex = annotations.findIndex((a) => a.name === 'SOS');
+
+    assert.deepStrictEqual(
+      annotations.slice(scanIndex + 1).map((a) => a.name),
+      ['entropy-coded data', 'RST0', 'entropy-coded data', 'EOI']
+    );
+    assert.ok(annotations.some((a) => a.details.includes('restart interval 1 MCUs')));
+  });
+});
+
+describe('formatAnnotations', () => {
+  it('should render one line per entry with indented details', () => {
+    const text = formatAnnotations(annotate(grayscaleJpeg(8, 8, '00' + '0')));
+
+    assert.match(text, /^0x000000 {2}SOI\n0x000002 {2}APP0 {2}length 18\n {12}identifier "JFIF"/);
+  });
+});
+
+describe('markerName', () => {
+  it('should name numbered markers', () => {
+    assert.strictEqual(markerName(0xe1), 'APP1');
+    assert.strictEqual(markerName(0xd3), 'RST3');
+    assert.strictEqual(markerName(0x02), '0x02');
+  });
+});
@@ -0,0 +1,61 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { Base64Encoder, Base64EncoderStream } from '../../src/base64.js';
+
+function bytes(length: number): Uint8Array {
+  return Uint8Array.from({ length }, (_, i) => (i * 97 + 13) & 0xff);
+}
+
+describe('Base64Encoder', () => {
+  it('should match Buffer for every tail length', () => {
+    for (const length of [0, 1, 2, 3, 4, 5, 100]) {
+      const data = bytes(length);
+      const encoder = new Base64Encoder();
+      assert.strictEqual(encoder.push(data) + encoder.flush(), Buffer.from(data).toString('base64'), `length ${length}`);
+    }
+  });
+
+  it('should carry partial groups between chunks of any size', () => {
+    const data = bytes(1000);
+    const expected = Buffer.from(data).toString('base64');
+    for (const size of [1, 2, 4, 7, 64]) {
+      const encoder = new Base64Encoder();
+      let text = '';
+      for (let i = 0; i < data.length; i += size) {
+        const piece = encoder.push(data.subarray(i, i + size));
+        assert.strictEqual(piece.length % 4, 0);
+        text += piece;
+      }
+      assert.strictEqual(text + encoder.flush(), expected, `chunks of ${size}`);
+    }
+  });
+
+  it('should start afresh after a flush', () => {
+    const encoder = new Base64Encoder();
+    encoder.push(bytes(5));
+    encoder.flush();
+    assert.strictEqual(encoder.push(Uint8Array.of(1)) + encoder.flush(), 'AQ==');
+  });
+});
+
+describe('Base64EncoderStream', () => {
+  it('should turn a byte stream into base64 text', async () => {
+    const data = bytes(50);
+    const input = new ReadableStream<Uint8Array>({
+      start(controller): void {
+        for (let i = 0; i < data.length; i += 11) {
+          controller.enqueue(data.slice(i, i + 11));
+        }
+        controller.close();
+      },
+    });
+
+    let text = '';
+    const reader = input.pipeThrough(new Base64EncoderStream()).getReader();
+    for (let result = await reader.read(); !result.done; result = await reader.read()) {
+      text += result.value;
+    }
+
+    assert.strictEqual(text, Buffer.from(data).toString('base64'));
+  });
+});
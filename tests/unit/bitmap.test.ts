@@ -0,0 +1,47 @@
+import { describe, it, afterEach } from 'node:test';
+import assert from 'node:assert';
+import { imageBitmapSource } from '../../src/bitmap.js';
+import { readAllStrips } from '../../src/pipeline.js';
+
+/**
+ * OffscreenCanvas stand-in whose pixels hold each row's index in every channel
+ */
+class FakeOffscreenCanvas {
+  static reads: Array<[number, number]> = [];
+  constructor(readonly width: number, readonly height: number) {}
+
+  getContext() {
+    return {
+      drawImage: () => {},
+      getImageData: (_x: number, y: number, w: number, h: number) => {
+        FakeOffscreenCanvas.reads.push([y, h]);
+        const data = new Uint8ClampedArray(w * h * 4);
+        for (let r = 0; r < h; r++) {
+          data.fill(y + r, r * w * 4, (r + 1) * w * 4);
+        }
+        return { data };
+      },
+    };
+  }
+}
+
+describe('imageBitmapSource', () => {
+  afterEach(() => {
+    delete (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas;
+    FakeOffscreenCanvas.reads = [];
+  });
+
+  it('should read the bitmap back one strip at a time', async () => {
+    (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas = FakeOffscreenCanvas;
+    const source = imageBitmapSource({ width: 2, height: 5 } as ImageBitmap);
+
+    const pixels = await readAllStrips(source, 2);
+
+    assert.deepStrictEqual(FakeOffscreenCanvas.reads, [[0, 2], [2, 2], [4, 1]]);
+    assert.deepStrictEqual(Array.from(pixels).filter((_, i) => i % 8 === 0), [0, 1, 2, 3, 4]);
+  });
+
+  it('should fail without a canvas implementation', () => {
+    assert.throws(() => imageBitmapSource({ width: 1, height: 1 } as ImageBitmap), /OffscreenCanvas/);
+  });
+});
@@ -0,0 +1,126 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { limitMcuBits } from '../../src/budget.js';
+import { readCoefficients } from '../../src/decoder.js';
+import { validate } from '../../src/validate.js';
+import { Marker, parseJpeg } from '../../src/jpeg.js';
+import { writeBaseline } from '../../src/transcode.js';
+import { STANDARD_TABLES, createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import type { ChromaSubsampling } from '../../src/subsample.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+function textured(width: number, height: number, subsampling: ChromaSubsampling): Uint8Array {
+  const rgba = rgbaPattern(width, height, (x, y) => [(x * 37 + y * 11) & 0xff, (x * y * 5) & 0xff, (x * 13 - y * 29) & 0xff]);
+  const encoder = createLibjpegStripEncoder(width, height, { quality: 95, subsampling });
+  return new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+}
+
+/**
+ * Re-write a JPEG with a restart marker after every MCU
+ */
+function withRestarts(jpeg: Uint8Array): Uint8Array {
+  const file = parseJpeg(jpeg);
+  const coefficients = readCoefficients(file);
+  return writeBaseline({
+    width: file.frame.width,
+    height: file.frame.height,
+    components: coefficients.map((c, index) => ({ ...file.frame.components[index], quantId: file.frame.components[index].quantTable, ...c })),
+    quantTables: [...file.scans[0].quantTables.values()],
+    restartInterval: 1,
+  });
+}
+
+/**
+ * Entropy-coded bytes of each restart interval, stuffed zeros removed
+ */
+function intervalSizes(jpeg: Uint8Array): number[] {
+  const scan = parseJpeg(jpeg).scans[0];
+  const sizes = [0];
+  for (let i = scan.dataOffset; i < scan.dataEnd; i++) {
+    if (jpeg[i] === 0xff && jpeg[i + 1] >= Marker.RST0 && jpeg[i + 1] <= Marker.RST0 + 7) {
+      sizes.push(0);
+      i++;
+    } else {
+      sizes[sizes.length - 1]++;
+      if (jpeg[i] === 0xff) {
+        i++;
+      }
+    }
+  }
+  return sizes;
+}
+
+describe('limitMcuBits', () => {
+  for (const subsampling of ['4:4:4', '4:2:0'] as const) {
+    it(`should keep every ${subsampling} MCU within the budget`, () => {
+      const input = withRestarts(textured(48, 32, subsampling));
+      const maxBits = 160;
+      assert.ok(Math.max(...intervalSizes(input)) > maxBits / 8);
+
+      const { jpeg, droppedCoefficients, limitedMcus } = limitMcuBits(input, maxBits);
+
+      assert.ok(droppedCoefficients > 0);
+      assert.ok(limitedMcus > 0);
+      assert.ok(Math.max(...intervalSizes(jpeg)) <= Math.ceil(maxBits / 8), `largest MCU ${Math.max(...intervalSizes(jpeg))} bytes`);
+      assert.strictEqual(validate(jpeg).valid, true);
+      assert.deepStrictEqual(limitMcuBits(jpeg, maxBits).droppedCoefficients, 0);
+    });
+  }
+
+  it('should drop only the highest-frequency terms, leaving DC alone', () => {
+    const input = textured(16, 16, '4:4:4');
+    const before = readCoefficients(input);
+
+    const after = readCoefficients(limitMcuBits(input, 120).jpeg);
+
+    after.forEach((component, index) => {
+      for (let base = 0; base < component.blocks.length; base += 64) {
+        assert.strictEqual(component.blocks[base], before[index].blocks[base]);
+        // Everything up to the last term kept is untouched; only a tail was zeroed
+        let last = 0;
+        for (let k = 1; k < 64; k++) {
+          if (component.blocks[base + k] !== 0) {
+            last = k;
+          }
+        }
+        for (let k = 1; k <= last; k++) {
+          assert.strictEqual(component.blocks[base + k], before[index].blocks[base + k], `block ${base / 64} term ${k}`);
+        }
+      }
+    });
+  });
+
+  it('should leave coefficients alone under a generous budget', () => {
+    const input = textured(32, 16, '4:2:0');
+
+    const { jpeg, droppedCoefficients, limitedMcus } = limitMcuBits(input, 100_000);
+
+    assert.deepStrictEqual([droppedCoefficients, limitedMcus], [0, 0]);
+    assert.deepStrictEqual(readCoefficients(jpeg).map((c) => c.blocks), readCoefficients(input).map((c) => c.blocks));
+  });
+
+  it('should reject a budget the DC terms cannot meet', () => {
+    assert.throws(() => limitMcuBits(textured(16, 16, '4:4:4'), 4), /DC terms alone/);
+    assert.throws(() => limitMcuBits(textured(16, 16, '4:4:4'), 0), /positive integer/);
+  });
+});
+
+describe('writeBaseline with supplied Huffman tables', () => {
+  it('should code with the given tables and reject incomplete ones', () => {
+    const file = parseJpeg(textured(16, 16, '4:4:4'));
+    const coefficients = readCoefficients(file);
+    const spec = {
+      width: 16,
+      height: 16,
+      components: coefficients.map((c, index) => ({ ...c, id: index + 1, h: 1, v: 1, quantId: index === 0 ? 0 : 1 })),
+      quantTables: [...file.scans[0].quantTables.values()],
+    };
+
+    const coded = parseJpeg(writeBaseline({ ...spec, huffmanTables: STANDARD_TABLES }));
+    assert.deepStrictEqual(coded.scans[0].acTables.get(0)?.symbols, STANDARD_TABLES[1].symbols);
+
+    const truncated = { ...STANDARD_TABLES[1], counts: Uint8Array.from([0, 2, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]) };
+    assert.throws(() => writeBaseline({ ...spec, huffmanTables: [STANDARD_TABLES[0], truncated, ...STANDARD_TABLES.slice(2)] }), /has no code for symbol/);
+    assert.throws(() => writeBaseline({ ...spec, huffmanTables: STANDARD_TABLES.slice(0, 2) }), /No DC Huffman table 1/);
+  });
+});
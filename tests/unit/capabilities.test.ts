@@ -0,0 +1,36 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { detectWasmFeatures, loadEngine, selectEngineVariant } from '../../src/capabilities.js';
+
+describe('detectWasmFeatures', () => {
+  it('should report SIMD and threads on current Node.js', () => {
+    assert.deepStrictEqual(detectWasmFeatures(), { simd: true, threads: true });
+  });
+});
+
+describe('selectEngineVariant', () => {
+  it('should prefer threads, then SIMD, then baseline', () => {
+    assert.strictEqual(selectEngineVariant({ simd: true, threads: true }), 'threads');
+    assert.strictEqual(selectEngineVariant({ simd: true, threads: false }), 'simd');
+    assert.strictEqual(selectEngineVariant({ simd: false, threads: true }), 'baseline');
+    assert.strictEqual(selectEngineVariant({ simd: true, threads: true }, ['baseline', 'simd']), 'simd');
+  });
+});
+
+describe('loadEngine', () => {
+  it('should load the selected build', async () => {
+    const loaded = await loadEngine(
+      { baseline: async () => 'base', simd: async () => 'simd' },
+      { simd: true, threads: true }
+    );
+    assert.deepStrictEqual(loaded, { variant: 'simd', engine: 'simd' });
+  });
+
+  it('should fall back to the baseline build when a build fails to load', async () => {
+    const loaded = await loadEngine(
+      { baseline: async () => 'base', threads: async () => { throw new Error('no COOP/COEP'); } },
+      { simd: true, threads: true }
+    );
+    assert.deepStrictEqual(loaded, { variant: 'baseline', engine: 'base' });
+  });
+});
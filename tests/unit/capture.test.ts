@@ -0,0 +1,189 @@
+import { afterEach, describe, it } from 'node:test';
+import assert from 'node:assert';
+import { FrameClock, captureCanvasFrames, captureVideoFrames } from '../../src/capture.js';
+
+describe('FrameClock', () => {
+  it('should wait for on-time slots', () => {
+    const clock = new FrameClock(10, 1000);
+    assert.deepStrictEqual(clock.next(1000), { wait: 0, dropped: 0, timestamp: 0 });
+    assert.deepStrictEqual(clock.next(1040), { wait: 60, dropped: 0, timestamp: 100 });
+  });
+
+  it('should count slots missed by a slow consumer as dropped', () => {
+    const clock = new FrameClock(10, 0);
+    clock.next(0);
+    assert.deepStrictEqual(clock.next(350), { wait: 0, dropped: 2, timestamp: 300 });
+    assert.deepStrictEqual(clock.next(400), { wait: 0, dropped: 0, timestamp: 400 });
+  });
+
+  it('should reject non-positive frame rates', () => {
+    assert.throws(() => new FrameClock(0, 0), /must be positive/);
+  });
+});
+
+describe('captureCanvasFrames', () => {
+  it('should read a 2D canvas directly when no track processor exists', async () => {
+    let reads = 0;
+    const canvas = {
+      width: 2,
+      height: 1,
+      getContext: () => ({
+        getImageData: (_x: number, _y: number, w: number, h: number) => {
+          reads++;
+          return { data: new Uint8ClampedArray(w * h * 4).fill(reads) };
+        },
+      }),
+    } as unknown as HTMLCanvasElement;
+
+    const frames = [];
+    for await (const frame of captureCanvasFrames(canvas, { fps: 1000, maxFrames: 3 })) {
+      frames.push(frame);
+    }
+
+    assert.strictEqual(frames.length, 3);
+    assert.deepStrictEqual(Array.from(frames[2].data), [3, 3, 3, 3, 3, 3, 3, 3]);
+    assert.deepStrictEqual([frames[0].width, frames[0].height, frames[0].delay], [2, 1, 1]);
+  });
+
+  it('should stop when aborted', async () => {
+    const controller = new AbortController();
+    const canvas = {
+      width: 1,
+      height: 1,
+      getContext: () => ({ getImageData: () => ({ data: new Uint8ClampedArray(4) }) }),
+    } as unknown as HTMLCanvasElement;
+
+    let count = 0;
+    for await (const _ of captureCanvasFrames(canvas, { fps: 1000, signal: controller.signal })) {
+      if (++count === 2) {
+        controller.abort();
+      }
+    }
+    assert.strictEqual(count, 2);
+  });
+
+  it('should read back in animation frames and release abort listeners', async () => {
+    const events: string[] = [];
+    const globals = globalThis as { requestAnimationFrame?: unknown; cancelAnimationFrame?: unknown };
+    globals.requestAnimationFrame = (callback: () => void) => setTimeout(() => { events.push('frame'); callback(); }, 1);
+    globals.cancelAnimationFrame = clearTimeout;
+    const canvas = {
+      width: 1,
+      height: 1,
+      getContext: () => ({ getImageData: () => { events.push('read'); return { data: new Uint8ClampedArray(4) }; } }),
+    } as unknown as HTMLCanvasElement;
+    const { signal } = new AbortController();
+    let listeners = 0;
+    const add = signal.addEventListener.bind(signal);
+    const remove = signal.removeEventListener.bind(signal);
+    signal.addEventListener = (...args: Parameters<typeof add>) => { listeners++; add(...args); };
+    signal.removeEventListener = (...args: Parameters<typeof remove>) => { listeners--; remove(...args); };
+
+    try {
+      for await (const _ of captureCanvasFrames(canvas, { fps: 200, maxFrames: 3, signal })) {
+        // Consume
+      }
+    } finally {
+      delete globals.requestAnimationFrame;
+      delete globals.cancelAnimationFrame;
+    }
+
+    assert.deepStrictEqual(events, ['frame', 'read', 'frame', 'read', 'frame', 'read']);
+    assert.strictEqual(listeners, 0);
+  });
+});
+
+describe('captureVideoFrames', () => {
+  class FakeOffscreenCanvas {
+    constructor(public width: number, public height: number) {}
+    getContext() {
+      let drawn = 0;
+      return {
+        canvas: this,
+        drawImage: (image: { value: number }) => { drawn = image.value; },
+        getImageData: (_x: number, _y: number, w: number, h: number) => ({ data: new Uint8ClampedArray(w * h * 4).fill(drawn) }),
+      };
+    }
+  }
+
+  function videoFrame(value: number, closed: number[]) {
+    return {
+      value,
+      displayWidth: 2,
+      displayHeight: 1,
+      timestamp: 1000 + value * 40000,
+      duration: 40000,
+      close: () => { closed.push(value); },
+    };
+  }
+
+  afterEach(() => {
+    delete (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas;
+  });
+
+  it('should deliver every frame under the queue policy', async () => {
+    (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas = FakeOffscreenCanvas;
+    const closed: number[] = [];
+    const stream = new ReadableStream({
+      start(controller) {
+        [1, 2, 3].forEach((v) => controller.enqueue(videoFrame(v, closed)));
+        controller.close();
+      },
+    });
+
+    const frames = [];
+    for await (const frame of captureVideoFrames(stream, { policy: 'queue' })) {
+      await new Promise((resolve) => setTimeout(resolve, 5));
+      frames.push(frame);
+    }
+
+    assert.deepStrictEqual(frames.map((f) => [f.data[0], f.timestamp, f.delay, f.dropped]), [[1, 0, 40, 0], [2, 40, 40, 0], [3, 80, 40, 0]]);
+    assert.deepStrictEqual(closed, [1, 2, 3]);
+  });
+
+  it('should drop stale frames under the latest policy', async () => {
+    (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas = FakeOffscreenCanvas;
+    const closed: number[] = [];
+    let push: (value: number) => void = () => {};
+    let end: () => void = () => {};
+    const stream = new ReadableStream({
+      start(controller) {
+        push = (v) => controller.enqueue(videoFrame(v, closed));
+        end = () => controller.close();
+      },
+    });
+
+    const seen: Array<[number, number]> = [];
+    push(1);
+    for await (const frame of captureVideoFrames(stream)) {
+      seen.push([frame.data[0], frame.dropped]);
+      if (frame.data[0] === 1) {
+        // Three frames arrive while this one is being "encoded"
+        push(2);
+        push(3);
+        push(4);
+        await new Promise((resolve) => setTimeout(resolve, 5));
+        end();
+      }
+    }
+
+    assert.deepStrictEqual(seen, [[1, 0], [4, 2]]);
+    assert.deepStrictEqual(closed.sort(), [1, 2, 3, 4]);
+  });
+
+  it('should stop after maxFrames', async () => {
+    (globalThis as { OffscreenCanvas?: unknown }).OffscreenCanvas = FakeOffscreenCanvas;
+    const closed: number[] = [];
+    const stream = new ReadableStream({
+      pull(controller) {
+        controller.enqueue(videoFrame(closed.length + 1, closed));
+      },
+    });
+
+    let count = 0;
+    for await (const _ of captureVideoFrames(stream, { policy: 'queue', maxFrames: 2 })) {
+      count++;
+    }
+    assert.strictEqual(count, 2);
+  });
+});
@@ -0,0 +1,104 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { createHash } from 'node:crypto';
+import { ChecksumSink, Crc32, Sha256, toBase64, toHex } from '../../src/checksum.js';
+import { CollectingSink, bufferSource, runPipeline } from '../../src/pipeline.js';
+import type { StripEncoder } from '../../src/pipeline.js';
+
+const ascii = (text: string): Uint8Array => new TextEncoder().encode(text);
+
+function pseudoRandom(length: number): Uint8Array {
+  const out = new Uint8Array(length);
+  let seed = 12345;
+  for (let i = 0; i < length; i++) {
+    seed = (seed * 1103515245 + 12345) >>> 0;
+    out[i] = seed >>> 24;
+  }
+  return out;
+}
+
+describe('Crc32', () => {
+  it('should match the standard check value', () => {
+    const crc = new Crc32();
+    crc.update(ascii('123456789'));
+    assert.strictEqual(toHex(crc.digest()), 'cbf43926');
+  });
+
+  it('should give the same result however the input is split', () => {
+    const data = pseudoRandom(1000);
+    const whole = new Crc32();
+    whole.update(data);
+    const pieces = new Crc32();
+    for (let i = 0; i < data.length; i += 37) {
+      pieces.update(data.subarray(i, i + 37));
+    }
+    assert.deepStrictEqual(pieces.digest(), whole.digest());
+  });
+});
+
+describe('Sha256', () => {
+  it('should match the FIPS 180-4 examples', () => {
+    const empty = new Sha256();
+    assert.strictEqual(toHex(empty.digest()), 'e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855');
+    const abc = new Sha256();
+    abc.update(ascii('abc'));
+    assert.strictEqual(toHex(abc.digest()), 'ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad');
+  });
+
+  it('should match node:crypto across block boundaries and chunk splits', () => {
+    for (const length of [55, 56, 63, 64, 65, 119, 1000]) {
+      const data = pseudoRandom(length);
+      const hash = new Sha256();
+      for (let i = 0; i < data.length; i += 13) {
+        hash.update(data.subarray(i, i + 13));
+      }
+      assert.strictEqual(toHex(hash.digest()), createHash('sha256').update(data).digest('hex'), `length ${length}`);
+    }
+  });
+
+  it('should keep accepting data after a digest', () => {
+    const hash = new Sha256();
+    hash.update(ascii('ab'));
+    hash.digest();
+    hash.update(ascii('c'));
+    assert.strictEqual(toHex(hash.digest()), createHash('sha256').update('abc').digest('hex'));
+  });
+});
+
+describe('toBase64', () => {
+  it('should pad like Buffer does', () => {
+    for (const length of [1, 2, 3, 4, 32]) {
+      const data = pseudoRandom(length);
+      assert.strictEqual(toBase64(data), Buffer.from(data).toString('base64'));
+    }
+  });
+});
+
+describe('ChecksumSink', () => {
+  it('should hash pipeline output while passing it on', async () => {
+    const encoder: StripEncoder = {
+      stripHeight: 8,
+      encodeStrip: (strip) => strip.subarray(0, 100),
+      finish: () => new Uint8Array([0xff, 0xd9]),
+    };
+    const inner = new CollectingSink();
+    const sink = new ChecksumSink(inner, 'crc32');
+
+    await runPipeline(bufferSource(pseudoRandom(16 * 32 * 4), 16, 32), encoder, sink);
+
+    const output = inner.toBytes();
+    const expected = new Crc32();
+    expected.update(output);
+    assert.strictEqual(sink.length, output.length);
+    assert.deepStrictEqual(sink.digest(), expected.digest());
+  });
+
+  it('should hash alone when there is no inner sink', async () => {
+    const sink = new ChecksumSink(undefined);
+    await sink.write(ascii('ab'));
+    await sink.write(ascii('c'));
+    await sink.close();
+    assert.strictEqual(sink.algorithm, 'sha256');
+    assert.strictEqual(toHex(sink.digest()), 'ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad');
+  });
+});
@@ -0,0 +1,37 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { CHROMA_DETAIL_THRESHOLD, ChromaAnalyzer } from '../../src/chroma.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+function analyze(rgba: Uint8Array, width: number, rowsPerStrip: number): ReturnType<ChromaAnalyzer['finish']> {
+  const analyzer = new ChromaAnalyzer(width);
+  for (let offset = 0; offset < rgba.length; offset += width * 4 * rowsPerStrip) {
+    analyzer.addStrip(rgba.subarray(offset, offset + width * 4 * rowsPerStrip));
+  }
+  return analyzer.finish();
+}
+
+describe('ChromaAnalyzer', () => {
+  it('should choose 4:2:0 for smooth color', () => {
+    const photo = rgbaPattern(64, 48, (x, y) => [100 + x, 80 + y, 150 - x]);
+
+    assert.deepStrictEqual(analyze(photo, 64, 8), { detail: 0, subsampling: '4:2:0' });
+  });
+
+  it('should keep 4:4:4 for one-pixel red strokes on white', () => {
+    const text = rgbaPattern(64, 48, (x, y) => (x % 6 === 1 && y > 8 && y < 40 ? [220, 0, 0] : [255, 255, 255]));
+
+    const analysis = analyze(text, 64, 8);
+
+    assert.strictEqual(analysis.subsampling, '4:4:4');
+    assert.ok(analysis.detail >= CHROMA_DETAIL_THRESHOLD);
+  });
+
+  it('should pair rows across strips of odd height', () => {
+    // Colors alternate by row, so every 2x2 block spans both
+    const stripes = rgbaPattern(8, 9, (_x, y) => (y % 2 === 0 ? [255, 0, 0] : [0, 0, 255]));
+
+    assert.deepStrictEqual(analyze(stripes, 8, 3), analyze(stripes, 8, 9));
+    assert.strictEqual(analyze(stripes, 8, 3).detail, 16 / 20);
+  });
+});
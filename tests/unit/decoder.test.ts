@@ -0,0 +1,109 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { decode, decodeDc, readCoefficients } from '../../src/decoder.js';
+import { psnr } from '../../src/selftest.js';
+import { DC_LUMA_COUNTS, DC_LUMA_SYMBOLS, AC_TINY_COUNTS, AC_TINY_SYMBOLS, grayscaleJpeg, packBits, segment } from '../utils/jpeg-fixtures.js';
+
+describe('decode', () => {
+  it('should reconstruct a flat block from its DC coefficient', () => {
+    // DC diff +8 (category 4) with a DC quantizer of 8 -> level shift + 64 / 8
+    const quant = new Array(64).fill(1);
+    quant[0] = 8;
+    const jpeg = grayscaleJpeg(8, 8, '101' + '1000' + '0', { quant });
+
+    const image = decode(jpeg);
+
+    assert.strictEqual(image.width, 8);
+    assert.strictEqual(image.height, 8);
+    for (let i = 0; i < image.data.length; i += 4) {
+      assert.deepStrictEqual(Array.from(image.data.subarray(i, i + 4)), [136, 136, 136, 255]);
+    }
+  });
+
+  it('should crop padding blocks outside the image', () => {
+    // Two blocks: DC 0, then DC diff -1 (category 1, bits '0')
+    const jpeg = grayscaleJpeg(9, 3, '00' + '0' + '010' + '0' + '0');
+
+    const image = decode(jpeg);
+
+    assert.strictEqual(image.data.length, 9 * 3 * 4);
+    assert.strictEqual(image.data[0], 128);
+    assert.strictEqual(image.data[8 * 4], 128);
+  });
+});
+
+describe('decodeDc', () => {
+  it('should turn each block into one pixel at its mean value', () => {
+    const quant = new Array(64).fill(2);
+    // DC 8, then a difference of -8 back to 0
+    const jpeg = grayscaleJpeg(16, 8, '101' + '1000' + '0' + '101' + '0111' + '0', { quant });
+
+    const image = decodeDc(jpeg);
+
+    assert.deepStrictEqual([image.width, image.height], [2, 1]);
+    assert.deepStrictEqual(Array.from(image.data), [130, 130, 130, 255, 128, 128, 128, 255]);
+  });
+
+  it('should match the full decode averaged over each block', () => {
+    const jpeg = grayscaleJpeg(16, 8, '00' + '10' + '1' + '0' + '010' + '1' + '0');
+
+    const full = decode(jpeg).data;
+    const dc = decodeDc(jpeg).data;
+
+    for (let block = 0; block < 2; block++) {
+      let sum = 0;
+      for (let y = 0; y < 8; y++) {
+        for (let x = 0; x < 8; x++) {
+          sum += full[(y * 16 + block * 8 + x) * 4];
+        }
+      }
+      assert.ok(Math.abs(sum / 64 - dc[block * 4]) <= 1, `Block ${block} mean should match its DC pixel`);
+    }
+  });
+});
+
+describe('readCoefficients', () => {
+  it('should return quantized zig-zag coefficients per block', () => {
+    const jpeg = grayscaleJpeg(16, 8, '00' + '10' + '1' + '0' + '010' + '1' + '0');
+
+    const [luma] = readCoefficients(jpeg);
+
+    assert.strictEqual(luma.componentId, 1);
+    assert.strictEqual(luma.blocksPerLine, 2);
+    assert.strictEqual(luma.blocks[1], 1);
+    assert.strictEqual(luma.blocks[64], 1);
+  });
+});
+
+describe('psnr', () => {
+  it('should be infinite for identical buffers and ignore alpha', () => {
+    assert.strictEqual(psnr([1, 2, 3, 0], [1, 2, 3, 255]), Infinity);
+  });
+
+  it('should match the closed form for a uniform error', () => {
+    assert.strictEqual(psnr([0, 0, 0, 255], [1, 1, 1, 255]).toFixed(2), (10 * Math.log10(255 * 255)).toFixed(2));
+  });
+});
+
+describe('readCoefficients (progressive)', () => {
+  it('should combine first and refinement scans of successive approximation', () => {
+    const bytes = [0xff, 0xd8];
+    bytes.push(...segment(0xdb, [0x00, ...new Array(64).fill(1)]));
+    bytes.push(...segment(0xc2, [8, 0, 8, 0, 8, 1, 1, 0x11, 0]));
+    bytes.push(...segment(0xc4, [0x00, ...DC_LUMA_COUNTS, ...DC_LUMA_SYMBOLS]));
+    // DC first scan, Al=1: 5 >> 1 = 2 (category 2, bits '10')
+    bytes.push(...segment(0xda, [1, 1, 0x00, 0, 0, 0x01]), ...packBits('011' + '10'));
+    // DC refinement, Ah=1 Al=0: low bit 1
+    bytes.push(...segment(0xda, [1, 1, 0x00, 0, 0, 0x10]), ...packBits('1'));
+    // AC first scan over 1-63, Al=1: 0x02 (size 2, value 3 -> 6), then EOB
+    bytes.push(...segment(0xc4, [0x10, ...AC_TINY_COUNTS, ...AC_TINY_SYMBOLS]));
+    bytes.push(...segment(0xda, [1, 1, 0x00, 1, 63, 0x01]), ...packBits('110' + '11' + '0'));
+    // AC refinement, Ah=1 Al=0: EOB run of 1 with a correction bit 1 for the nonzero coefficient
+    bytes.push(...segment(0xda, [1, 1, 0x00, 1, 63, 0x10]), ...packBits('0' + '1'));
+    bytes.push(0xff, 0xd9);
+
+    const [component] = readCoefficients(new Uint8Array(bytes));
+
+    assert.deepStrictEqual(Array.from(component.blocks.subarray(0, 3)), [5, 6 + 1, 0]);
+  });
+});
@@ -0,0 +1,43 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { toDicomBaseline } from '../../src/dicom.js';
+import { insertSegments } from '../../src/markers.js';
+import { readSegments } from '../../src/jpeg.js';
+import { decode } from '../../src/decoder.js';
+import { grayscaleJpeg, segment } from '../utils/jpeg-fixtures.js';
+
+describe('toDicomBaseline', () => {
+  it('should drop application and comment segments and order the tables', () => {
+    const base = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+    // Move a comment and an EXIF-style APP1 into the header, and a stray DQT after the DHTs
+    const withExif = insertSegments(base, [new Uint8Array(segment(0xe1, [0x45, 0x78, 0x69, 0x66, 0, 0]))]);
+    const sos = readSegments(withExif).find((s) => s.marker === 0xda)!.offset;
+    const withExtras = insertSegments(
+      withExif,
+      [new Uint8Array(segment(0xfe, [0x68, 0x69])), new Uint8Array(segment(0xdb, [0x01, ...new Array(64).fill(2)]))],
+      sos
+    );
+
+    const dicom = toDicomBaseline(withExtras);
+
+    assert.deepStrictEqual(readSegments(dicom).map((s) => s.marker), [0xd8, 0xdb, 0xdb, 0xc0, 0xc4, 0xc4, 0xda, 0xd9]);
+    assert.deepStrictEqual(decode(dicom).data, decode(base).data);
+  });
+
+  it('should keep application segments and comments on request', () => {
+    const jpeg = insertSegments(grayscaleJpeg(8, 8, '00' + '0'), [new Uint8Array(segment(0xfe, [0x68, 0x69]))], 2);
+
+    const markers = readSegments(toDicomBaseline(jpeg, { keepApplicationSegments: true, keepComments: true })).map((s) => s.marker);
+
+    assert.deepStrictEqual(markers.slice(0, 4), [0xd8, 0xe0, 0xfe, 0xdb]);
+  });
+
+  it('should enforce the restart policy', () => {
+    const plain = grayscaleJpeg(8, 8, '00' + '0');
+    const restarted = grayscaleJpeg(16, 8, ['00' + '0', '00' + '0'], { restartInterval: 1 });
+
+    assert.throws(() => toDicomBaseline(plain, { restart: 'required' }), /requires restart markers/);
+    assert.throws(() => toDicomBaseline(restarted, { restart: 'forbidden' }), /forbids restart markers/);
+    assert.deepStrictEqual(readSegments(toDicomBaseline(restarted, { restart: 'required' })).map((s) => s.marker).slice(-3), [0xdd, 0xda, 0xd9]);
+  });
+});
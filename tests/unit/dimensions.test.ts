@@ -0,0 +1,37 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { DimensionError, MAX_JPEG_DIMENSION, checkDimensions } from '../../src/dimensions.js';
+import { EncodeError } from '../../src/errors.js';
+
+describe('checkDimensions', () => {
+  it('should accept sizes from a single pixel up to the 16-bit SOF limit', () => {
+    assert.doesNotThrow(() => checkDimensions(1, 1));
+    assert.doesNotThrow(() => checkDimensions(1, MAX_JPEG_DIMENSION));
+    assert.doesNotThrow(() => checkDimensions(MAX_JPEG_DIMENSION, MAX_JPEG_DIMENSION));
+  });
+
+  it('should reject empty, negative, and fractional sizes', () => {
+    assert.throws(() => checkDimensions(0, 10), /positive integers, got 0x10/);
+    assert.throws(() => checkDimensions(10, -1), DimensionError);
+    assert.throws(() => checkDimensions(2.5, 4), DimensionError);
+    assert.throws(() => checkDimensions(NaN, 4), DimensionError);
+  });
+
+  it('should throw a DimensionError carrying the rejected size', () => {
+    assert.throws(() => checkDimensions(70000, 10), (error: unknown) => {
+      assert.ok(error instanceof DimensionError);
+      assert.deepStrictEqual([error.width, error.height], [70000, 10]);
+      assert.match(error.message, /65535x65535 pixels, got 70000x10; use encodeTiled/);
+      return true;
+    });
+    assert.throws(() => checkDimensions(1, 65536), DimensionError);
+  });
+
+  it('should be an EncodeError with the bad-dimensions code', () => {
+    assert.throws(() => checkDimensions(0, 0), (error: unknown) => {
+      assert.ok(error instanceof EncodeError);
+      assert.deepStrictEqual([error.code, error.name], ['bad-dimensions', 'DimensionError']);
+      return true;
+    });
+  });
+});
@@ -0,0 +1,33 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { RowDownscaler } from '../../src/downscale.js';
+
+describe('RowDownscaler', () => {
+  it('should average 2x2 boxes and emit each row once complete', () => {
+    const scaler = new RowDownscaler(4, 2, 2, 1);
+    const emitted: number[][] = [];
+    const emit = (row: Uint8Array): void => { emitted.push(Array.from(row)); };
+
+    scaler.push(new Uint8Array([0, 0, 0, 255, 10, 20, 30, 255, 100, 100, 100, 0, 100, 100, 100, 0]), emit);
+    assert.strictEqual(emitted.length, 0);
+    scaler.push(new Uint8Array([20, 40, 60, 255, 30, 60, 90, 255, 0, 0, 0, 0, 0, 0, 0, 0]), emit);
+
+    assert.deepStrictEqual(emitted, [[15, 30, 45, 255, 50, 50, 50, 0]]);
+  });
+
+  it('should handle uneven ratios without losing rows', () => {
+    const scaler = new RowDownscaler(3, 5, 2, 2);
+    const emitted: number[][] = [];
+
+    for (let y = 0; y < 5; y++) {
+      scaler.push(new Uint8Array(12).fill(y * 10), (row) => emitted.push(Array.from(row)));
+    }
+
+    assert.strictEqual(emitted.length, 2);
+    assert.deepStrictEqual(emitted.map((row) => row[0]), [10, 35]);
+  });
+
+  it('should refuse to upscale', () => {
+    assert.throws(() => new RowDownscaler(2, 2, 3, 2), /Cannot downscale/);
+  });
+});
@@ -0,0 +1,69 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { gifFrames, isGif, lzwDecode, readGifInfo } from '../../src/gif.js';
+import { gifFile, lzwLiterals } from '../utils/gif-fixtures.js';
+
+const PALETTE = [0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255];
+
+describe('lzwDecode', () => {
+  it('should decode literal codes across clear codes', () => {
+    const indices = [0, 1, 2, 3, 3, 2, 1, 0, 1, 1];
+    assert.deepStrictEqual(Array.from(lzwDecode(new Uint8Array(lzwLiterals(indices, 2)), 2, indices.length)), indices);
+  });
+
+  it('should expand table entries including the KwKwK case', () => {
+    // Codes (3 bits): clear, 1, 6 (= 1 1, not yet defined), 6, end
+    const codes = [4, 1, 6, 6, 5];
+    let buffer = 0;
+    codes.forEach((code, i) => { buffer |= code << (i * 3); });
+    const data = new Uint8Array([buffer & 0xff, (buffer >> 8) & 0xff]);
+    assert.deepStrictEqual(Array.from(lzwDecode(data, 2, 5)), [1, 1, 1, 1, 1]);
+  });
+});
+
+describe('readGifInfo', () => {
+  it('should read the logical screen and background color', () => {
+    const gif = gifFile(3, 2, PALETTE, [], 2);
+    assert.strictEqual(isGif(gif), true);
+    assert.deepStrictEqual(readGifInfo(gif), { width: 3, height: 2, background: [0, 255, 0] });
+  });
+
+  it('should reject other formats', () => {
+    assert.strictEqual(isGif(new Uint8Array([0x89, 0x50, 0x4e, 0x47, 0, 0])), false);
+    assert.throws(() => readGifInfo(new Uint8Array(16)), /Not a GIF/);
+  });
+});
+
+describe('gifFrames', () => {
+  it('should decode frames with timing, disposal, and transparency', () => {
+    const gif = gifFile(2, 2, PALETTE, [
+      { indices: [1, 2, 3, 0], width: 2, height: 2, delay: 5, disposal: 2 },
+      { indices: [0, 1], left: 1, top: 0, width: 1, height: 2, transparent: 0, disposal: 3 },
+    ]);
+    const frames = [...gifFrames(gif)];
+
+    assert.strictEqual(frames.length, 2);
+    assert.deepStrictEqual(Array.from(frames[0].data), [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 0, 0, 0, 255]);
+    assert.strictEqual(frames[0].delay, 50);
+    assert.strictEqual(frames[0].disposal, 'background');
+    assert.deepStrictEqual(
+      { left: frames[1].left, top: frames[1].top, width: frames[1].width, height: frames[1].height },
+      { left: 1, top: 0, width: 1, height: 2 }
+    );
+    assert.deepStrictEqual(Array.from(frames[1].data), [0, 0, 0, 0, 255, 0, 0, 255]);
+    assert.strictEqual(frames[1].disposal, 'previous');
+  });
+
+  it('should reorder interlaced rows and use local color tables', () => {
+    const gif = gifFile(1, 5, undefined, [{
+      // Stored pass order for 5 rows: 0, 4, 2, 1, 3
+      indices: [0, 3, 2, 1, 2],
+      width: 1,
+      height: 5,
+      interlaced: true,
+      palette: [0, 0, 0, 10, 10, 10, 20, 20, 20, 30, 30, 30],
+    }]);
+    const [frame] = [...gifFrames(gif)];
+    assert.deepStrictEqual(Array.from(frame.data).filter((_, i) => i % 4 === 0), [0, 10, 20, 20, 30]);
+  });
+});
@@ -0,0 +1,27 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { distortionHeatmap } from '../../src/heatmap.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('distortionHeatmap', () => {
+  it('should measure the error of each block against the source pixels', () => {
+    // Block 1 decodes to 128, block 2 to 136 (DC diff +8 with a DC quantizer of 8)
+    const quant = new Array(64).fill(1);
+    quant[0] = 8;
+    const jpeg = grayscaleJpeg(16, 8, '00' + '0' + '101' + '1000' + '0', { quant });
+    const pixels = new Uint8Array(16 * 8 * 4).fill(128);
+
+    const heatmap = distortionHeatmap(pixels, jpeg);
+
+    assert.strictEqual(heatmap.width, 2);
+    assert.strictEqual(heatmap.height, 1);
+    assert.deepStrictEqual(Array.from(heatmap.mse), [0, 64]);
+    assert.deepStrictEqual(Array.from(heatmap.data), [0, 255]);
+  });
+
+  it('should reject a source buffer smaller than the image', () => {
+    const jpeg = grayscaleJpeg(8, 8, '00' + '0');
+
+    assert.throws(() => distortionHeatmap(new Uint8Array(16), jpeg), /Buffer too small/);
+  });
+});
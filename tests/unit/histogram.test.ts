@@ -0,0 +1,73 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { huffmanHistogram, optimalHuffmanTables } from '../../src/histogram.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { writeBaseline } from '../../src/transcode.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('huffmanHistogram', () => {
+  it('should count DC categories and AC run/size symbols per component', () => {
+    // Block 1: DC diff 0, AC[1] = 1, EOB. Block 2: DC diff +1, EOB.
+    const jpeg = grayscaleJpeg(16, 8, '00' + '10' + '1' + '0' + '010' + '1' + '0');
+
+    const [luma] = huffmanHistogram(jpeg);
+
+    assert.strictEqual(luma.componentId, 1);
+    assert.strictEqual(luma.dcTable, 0);
+    assert.strictEqual(luma.acTable, 0);
+    assert.strictEqual(luma.dc[0], 1);
+    assert.strictEqual(luma.dc[1], 1);
+    assert.strictEqual(luma.ac[0x01], 1);
+    assert.strictEqual(luma.ac[0x00], 2, 'Each block should end with EOB');
+  });
+
+  it('should continue across restart intervals', () => {
+    const jpeg = grayscaleJpeg(16, 8, ['00' + '0', '010' + '1' + '0'], { restartInterval: 1 });
+
+    const [luma] = huffmanHistogram(jpeg);
+
+    assert.strictEqual(luma.dc[0], 1);
+    assert.strictEqual(luma.dc[1], 1);
+    assert.strictEqual(luma.ac[0x00], 2);
+  });
+
+  it('should reject streams that are not JPEG', () => {
+    assert.throws(() => huffmanHistogram(new Uint8Array([0x89, 0x50, 0x4e, 0x47])), /missing SOI/);
+  });
+});
+
+describe('optimalHuffmanTables', () => {
+  const rgba = new Uint8Array(40 * 24 * 4).map((_, i) => (i * 29) % 253);
+  const encoder = createLibjpegStripEncoder(40, 24, { quality: 85, subsampling: '4:4:4' });
+  const jpeg = new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+
+  it('should merge components that share a table, DC tables first', () => {
+    const tables = optimalHuffmanTables(huffmanHistogram(jpeg));
+    assert.deepStrictEqual(tables.map((t) => [t.tableClass, t.id]), [[0, 0], [0, 1], [1, 0], [1, 1]]);
+  });
+
+  it('should give tables a second pass codes the image with in fewer bytes', () => {
+    const file = parseJpeg(jpeg);
+    const coefficients = readCoefficients(file);
+    const recoded = writeBaseline({
+      width: 40,
+      height: 24,
+      components: file.frame.components.map((c, index) => ({
+        id: c.id,
+        h: c.h,
+        v: c.v,
+        quantId: c.quantTable,
+        blocksPerLine: coefficients[index].blocksPerLine,
+        blocksPerColumn: coefficients[index].blocksPerColumn,
+        blocks: coefficients[index].blocks,
+      })),
+      quantTables: [...file.scans[0].quantTables.values()],
+      huffmanTables: optimalHuffmanTables(huffmanHistogram(jpeg)),
+    });
+
+    assert.ok(recoded.length < jpeg.length, `${recoded.length} should be below ${jpeg.length}`);
+    assert.deepStrictEqual(decode(recoded).data, decode(jpeg).data);
+  });
+});
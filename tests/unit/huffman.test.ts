@@ -0,0 +1,76 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { BitWriter, buildHuffmanCodes, huffmanTablePayload, magnitudeCategory, optimalHuffmanSpec } from '../../src/huffman.js';
+import { DC_LUMA_COUNTS, DC_LUMA_SYMBOLS } from '../utils/jpeg-fixtures.js';
+
+function codeLengths(counts: Uint8Array, symbols: Uint8Array): Map<number, number> {
+  const lengths = new Map<number, number>();
+  let k = 0;
+  counts.forEach((count, i) => {
+    for (let j = 0; j < count; j++) {
+      lengths.set(symbols[k++], i + 1);
+    }
+  });
+  return lengths;
+}
+
+describe('buildHuffmanCodes', () => {
+  it('should assign canonical codes in length order', () => {
+    const table = buildHuffmanCodes({ tableClass: 0, id: 0, counts: Uint8Array.from(DC_LUMA_COUNTS), symbols: Uint8Array.from(DC_LUMA_SYMBOLS) });
+
+    assert.deepStrictEqual([table.codes[0], table.lengths[0]], [0b00, 2]);
+    assert.deepStrictEqual([table.codes[1], table.lengths[1]], [0b010, 3]);
+    assert.deepStrictEqual([table.codes[6], table.lengths[6]], [0b1110, 4]);
+    assert.strictEqual(table.lengths[12], 0);
+  });
+});
+
+describe('optimalHuffmanSpec', () => {
+  it('should give frequent symbols shorter codes and leave the all-ones code unused', () => {
+    const frequencies = new Array(256).fill(0);
+    frequencies[0x00] = 100;
+    frequencies[0x01] = 50;
+    frequencies[0x11] = 10;
+    frequencies[0xf0] = 1;
+
+    const spec = optimalHuffmanSpec(frequencies, 1, 0);
+    const lengths = codeLengths(spec.counts, spec.symbols);
+
+    assert.deepStrictEqual([...lengths.keys()].sort((a, b) => a - b), [0x00, 0x01, 0x11, 0xf0]);
+    assert.ok(lengths.get(0x00)! <= lengths.get(0x01)! && lengths.get(0x01)! <= lengths.get(0x11)!);
+    const kraft = [...lengths.values()].reduce((sum, length) => sum + 2 ** -length, 0);
+    assert.ok(kraft < 1, 'A code point must remain for the reserved symbol');
+  });
+
+  it('should limit code lengths to 16 bits', () => {
+    // Fibonacci frequencies produce a maximally skewed tree
+    const frequencies = new Array(256).fill(0);
+    let [a, b] = [1, 1];
+    for (let i = 0; i < 30; i++) {
+      frequencies[i] = a;
+      [a, b] = [b, a + b];
+    }
+
+    const spec = optimalHuffmanSpec(frequencies, 0, 1);
+
+    assert.strictEqual(spec.symbols.length, 30);
+    assert.strictEqual(spec.counts.reduce((sum, count) => sum + count, 0), 30);
+    assert.ok([...codeLengths(spec.counts, spec.symbols).values()].every((length) => length <= 16));
+    assert.deepStrictEqual(Array.from(huffmanTablePayload([spec]).subarray(0, 1)), [0x01]);
+  });
+});
+
+describe('BitWriter', () => {
+  it('should stuff 0xFF bytes and pad with 1-bits', () => {
+    const writer = new BitWriter();
+    writer.writeBits(0xff, 8);
+    writer.writeBits(0, 1);
+    writer.writeMagnitude(-3, 2);
+
+    assert.deepStrictEqual(Array.from(writer.finish()), [0xff, 0x00, 0b00011111]);
+  });
+
+  it('should report magnitude categories', () => {
+    assert.deepStrictEqual([0, 1, -1, 2, -3, 255, -1024].map(magnitudeCategory), [0, 1, 1, 2, 2, 8, 11]);
+  });
+});
@@ -0,0 +1,34 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { addJpsDescriptor, jpsSegment, sideBySide } from '../../src/jps.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('jpsSegment', () => {
+  it('should describe a side-by-side stereo image', () => {
+    const segment = jpsSegment({ leftFirst: true, separation: 12, comment: 'rig A' });
+
+    assert.strictEqual(segment[1], 0xe3);
+    assert.strictEqual(String.fromCharCode(...segment.subarray(4, 12)), '_JPSJPS_');
+    assert.deepStrictEqual(Array.from(segment.subarray(12, 18)), [0, 4, 12, 0x02, 0x04, 0x01]);
+    assert.strictEqual(String.fromCharCode(...segment.subarray(20)), 'rig A');
+  });
+});
+
+describe('addJpsDescriptor', () => {
+  it('should insert the descriptor after APP0', () => {
+    const jps = addJpsDescriptor(grayscaleJpeg(8, 8, '00' + '0'));
+
+    assert.deepStrictEqual(parseJpeg(jps).segments.slice(0, 3).map((s) => s.marker), [0xd8, 0xe0, 0xe3]);
+  });
+});
+
+describe('sideBySide', () => {
+  it('should put the right view first by default', () => {
+    const left = new Uint8Array([1, 1, 1, 255]);
+    const right = new Uint8Array([2, 2, 2, 255]);
+
+    assert.deepStrictEqual(Array.from(sideBySide(left, right, 1, 1)), [2, 2, 2, 255, 1, 1, 1, 255]);
+    assert.deepStrictEqual(Array.from(sideBySide(left, right, 1, 1, true)), [1, 1, 1, 255, 2, 2, 2, 255]);
+  });
+});
@@ -0,0 +1,83 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { applyFrameLayout, validateFrameLayout } from '../../src/layout.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { validate } from '../../src/validate.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// 4:4:4 encode of a colored pattern whose size is not a multiple of the MCU
+function encoded(width: number, height: number): Uint8Array {
+  const rgba = rgbaPattern(width, height, (x, y) => [(x * 9) & 0xff, (y * 13) & 0xff, ((x + y) * 5) & 0xff]);
+  const encoder = createLibjpegStripEncoder(width, height, { quality: 85, subsampling: '4:4:4' });
+  return new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+}
+
+describe('validateFrameLayout', () => {
+  it('should report the subsampling the factors describe', () => {
+    assert.strictEqual(validateFrameLayout({ components: [{ id: 89, h: 3, v: 2 }] }), '4:4:4');
+    assert.strictEqual(validateFrameLayout({ components: [{ id: 1, h: 2, v: 2 }, { id: 2 }, { id: 3 }] }), '4:2:0');
+    assert.strictEqual(validateFrameLayout({ components: [{ id: 1, h: 2 }, { id: 2, h: 2 }, { id: 3, h: 2 }] }), '4:4:4');
+  });
+
+  it('should reject inconsistent layouts', () => {
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1 }, { id: 2 }] }), /needs 1 \(Y\) or 3/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1 }, { id: 1 }, { id: 3 }] }), /Component 1 repeats id 1/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 256 }] }), /Component 0 id must be an integer 0-255, got 256/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1, v: 5 }] }), /vertical sampling factor must be an integer 1-4, got 5/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1, huffmanTable: 2 }] }), /Huffman table must be an integer 0-1/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1, h: 2, v: 2 }, { id: 2, h: 2, v: 2 }, { id: 3, h: 2, v: 2 }] }), /12 blocks; at most 10/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1, h: 2 }, { id: 2 }, { id: 3, h: 2 }] }), /same sampling factors/);
+    assert.throws(() => validateFrameLayout({ components: [{ id: 1, h: 3 }, { id: 2 }, { id: 3 }] }), /3x1 for Y and 1x1 for chroma match no supported subsampling/);
+  });
+});
+
+describe('applyFrameLayout', () => {
+  it('should keep only luma under a nonstandard id', () => {
+    const jpeg = encoded(20, 12);
+
+    const gray = applyFrameLayout(jpeg, { components: [{ id: 0x59 }] });
+
+    assert.strictEqual(validate(gray).valid, true);
+    assert.deepStrictEqual(parseJpeg(gray).frame.components.map((c) => c.id), [0x59]);
+    assert.deepStrictEqual(readCoefficients(gray)[0].blocks, readCoefficients(jpeg)[0].blocks);
+  });
+
+  it('should widen grids to larger MCUs without changing the image', () => {
+    const jpeg = encoded(20, 12);
+
+    const wide = applyFrameLayout(jpeg, { components: [{ id: 1, h: 2 }, { id: 2, h: 2 }, { id: 3, h: 2 }] });
+
+    assert.strictEqual(validate(wide).valid, true);
+    assert.deepStrictEqual(parseJpeg(wide).frame.components.map((c) => [c.h, c.v]), [[2, 1], [2, 1], [2, 1]]);
+    assert.deepStrictEqual(decode(wide).data, decode(jpeg).data);
+  });
+
+  it('should subsample and share table slots as assigned', () => {
+    const jpeg = encoded(24, 24);
+
+    const shared = applyFrameLayout(jpeg, {
+      components: [{ id: 1, h: 2, v: 2 }, { id: 2, quantTable: 0, huffmanTable: 0 }, { id: 3, quantTable: 0, huffmanTable: 0 }],
+    });
+
+    assert.strictEqual(validate(shared).valid, true);
+    const file = parseJpeg(shared);
+    assert.deepStrictEqual(file.frame.components.map((c) => [c.h, c.v, c.quantTable]), [[2, 2, 0], [1, 1, 0], [1, 1, 0]]);
+    assert.deepStrictEqual([...file.scans[0].quantTables.keys()], [0]);
+    assert.deepStrictEqual([...file.scans[0].dcTables.keys(), ...file.scans[0].acTables.keys()], [0, 0]);
+    const original = decode(jpeg).data;
+    const result = decode(shared).data;
+    let error = 0;
+    for (let i = 0; i < original.length; i++) {
+      error += Math.abs(original[i] - result[i]);
+    }
+    assert.ok(error / original.length < 6, `mean error ${error / original.length}`);
+  });
+
+  it('should refuse a layout the image cannot supply', () => {
+    const gray = applyFrameLayout(encoded(8, 8), { components: [{ id: 1 }] });
+
+    assert.throws(() => applyFrameLayout(gray, { components: [{ id: 1 }, { id: 2 }, { id: 3 }] }), /has 1 component\(s\); the layout needs 3/);
+  });
+});
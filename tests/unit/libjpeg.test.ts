@@ -0,0 +1,253 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { ISLOW_TRANSFORM, LIBJPEG_QUANTIZER, STANDARD_TABLES, createLibjpegStripEncoder, createLibjpegYuvEncoder, encodeYuv, islowForwardDct, libjpegQuantTables, sharedStandardCodes } from '../../src/libjpeg.js';
+import { buildHuffmanCodes } from '../../src/huffman.js';
+import type { LibjpegEncoderOptions } from '../../src/libjpeg.js';
+import { readSegments } from '../../src/jpeg.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { toProgressive } from '../../src/progressive.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// 16x16 gradient: R = 16x, G = 16y, B = 8(x + y)
+const gradient = (x: number, y: number): number[] => [x * 16, y * 16, (x + y) * 8];
+
+// Entropy-coded data libjpeg writes for the gradient with `cjpeg -quality 75` (4:2:0)
+const LIBJPEG_SCAN = 'f37d3b40e9f2575da7681d3e4fd2ba4d3b40e9f27e95d769da074f928a554321cfb6d4ffd9';
+
+function encodeRows(rgba: Uint8Array, width: number, height: number, rowsPerStrip: number, quality: number, options: LibjpegEncoderOptions = {}): Uint8Array {
+  const encoder = createLibjpegStripEncoder(width, height, { quality, ...options });
+  const chunks: number[] = [];
+  for (let y = 0; y < height; y += rowsPerStrip) {
+    chunks.push(...encoder.encodeStrip(rgba.subarray(y * width * 4, Math.min(height, y + rowsPerStrip) * width * 4)));
+  }
+  chunks.push(...encoder.finish());
+  return new Uint8Array(chunks);
+}
+
+describe('libjpegQuantTables', () => {
+  it('should scale the Annex K tables as jpeg_set_quality does', () => {
+    const [luma50, chroma50] = libjpegQuantTables(50);
+    assert.deepStrictEqual(Array.from(luma50.subarray(0, 8)), [16, 11, 10, 16, 24, 40, 51, 61]);
+    assert.strictEqual(chroma50[63], 99);
+
+    const [luma75] = libjpegQuantTables(75);
+    assert.deepStrictEqual(Array.from(luma75.subarray(0, 4)), [8, 6, 5, 8]);
+    assert.ok(libjpegQuantTables(100).every((table) => table.every((v) => v === 1)));
+    assert.strictEqual(libjpegQuantTables(1)[1][63], 255);
+  });
+});
+
+describe('STANDARD_TABLES', () => {
+  it('should hold the complete Annex K tables, with a code for every baseline symbol', () => {
+    assert.deepStrictEqual(STANDARD_TABLES.map((t) => t.symbols.length), [12, 162, 12, 162]);
+    // DC categories 0-11; AC EOB, ZRL, and every run 0-15 with size 1-10
+    const acSymbols = [0x00, 0xf0, ...Array.from({ length: 160 }, (_, i) => ((i / 10) << 4) | ((i % 10) + 1))];
+    STANDARD_TABLES.forEach((spec) => {
+      const code = buildHuffmanCodes(spec);
+      const symbols = spec.tableClass === 0 ? Array.from({ length: 12 }, (_, i) => i) : acSymbols;
+      symbols.forEach((symbol) => assert.ok(code.lengths[symbol] > 0, `no code for 0x${symbol.toString(16)}`));
+      assert.ok(symbols.every((symbol) => code.lengths[symbol] <= 16));
+    });
+  });
+});
+
+describe('sharedStandardCodes', () => {
+  it('should build the standard code tables once for every encoder', () => {
+    const rgba = rgbaPattern(16, 16, gradient);
+    const codes = sharedStandardCodes();
+    const first = createLibjpegStripEncoder(16, 16, { quality: 75 });
+    const second = createLibjpegStripEncoder(16, 16, { quality: 75 });
+
+    // Interleaved encoders read the same tables without disturbing each other
+    const chunks = [first.encodeStrip(rgba.subarray(0, 8 * 64)), second.encodeStrip(rgba.subarray(0, 8 * 64))];
+    chunks.push(first.encodeStrip(rgba.subarray(8 * 64)), second.encodeStrip(rgba.subarray(8 * 64)));
+    const a = new Uint8Array([...chunks[0], ...chunks[2], ...first.finish()]);
+    const b = new Uint8Array([...chunks[1], ...chunks[3], ...second.finish()]);
+
+    assert.strictEqual(sharedStandardCodes(), codes);
+    assert.deepStrictEqual(codes, STANDARD_TABLES.map(buildHuffmanCodes));
+    assert.deepStrictEqual(a, b);
+    assert.deepStrictEqual(a, encodeRows(rgba, 16, 16, 16, 75));
+  });
+});
+
+describe('islowForwardDct', () => {
+  it('should leave a flat block as a DC value scaled by 8', () => {
+    const block = new Int32Array(64).fill(10);
+
+    islowForwardDct(block);
+
+    assert.strictEqual(block[0], 640);
+    assert.ok(block.subarray(1).every((v) => v === 0));
+  });
+});
+
+describe('createLibjpegStripEncoder', () => {
+  it('should write the same scan as libjpeg', () => {
+    const jpeg = encodeRows(rgbaPattern(16, 16, gradient), 16, 16, 16, 75);
+
+    const segments = readSegments(jpeg);
+    assert.deepStrictEqual(segments.map((s) => s.marker), [0xd8, 0xe0, 0xdb, 0xdb, 0xc0, 0xc4, 0xc4, 0xc4, 0xc4, 0xda, 0xd9]);
+    assert.deepStrictEqual(Array.from(segments[4].data.subarray(5)), [3, 1, 0x22, 0, 2, 0x11, 1, 3, 0x11, 1]);
+    const sos = segments[9];
+    assert.strictEqual(Buffer.from(jpeg.subarray(sos.offset + sos.length)).toString('hex'), LIBJPEG_SCAN);
+  });
+
+  it('should produce the same bytes whatever the strip height', () => {
+    const rgba = new Uint8Array(23 * 19 * 4).map((_, i) => (i * 37) % 251);
+
+    const whole = encodeRows(rgba, 23, 19, 19, 80);
+
+    assert.deepStrictEqual(encodeRows(rgba, 23, 19, 5, 80), whole);
+    assert.deepStrictEqual(encodeRows(rgba, 23, 19, 1, 80), whole);
+    const decoded = decode(whole);
+    assert.deepStrictEqual([decoded.width, decoded.height], [23, 19]);
+    assert.deepStrictEqual(decode(toProgressive(whole)).data, decoded.data);
+  });
+
+  it('should hold output until finish and build tables from the image with optimizeCoding', () => {
+    const rgba = rgbaPattern(16, 16, gradient);
+    const encoder = createLibjpegStripEncoder(16, 16, { quality: 75, optimizeCoding: true });
+
+    assert.strictEqual(encoder.encodeStrip(rgba).length, 0);
+    const optimized = encoder.finish();
+
+    const standard = encodeRows(rgba, 16, 16, 16, 75);
+    assert.deepStrictEqual(readSegments(optimized).map((s) => s.marker), readSegments(standard).map((s) => s.marker));
+    assert.ok(optimized.length < standard.length, `${optimized.length} should be below ${standard.length}`);
+    assert.deepStrictEqual(decode(optimized).data, decode(standard).data);
+  });
+
+  it('should run custom transform and quantizer stages', () => {
+    const rgba = new Uint8Array(23 * 19 * 4).map((_, i) => (i * 37) % 251);
+    const standard = encodeRows(rgba, 23, 19, 19, 80);
+    assert.deepStrictEqual(encodeRows(rgba, 23, 19, 19, 80, { transform: ISLOW_TRANSFORM, quantizer: LIBJPEG_QUANTIZER }), standard);
+
+    // Keep luma as libjpeg would and drop every chroma AC term
+    const components = new Set<number>();
+    const flatChroma = encodeRows(rgba, 23, 19, 19, 80, {
+      quantizer: {
+        quantize(coefficients, table, out, offset, component): void {
+          components.add(component);
+          LIBJPEG_QUANTIZER.quantize(coefficients, table, out, offset, component);
+          if (component > 0) {
+            out.fill(0, offset + 1, offset + 64);
+          }
+        },
+      },
+    });
+
+    assert.deepStrictEqual([...components].sort(), [0, 1, 2]);
+    const [luma, cb, cr] = readCoefficients(flatChroma);
+    assert.deepStrictEqual(luma.blocks, readCoefficients(standard)[0].blocks);
+    assert.ok([cb, cr].every((c) => c.blocks.every((value, i) => i % 64 === 0 || value === 0)));
+
+    // A transform that keeps only the block mean gives DC-only blocks everywhere
+    const meanOnly = encodeRows(rgba, 23, 19, 19, 80, {
+      transform: {
+        forward(data): void {
+          ISLOW_TRANSFORM.forward(data);
+          data.fill(0, 1);
+        },
+      },
+    });
+    assert.ok(readCoefficients(meanOnly).every((c) => c.blocks.every((value, i) => i % 64 === 0 || value === 0)));
+  });
+
+  it('should write only luma in grayscale mode', () => {
+    const rgba = new Uint8Array(23 * 19 * 4).map((_, i) => (i * 37) % 251);
+
+    const gray = encodeRows(rgba, 23, 19, 8, 80, { grayscale: true, subsampling: '4:2:0' });
+
+    const segments = readSegments(gray);
+    assert.deepStrictEqual(segments.map((s) => s.marker), [0xd8, 0xe0, 0xdb, 0xc0, 0xc4, 0xc4, 0xda, 0xd9]);
+    assert.deepStrictEqual(Array.from(segments[3].data.subarray(5)), [1, 1, 0x11, 0]);
+    // The same luma blocks as a color encode, and the same with optimized tables
+    const color = encodeRows(rgba, 23, 19, 8, 80, { subsampling: '4:4:4' });
+    assert.deepStrictEqual(readCoefficients(gray).map((c) => c.blocks), [readCoefficients(color)[0].blocks]);
+    const optimized = encodeRows(rgba, 23, 19, 8, 80, { grayscale: true, optimizeCoding: true });
+    assert.deepStrictEqual(decode(optimized).data, decode(gray).data);
+    assert.ok(optimized.length < gray.length);
+  });
+
+  it('should reject missing rows and extra rows', () => {
+    const encoder = createLibjpegStripEncoder(8, 8);
+    encoder.encodeStrip(new Uint8Array(8 * 4 * 4));
+    assert.throws(() => encoder.finish(), /after 4 of 8 rows/);
+    assert.throws(() => encoder.encodeStrip(new Uint8Array(8 * 5 * 4)), /more than the 8 rows/);
+  });
+});
+
+// Y, Cb and Cr with libjpeg's fixed-point conversion, chroma downsampled h2v2 as jcsample does
+function toI420(rgba: Uint8Array, width: number, height: number): { y: Uint8Array; u: Uint8Array; v: Uint8Array } {
+  const fix = (value: number): number => Math.floor(value * 65536 + 0.5);
+  const offset = 128 * 65536 + 32767;
+  const y = new Uint8Array(width * height);
+  const full = [new Uint8Array(width * height), new Uint8Array(width * height)];
+  for (let i = 0; i < width * height; i++) {
+    const [r, g, b] = rgba.subarray(i * 4, i * 4 + 3);
+    y[i] = (fix(0.299) * r + fix(0.587) * g + fix(0.114) * b + 32768) >> 16;
+    full[0][i] = (-fix(0.16874) * r - fix(0.33126) * g + fix(0.5) * b + offset) >> 16;
+    full[1][i] = (fix(0.5) * r - fix(0.41869) * g - fix(0.08131) * b + offset) >> 16;
+  }
+  const chromaWidth = Math.ceil(width / 2);
+  const chromaHeight = Math.ceil(height / 2);
+  const [u, v] = full.map((plane) => {
+    const at = (x: number, row: number): number => plane[Math.min(row, height - 1) * width + Math.min(x, width - 1)];
+    const out = new Uint8Array(chromaWidth * chromaHeight);
+    for (let row = 0; row < chromaHeight; row++) {
+      for (let x = 0; x < chromaWidth; x++) {
+        const sum = at(2 * x, 2 * row) + at(2 * x + 1, 2 * row) + at(2 * x, 2 * row + 1) + at(2 * x + 1, 2 * row + 1);
+        out[row * chromaWidth + x] = (sum + 1 + (x & 1)) >> 2;
+      }
+    }
+    return out;
+  });
+  return { y, u, v };
+}
+
+describe('createLibjpegYuvEncoder', () => {
+  // Chroma exactly two blocks wide: past the right edge libjpeg pads before downsampling
+  const rgba = new Uint8Array(32 * 19 * 4).map((_, i) => (i * 37) % 251);
+  const { y, u, v } = toI420(rgba, 32, 19);
+  const expected = encodeRows(rgba, 32, 19, 19, 80, { subsampling: '4:2:0' });
+
+  it('should write what the RGBA encoder writes for the same samples', () => {
+    assert.deepStrictEqual(encodeYuv({ width: 32, height: 19, y, u, v }, { quality: 80 }), expected);
+
+    // In strips of 16 rows
+    const encoder = createLibjpegYuvEncoder(32, 19, { quality: 80 });
+    const chunks = [
+      ...encoder.encodeStrip({ y: y.subarray(0, 16 * 32), u: u.subarray(0, 8 * 16), v: v.subarray(0, 8 * 16) }),
+      ...encoder.encodeStrip({ y: y.subarray(16 * 32), u: u.subarray(8 * 16), v: v.subarray(8 * 16) }),
+      ...encoder.finish(),
+    ];
+    assert.deepStrictEqual(new Uint8Array(chunks), expected);
+  });
+
+  it('should read interleaved NV12 chroma and padded rows', () => {
+    // Rows padded to 40 bytes, and planes running on past the frame as aligned decoder output does
+    const yPadded = new Uint8Array(40 * 24).fill(0x5a);
+    const uvPadded = new Uint8Array(40 * 12).fill(0x5a);
+    for (let row = 0; row < 19; row++) {
+      yPadded.set(y.subarray(row * 32, (row + 1) * 32), row * 40);
+    }
+    for (let row = 0; row < 10; row++) {
+      for (let x = 0; x < 16; x++) {
+        uvPadded.set([u[row * 16 + x], v[row * 16 + x]], row * 40 + x * 2);
+      }
+    }
+
+    const jpeg = encodeYuv({ width: 32, height: 19, y: yPadded, uv: uvPadded, yStride: 40, chromaStride: 40 }, { quality: 80 });
+
+    assert.deepStrictEqual(jpeg, expected);
+  });
+
+  it('should reject strips that split an iMCU row and mixed chroma planes', () => {
+    const encoder = createLibjpegYuvEncoder(32, 19);
+    assert.throws(() => encoder.encodeStrip({ y: y.subarray(0, 8 * 32), u, v }), /strips hold multiples of 16 rows/);
+    assert.throws(() => encoder.encodeStrip({ y, u, v, uv: u }), /either u and v planes or an interleaved uv plane/);
+    assert.throws(() => encoder.encodeStrip({ y, u: u.subarray(0, 10), v }), /need at least 160 bytes/);
+    assert.throws(() => encoder.finish(), /after 0 of 19 rows/);
+  });
+});
@@ -0,0 +1,69 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { createRowFlushEncoder } from '../../src/lowlatency.js';
+import { decodePartial } from '../../src/recovery.js';
+import { decode } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { concatBytes } from '../../src/pipeline.js';
+import type { BandEncoderFactory } from '../../src/lowlatency.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const WIDTH = 24;
+const HEIGHT = 30;
+
+const pixels = (x: number, y: number, width: number): number[] => {
+  const i = y * width + x;
+  return [i * 5, i >> 1, i * 11];
+};
+
+const bands: BandEncoderFactory = (width, rows) => createLibjpegStripEncoder(width, rows, { quality: 75, subsampling: '4:4:4' });
+
+describe('createRowFlushEncoder', () => {
+  const rgba = rgbaPattern(WIDTH, HEIGHT, pixels);
+  const rowBytes = WIDTH * 4;
+
+  it('should emit each band complete, ending in a restart marker', () => {
+    const encoder = createRowFlushEncoder(WIDTH, HEIGHT, bands);
+    const chunks: Uint8Array[] = [];
+    for (let y = 0; y < HEIGHT; y += encoder.stripHeight) {
+      chunks.push(encoder.encodeStrip(rgba.subarray(y * rowBytes, Math.min(y + encoder.stripHeight, HEIGHT) * rowBytes)));
+    }
+    chunks.push(encoder.finish());
+
+    assert.strictEqual(chunks.length, 5);
+    chunks.slice(0, 3).forEach((chunk, index) => {
+      assert.deepStrictEqual(Array.from(chunk.subarray(-2)), [0xff, 0xd0 + index]);
+    });
+    assert.deepStrictEqual(Array.from(chunks[3].subarray(-2)), [0xff, 0xd9]);
+    assert.strictEqual(chunks[4].length, 0);
+
+    // Every prefix ending on a chunk shows the bands received so far
+    const jpeg = concatBytes(chunks);
+    const file = parseJpeg(jpeg);
+    assert.deepStrictEqual([file.frame.height, file.scans[0].restartInterval], [HEIGHT, 3]);
+    assert.strictEqual(decodePartial(concatBytes(chunks.slice(0, 2))).height, 16);
+    assert.strictEqual(decode(jpeg).height, HEIGHT);
+  });
+
+  it('should match bands encoded separately, whatever the strip sizes', () => {
+    const whole = createRowFlushEncoder(WIDTH, HEIGHT, bands, 16);
+    const expected = concatBytes([whole.encodeStrip(rgba), whole.finish()]);
+
+    const uneven = createRowFlushEncoder(WIDTH, HEIGHT, bands, 16);
+    const chunks: Uint8Array[] = [];
+    for (const [from, to] of [[0, 5], [5, 21], [21, 30]]) {
+      chunks.push(uneven.encodeStrip(rgba.subarray(from * rowBytes, to * rowBytes)));
+    }
+    chunks.push(uneven.finish());
+
+    assert.deepStrictEqual(Array.from(concatBytes(chunks)), Array.from(expected));
+  });
+
+  it('should reject bands that split MCUs and an early finish', () => {
+    const subsampled: BandEncoderFactory = (width, rows) => createLibjpegStripEncoder(width, rows, { subsampling: '4:2:0' });
+    assert.throws(() => createRowFlushEncoder(WIDTH, HEIGHT, bands, 12), /positive multiple of 8/);
+    assert.throws(() => createRowFlushEncoder(WIDTH, HEIGHT, subsampled).encodeStrip(rgba.subarray(0, 8 * rowBytes)), /whole 16-row MCUs/);
+    assert.throws(() => createRowFlushEncoder(WIDTH, HEIGHT, bands).finish(), /after 0 of 30 rows/);
+  });
+});
@@ -0,0 +1,26 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { applicationInsertOffset, createSegment, insertSegments } from '../../src/markers.js';
+import { readSegments } from '../../src/jpeg.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('createSegment', () => {
+  it('should prefix the payload with the marker and length', () => {
+    assert.deepStrictEqual(Array.from(createSegment(0xe3, new Uint8Array([1, 2]))), [0xff, 0xe3, 0, 4, 1, 2]);
+  });
+});
+
+describe('insertSegments', () => {
+  it('should keep application segments in ascending order after SOI', () => {
+    const jpeg = grayscaleJpeg(8, 8, '00' + '0');
+
+    const withApp2 = insertSegments(jpeg, [createSegment(0xe2, new Uint8Array([7]))]);
+    const withCom = insertSegments(withApp2, [createSegment(0xfe, new Uint8Array([8]))]);
+
+    assert.deepStrictEqual(readSegments(withCom).slice(0, 5).map((s) => s.marker), [0xd8, 0xe0, 0xe2, 0xfe, 0xdb]);
+  });
+
+  it('should insert before APP0 when the new marker sorts first', () => {
+    assert.strictEqual(applicationInsertOffset(grayscaleJpeg(8, 8, '00' + '0'), 0xe0 - 1), 2);
+  });
+});
@@ -0,0 +1,103 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { copyMetadata, readMetadata, readOrientation, stripMetadata } from '../../src/metadata.js';
+import { insertSegments } from '../../src/markers.js';
+import { readSegments } from '../../src/jpeg.js';
+import { decode } from '../../src/decoder.js';
+import { grayscaleJpeg, segment } from '../utils/jpeg-fixtures.js';
+
+const ascii = (text: string): number[] => Array.from(text, (c) => c.charCodeAt(0));
+
+/**
+ * Little-endian EXIF with Orientation 6 in IFD0 and 4000x3000 pixel dimensions in the Exif IFD
+ */
+function exifSegment(): number[] {
+  const tiff = new DataView(new ArrayBuffer(8 + 2 + 2 * 12 + 4 + 2 + 2 * 12 + 4));
+  tiff.setUint16(0, 0x4949);
+  tiff.setUint16(2, 42, true);
+  tiff.setUint32(4, 8, true);
+  const entry = (offset: number, tag: number, type: number, value: number): void => {
+    tiff.setUint16(offset, tag, true);
+    tiff.setUint16(offset + 2, type, true);
+    tiff.setUint32(offset + 4, 1, true);
+    if (type === 3) {
+      tiff.setUint16(offset + 8, value, true);
+    } else {
+      tiff.setUint32(offset + 8, value, true);
+    }
+  };
+  tiff.setUint16(8, 2, true);
+  entry(10, 0x0112, 3, 6);
+  entry(22, 0x8769, 4, 38);
+  tiff.setUint16(38, 2, true);
+  entry(40, 0xa002, 4, 4000);
+  entry(52, 0xa003, 3, 3000);
+  return segment(0xe1, [...ascii('Exif\0\0'), ...new Uint8Array(tiff.buffer)]);
+}
+
+function iccSegment(sequence: number, body: number[]): number[] {
+  return segment(0xe2, [...ascii('ICC_PROFILE\0'), sequence, 2, ...body]);
+}
+
+function photo(): Uint8Array {
+  const base = grayscaleJpeg(8, 8, '00' + '0');
+  return insertSegments(base, [exifSegment(), iccSegment(2, [0xbb]), iccSegment(1, [0xaa])].map((s) => new Uint8Array(s)));
+}
+
+describe('readMetadata', () => {
+  it('should find EXIF and order ICC chunks by sequence number', () => {
+    const metadata = readMetadata(photo());
+
+    assert.ok(metadata.exif);
+    assert.deepStrictEqual(metadata.icc.map((chunk) => chunk[chunk.length - 1]), [0xaa, 0xbb]);
+    assert.strictEqual(readOrientation(photo()), 6);
+    assert.strictEqual(readOrientation(grayscaleJpeg(8, 8, '00' + '0')), undefined);
+  });
+});
+
+describe('copyMetadata', () => {
+  it('should copy EXIF and ICC with the target pixel dimensions', () => {
+    const target = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+
+    const output = copyMetadata(photo(), target);
+
+    const markers = readSegments(output).map((s) => s.marker);
+    assert.deepStrictEqual(markers.slice(0, 5), [0xd8, 0xe0, 0xe1, 0xe2, 0xe2]);
+    assert.strictEqual(readOrientation(output), 6);
+    const exif = readMetadata(output).exif!;
+    const view = new DataView(exif.buffer, exif.byteOffset + 10);
+    assert.strictEqual(view.getUint32(38 + 2 + 8, true), 16);
+    assert.strictEqual(view.getUint16(38 + 2 + 12 + 8, true), 8);
+  });
+
+  it('should reset orientation and skip what was not asked for', () => {
+    const target = grayscaleJpeg(8, 8, '00' + '0');
+
+    const output = copyMetadata(photo(), target, { icc: false, orientation: 'reset' });
+
+    assert.strictEqual(readOrientation(output), 1);
+    assert.strictEqual(readMetadata(output).icc.length, 0);
+    assert.strictEqual(readOrientation(photo()), 6, 'The source must not be modified');
+  });
+});
+
+describe('stripMetadata', () => {
+  it('should drop EXIF, comments, and thumbnails but keep JFIF and the ICC profile', () => {
+    const jfxx = new Uint8Array(segment(0xe0, [...ascii('JFXX\0'), 0x10, 0xff, 0xd8, 0xff, 0xd9]));
+    const comment = new Uint8Array(segment(0xfe, ascii('made by hand')));
+    const source = insertSegments(photo(), [jfxx, comment]);
+
+    const stripped = stripMetadata(source);
+
+    assert.deepStrictEqual(readSegments(stripped).map((s) => s.marker).slice(0, 4), [0xd8, 0xe0, 0xe2, 0xe2]);
+    assert.strictEqual(readMetadata(stripped).icc.length, 2);
+    assert.deepStrictEqual(decode(stripped).data, decode(source).data);
+    assert.deepStrictEqual(readSegments(stripMetadata(source, { icc: false })).map((s) => s.marker).slice(0, 3), [0xd8, 0xe0, 0xdb]);
+  });
+
+  it('should return a file without metadata unchanged', () => {
+    const plain = grayscaleJpeg(8, 8, '00' + '0');
+
+    assert.strictEqual(stripMetadata(plain), plain);
+  });
+});
@@ -0,0 +1,37 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { createMpo, splitMpo } from '../../src/mpo.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('createMpo', () => {
+  it('should index every image so it can be split back out', () => {
+    const left = grayscaleJpeg(8, 8, '00' + '0');
+    const right = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+
+    const mpo = createMpo([left, right]);
+    const [first, second] = splitMpo(mpo);
+
+    assert.strictEqual(first.length + second.length, mpo.length);
+    assert.strictEqual(parseJpeg(first).frame.width, 8);
+    assert.strictEqual(parseJpeg(second).frame.width, 16);
+    assert.ok(second.length > right.length, 'Second image should carry its own MPF attribute segment');
+  });
+
+  it('should mark the first image as representative with the requested MP type', () => {
+    const image = grayscaleJpeg(8, 8, '00' + '0');
+
+    const mpo = createMpo([image, image, image], { type: 'panorama' });
+    const app2 = parseJpeg(mpo).segments.find((s) => s.marker === 0xe2)!;
+    const view = new DataView(app2.data.buffer, app2.data.byteOffset + 4);
+    const entries = 8 + 2 + 3 * 12 + 4;
+
+    assert.strictEqual(view.getUint32(entries), 0x20020001);
+    assert.strictEqual(view.getUint32(entries + 16), 0x00020001);
+    assert.strictEqual(splitMpo(mpo).length, 3);
+  });
+
+  it('should require at least two images', () => {
+    assert.throws(() => createMpo([grayscaleJpeg(8, 8, '00' + '0')]), /at least two/);
+  });
+});
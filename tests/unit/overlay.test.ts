@@ -0,0 +1,34 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { debugOverlay } from '../../src/overlay.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+function pixel(data: Uint8ClampedArray, width: number, x: number, y: number): number[] {
+  const offset = (y * width + x) * 4;
+  return Array.from(data.subarray(offset, offset + 4));
+}
+
+describe('debugOverlay', () => {
+  // Block 1 keeps one AC coefficient, block 2 keeps none; one restart between them
+  const jpeg = grayscaleJpeg(16, 8, ['00' + '10' + '1' + '0', '010' + '1' + '0'], { restartInterval: 1 });
+
+  it('should match the image dimensions', () => {
+    const overlay = debugOverlay(jpeg);
+    assert.strictEqual(overlay.width, 16);
+    assert.strictEqual(overlay.height, 8);
+    assert.strictEqual(overlay.data.length, 16 * 8 * 4);
+  });
+
+  it('should tint blocks by retained coefficients', () => {
+    const overlay = debugOverlay(jpeg, { blocks: false, restarts: false });
+    assert.deepStrictEqual(pixel(overlay.data, 16, 3, 3), [4, 0, 251, 80]);
+    assert.deepStrictEqual(pixel(overlay.data, 16, 11, 3), [0, 0, 255, 80]);
+  });
+
+  it('should draw MCU edges and restart positions', () => {
+    const overlay = debugOverlay(jpeg, { quantization: false });
+    assert.deepStrictEqual(pixel(overlay.data, 16, 0, 4), [255, 255, 255, 160]);
+    assert.deepStrictEqual(pixel(overlay.data, 16, 8, 4), [255, 0, 0, 255], 'Second MCU starts a restart interval');
+    assert.deepStrictEqual(pixel(overlay.data, 16, 4, 4), [0, 0, 0, 0]);
+  });
+});
@@ -0,0 +1,230 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { CollectingSink, PartSink, bufferSource, iterableSource, pipelineStream, readableStreamSource, runPipeline, standaloneChunk, transferList, transferableEncoder } from '../../src/pipeline.js';
+import type { OutputPart, StripEncoder } from '../../src/pipeline.js';
+
+/**
+ * Encoder that emits the row count of each strip, then a trailer
+ */
+function rowCountingEncoder(width: number, stripHeight: number): StripEncoder {
+  return {
+    stripHeight,
+    encodeStrip: (strip) => new Uint8Array([strip.length / (width * 4)]),
+    finish: () => new Uint8Array([0xff]),
+  };
+}
+
+describe('runPipeline', () => {
+  it('should feed strips in order and forward all output to the sink', async () => {
+    const sink = new CollectingSink();
+    const seen: number[] = [];
+
+    const written = await runPipeline(
+      bufferSource(new Uint8Array(2 * 5 * 4), 2, 5),
+      rowCountingEncoder(2, 2),
+      sink,
+      { onStrip: (_strip, y) => seen.push(y) }
+    );
+
+    assert.deepStrictEqual(Array.from(sink.toBytes()), [2, 2, 1, 0xff]);
+    assert.deepStrictEqual(seen, [0, 2, 4]);
+    assert.strictEqual(written, 4);
+  });
+
+  it('should skip empty output chunks and close the sink', async () => {
+    let closed = false;
+    const chunks: Uint8Array[] = [];
+    const encoder: StripEncoder = {
+      stripHeight: 8,
+      encodeStrip: () => new Uint8Array(0),
+      finish: () => new Uint8Array([1, 2]),
+    };
+
+    await runPipeline(bufferSource(new Uint8Array(16 * 4), 1, 16), encoder, {
+      write: (chunk) => { chunks.push(chunk); },
+      close: () => { closed = true; },
+    });
+
+    assert.strictEqual(chunks.length, 1);
+    assert.strictEqual(closed, true);
+  });
+
+  it('should reject sources that end early', async () => {
+    const source = { width: 1, height: 4, readStrip: () => null };
+
+    await assert.rejects(runPipeline(source, rowCountingEncoder(1, 8), new CollectingSink()), /ended after 0 of 4 rows/);
+  });
+});
+
+describe('readableStreamSource', () => {
+  function chunkedStream(chunks: number[][]): ReadableStream<Uint8Array> {
+    return new ReadableStream({
+      start(controller) {
+        chunks.forEach((chunk) => controller.enqueue(new Uint8Array(chunk)));
+        controller.close();
+      },
+    });
+  }
+
+  it('should assemble strips from chunks that straddle row boundaries', async () => {
+    const pixels = Array.from({ length: 3 * 4 }, (_, i) => i);
+    const source = readableStreamSource(chunkedStream([pixels.slice(0, 3), pixels.slice(3, 9), pixels.slice(9)]), 1, 3);
+
+    const sink = new CollectingSink();
+    await runPipeline(source, { stripHeight: 2, encodeStrip: (strip) => strip.slice(), finish: () => new Uint8Array(0) }, sink);
+
+    assert.deepStrictEqual(Array.from(sink.toBytes()), pixels);
+  });
+
+  it('should drop a trailing partial row and report the short image', async () => {
+    const source = readableStreamSource(chunkedStream([[1, 2, 3, 4, 5, 6]]), 1, 2);
+
+    await assert.rejects(runPipeline(source, rowCountingEncoder(1, 8), new CollectingSink()), /ended after 1 of 2 rows/);
+  });
+});
+
+describe('bufferSource', () => {
+  it('should pass packed RGBA through as views', () => {
+    const data = new Uint8Array(2 * 3 * 4).map((_, i) => i);
+    const strip = bufferSource(data, 2, 3).readStrip(2) as Uint8Array;
+
+    assert.strictEqual(strip.buffer, data.buffer);
+    assert.deepStrictEqual(Array.from(strip), Array.from(data.subarray(0, 16)));
+  });
+
+  it('should convert other formats and skip row padding', () => {
+    // Two BGR pixels per row, padded to 8 bytes; the last row is unpadded
+    const data = new Uint8Array([3, 2, 1, 6, 5, 4, 0xee, 0xee, 9, 8, 7, 12, 11, 10]);
+    const source = bufferSource(data, 2, 2, 'bgr', 8);
+
+    assert.deepStrictEqual(Array.from(source.readStrip(1) as Uint8Array), [1, 2, 3, 255, 4, 5, 6, 255]);
+    assert.deepStrictEqual(Array.from(source.readStrip(1) as Uint8Array), [7, 8, 9, 255, 10, 11, 12, 255]);
+    assert.strictEqual(source.readStrip(1), null);
+  });
+});
+
+describe('iterableSource', () => {
+  it('should only pull chunks as strips are read', async () => {
+    const pulled: number[] = [];
+    let closed = false;
+    async function* rows(): AsyncGenerator<Uint8Array> {
+      try {
+        for (let row = 0; row < 6; row++) {
+          pulled.push(row);
+          yield new Uint8Array(4).fill(row);
+        }
+      } finally {
+        closed = true;
+      }
+    }
+    const source = iterableSource(rows(), 1, 4);
+
+    const strip = await source.readStrip(2);
+
+    assert.deepStrictEqual(Array.from(strip!), [0, 0, 0, 0, 1, 1, 1, 1]);
+    assert.deepStrictEqual(pulled, [0, 1]);
+    await source.readStrip(2);
+    assert.strictEqual(await source.readStrip(2), null);
+    // The generator is closed at the image's last row, not run on to its end
+    assert.deepStrictEqual(pulled, [0, 1, 2, 3]);
+    assert.strictEqual(closed, true);
+  });
+
+  it('should accept a sync iterable of uneven chunks', async () => {
+    const pixels = Array.from({ length: 3 * 4 }, (_, i) => i);
+    const chunks = [pixels.slice(0, 5), pixels.slice(5)].map((chunk) => new Uint8Array(chunk));
+
+    const sink = new CollectingSink();
+    await runPipeline(iterableSource(chunks, 1, 3), { stripHeight: 2, encodeStrip: (strip) => strip.slice(), finish: () => new Uint8Array(0) }, sink);
+
+    assert.deepStrictEqual(Array.from(sink.toBytes()), pixels);
+  });
+});
+
+describe('pipelineStream', () => {
+  it('should only encode strips as the stream is pulled', async () => {
+    let encoded = 0;
+    const encoder: StripEncoder = {
+      stripHeight: 2,
+      encodeStrip: (strip) => { encoded++; return new Uint8Array([strip.length / 4]); },
+      finish: () => new Uint8Array([0xff]),
+    };
+    const reader = pipelineStream(bufferSource(new Uint8Array(5 * 4), 1, 5), encoder).getReader();
+
+    await new Promise((resolve) => setTimeout(resolve, 0));
+    assert.strictEqual(encoded, 0, 'Nothing is encoded before the first read');
+
+    assert.deepStrictEqual(Array.from((await reader.read()).value!), [2]);
+    assert.strictEqual(encoded, 1);
+    const rest: number[] = [];
+    for (let result = await reader.read(); !result.done; result = await reader.read()) {
+      rest.push(...result.value);
+    }
+    assert.deepStrictEqual(rest, [2, 1, 0xff]);
+  });
+
+  it('should error the stream when the source ends early', async () => {
+    const source = { width: 1, height: 4, readStrip: () => null };
+    const reader = pipelineStream(source, rowCountingEncoder(1, 8)).getReader();
+
+    await assert.rejects(reader.read(), /ended after 0 of 4 rows/);
+  });
+});
+
+describe('transferable chunks', () => {
+  it('should copy views and keep chunks that own their buffer', () => {
+    const owned = new Uint8Array([1, 2, 3]);
+    const view = new Uint8Array([0, 1, 2, 3]).subarray(1);
+
+    assert.strictEqual(standaloneChunk(owned), owned);
+    const copy = standaloneChunk(view);
+    assert.notStrictEqual(copy, view);
+    assert.deepStrictEqual(Array.from(copy), [1, 2, 3]);
+    assert.strictEqual(copy.buffer.byteLength, 3);
+  });
+
+  it('should detach transferred chunks without touching the encoder', () => {
+    const memory = new Uint8Array(16);
+    const encoder = transferableEncoder({
+      stripHeight: 8,
+      encodeStrip: () => memory.subarray(0, 4),
+      finish: () => memory.subarray(4, 6),
+    });
+    const chunks = [encoder.encodeStrip(new Uint8Array(0)), encoder.finish()];
+
+    const buffers = transferList(chunks);
+    structuredClone(chunks, { transfer: buffers });
+
+    assert.ok(chunks.every((chunk) => chunk.byteLength === 0));
+    assert.strictEqual(memory.byteLength, 16);
+    assert.throws(() => transferList([memory.subarray(2)]), /standaloneChunk/);
+  });
+});
+
+describe('PartSink', () => {
+  it('should regroup chunks into exact parts with a short final part', async () => {
+    const parts: OutputPart[] = [];
+    const sink = new PartSink(4, async (part) => { parts.push(part); });
+
+    await sink.write(new Uint8Array([1, 2, 3]));
+    await sink.write(new Uint8Array([4, 5, 6, 7, 8, 9, 10]));
+    await sink.write(new Uint8Array([11]));
+    await sink.close();
+
+    assert.deepStrictEqual(parts.map((p) => [p.index, p.offset, Array.from(p.data)]), [
+      [0, 0, [1, 2, 3, 4]],
+      [1, 4, [5, 6, 7, 8]],
+      [2, 8, [9, 10, 11]],
+    ]);
+  });
+
+  it('should not emit an empty trailing part', async () => {
+    const parts: OutputPart[] = [];
+    const sink = new PartSink(2, (part) => { parts.push(part); });
+
+    await runPipeline(bufferSource(new Uint8Array(1 * 3 * 4), 1, 3), rowCountingEncoder(1, 2), sink);
+
+    assert.deepStrictEqual(parts.map((p) => Array.from(p.data)), [[2, 1], [0xff]]);
+    await assert.rejects(async () => new PartSink(0, () => {}), /positive integer/);
+  });
+});
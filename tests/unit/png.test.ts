@@ -0,0 +1,70 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { decodePng, isPng, pngSource, readPngHeader } from '../../src/png.js';
+import { pngChunk, pngFile } from '../utils/png-fixtures.js';
+
+describe('pngSource', () => {
+  it('should undo every filter type across strips', async () => {
+    // 2x5 RGBA; rows use filters None, Sub, Up, Average, Paeth
+    const png = pngFile(2, 5, [
+      [0, 10, 20, 30, 255, 40, 50, 60, 255],
+      [1, 10, 20, 30, 255, 30, 30, 30, 0],
+      [2, 1, 1, 1, 0, 1, 1, 1, 0],
+      [3, 5, 5, 5, 128, 0, 0, 0, 0],
+      [4, 0, 0, 0, 0, 0, 0, 0, 0],
+    ], { idatSplit: 7 });
+
+    const source = pngSource(png);
+    const first = await source.readStrip(3);
+    const rest = await source.readStrip(3);
+
+    assert.strictEqual(first!.length, 3 * 2 * 4);
+    assert.deepStrictEqual(Array.from(first!), [
+      10, 20, 30, 255, 40, 50, 60, 255,
+      10, 20, 30, 255, 40, 50, 60, 255,
+      11, 21, 31, 255, 41, 51, 61, 255,
+    ]);
+    // Average: left is 0 for the first pixel, so 5 + (0 + 11) >> 1 = 10
+    assert.deepStrictEqual(Array.from(rest!.subarray(0, 4)), [10, 15, 20, 255]);
+    // Paeth with zero deltas copies the pixel above
+    assert.deepStrictEqual(Array.from(rest!.subarray(8, 12)), [10, 15, 20, 255]);
+    assert.strictEqual(await source.readStrip(3), null);
+  });
+
+  it('should expand palette images with transparency', async () => {
+    const png = pngFile(3, 1, [[0, 0b00011011]], {
+      colorType: 3,
+      bitDepth: 2,
+      chunks: [pngChunk('PLTE', [255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9]), pngChunk('tRNS', [0])],
+    });
+
+    const { data } = await decodePng(png);
+
+    assert.deepStrictEqual(Array.from(data), [255, 0, 0, 0, 0, 255, 0, 255, 0, 0, 255, 255]);
+  });
+
+  it('should scale 16-bit grayscale to 8 bits', async () => {
+    const png = pngFile(1, 1, [[0, 0x80, 0xff]], { colorType: 0, bitDepth: 16 });
+
+    const { data } = await decodePng(png);
+
+    assert.deepStrictEqual(Array.from(data), [128, 128, 128, 255]);
+  });
+
+  it('should reject interlaced images', () => {
+    const png = pngFile(1, 1, [[0, 0, 0, 0, 0]]);
+    png[8 + 8 + 12] = 1; // IHDR interlace byte
+
+    assert.throws(() => pngSource(png), /Interlaced/);
+  });
+});
+
+describe('isPng', () => {
+  it('should detect the PNG signature', () => {
+    const png = pngFile(1, 1, [[0, 0, 0, 0, 0]]);
+
+    assert.strictEqual(isPng(png), true);
+    assert.strictEqual(isPng(new Uint8Array([0xff, 0xd8])), false);
+    assert.deepStrictEqual(readPngHeader(png), { width: 1, height: 1, bitDepth: 8, colorType: 6, interlace: 0 });
+  });
+});
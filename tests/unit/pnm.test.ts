@@ -0,0 +1,56 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { isPnm, pnmSource, readPnmHeader } from '../../src/pnm.js';
+import { decodedSource } from '../../src/inputs.js';
+
+const ascii = (text: string): number[] => Array.from(text, (c) => c.charCodeAt(0));
+
+describe('readPnmHeader', () => {
+  it('should skip comments in PPM headers', () => {
+    const ppm = new Uint8Array([...ascii('P6\n# made by hand\n2 1\n255\n'), 1, 2, 3, 4, 5, 6]);
+
+    const header = readPnmHeader(ppm);
+
+    assert.deepStrictEqual(header, { format: 'P6', width: 2, height: 1, depth: 3, maxval: 255, dataOffset: ppm.length - 6 });
+  });
+
+  it('should read PAM tuple headers', () => {
+    const pam = new Uint8Array([...ascii('P7\nWIDTH 1\nHEIGHT 1\nDEPTH 2\nMAXVAL 255\nTUPLTYPE GRAYSCALE_ALPHA\nENDHDR\n'), 9, 8]);
+
+    const header = readPnmHeader(pam);
+
+    assert.strictEqual(header.depth, 2);
+    assert.strictEqual(header.dataOffset, pam.length - 2);
+  });
+});
+
+describe('pnmSource', () => {
+  it('should expand 16-bit PGM rows to RGBA strips', () => {
+    const pgm = new Uint8Array([...ascii('P5 1 3 65535\n'), 0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+
+    const source = pnmSource(pgm);
+
+    assert.deepStrictEqual(Array.from(source.readStrip(2) as Uint8Array), [255, 255, 255, 255, 128, 128, 128, 255]);
+    assert.deepStrictEqual(Array.from(source.readStrip(2) as Uint8Array), [0, 0, 0, 255]);
+    assert.strictEqual(source.readStrip(2), null);
+  });
+
+  it('should keep PAM alpha', () => {
+    const pam = new Uint8Array([...ascii('P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n'), 1, 2, 3, 4]);
+
+    assert.deepStrictEqual(Array.from(pnmSource(pam).readStrip(8) as Uint8Array), [1, 2, 3, 4]);
+  });
+
+  it('should reject truncated pixel data', () => {
+    assert.throws(() => pnmSource(new Uint8Array(ascii('P6 2 2 255\n\x00'))), /truncated/);
+  });
+});
+
+describe('decodedSource', () => {
+  it('should ignore Netpbm magic when raw dimensions were given', () => {
+    const raw = new Uint8Array([...ascii('P6\n'), 255]);
+
+    assert.strictEqual(isPnm(raw), true);
+    assert.strictEqual(decodedSource(raw, true), undefined);
+  });
+});
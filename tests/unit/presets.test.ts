@@ -0,0 +1,32 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { ENCODER_PRESETS, applyPreset } from '../../src/presets.js';
+import type { EncoderPreset } from '../../src/presets.js';
+
+describe('applyPreset', () => {
+  it('should expand the named settings', () => {
+    assert.deepStrictEqual(applyPreset({ preset: 'balanced', quality: 80 }), {
+      subsampling: '4:2:0',
+      optimizeHuffman: true,
+      progressive: false,
+      preset: 'balanced',
+      quality: 80,
+    });
+    assert.strictEqual(applyPreset({ preset: 'smallest' }).progressive, true);
+    assert.deepStrictEqual(applyPreset({ preset: 'web' }), { ...ENCODER_PRESETS.smallest, stripMetadata: true, preset: 'web' });
+  });
+
+  it('should let explicit options override the preset', () => {
+    const options = applyPreset({ preset: 'fast', subsampling: '4:2:2', progressive: undefined });
+
+    assert.strictEqual(options.subsampling, '4:2:2');
+    assert.strictEqual(options.progressive, ENCODER_PRESETS.fast.progressive);
+  });
+
+  it('should leave options without a preset alone and reject unknown names', () => {
+    const options = { quality: 90 };
+
+    assert.strictEqual(applyPreset(options), options);
+    assert.throws(() => applyPreset({ preset: 'tiny' as EncoderPreset }), /Unknown encoder preset: tiny/);
+  });
+});
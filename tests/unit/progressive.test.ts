@@ -0,0 +1,98 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { toProgressive } from '../../src/progressive.js';
+import type { ProgressiveScanInfo } from '../../src/progressive.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { readSegments } from '../../src/jpeg.js';
+import { validate } from '../../src/validate.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+// Block 1: DC 3, AC 1 at k=1 and 2 at k=2; block 2: DC 2, no AC
+const BITS = '011' + '11' + '10' + '1' + '110' + '10' + '0' + '010' + '0' + '0';
+
+describe('toProgressive', () => {
+  it('should keep every coefficient while regrouping them into scans', () => {
+    const baseline = grayscaleJpeg(16, 8, BITS);
+
+    const progressive = toProgressive(baseline);
+
+    const markers = readSegments(progressive).map((s) => s.marker);
+    assert.deepStrictEqual(markers.slice(0, 4), [0xd8, 0xe0, 0xdb, 0xc2]);
+    assert.strictEqual(markers.filter((m) => m === 0xda).length, 6);
+    assert.deepStrictEqual(readCoefficients(progressive)[0].blocks, readCoefficients(baseline)[0].blocks);
+    assert.deepStrictEqual(decode(progressive).data, decode(baseline).data);
+  });
+
+  it('should report each scan and hand out a DC preview', () => {
+    const baseline = grayscaleJpeg(16, 8, BITS);
+    const scans: ProgressiveScanInfo[] = [];
+    let preview: Uint8Array | undefined;
+
+    const progressive = toProgressive(baseline, {
+      onScan: (info) => scans.push(info),
+      onDcScan: (_info, bytes) => { preview = bytes; },
+    });
+
+    assert.deepStrictEqual(
+      scans.map((s) => [s.spectralStart, s.spectralEnd, s.approxHigh, s.approxLow]),
+      [[0, 0, 0, 1], [1, 5, 0, 2], [6, 63, 0, 2], [1, 63, 2, 1], [0, 0, 1, 0], [1, 63, 1, 0]]
+    );
+    assert.strictEqual(scans[1].offset, scans[0].offset + scans[0].length);
+    assert.strictEqual(scans[5].offset + scans[5].length + 2, progressive.length);
+    assert.ok(preview);
+    assert.deepStrictEqual(Array.from(preview!.subarray(0, -2)), Array.from(progressive.subarray(0, scans[0].offset + scans[0].length)));
+    const dcOnly = readCoefficients(preview!)[0].blocks;
+    assert.deepStrictEqual([dcOnly[0], dcOnly[1], dcOnly[64]], [2, 0, 2]);
+  });
+
+  it('should code EOB runs and refinement bits losslessly across long runs of blocks', () => {
+    // Every fourth of 40 blocks has AC 3 at k=1 and -1 at k=2, the rest are empty
+    let bits = '';
+    for (let i = 0; i < 40; i++) {
+      bits += '00' + (i % 4 === 0 ? '110' + '11' + '10' + '0' : '') + '0';
+    }
+    const baseline = grayscaleJpeg(320, 8, bits);
+    const expected = readCoefficients(baseline)[0].blocks;
+    assert.deepStrictEqual([expected[1], expected[2], expected[64 + 1]], [3, -1, 0]);
+
+    const progressive = toProgressive(baseline, {
+      scans: [
+        { components: [0], spectralStart: 0, spectralEnd: 0 },
+        { components: [0], spectralStart: 1, spectralEnd: 63, approxLow: 2 },
+        { components: [0], spectralStart: 1, spectralEnd: 63, approxHigh: 2, approxLow: 1 },
+        { components: [0], spectralStart: 1, spectralEnd: 63, approxHigh: 1, approxLow: 0 },
+      ],
+    });
+
+    assert.deepStrictEqual(readCoefficients(progressive)[0].blocks, expected);
+    assert.strictEqual(validate(progressive).valid, true);
+  });
+
+  it('should clamp out-of-range DC values so differences stay within category 11', () => {
+    // Two differences of +2047 accumulate to a DC value no 8-bit image can have
+    const dc2047 = '111111110' + '11111111111';
+    const baseline = grayscaleJpeg(16, 8, dc2047 + '0' + dc2047 + '0');
+
+    const progressive = toProgressive(baseline);
+
+    const dc = readCoefficients(progressive)[0].blocks;
+    assert.deepStrictEqual([dc[0], dc[64]], [1023, 1023]);
+    assert.strictEqual(validate(progressive).valid, true);
+  });
+
+  it('should reject scripts that send AC bands before DC or interleave them', () => {
+    const baseline = grayscaleJpeg(8, 8, '00' + '0');
+
+    assert.throws(() => toProgressive(baseline, { scans: [{ components: [0], spectralStart: 1, spectralEnd: 63 }] }), /must follow the DC scan/);
+    assert.throws(() => toProgressive(baseline, { scans: [{ components: [0, 0], spectralStart: 0, spectralEnd: 0 }, { components: [0, 0], spectralStart: 1, spectralEnd: 63 }] }), /Invalid progressive scan/);
+  });
+
+  it('should reject refinement scans that skip a bit or leave coefficients unfinished', () => {
+    const baseline = grayscaleJpeg(8, 8, '00' + '0');
+    const dc = { components: [0], spectralStart: 0, spectralEnd: 0 };
+
+    assert.throws(() => toProgressive(baseline, { scans: [dc, { components: [0], spectralStart: 1, spectralEnd: 63, approxHigh: 2, approxLow: 0 }] }), /Invalid progressive scan/);
+    assert.throws(() => toProgressive(baseline, { scans: [dc, { components: [0], spectralStart: 1, spectralEnd: 63, approxHigh: 1, approxLow: 0 }] }), /does not continue coefficient 1/);
+    assert.throws(() => toProgressive(baseline, { scans: [dc, { components: [0], spectralStart: 1, spectralEnd: 62 }] }), /every coefficient/);
+  });
+});
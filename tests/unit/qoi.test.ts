@@ -0,0 +1,61 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { isQoi, qoiSource, readQoiHeader } from '../../src/qoi.js';
+import { decodedSource } from '../../src/inputs.js';
+
+function qoiFile(width: number, height: number, ops: number[]): Uint8Array {
+  return new Uint8Array([
+    0x71, 0x6f, 0x69, 0x66,
+    0, 0, 0, width, 0, 0, 0, height, 4, 0,
+    ...ops,
+    0, 0, 0, 0, 0, 0, 0, 1,
+  ]);
+}
+
+describe('qoiSource', () => {
+  it('should decode every op across strip boundaries', () => {
+    const qoi = qoiFile(2, 3, [
+      0xfe, 100, 50, 25,        // RGB
+      0x40 | (3 << 4) | (2 << 2) | 1, // DIFF +1, 0, -1
+      0x80 | (32 + 4), 0x88,    // LUMA dg +4, dr-dg 0, db-dg 0
+      0xc0 | 1,                 // RUN of 2
+      0x00 | ((100 * 3 + 50 * 5 + 25 * 7 + 255 * 11) % 64), // INDEX of the first pixel
+    ]);
+
+    const source = qoiSource(qoi);
+    const first = source.readStrip(2) as Uint8Array;
+    const second = source.readStrip(2) as Uint8Array;
+
+    assert.deepStrictEqual(Array.from(first), [
+      100, 50, 25, 255, 101, 50, 24, 255,
+      105, 54, 28, 255, 105, 54, 28, 255,
+    ]);
+    assert.deepStrictEqual(Array.from(second), [105, 54, 28, 255, 100, 50, 25, 255]);
+    assert.strictEqual(source.readStrip(2), null);
+  });
+
+  it('should report truncated data', () => {
+    const qoi = qoiFile(2, 1, [0xff, 1, 2, 3, 4]).subarray(0, 19);
+
+    assert.throws(() => qoiSource(qoi).readStrip(1), /ended at row 0/);
+  });
+});
+
+describe('readQoiHeader', () => {
+  it('should read dimensions and channels', () => {
+    const qoi = qoiFile(7, 5, []);
+
+    assert.strictEqual(isQoi(qoi), true);
+    assert.deepStrictEqual(readQoiHeader(qoi), { width: 7, height: 5, channels: 4, colorspace: 0 });
+  });
+});
+
+describe('decodedSource', () => {
+  it('should treat a raw pixel that spells the QOI magic as pixel data', () => {
+    const raw = new Uint8Array([0x71, 0x6f, 0x69, 0x66, 1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255]);
+
+    assert.strictEqual(isQoi(raw), true);
+    assert.ok(decodedSource(qoiFile(2, 1, [])));
+    assert.strictEqual(decodedSource(raw, true), undefined);
+  });
+});
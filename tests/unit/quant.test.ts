@@ -0,0 +1,91 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { dqtSegment, naturalToZigzag, normalizeQuantTable, zigzagToNatural } from '../../src/quant.js';
+import { EncodeError } from '../../src/errors.js';
+import { parseQuantTables, readSegments } from '../../src/jpeg.js';
+
+const natural = Array.from({ length: 64 }, (_, i) => i + 1);
+
+describe('zig-zag conversion', () => {
+  it('should walk the block diagonally', () => {
+    assert.deepStrictEqual(Array.from(naturalToZigzag(natural).subarray(0, 6)), [1, 2, 9, 17, 10, 3]);
+    assert.deepStrictEqual(Array.from(zigzagToNatural(naturalToZigzag(natural))), natural);
+  });
+
+  it('should reject tables without 64 entries', () => {
+    assert.throws(() => naturalToZigzag([1, 2, 3]), /64 entries, got 3/);
+  });
+});
+
+describe('dqtSegment', () => {
+  it('should store natural-order input in zig-zag order', () => {
+    const segment = dqtSegment([{ id: 1, values: natural }]);
+
+    const [table] = parseQuantTables(readSegments(new Uint8Array([0xff, 0xd8, ...segment]))[1].data);
+
+    assert.strictEqual(table.id, 1);
+    assert.strictEqual(table.precision, 0);
+    assert.deepStrictEqual(Array.from(zigzagToNatural(table.values)), natural);
+  });
+
+  it('should switch to 16-bit entries only when needed', () => {
+    const coarse = natural.map((v) => v * 10);
+
+    const segment = dqtSegment([{ id: 0, values: natural }, { id: 2, values: coarse }]);
+
+    const tables = parseQuantTables(segment.subarray(4));
+    assert.deepStrictEqual(tables.map((t) => [t.id, t.precision]), [[0, 0], [2, 1]]);
+    assert.deepStrictEqual(Array.from(zigzagToNatural(tables[1].values)), coarse);
+    assert.throws(() => dqtSegment([{ id: 4, values: natural }]), /0-3/);
+    assert.throws(() => dqtSegment([{ id: 0, values: natural.map(() => 0) }]), /1-65535/);
+  });
+
+  it('should write 16-bit entries when the table asks for them', () => {
+    const [table] = parseQuantTables(dqtSegment([{ id: 0, values: natural, precision: 1 }]).subarray(4));
+
+    assert.strictEqual(table.precision, 1);
+    assert.deepStrictEqual(Array.from(zigzagToNatural(table.values)), natural);
+  });
+});
+
+describe('normalizeQuantTable', () => {
+  it('should pass valid tables through unchanged', () => {
+    assert.deepStrictEqual(Array.from(normalizeQuantTable(natural)), natural);
+  });
+
+  it('should reject zero, fractional, or oversized entries by default', () => {
+    assert.throws(() => normalizeQuantTable(natural.map((v, i) => (i === 5 ? 0 : v))), /entry 5 must be an integer 1-255, got 0/);
+    assert.throws(() => normalizeQuantTable(natural.map((v) => v + 0.5)), /entry 0/);
+    assert.throws(() => normalizeQuantTable(natural.map((v) => v * 10)), /got 260/);
+  });
+
+  it('should tell zero entries apart from other bad tables by error code', () => {
+    const code = (values: number[]): string | undefined => {
+      try {
+        normalizeQuantTable(values);
+      } catch (error) {
+        return error instanceof EncodeError ? error.code : undefined;
+      }
+      return undefined;
+    };
+    assert.strictEqual(code(natural.map((v, i) => (i === 9 ? 0 : v))), 'zero-quant-entry');
+    assert.strictEqual(code(natural.map((v) => v * 10)), 'bad-table');
+    assert.strictEqual(code(natural.slice(1)), 'bad-table');
+  });
+
+  it('should clamp into range when asked', () => {
+    const table = normalizeQuantTable(natural.map((v, i) => (i === 0 ? 0 : i === 1 ? 2.4 : v * 10)), 'clamp');
+
+    assert.deepStrictEqual(Array.from(table.subarray(0, 3)), [1, 2, 30]);
+    assert.strictEqual(table[63], 255);
+    assert.throws(() => normalizeQuantTable(natural.map(() => NaN), 'clamp'), /got NaN/);
+  });
+
+  it('should allow entries up to 65535 for 16-bit tables', () => {
+    const coarse = natural.map((v) => v * 1000);
+
+    assert.deepStrictEqual(Array.from(normalizeQuantTable(coarse, 'reject', 16)), coarse);
+    assert.throws(() => normalizeQuantTable(coarse.map((v) => v * 10), 'reject', 16), /entry 6 must be an integer 1-65535, got 70000/);
+    assert.strictEqual(normalizeQuantTable(coarse.map((v) => v * 10), 'clamp', 16)[6], 65535);
+  });
+});
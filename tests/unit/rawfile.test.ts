@@ -0,0 +1,62 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { mkdtemp, rm, writeFile } from 'fs/promises';
+import { tmpdir } from 'os';
+import { join } from 'path';
+import { rawFileSource, rawRowsToRgba } from '../../src/rawfile.js';
+import { readAllStrips } from '../../src/pipeline.js';
+
+describe('rawRowsToRgba', () => {
+  it('should expand gray, reorder BGR, and skip row padding', () => {
+    assert.deepStrictEqual(Array.from(rawRowsToRgba(new Uint8Array([7, 9]), 2, 1, 2, 'gray')), [7, 7, 7, 255, 9, 9, 9, 255]);
+    assert.deepStrictEqual(Array.from(rawRowsToRgba(new Uint8Array([1, 2, 3, 0, 4, 5, 6, 0]), 1, 2, 4, 'bgr')), [3, 2, 1, 255, 6, 5, 4, 255]);
+    assert.deepStrictEqual(Array.from(rawRowsToRgba(new Uint8Array([1, 2, 3, 4]), 1, 1, 4, 'bgra')), [3, 2, 1, 4]);
+  });
+});
+
+describe('rawFileSource', () => {
+  it('should read strips at the right offsets past a header', async () => {
+    const dir = await mkdtemp(join(tmpdir(), 'raw-'));
+    try {
+      const path = join(dir, 'frame.raw');
+      // 4-byte header, 2x3 RGB rows padded to 8 bytes; the last row has no padding
+      const rows = [[1, 2, 3, 4, 5, 6, 0, 0], [7, 8, 9, 10, 11, 12, 0, 0], [13, 14, 15, 16, 17, 18]];
+      await writeFile(path, new Uint8Array([0xee, 0xee, 0xee, 0xee, ...rows.flat()]));
+
+      const source = await rawFileSource(path, { width: 2, height: 3, format: 'rgb', stride: 8, offset: 4 });
+      const pixels = await readAllStrips(source, 2);
+
+      assert.deepStrictEqual(Array.from(pixels.subarray(16, 24)), [13, 14, 15, 255, 16, 17, 18, 255]);
+      assert.deepStrictEqual(Array.from(pixels.subarray(0, 4)), [1, 2, 3, 255]);
+    } finally {
+      await rm(dir, { recursive: true });
+    }
+  });
+
+  it('should open files wider than one JPEG frame', async () => {
+    const dir = await mkdtemp(join(tmpdir(), 'raw-'));
+    try {
+      const path = join(dir, 'wide.raw');
+      await writeFile(path, new Uint8Array(70000).fill(9));
+
+      const source = await rawFileSource(path, { width: 70000, height: 1, format: 'gray' });
+
+      assert.strictEqual(source.width, 70000);
+      await source.close();
+    } finally {
+      await rm(dir, { recursive: true });
+    }
+  });
+
+  it('should reject files shorter than the layout', async () => {
+    const dir = await mkdtemp(join(tmpdir(), 'raw-'));
+    try {
+      const path = join(dir, 'short.raw');
+      await writeFile(path, new Uint8Array(10));
+
+      await assert.rejects(rawFileSource(path, { width: 2, height: 2 }), /too small: expected at least 16 bytes, found 10/);
+    } finally {
+      await rm(dir, { recursive: true });
+    }
+  });
+});
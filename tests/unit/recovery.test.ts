@@ -0,0 +1,67 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { addRecoveryPoints, decodePartial } from '../../src/recovery.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const WIDTH = 40;
+const HEIGHT = 100;
+
+function plainJpeg(): Uint8Array {
+  const rgba = rgbaPattern(WIDTH, HEIGHT, (x, y) => {
+    const i = y * WIDTH + x;
+    return [(i * 7) & 0xff, (i >> 2) & 0xff, (i * 13) & 0xff];
+  });
+  const encoder = createLibjpegStripEncoder(WIDTH, HEIGHT, { quality: 80, subsampling: '4:4:4' });
+  return new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+}
+
+describe('addRecoveryPoints', () => {
+  const jpeg = plainJpeg();
+
+  it('should place a restart marker every few MCU rows without changing the image', () => {
+    const { jpeg: output, index } = addRecoveryPoints(jpeg, 2);
+
+    const file = parseJpeg(output);
+    assert.strictEqual(file.scans[0].restartInterval, 10);
+    assert.strictEqual(index.rowsPerSegment, 16);
+    assert.strictEqual(index.segments.length, 7);
+    assert.deepStrictEqual(readCoefficients(file), readCoefficients(parseJpeg(jpeg)));
+  });
+
+  it('should reject a non-positive interval', () => {
+    assert.throws(() => addRecoveryPoints(jpeg, 0), /positive number of MCU rows/);
+  });
+});
+
+describe('decodePartial', () => {
+  const { jpeg, index } = addRecoveryPoints(plainJpeg(), 1);
+  const full = decode(jpeg);
+
+  it('should decode the intervals completed before the cut', () => {
+    // Cut partway through the fourth interval: three intervals of 8 rows survive
+    const cut = index.segments[3].offset + 5;
+
+    const image = decodePartial(jpeg.subarray(0, cut));
+
+    assert.deepStrictEqual([image.width, image.height, image.fullHeight], [WIDTH, 24, HEIGHT]);
+    assert.deepStrictEqual(Array.from(image.data), Array.from(full.data.subarray(0, WIDTH * 24 * 4)));
+  });
+
+  it('should decode a complete stream in full', () => {
+    const image = decodePartial(jpeg);
+    assert.strictEqual(image.height, HEIGHT);
+    assert.deepStrictEqual(Array.from(image.data), Array.from(full.data));
+  });
+
+  it('should return no rows before the first recovery point', () => {
+    const image = decodePartial(jpeg.subarray(0, index.segments[0].offset + 10));
+    assert.deepStrictEqual([image.height, image.data.length], [0, 0]);
+  });
+
+  it('should need the header up to the scan', () => {
+    assert.throws(() => decodePartial(jpeg.subarray(0, index.headerLength - 1)), /ends before its scan starts/);
+  });
+});
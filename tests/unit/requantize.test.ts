@@ -0,0 +1,151 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { requantize, requantizeRows } from '../../src/requantize.js';
+import { readCoefficients } from '../../src/decoder.js';
+import { validate } from '../../src/validate.js';
+import { Marker, parseJpeg } from '../../src/jpeg.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+import { createLibjpegStripEncoder, libjpegQuantTables } from '../../src/libjpeg.js';
+import { naturalToZigzag } from '../../src/quant.js';
+import type { ChromaSubsampling } from '../../src/subsample.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// Block 1: DC 3, AC 1 at k=1 and 2 at k=2; block 2: DC 2, no AC
+const BITS = '011' + '11' + '10' + '1' + '110' + '10' + '0' + '010' + '0' + '0';
+
+function textured(width: number, height: number, subsampling: ChromaSubsampling): Uint8Array {
+  const rgba = rgbaPattern(width, height, (x, y) => [(x * 37 + y * 11) & 0xff, (x * y * 5) & 0xff, (x * 13 - y * 29) & 0xff]);
+  const encoder = createLibjpegStripEncoder(width, height, { quality: 95, subsampling });
+  return new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+}
+
+function nonZero(blocks: Int32Array, from: number, to: number): number {
+  return blocks.subarray(from, to).filter((v) => v !== 0).length;
+}
+
+describe('requantize', () => {
+  it('should divide coefficients by the new quantizers and store the new table', () => {
+    const baseline = grayscaleJpeg(16, 8, BITS);
+    const table = Array.from({ length: 64 }, (_, i) => (i === 0 ? 2 : 1));
+
+    const result = requantize(baseline, { luma: table });
+
+    const [luma] = readCoefficients(result);
+    assert.deepStrictEqual(Array.from(luma.quantTable.subarray(0, 3)), [2, 1, 1]);
+    assert.deepStrictEqual([luma.blocks[0], luma.blocks[1], luma.blocks[2], luma.blocks[64]], [2, 1, 2, 1]);
+    assert.strictEqual(validate(result).valid, true);
+  });
+
+  it('should write 16-bit tables in an extended sequential frame', () => {
+    const baseline = grayscaleJpeg(16, 8, BITS);
+    const table = Array.from({ length: 64 }, (_, i) => (i === 0 ? 300 : 1));
+
+    const result = requantize(baseline, { luma: table, precision: 16 });
+
+    const file = parseJpeg(result);
+    assert.strictEqual(file.frame.marker, Marker.SOF1);
+    assert.deepStrictEqual([...file.scans[0].quantTables.values()].map((t) => [t.precision, t.values[0]]), [[1, 300]]);
+    assert.strictEqual(readCoefficients(result)[0].blocks[0], 0);
+    assert.strictEqual(validate(result).valid, true);
+    assert.throws(() => requantize(baseline, { luma: table }), /entry 0 must be an integer 1-255, got 300/);
+  });
+
+  it('should dither the DC level of a flat area to its true mean', () => {
+    // Gray 131 gives DC 24 at quality 100; a step of 20 rounds every block to 1
+    const rgba = new Uint8Array(64 * 64 * 4).fill(131);
+    const encoder = createLibjpegStripEncoder(64, 64, { quality: 100, subsampling: '4:4:4' });
+    const flat = new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+    const table = Array.from({ length: 64 }, (_, i) => (i === 0 ? 20 : 1));
+    const meanDc = (jpeg: Uint8Array): number => {
+      const [luma] = readCoefficients(jpeg);
+      const dc = luma.blocks.filter((_, i) => i % 64 === 0);
+      return (dc.reduce((sum, v) => sum + v, 0) / dc.length) * luma.quantTable[0];
+    };
+
+    const plain = requantize(flat, { luma: table });
+    const dithered = requantize(flat, { luma: table }, { dither: true });
+
+    assert.strictEqual(validate(dithered).valid, true);
+    assert.strictEqual(meanDc(plain), 20);
+    assert.ok(Math.abs(meanDc(dithered) - 24) < 1, `mean DC ${meanDc(dithered)}`);
+    assert.deepStrictEqual(readCoefficients(dithered)[1].blocks, readCoefficients(plain)[1].blocks);
+  });
+
+  it('should keep the lowest AC terms of smooth blocks at a finer step', () => {
+    // Left half a shallow ramp, right half a checkerboard
+    const rgba = rgbaPattern(16, 8, (x, y) => {
+      const level = x < 8 ? 100 + x * 1.5 : (x + y) % 2 ? 60 : 190;
+      return [level, level, level];
+    });
+    const encoder = createLibjpegStripEncoder(16, 8, { quality: 100, subsampling: '4:4:4' });
+    const exact = new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+    const [luma, chroma] = libjpegQuantTables(30);
+    const normal = naturalToZigzag(luma);
+
+    const result = requantize(exact, { luma, chroma }, { deband: true });
+
+    assert.strictEqual(validate(result).valid, true);
+    const [source] = readCoefficients(exact);
+    const [banded] = readCoefficients(result);
+    assert.strictEqual(banded.quantTable[1], Math.round(normal[1] / 4));
+    assert.strictEqual(banded.quantTable[3], normal[3]);
+    // The ramp's slope survives to within the fine step, closer than without debanding
+    const [plain] = readCoefficients(requantize(exact, { luma, chroma }));
+    const bandedError = Math.abs(banded.blocks[1] * banded.quantTable[1] - source.blocks[1]);
+    assert.ok(bandedError <= banded.quantTable[1] / 2);
+    assert.ok(bandedError < Math.abs(plain.blocks[1] * plain.quantTable[1] - source.blocks[1]));
+    // The checkerboard's lowest terms stay on the normal step
+    for (const k of [1, 2]) {
+      const value = banded.blocks[64 + k] * banded.quantTable[k];
+      assert.ok(Math.abs(value - plain.blocks[64 + k] * normal[k]) <= banded.quantTable[k] / 2);
+    }
+  });
+
+  it('should reject tables with zero entries unless clamping', () => {
+    const baseline = grayscaleJpeg(8, 8, '00' + '0');
+    const zeros = new Array(64).fill(0);
+
+    assert.throws(() => requantize(baseline, { luma: zeros }), /entry 0 must be an integer 1-255/);
+    assert.deepStrictEqual(Array.from(readCoefficients(requantize(baseline, { luma: zeros, mode: 'clamp' }))[0].quantTable.subarray(0, 2)), [1, 1]);
+  });
+});
+
+describe('requantizeRows', () => {
+  it('should coarsen only the rows given a lower quality', () => {
+    const jpeg = textured(16, 32, '4:4:4');
+
+    const result = requantizeRows(jpeg, (top) => (top >= 16 ? 20 : undefined));
+
+    assert.strictEqual(validate(result).valid, true);
+    const [before] = readCoefficients(jpeg);
+    const [after] = readCoefficients(result);
+    assert.deepStrictEqual(after.quantTable, before.quantTable);
+    const half = 2 * 2 * 64;
+    assert.deepStrictEqual(after.blocks.subarray(0, half), before.blocks.subarray(0, half));
+    assert.ok(nonZero(after.blocks, half, 2 * half) < nonZero(before.blocks, half, 2 * half));
+    assert.ok(result.length < requantizeRows(jpeg, () => undefined).length);
+  });
+
+  it('should ask once per MCU row with its pixel rows', () => {
+    const calls: number[][] = [];
+
+    requantizeRows(textured(16, 40, '4:2:0'), (top, rows) => {
+      calls.push([top, rows]);
+      return undefined;
+    });
+
+    assert.deepStrictEqual(calls, [[0, 16], [16, 16], [32, 8]]);
+  });
+
+  it('should keep quantizers finer than the encoded ones', () => {
+    const jpeg = textured(16, 16, '4:4:4');
+
+    const result = requantizeRows(jpeg, () => 100);
+
+    assert.deepStrictEqual(readCoefficients(result)[0].blocks, readCoefficients(jpeg)[0].blocks);
+  });
+
+  it('should reject qualities outside 1-100', () => {
+    assert.throws(() => requantizeRows(textured(16, 16, '4:4:4'), (top) => (top === 8 ? 0 : 50)), /integer 1-100, got 0 for rows from 8/);
+  });
+});
@@ -0,0 +1,140 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { RestartStitcher, renumberRestartMarkers, stitchRestartSegments } from '../../src/restart.js';
+import { decode } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { validate } from '../../src/validate.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const quant = new Array(64).fill(1);
+quant[0] = 8;
+
+describe('stitchRestartSegments', () => {
+  it('should join bands with restart markers and reset DC prediction per band', () => {
+    // Each band starts at DC +8 (level 136); the second band's first block relies on the reset predictor
+    const top = grayscaleJpeg(16, 8, '101' + '1000' + '0' + '00' + '0', { quant });
+    const bottom = grayscaleJpeg(16, 5, '101' + '1000' + '0' + '00' + '0', { quant });
+
+    const stitched = stitchRestartSegments([top, bottom]);
+    const file = parseJpeg(stitched);
+    const image = decode(stitched);
+
+    assert.strictEqual(file.frame.height, 13);
+    assert.strictEqual(file.scans[0].restartInterval, 2);
+    assert.strictEqual(validate(stitched).valid, true);
+    for (let i = 0; i < image.data.length; i += 4) {
+      assert.strictEqual(image.data[i], 136);
+    }
+  });
+
+  it('should return a single band unchanged', () => {
+    const band = grayscaleJpeg(8, 8, '00' + '0');
+
+    assert.strictEqual(stitchRestartSegments([band]), band);
+  });
+
+  it('should reject bands encoded with different tables', () => {
+    const top = grayscaleJpeg(8, 8, '00' + '0');
+    const bottom = grayscaleJpeg(8, 8, '00' + '0', { quant });
+
+    assert.throws(() => stitchRestartSegments([top, bottom]), /different tables/);
+  });
+
+  it('should reject inner bands that do not end on an MCU row', () => {
+    const top = grayscaleJpeg(8, 5, '00' + '0');
+    const bottom = grayscaleJpeg(8, 8, '00' + '0');
+
+    assert.throws(() => stitchRestartSegments([top, bottom]), /multiple of 8/);
+  });
+
+  it('should keep the RSTn sequence running through bands with their own restart markers', () => {
+    // Five bands of two one-MCU intervals: nine markers, so the numbering wraps past RST7
+    const band = grayscaleJpeg(16, 8, ['00' + '0', '00' + '0'], { restartInterval: 1 });
+
+    const stitched = stitchRestartSegments([band, band, band, band, band]);
+
+    const file = parseJpeg(stitched);
+    assert.strictEqual(file.scans[0].restartInterval, 1);
+    assert.strictEqual(file.frame.height, 40);
+    assert.strictEqual(validate(stitched).valid, true);
+    const markers = Array.from(stitched.subarray(file.scans[0].dataOffset, file.scans[0].dataEnd))
+      .filter((byte, i, bytes) => i > 0 && bytes[i - 1] === 0xff && byte !== 0);
+    assert.deepStrictEqual(markers, [0xd0, 0xd1, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd0]);
+  });
+
+  it('should reject bands with a different or partial restart interval', () => {
+    const plain = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+    const restarted = grayscaleJpeg(16, 8, ['00' + '0', '00' + '0'], { restartInterval: 1 });
+    const uneven = grayscaleJpeg(24, 8, ['00' + '0' + '00' + '0', '00' + '0'], { restartInterval: 2 });
+
+    assert.throws(() => stitchRestartSegments([plain, restarted]), /interval of 1, expected 0/);
+    assert.throws(() => stitchRestartSegments([uneven, uneven]), /whole number of 2-MCU restart intervals/);
+  });
+});
+
+describe('renumberRestartMarkers', () => {
+  it('should number markers on from the seed and report the next index', () => {
+    const data = new Uint8Array([0x12, 0xff, 0x00, 0xff, 0xd0, 0x34, 0xff, 0xd0, 0x56]);
+
+    const { data: renumbered, nextRestartIndex } = renumberRestartMarkers(data, 14);
+
+    assert.deepStrictEqual(Array.from(renumbered), [0x12, 0xff, 0x00, 0xff, 0xd6, 0x34, 0xff, 0xd7, 0x56]);
+    assert.strictEqual(nextRestartIndex, 0);
+    assert.strictEqual(data[4], 0xd0, 'Input should not be modified');
+    assert.throws(() => renumberRestartMarkers(data, -1), /non-negative integer/);
+  });
+});
+
+describe('RestartStitcher', () => {
+  const width = 24;
+  const height = 40;
+
+  // Bands of 16 rows (8 for the last), as separate workers would encode them
+  function bands(): Uint8Array[] {
+    const out: Uint8Array[] = [];
+    for (let top = 0; top < height; top += 16) {
+      const rows = Math.min(16, height - top);
+      const rgba = rgbaPattern(width, rows, (x, y) => {
+        const i = y * width + x;
+        return [(i * 3) & 0xff, top * 4, (i * 7) & 0xff];
+      });
+      const encoder = createLibjpegStripEncoder(width, rows, { quality: 85, subsampling: '4:4:4' });
+      const chunks = [];
+      for (let y = 0; y < rows; y += 8) {
+        chunks.push(...encoder.encodeStrip(rgba.subarray(y * width * 4, (y + 8) * width * 4)));
+      }
+      out.push(new Uint8Array([...chunks, ...encoder.finish()]));
+    }
+    return out;
+  }
+
+  it('should write bands in file order as they become available', () => {
+    const encoded = bands();
+    const stitcher = new RestartStitcher(height, 16);
+
+    const afterSecond = stitcher.add(2, encoded[2]);
+    const afterFirst = stitcher.add(0, encoded[0]);
+    const afterLast = stitcher.add(1, encoded[1]);
+
+    assert.strictEqual(afterSecond.length, 0);
+    assert.ok(afterFirst.length > 0 && afterLast.length > 0);
+    assert.strictEqual(stitcher.complete, true);
+    const joined = new Uint8Array([...afterSecond, ...afterFirst, ...afterLast]);
+    assert.deepStrictEqual(joined, stitchRestartSegments(encoded));
+    assert.strictEqual(validate(joined).valid, true);
+  });
+
+  it('should reject repeated, misplaced, and mismatched bands', () => {
+    const encoded = bands();
+    const stitcher = new RestartStitcher(height, 16);
+    stitcher.add(0, encoded[0]);
+
+    assert.throws(() => stitcher.add(0, encoded[0]), /already added/);
+    assert.throws(() => stitcher.add(3, encoded[1]), /outside 0-2/);
+    assert.throws(() => stitcher.add(1, encoded[2]), /8 rows tall, expected 16/);
+    assert.throws(() => new RestartStitcher(height, 12), /multiple of 8/);
+    assert.strictEqual(stitcher.complete, false);
+  });
+});
@@ -0,0 +1,34 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { qualityQuantTables, qualityScaleFactor } from '../../src/scaling.js';
+import { libjpegQuantTables, scaledQuantTables } from '../../src/libjpeg.js';
+
+describe('qualityScaleFactor', () => {
+  it('should follow jpeg_set_quality by default', () => {
+    assert.deepStrictEqual([1, 25, 50, 75, 100].map((q) => qualityScaleFactor(q)), [5000, 200, 100, 50, 0]);
+  });
+
+  it('should change the linear scale by a constant ratio per quality point', () => {
+    assert.ok(Math.abs(qualityScaleFactor(1, 'linear') - 5000) < 1e-6);
+    assert.ok(Math.abs(qualityScaleFactor(50, 'linear') - 100) < 1e-9);
+    const ratios = [20, 60, 90].map((q) => qualityScaleFactor(q, 'linear') / qualityScaleFactor(q + 1, 'linear'));
+    ratios.forEach((ratio) => assert.ok(Math.abs(ratio - ratios[0]) < 1e-9));
+    assert.ok(qualityScaleFactor(100, 'linear') > 1 && qualityScaleFactor(100, 'linear') < 3);
+  });
+
+  it('should take a curve or a 100-entry table', () => {
+    const table = Array.from({ length: 100 }, (_, i) => 300 - i * 3);
+    assert.strictEqual(qualityScaleFactor(10, table), 273);
+    assert.strictEqual(qualityScaleFactor(10, (q) => q * 2), 20);
+    assert.throws(() => qualityScaleFactor(10, [1, 2, 3]), /100 entries/);
+    assert.throws(() => qualityScaleFactor(10, () => Number.NaN), /non-negative percentages/);
+  });
+});
+
+describe('qualityQuantTables', () => {
+  it('should match libjpeg\'s tables by default and scale the Annex K tables otherwise', () => {
+    assert.deepStrictEqual(qualityQuantTables(83), libjpegQuantTables(83));
+    assert.deepStrictEqual(qualityQuantTables(50, 'linear'), scaledQuantTables(100));
+    assert.deepStrictEqual(qualityQuantTables(70, () => 60), libjpegQuantTables(70));
+  });
+});
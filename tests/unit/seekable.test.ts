@@ -0,0 +1,86 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { decodeRegion } from '../../src/seekable.js';
+import type { RangeFetch } from '../../src/seekable.js';
+import { addStripIndex, readStripIndex } from '../../src/stripindex.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { writeBaseline } from '../../src/transcode.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+const WIDTH = 40;
+const HEIGHT = 100;
+
+/**
+ * 4:4:4 JPEG with a restart marker every two MCU rows and an embedded index
+ */
+function indexedJpeg(): Uint8Array {
+  const rgba = rgbaPattern(WIDTH, HEIGHT, (x, y) => {
+    const i = y * WIDTH + x;
+    return [(i * 7) & 0xff, (i >> 2) & 0xff, (i * 13) & 0xff];
+  });
+  const encoder = createLibjpegStripEncoder(WIDTH, HEIGHT, { quality: 80, subsampling: '4:4:4' });
+  const file = parseJpeg(new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]));
+  const jpeg = writeBaseline({
+    width: WIDTH,
+    height: HEIGHT,
+    components: readCoefficients(file).map((c, index) => ({ ...c, id: index + 1, h: 1, v: 1, quantId: index ? 1 : 0 })),
+    quantTables: [...file.scans[0].quantTables.values()],
+    restartInterval: 10,
+  });
+  return addStripIndex(jpeg).jpeg;
+}
+
+function rangeReader(bytes: Uint8Array): { fetchRange: RangeFetch; requests: [number, number][] } {
+  const requests: [number, number][] = [];
+  return {
+    requests,
+    fetchRange: async (start, end) => {
+      requests.push([start, end]);
+      return bytes.slice(start, end);
+    },
+  };
+}
+
+function crop(image: { width: number; data: Uint8ClampedArray }, x: number, y: number, width: number, height: number): number[] {
+  const out: number[] = [];
+  for (let row = y; row < y + height; row++) {
+    out.push(...image.data.subarray((row * image.width + x) * 4, (row * image.width + x + width) * 4));
+  }
+  return out;
+}
+
+describe('decodeRegion', () => {
+  const jpeg = indexedJpeg();
+  const full = decode(jpeg);
+
+  it('should decode a region from the header and the segments covering it', async () => {
+    const { fetchRange, requests } = rangeReader(jpeg);
+
+    const region = await decodeRegion(fetchRange, { x: 5, y: 20, width: 30, height: 20 }, { headerBytes: 4096 });
+
+    assert.deepStrictEqual([region.width, region.height], [30, 20]);
+    assert.deepStrictEqual(Array.from(region.data), crop(full, 5, 20, 30, 20));
+    // Rows 20-39 lie in segments 1 and 2 (16 rows each); nothing past them is read
+    assert.strictEqual(requests.length, 2);
+    assert.ok(requests[1][1] < jpeg.length / 2 + 200, `read up to ${requests[1][1]} of ${jpeg.length}`);
+  });
+
+  it('should use a sidecar index and reach the last, shorter segment', async () => {
+    const index = JSON.parse(JSON.stringify(readStripIndex(jpeg)));
+    const { fetchRange, requests } = rangeReader(jpeg);
+
+    const region = await decodeRegion(fetchRange, { y: 90, height: 10 }, { index });
+
+    assert.deepStrictEqual(Array.from(region.data), crop(full, 0, 90, WIDTH, 10));
+    assert.deepStrictEqual(requests[0], [0, index.headerLength]);
+  });
+
+  it('should reject regions outside the image and files without an index', async () => {
+    const { fetchRange } = rangeReader(jpeg);
+    await assert.rejects(decodeRegion(fetchRange, { y: 90, height: 20 }), /outside the 40x100 image/);
+    const plain = rangeReader(new Uint8Array([0xff, 0xd8, 0xff, 0xd9]));
+    await assert.rejects(decodeRegion(plain.fetchRange, { y: 0, height: 1 }), /No strip index/);
+  });
+});
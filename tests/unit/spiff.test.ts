@@ -0,0 +1,36 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { toSpiff } from '../../src/spiff.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { decode } from '../../src/decoder.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('toSpiff', () => {
+  it('should replace the JFIF header with a SPIFF header and directory', () => {
+    const jpeg = grayscaleJpeg(16, 8, '00' + '0' + '00' + '0');
+
+    const spiff = toSpiff(jpeg);
+    const file = parseJpeg(spiff);
+    const [header, endOfDirectory] = file.segments.slice(1, 3);
+
+    assert.deepStrictEqual(file.segments.slice(0, 4).map((s) => s.marker), [0xd8, 0xe8, 0xe8, 0xdb]);
+    assert.strictEqual(String.fromCharCode(...header.data.subarray(0, 5)), 'SPIFF');
+    assert.strictEqual(header.data[9], 1, 'Component count');
+    assert.strictEqual(header.data[18], 8, 'Grayscale color space');
+    assert.deepStrictEqual(Array.from(endOfDirectory.data), [0, 0, 0, 1, 0xff, 0xd8]);
+    assert.deepStrictEqual(decode(spiff).data, decode(jpeg).data);
+  });
+
+  it('should carry over the JFIF resolution unless overridden', () => {
+    const jpeg = grayscaleJpeg(8, 8, '00' + '0');
+
+    const inherited = parseJpeg(toSpiff(jpeg)).segments[1].data;
+    const overridden = parseJpeg(toSpiff(jpeg, { resolutionUnit: 'dpi', xDensity: 300, yDensity: 150 })).segments[1].data;
+    const view = new DataView(overridden.buffer, overridden.byteOffset);
+
+    assert.strictEqual(inherited[21], 0);
+    assert.strictEqual(overridden[21], 1);
+    assert.strictEqual(view.getUint32(22), 150);
+    assert.strictEqual(view.getUint32(26), 300);
+  });
+});
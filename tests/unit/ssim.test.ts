@@ -0,0 +1,40 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { ssim } from '../../src/ssim.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+// Seeded noise; the generator also steps over each alpha byte
+function noise(seed: number): () => number[] {
+  let state = seed;
+  const next = (): number => (state = (state * 1103515245 + 12345) >>> 0) >>> 24;
+  return () => {
+    const rgb = [next(), next(), next()];
+    next();
+    return rgb;
+  };
+}
+
+describe('ssim', () => {
+  it('should score identical images 1 and unrelated ones near 0', () => {
+    const image = rgbaPattern(32, 24, noise(1));
+
+    assert.strictEqual(ssim(image, image, 32, 24), 1);
+    assert.ok(Math.abs(ssim(image, rgbaPattern(32, 24, noise(2)), 32, 24)) < 0.1);
+  });
+
+  it('should fall as distortion grows', () => {
+    const image = rgbaPattern(16, 16, noise(3));
+    const slight = image.map((v, i) => (i % 4 === 3 ? v : Math.min(255, v + ((i * 7) % 5))));
+    const heavy = image.map((v, i) => (i % 4 === 3 ? v : Math.min(255, v + ((i * 7) % 41))));
+
+    const slightScore = ssim(image, slight, 16, 16);
+    assert.ok(slightScore < 1 && slightScore > ssim(image, heavy, 16, 16));
+  });
+
+  it('should compare images smaller than a window as a whole', () => {
+    const image = rgbaPattern(3, 2, noise(4));
+
+    assert.strictEqual(ssim(image, image, 3, 2), 1);
+    assert.throws(() => ssim(image, image, 4, 4), /4x4 RGBA/);
+  });
+});
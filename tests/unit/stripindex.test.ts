@@ -0,0 +1,81 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { addStripIndex, buildStripIndex, readStripIndex } from '../../src/stripindex.js';
+import { readCoefficients } from '../../src/decoder.js';
+import { validate } from '../../src/validate.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { writeBaseline } from '../../src/transcode.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+import { rgbaPattern } from '../utils/pixel-fixtures.js';
+
+/**
+ * 4:2:0 JPEG (16-row MCUs) with a restart marker every `interval` MCUs
+ */
+function restartJpeg(width: number, height: number, interval: number): Uint8Array {
+  const rgba = rgbaPattern(width, height, (x, y) => {
+    const i = y * width + x;
+    return [(i * 7) & 0xff, (i >> 3) & 0xff, (i * 13) & 0xff];
+  });
+  const encoder = createLibjpegStripEncoder(width, height, { quality: 80 });
+  const file = parseJpeg(new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]));
+  return writeBaseline({
+    width,
+    height,
+    components: readCoefficients(file).map((c, index) => ({ ...c, id: index + 1, h: index ? 1 : 2, v: index ? 1 : 2, quantId: index ? 1 : 0 })),
+    quantTables: [...file.scans[0].quantTables.values()],
+    restartInterval: interval,
+  });
+}
+
+describe('buildStripIndex', () => {
+  it('should locate every restart segment and the rows it covers', () => {
+    // 3 MCUs per row, 2 MCU rows per interval: segments of 32 rows, the last one 8
+    const jpeg = restartJpeg(48, 72, 6);
+
+    const index = buildStripIndex(jpeg);
+
+    assert.deepStrictEqual([index.width, index.height, index.rowsPerSegment], [48, 72, 32]);
+    assert.deepStrictEqual(index.segments.map((s) => [s.top, s.rows]), [[0, 32], [32, 32], [64, 8]]);
+    assert.strictEqual(index.headerLength, index.segments[0].offset);
+    index.segments.slice(0, -1).forEach((segment, i) => {
+      assert.deepStrictEqual([jpeg[segment.end], jpeg[segment.end + 1]], [0xff, 0xd0 + i]);
+      assert.strictEqual(index.segments[i + 1].offset, segment.end + 2);
+    });
+    assert.deepStrictEqual([jpeg[index.segments[2].end], jpeg[index.segments[2].end + 1]], [0xff, 0xd9]);
+  });
+
+  it('should index a file without restart markers as one segment', () => {
+    const index = buildStripIndex(restartJpeg(32, 40, 0));
+    assert.deepStrictEqual(index.segments.map((s) => [s.top, s.rows]), [[0, 40]]);
+  });
+
+  it('should reject intervals that split an MCU row', () => {
+    assert.throws(() => buildStripIndex(restartJpeg(48, 32, 2)), /whole rows of 3 MCUs/);
+  });
+});
+
+describe('addStripIndex', () => {
+  it('should embed an index that the start of the file is enough to read', () => {
+    const original = restartJpeg(48, 72, 3);
+    const before = buildStripIndex(original);
+
+    const { jpeg, index } = addStripIndex(original);
+
+    assert.strictEqual(validate(jpeg).valid, true);
+    assert.deepStrictEqual(readStripIndex(jpeg.subarray(0, index.headerLength)), index);
+    assert.deepStrictEqual(buildStripIndex(jpeg), index);
+    index.segments.forEach((segment, i) => {
+      assert.deepStrictEqual(jpeg.subarray(segment.offset, segment.end), original.subarray(before.segments[i].offset, before.segments[i].end));
+    });
+    assert.throws(() => addStripIndex(jpeg), /already holds/);
+  });
+
+  it('should find no index in plain files, and say how much of a cut-off one is missing', () => {
+    const original = restartJpeg(48, 32, 3);
+    const { jpeg } = addStripIndex(original);
+    const segmentEnd = 4 + ((jpeg[4] << 8) | jpeg[5]);
+    assert.strictEqual(readStripIndex(original), undefined);
+    assert.strictEqual(readStripIndex(jpeg.subarray(0, 8)), undefined);
+    assert.throws(() => readStripIndex(jpeg.subarray(0, 20)), new RegExp(`ends at byte ${segmentEnd};`));
+  });
+});
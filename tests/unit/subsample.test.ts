@@ -0,0 +1,78 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { toSubsampled } from '../../src/subsample.js';
+import { forwardDct, quantizeBlock, writeBaseline } from '../../src/transcode.js';
+import { decode, readCoefficients } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { validate } from '../../src/validate.js';
+
+const ones = new Uint16Array(64).fill(1);
+
+/**
+ * 4:4:4 JPEG whose Y, Cb, and Cr planes come from sample functions
+ */
+function yccJpeg(width: number, height: number, planes: ((x: number, y: number) => number)[]): Uint8Array {
+  const line = Math.ceil(width / 8);
+  const column = Math.ceil(height / 8);
+  const samples = new Float64Array(64);
+  const natural = new Float64Array(64);
+  const components = planes.map((plane, index) => {
+    const blocks = new Int32Array(line * column * 64);
+    for (let row = 0; row < column; row++) {
+      for (let col = 0; col < line; col++) {
+        for (let i = 0; i < 64; i++) {
+          samples[i] = plane(col * 8 + (i % 8), row * 8 + Math.floor(i / 8)) - 128;
+        }
+        forwardDct(samples, natural);
+        quantizeBlock(natural, ones, blocks, (row * line + col) * 64);
+      }
+    }
+    return { id: index + 1, h: 1, v: 1, quantId: 0, blocksPerLine: line, blocksPerColumn: column, blocks };
+  });
+  return writeBaseline({ width, height, components, quantTables: [{ id: 0, precision: 0, values: ones }] });
+}
+
+describe('toSubsampled', () => {
+  it('should keep luma exactly and average chroma over 4x1 boxes', () => {
+    const source = yccJpeg(40, 12, [(x) => x * 4, (x) => (x % 4 < 2 ? 100 : 140), () => 128]);
+
+    const output = toSubsampled(source, '4:1:1');
+
+    assert.strictEqual(validate(output).valid, true, JSON.stringify(validate(output).issues));
+    const { frame } = parseJpeg(output);
+    assert.deepStrictEqual(frame.components.map((c) => [c.h, c.v]), [[4, 1], [1, 1], [1, 1]]);
+    const [luma, cb] = readCoefficients(output);
+    const [sourceLuma] = readCoefficients(source);
+    // 40 pixels need 2 MCUs of 32, so the luma grid widens from 5 to 8 blocks
+    assert.strictEqual(luma.blocksPerLine, 8);
+    for (let col = 0; col < 5; col++) {
+      assert.deepStrictEqual(luma.blocks.subarray(col * 64, col * 64 + 64), sourceLuma.blocks.subarray(col * 64, col * 64 + 64));
+    }
+    // Alternating 100/140 pairs average to a flat 120: DC only, no AC energy
+    assert.strictEqual(cb.blocks[0], Math.round((120 - 128) * 8));
+    assert.ok(cb.blocks.subarray(1, 64).every((value) => value === 0));
+  });
+
+  it('should decode close to the source at every mode', () => {
+    const planes = [(x: number, y: number): number => 40 + x * 3 + y, (x: number): number => 110 + x, (_x: number, y: number): number => 150 - y * 2];
+    const source = yccJpeg(37, 21, planes);
+    const expected = decode(source).data;
+
+    for (const mode of ['4:2:2', '4:2:0', '4:1:1'] as const) {
+      const output = toSubsampled(source, mode);
+      const actual = decode(output).data;
+      let worst = 0;
+      for (let i = 0; i < actual.length; i++) {
+        worst = Math.max(worst, Math.abs(actual[i] - expected[i]));
+      }
+      assert.ok(worst <= 12, `${mode} differs by up to ${worst}`);
+    }
+    assert.strictEqual(toSubsampled(source, '4:4:4'), source);
+  });
+
+  it('should reject input that is already subsampled', () => {
+    const subsampled = toSubsampled(yccJpeg(16, 16, [() => 128, () => 128, () => 128]), '4:2:0');
+
+    assert.throws(() => toSubsampled(subsampled, '4:1:1'), /4:4:4 YCbCr/);
+  });
+});
@@ -0,0 +1,47 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { ThumbnailBuilder, jfxxJpegSegment, jfxxRgbSegment } from '../../src/thumbnail.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+describe('ThumbnailBuilder', () => {
+  it('should box-filter strips into the thumbnail grid', () => {
+    // 4x2 image, left half black, right half white, reduced to 2x1
+    const builder = new ThumbnailBuilder(4, 2, 2);
+    const row = [0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255];
+
+    builder.addStrip(new Uint8Array(row));
+    builder.addStrip(new Uint8Array(row));
+
+    assert.strictEqual(builder.width, 2);
+    assert.strictEqual(builder.height, 1);
+    assert.deepStrictEqual(Array.from(builder.toRgb()), [0, 0, 0, 255, 255, 255]);
+    assert.deepStrictEqual(Array.from(builder.toRgba()), [0, 0, 0, 255, 255, 255, 255, 255]);
+  });
+
+  it('should not upscale small images', () => {
+    const builder = new ThumbnailBuilder(3, 2, 80);
+
+    assert.strictEqual(builder.width, 3);
+    assert.strictEqual(builder.height, 2);
+  });
+});
+
+describe('JFXX segments', () => {
+  it('should encode RGB thumbnails with extension code 0x13', () => {
+    const segment = jfxxRgbSegment(1, 1, new Uint8Array([1, 2, 3]));
+
+    assert.deepStrictEqual(Array.from(segment), [0xff, 0xe0, 0, 13, 0x4a, 0x46, 0x58, 0x58, 0, 0x13, 1, 1, 1, 2, 3]);
+  });
+
+  it('should embed JPEG thumbnails with extension code 0x10', () => {
+    const thumbnail = grayscaleJpeg(8, 8, '00' + '0');
+    const segment = jfxxJpegSegment(thumbnail);
+
+    assert.strictEqual(segment[9], 0x10);
+    assert.deepStrictEqual(segment.subarray(10), thumbnail);
+  });
+
+  it('should reject RGB thumbnails that cannot fit in a segment', () => {
+    assert.throws(() => jfxxRgbSegment(148, 148, new Uint8Array(148 * 148 * 3)), /does not fit/);
+  });
+});
@@ -0,0 +1,117 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { forwardDct, optimizeHuffman, quantizeBlock, toGrayscale, writeBaseline } from '../../src/transcode.js';
+import type { CodedComponent } from '../../src/transcode.js';
+import { inverseDct, readCoefficients } from '../../src/decoder.js';
+import { parseJpeg } from '../../src/jpeg.js';
+import { validate } from '../../src/validate.js';
+import { toProgressive } from '../../src/progressive.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+import { createLibjpegStripEncoder } from '../../src/libjpeg.js';
+
+const ones = new Uint16Array(64).fill(1);
+
+function component(id: number, h: number, v: number, blocksPerLine: number, blocksPerColumn: number, seed: number): CodedComponent {
+  const blocks = new Int32Array(blocksPerLine * blocksPerColumn * 64);
+  for (let i = 0; i < blocks.length; i += 64) {
+    blocks[i] = ((i / 64) * seed) % 200 - 100;
+    blocks[i + 1] = (i / 64) % 3 - 1;
+    blocks[i + 40] = seed;
+  }
+  return { id, h, v, quantId: id === 1 ? 0 : 1, blocksPerLine, blocksPerColumn, blocks };
+}
+
+describe('forwardDct', () => {
+  it('should invert the decoder transform', () => {
+    const samples = Float64Array.from({ length: 64 }, (_, i) => ((i * 37) % 255) - 128);
+    const natural = new Float64Array(64);
+    const zigzag = new Int32Array(64);
+    const restored = new Float64Array(64);
+
+    forwardDct(samples, natural);
+    quantizeBlock(natural, ones, zigzag);
+    inverseDct(zigzag, 0, ones, restored);
+
+    samples.forEach((value, i) => assert.ok(Math.abs(restored[i] - 128 - value) <= 1, `Sample ${i} should survive the round trip`));
+  });
+});
+
+describe('writeBaseline', () => {
+  it('should write coefficients that read back unchanged, across restart intervals', () => {
+    // 40x20 at 4:1:1: 2x2 MCUs of 32x8
+    const components = [component(1, 4, 1, 8, 3, 7), component(2, 1, 1, 2, 3, 11), component(3, 1, 1, 2, 3, 13)];
+    const quantTables = [{ id: 0, precision: 0, values: ones }, { id: 1, precision: 0, values: new Uint16Array(64).fill(2) }];
+
+    const jpeg = writeBaseline({ width: 40, height: 20, components, quantTables, restartInterval: 1 });
+
+    assert.strictEqual(validate(jpeg).valid, true, JSON.stringify(validate(jpeg).issues));
+    const { frame, scans } = parseJpeg(jpeg);
+    assert.deepStrictEqual(frame.components.map((c) => [c.h, c.v]), [[4, 1], [1, 1], [1, 1]]);
+    assert.strictEqual(scans[0].restartInterval, 1);
+    readCoefficients(jpeg).forEach((read, index) => {
+      assert.deepStrictEqual(read.blocks, components[index].blocks, `Component ${index} should match`);
+    });
+  });
+
+  it('should code a single component without interleaving', () => {
+    const gray = component(1, 1, 1, 3, 2, 5);
+
+    const jpeg = writeBaseline({ width: 17, height: 9, components: [gray], quantTables: [{ id: 0, precision: 0, values: ones }] });
+
+    assert.deepStrictEqual(readCoefficients(jpeg)[0].blocks, gray.blocks);
+  });
+
+  it('should clamp out-of-range coefficients so DC stays within category 11 and AC within 10', () => {
+    // DC swings of 6000 and an AC of 5000 would need categories 13 and 13
+    const blocks = new Int32Array(3 * 64);
+    blocks.set([3000, 5000], 0);
+    blocks.set([-3000, -5000], 64);
+    blocks[128] = 3000;
+    const gray: CodedComponent = { id: 1, h: 1, v: 1, quantId: 0, blocksPerLine: 3, blocksPerColumn: 1, blocks };
+
+    const jpeg = writeBaseline({ width: 24, height: 8, components: [gray], quantTables: [{ id: 0, precision: 0, values: ones }] });
+
+    assert.strictEqual(validate(jpeg).valid, true, JSON.stringify(validate(jpeg).issues));
+    const { scans } = parseJpeg(jpeg);
+    assert.ok(Math.max(...scans[0].dcTables.get(0)!.symbols) <= 11, 'DC categories should not exceed 11');
+    assert.ok(Math.max(...scans[0].acTables.get(0)!.symbols.map((symbol) => symbol & 0x0f)) <= 10, 'AC categories should not exceed 10');
+    const read = readCoefficients(jpeg)[0].blocks;
+    assert.deepStrictEqual([read[0], read[1], read[64], read[65], read[128]], [1023, 1023, -1024, -1023, 1023]);
+  });
+});
+
+describe('optimizeHuffman', () => {
+  it('should re-code with tables from the image, keeping coefficients and restarts', () => {
+    // DC 3 with AC 1 at k=1 in both intervals
+    const source = grayscaleJpeg(16, 8, ['011' + '11' + '10' + '0', '011' + '11' + '10' + '0'], { restartInterval: 1 });
+
+    const optimized = optimizeHuffman(source);
+
+    assert.strictEqual(validate(optimized).valid, true);
+    assert.strictEqual(parseJpeg(optimized).scans[0].restartInterval, 1);
+    assert.deepStrictEqual(readCoefficients(optimized)[0].blocks, readCoefficients(source)[0].blocks);
+    assert.ok(optimized.length < source.length, `${optimized.length} should be below ${source.length}`);
+  });
+
+  it('should reject progressive input', () => {
+    assert.throws(() => optimizeHuffman(toProgressive(grayscaleJpeg(8, 8, '00' + '0'))), /sequential JPEG with a single scan/);
+  });
+});
+
+describe('toGrayscale', () => {
+  it('should keep the luma coefficients of a color image', () => {
+    const rgba = new Uint8Array(16 * 8 * 4).map((_, i) => (i % 4 === 3 ? 255 : (i * 7) & 0xff));
+    const encoder = createLibjpegStripEncoder(16, 8, { quality: 90, subsampling: '4:4:4' });
+    const color = new Uint8Array([...encoder.encodeStrip(rgba), ...encoder.finish()]);
+
+    const gray = toGrayscale(color);
+
+    assert.strictEqual(validate(gray).valid, true);
+    assert.strictEqual(parseJpeg(gray).frame.components.length, 1);
+    assert.deepStrictEqual(readCoefficients(gray)[0].blocks, readCoefficients(color)[0].blocks);
+  });
+
+  it('should reject progressive input', () => {
+    assert.throws(() => toGrayscale(toProgressive(grayscaleJpeg(8, 8, '00' + '0'))), /sequential JPEG with a single scan/);
+  });
+});
@@ -0,0 +1,70 @@
+import { describe, it } from 'node:test';
+import assert from 'node:assert';
+import { validate } from '../../src/validate.js';
+import { grayscaleJpeg } from '../utils/jpeg-fixtures.js';
+
+// Two 8x8 blocks: DC diff 0 + EOB, then DC diff +1 + EOB
+const VALID_BITS = '000' + '01010';
+
+describe('validate', () => {
+  it('should accept a well-formed stream', () => {
+    const report = validate(grayscaleJpeg(16, 8, VALID_BITS));
+
+    assert.strictEqual(report.valid, true);
+    assert.deepStrictEqual(report.issues, []);
+    assert.strictEqual(report.width, 16);
+    assert.strictEqual(report.height, 8);
+    assert.strictEqual(report.expectedMcus, 2);
+    assert.strictEqual(report.decodedMcus, 2);
+  });
+
+  it('should report scan data that ends before the last MCU', () => {
+    const report = validate(grayscaleJpeg(16, 8, '000'));
+
+    assert.strictEqual(report.valid, false);
+    assert.ok(report.issues.some((issue) => /ends .* before all 2 MCUs/.test(issue.message)));
+  });
+
+  it('should report a missing EOI', () => {
+    const jpeg = grayscaleJpeg(16, 8, VALID_BITS);
+    const report = validate(jpeg.subarray(0, jpeg.length - 2));
+
+    assert.strictEqual(report.valid, false);
+    assert.ok(report.issues.some((issue) => /EOI/.test(issue.message)));
+  });
+
+  it('should report restart markers without a restart interval', () => {
+    const report = validate(grayscaleJpeg(16, 8, ['000', '01010']));
+
+    assert.strictEqual(report.valid, false);
+    assert.ok(report.issues.some((issue) => /without a restart interval/.test(issue.message)));
+  });
+
+  it('should report over-subscribed Huffman tables', () => {
+    const jpeg = grayscaleJpeg(16, 8, VALID_BITS);
+    // Rewrite the AC table code-length counts from [1, 1, 1] to [2, 1, 0]
+    const acTable = jpeg.findIndex((byte, i) => byte === 0xc4 && jpeg[i - 1] === 0xff && jpeg[i + 3] === 0x10);
+    jpeg[acTable + 4] = 2;
+    jpeg[acTable + 5] = 1;
+    jpeg[acTable + 6] = 0;
+
+    const report = validate(jpeg);
+
+    assert.strictEqual(report.valid, false);
+    assert.ok(report.issues.some((issue) => /over-subscribed/.test(issue.message)));
+  });
+
+  it('should warn about padding that is not all 1-bits', () => {
+    const report = validate(grayscaleJpeg(8, 8, '000' + '00000'));
+
+    assert.strictEqual(report.valid, true);
+    assert.ok(report.issues.some((issue) => issue.severity === 'warning' && /padded/.test(issue.message)));
+  });
+
+  it('should report streams without SOI', () => {
+    const report = validate(new Uint8Array([0x00, 0x01, 0x02]));
+
+    assert.strictEqual(report.valid, false);
+    assert.match(report.issues[0].message, /missing SOI/);
+  });
+});
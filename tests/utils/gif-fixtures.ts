@@ -0,0 +1,99 @@
+/**
+ * Hand-assembled GIF files for unit tests
+ */
+
+export interface GifFixtureFrame {
+  indices: number[];
+  left?: number;
+  top?: number;
+  width: number;
+  height: number;
+  delay?: number;          // Hundredths of a second
+  disposal?: number;       // Raw GIF disposal method
+  transparent?: number;
+  interlaced?: boolean;
+  palette?: number[];      // Local color table (2^n RGB triples)
+}
+
+/**
+ * LZW-encode indices using only literal codes, clearing before the code size grows
+ */
+export function lzwLiterals(indices: number[], minCodeSize: number): number[] {
+  const clear = 1 << minCodeSize;
+  const codeSize = minCodeSize + 1;
+  const limit = (1 << codeSize) - clear - 2;
+  const codes = [clear];
+  indices.forEach((index, i) => {
+    if (i > 0 && i % limit === 0) {
+      codes.push(clear);
+    }
+    codes.push(index);
+  });
+  codes.push(clear + 1);
+
+  const bytes: number[] = [];
+  let buffer = 0;
+  let bits = 0;
+  for (const code of codes) {
+    buffer |= code << bits;
+    bits += codeSize;
+    while (bits >= 8) {
+      bytes.push(buffer & 0xff);
+      buffer >>>= 8;
+      bits -= 8;
+    }
+  }
+  if (bits > 0) {
+    bytes.push(buffer & 0xff);
+  }
+  return bytes;
+}
+
+function subBlocks(data: number[]): number[] {
+  const out: number[] = [];
+  for (let i = 0; i < data.length; i += 255) {
+    const block = data.slice(i, i + 255);
+    out.push(block.length, ...block);
+  }
+  out.push(0);
+  return out;
+}
+
+function tableSize(palette: number[]): number {
+  return Math.max(0, Math.log2(palette.length / 3) - 1);
+}
+
+export function gifFile(
+  width: number,
+  height: number,
+  globalPalette: number[] | undefined,
+  frames: GifFixtureFrame[],
+  backgroundIndex = 0
+): Uint8Array {
+  const bytes: number[] = [...Array.from('GIF89a', (c) => c.charCodeAt(0))];
+  bytes.push(width & 0xff, width >> 8, height & 0xff, height >> 8);
+  bytes.push(globalPalette ? 0x80 | tableSize(globalPalette) : 0, backgroundIndex, 0);
+  if (globalPalette) {
+    bytes.push(...globalPalette);
+  }
+  // NETSCAPE looping extension, which decoders must skip
+  bytes.push(0x21, 0xff, ...subBlocks([...Array.from('NETSCAPE2.0', (c) => c.charCodeAt(0))]).slice(0, -1), 3, 1, 0, 0, 0);
+
+  for (const frame of frames) {
+    const transparent = frame.transparent ?? -1;
+    const delay = frame.delay ?? 0;
+    bytes.push(0x21, 0xf9, 4, ((frame.disposal ?? 0) << 2) | (transparent >= 0 ? 1 : 0),
+      delay & 0xff, delay >> 8, Math.max(transparent, 0), 0);
+    const left = frame.left ?? 0;
+    const top = frame.top ?? 0;
+    bytes.push(0x2c, left & 0xff, left >> 8, top & 0xff, top >> 8,
+      frame.width & 0xff, frame.width >> 8, frame.height & 0xff, frame.height >> 8);
+    bytes.push((frame.palette ? 0x80 | tableSize(frame.palette) : 0) | (frame.interlaced ? 0x40 : 0));
+    if (frame.palette) {
+      bytes.push(...frame.palette);
+    }
+    bytes.push(2, ...subBlocks(lzwLiterals(frame.indices, 2)));
+  }
+  bytes.push(0x3b);
+  return new Uint8Array(bytes);
+}
@@ -0,0 +1,78 @@
+/**
+ * Hand-assembled JPEG streams for unit tests
+ * Built marker by marker so parsing and analysis helpers can be tested without
+ * the WASM encoder.
+ */
+
+/** Standard luminance DC table (ITU T.81 Table K.3) */
+export const DC_LUMA_COUNTS = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+export const DC_LUMA_SYMBOLS = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+/**
+ * Minimal AC table: EOB = '0', run/size 0x01 = '10', 0x02 = '110'
+ */
+export const AC_TINY_COUNTS = [1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+export const AC_TINY_SYMBOLS = [0x00, 0x01, 0x02];
+
+export function segment(marker: number, payload: number[]): number[] {
+  const length = payload.length + 2;
+  return [0xff, marker, length >> 8, length & 0xff, ...payload];
+}
+
+/**
+ * Pack a string of '0'/'1' characters into entropy-coded bytes
+ * Pads the final byte with 1-bits and stuffs a zero after every 0xFF.
+ */
+export function packBits(bits: string): number[] {
+  const padded = bits.padEnd(Math.ceil(bits.length / 8) * 8, '1');
+  const bytes: number[] = [];
+  for (let i = 0; i < padded.length; i += 8) {
+    const byte = parseInt(padded.slice(i, i + 8), 2);
+    bytes.push(byte);
+    if (byte === 0xff) {
+      bytes.push(0x00);
+    }
+  }
+  return bytes;
+}
+
+export interface GrayscaleFixtureOptions {
+  restartInterval?: number;
+  quant?: number[];
+}
+
+/**
+ * Build a single-component baseline JPEG
+ *
+ * @param bits Entropy-coded bits (DC table K.3, AC table AC_TINY); pass an array
+ *   to split the scan into restart intervals
+ */
+export function grayscaleJpeg(
+  width: number,
+  height: number,
+  bits: string | string[],
+  options: GrayscaleFixtureOptions = {}
+): Uint8Array {
+  const quant = options.quant ?? new Array(64).fill(1);
+  const intervals = Array.isArray(bits) ? bits : [bits];
+  const bytes: number[] = [0xff, 0xd8];
+
+  bytes.push(...segment(0xe0, [0x4a, 0x46, 0x49, 0x46, 0x00, 1, 1, 0, 0, 1, 0, 1, 0, 0]));
+  bytes.push(...segment(0xdb, [0x00, ...quant]));
+  bytes.push(...segment(0xc0, [8, height >> 8, height & 0xff, width >> 8, width & 0xff, 1, 1, 0x11, 0]));
+  bytes.push(...segment(0xc4, [0x00, ...DC_LUMA_COUNTS, ...DC_LUMA_SYMBOLS]));
+  bytes.push(...segment(0xc4, [0x10, ...AC_TINY_COUNTS, ...AC_TINY_SYMBOLS]));
+  if (options.restartInterval) {
+    bytes.push(...segment(0xdd, [options.restartInterval >> 8, options.restartInterval & 0xff]));
+  }
+  bytes.push(...segment(0xda, [1, 1, 0x00, 0, 63, 0]));
+  intervals.forEach((interval, index) => {
+    if (index > 0) {
+      bytes.push(0xff, 0xd0 + ((index - 1) % 8));
+    }
+    bytes.push(...packBits(interval));
+  });
+  bytes.push(0xff, 0xd9);
+
+  return new Uint8Array(bytes);
+}
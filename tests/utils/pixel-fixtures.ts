@@ -0,0 +1,18 @@
+/**
+ * Synthetic RGBA images for tests
+ */
+
+/**
+ * Opaque RGBA image with each pixel's color given by `color`
+ * Pixels are visited row by row, so stateful generators (seeded noise) are
+ * reproducible. Channel values wrap and truncate as Uint8Array stores do.
+ */
+export function rgbaPattern(width: number, height: number, color: (x: number, y: number, width: number, height: number) => number[]): Uint8Array {
+  const pixels = new Uint8Array(width * height * 4);
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      pixels.set([...color(x, y, width, height), 255], (y * width + x) * 4);
+    }
+  }
+  return pixels;
+}
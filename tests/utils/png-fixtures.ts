@@ -0,0 +1,64 @@
+/**
+ * Hand-assembled PNG files for unit tests
+ */
+
+import { deflateSync } from 'node:zlib';
+
+const CRC_TABLE = Array.from({ length: 256 }, (_, n) => {
+  let c = n;
+  for (let k = 0; k < 8; k++) {
+    c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1;
+  }
+  return c >>> 0;
+});
+
+function crc32(bytes: Uint8Array): number {
+  let crc = 0xffffffff;
+  for (const byte of bytes) {
+    crc = CRC_TABLE[(crc ^ byte) & 0xff] ^ (crc >>> 8);
+  }
+  return (crc ^ 0xffffffff) >>> 0;
+}
+
+export function pngChunk(type: string, body: number[] | Uint8Array): number[] {
+  const typed = new Uint8Array([...Array.from(type, (c) => c.charCodeAt(0)), ...body]);
+  const crc = crc32(typed);
+  const length = body.length;
+  return [
+    length >>> 24, (length >> 16) & 0xff, (length >> 8) & 0xff, length & 0xff,
+    ...typed,
+    crc >>> 24, (crc >> 16) & 0xff, (crc >> 8) & 0xff, crc & 0xff,
+  ];
+}
+
+export interface PngFixtureOptions {
+  colorType?: number;     // Defaults to 6 (RGBA)
+  bitDepth?: number;      // Defaults to 8
+  chunks?: number[][];    // Extra chunks (PLTE, tRNS) placed before IDAT
+  idatSplit?: number;     // Split the compressed data into IDAT chunks of this size
+}
+
+/**
+ * Build a PNG from already-filtered scanlines (each starting with its filter byte)
+ */
+export function pngFile(width: number, height: number, scanlines: number[][], options: PngFixtureOptions = {}): Uint8Array {
+  const { colorType = 6, bitDepth = 8, chunks = [] } = options;
+  const ihdr = [
+    width >>> 24, (width >> 16) & 0xff, (width >> 8) & 0xff, width & 0xff,
+    height >>> 24, (height >> 16) & 0xff, (height >> 8) & 0xff, height & 0xff,
+    bitDepth, colorType, 0, 0, 0,
+  ];
+  const compressed = deflateSync(new Uint8Array(scanlines.flat()));
+  const split = options.idatSplit ?? compressed.length;
+  const idats: number[] = [];
+  for (let i = 0; i < compressed.length; i += split) {
+    idats.push(...pngChunk('IDAT', compressed.subarray(i, i + split)));
+  }
+  return new Uint8Array([
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a,
+    ...pngChunk('IHDR', ihdr),
+    ...chunks.flat(),
+    ...idats,
+    ...pngChunk('IEND', []),
+  ]);
+}